@@ -0,0 +1,144 @@
+// Indexing, walking and placing trees the size of cargo's own and servo's,
+// made up so they don't depend on a checkout of either. Run with
+//
+//     cargo bench --bench dependency_tree
+//
+// For every tree size it prints how long each step takes on average.
+
+use std::collections::{HashMap, HashSet};
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use treebuild::dependency_tree::{Dependency, DependencyTree};
+use treebuild::layout::{self, LayoutView, SizeMetric, LAYOUT_NAMES};
+use treebuild::parse_cargo_tree_output::TreeNode;
+
+// Crates in the tree and how many each depends on at most, roughly what
+// `cargo tree` prints for cargo and for servo
+const SIZES: [(&str, usize, usize); 2] = [("cargo", 350, 6), ("servo", 1100, 8)];
+
+const MIN_TIME: Duration = Duration::from_millis(500);
+const MAX_RUNS: u32 = 1000;
+
+// Numbers that look random enough but are the same every run
+struct Lcg(u64);
+
+impl Lcg {
+    fn below(&mut self, max: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        ((self.0 >> 33) as usize) % max.max(1)
+    }
+}
+
+// Crates only depend on crates after them, so there are no cycles, and
+// each is depended on by at least one before it, so all are in the tree
+fn dependencies(crates: usize, most: usize) -> Vec<Vec<usize>> {
+    let mut rng = Lcg(crates as u64);
+    let mut dependencies = vec![Vec::new(); crates];
+
+    for idx in 1..crates {
+        dependencies[rng.below(idx)].push(idx);
+    }
+    for (idx, picked) in dependencies.iter_mut().enumerate() {
+        let later = crates - idx - 1;
+        for _ in 0..rng.below(most).min(later) {
+            picked.push(idx + 1 + rng.below(later));
+        }
+        picked.sort_unstable();
+        picked.dedup();
+    }
+
+    dependencies
+}
+
+// Like `cargo tree`, a crate's dependencies only show the first time round
+fn tree(idx: usize, dependencies: &[Vec<usize>], seen: &mut HashSet<usize>) -> Arc<TreeNode> {
+    let expand = seen.insert(idx);
+
+    Arc::new(TreeNode {
+        name: format!("crate-{}", idx),
+        children: if expand {
+            dependencies[idx]
+                .iter()
+                .map(|&dependency| tree(dependency, dependencies, seen))
+                .collect()
+        } else {
+            Vec::new()
+        },
+        color: (0x80, 0x80, 0x80),
+    })
+}
+
+fn depth_sum_by_arc(node: &Arc<TreeNode>, depth: usize) -> usize {
+    depth
+        + node
+            .children
+            .iter()
+            .map(|child| depth_sum_by_arc(child, depth + 1))
+            .sum::<usize>()
+}
+
+fn depth_sum_by_index(node: Dependency, depth: usize) -> usize {
+    depth
+        + node
+            .children()
+            .map(|child| depth_sum_by_index(child, depth + 1))
+            .sum::<usize>()
+}
+
+fn bench<T>(name: &str, mut run: impl FnMut() -> T) {
+    let started = Instant::now();
+    let mut runs = 0;
+
+    while runs < MAX_RUNS && started.elapsed() < MIN_TIME {
+        black_box(run());
+        runs += 1;
+    }
+
+    println!(
+        "  {:<28} {:>10.1} µs",
+        name,
+        started.elapsed().as_secs_f64() * 1e6 / f64::from(runs)
+    );
+}
+
+fn main() {
+    for &(name, crates, most) in SIZES.iter() {
+        let root = tree(0, &dependencies(crates, most), &mut HashSet::new());
+        let tree = DependencyTree::new(Arc::clone(&root));
+        println!("{}: {} crates, {} nodes", name, crates, tree.len());
+
+        bench("index", || DependencyTree::new(Arc::clone(&root)));
+        bench("walk by Arc", || depth_sum_by_arc(&root, 0));
+        bench("walk by index", || {
+            depth_sum_by_index(tree.get(&root).expect("The root is indexed"), 0)
+        });
+        bench("depth of every node", || {
+            tree.iter().map(Dependency::depth).sum::<usize>()
+        });
+        bench("depth by going up", || {
+            tree.iter()
+                .map(|node| std::iter::successors(node.parent(), |up| up.parent()).count())
+                .sum::<usize>()
+        });
+
+        let (weights, fixed) = (HashMap::new(), HashMap::new());
+        let view = LayoutView {
+            root: &root,
+            aggregate_depth: None,
+            expanded: None,
+            time: 0.0,
+            metric: SizeMetric::Dependencies,
+            weights: &weights,
+            fixed: &fixed,
+        };
+        for (name, mut layout) in LAYOUT_NAMES.iter().zip(layout::all()) {
+            bench(&format!("place with {}", name), || {
+                layout.place(&tree, &view)
+            });
+        }
+    }
+}
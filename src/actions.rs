@@ -0,0 +1,100 @@
+use std::env;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const DOCS: &str = "https://docs.rs";
+
+fn spawn(mut command: Command, what: &str) -> Result<(), String> {
+    command
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open {}: {}", what, e))
+}
+
+// In the browser, or whatever the desktop opens links with
+pub fn open_url(url: &str) -> Result<(), String> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        Command::new("explorer")
+    } else {
+        Command::new("xdg-open")
+    };
+
+    command.arg(url);
+    spawn(command, url)
+}
+
+// With $VISUAL or $EDITOR, which may come with arguments of their own, and
+// VS Code when neither is set
+pub fn open_in_editor(directory: &Path) -> Result<(), String> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "code".to_owned());
+    let mut words = editor.split_whitespace();
+
+    let mut command = Command::new(words.next().unwrap_or("code"));
+    command.args(words).arg(directory);
+    spawn(command, &directory.display().to_string())
+}
+
+// Through the first of the platform's clipboard tools that works, Wayland's
+// and then X's on Linux
+pub fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    let tools: &[(&str, &[&str])] = if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else if cfg!(windows) {
+        &[("clip", &[])]
+    } else {
+        &[
+            ("wl-copy", &[]),
+            ("xclip", &["-selection", "clipboard"]),
+            ("xsel", &["--clipboard", "--input"]),
+        ]
+    };
+
+    for (program, args) in tools {
+        let mut child = match Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(_) => continue,
+        };
+
+        // Dropping stdin closes it, which is when the tool takes the text
+        let written = child
+            .stdin
+            .take()
+            .is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        let copied = child.wait().is_ok_and(|status| status.success());
+        if written && copied {
+            return Ok(());
+        }
+    }
+
+    let names = tools
+        .iter()
+        .map(|(program, _)| *program)
+        .collect::<Vec<_>>();
+    Err(format!(
+        "Failed to copy to the clipboard, tried {}",
+        names.join(", ")
+    ))
+}
+
+// The docs of that version, or of the newest one when it isn't known
+pub fn docs_url(published_name: &str, version: Option<&str>) -> String {
+    format!(
+        "{}/{}/{}",
+        DOCS,
+        published_name,
+        version.unwrap_or("latest")
+    )
+}
@@ -1,6 +1,56 @@
+use crate::units::{Unit, UnitKind};
+use std::path::Path;
 use std::{collections::HashSet, process::Command};
 
-pub fn get_children(parent: usize) -> HashSet<String> {
+// Registry sources sit in a directory named after the package and its
+// version, e.g. serde-1.0.100
+fn package_of_path(path: &str) -> Option<String> {
+    let dir = Path::new(path).parent()?.file_name()?.to_str()?;
+
+    Some(match dir.rfind('-') {
+        Some(idx) if dir[idx + 1..].starts_with(|c: char| c.is_ascii_digit()) => {
+            dir[..idx].to_owned()
+        }
+        _ => dir.to_owned(),
+    })
+}
+
+fn unit_of_command_line(line: &str) -> Option<Unit> {
+    let fields = line.split_whitespace().collect::<Vec<_>>();
+    let crate_name = fields
+        .iter()
+        .position(|field| *field == "--crate-name")
+        .and_then(|idx| fields.get(idx + 1))?;
+
+    // Build scripts are all called build_script_build, the package is only
+    // in the path of the script
+    if *crate_name == "build_script_build" {
+        let script = fields.iter().find(|field| field.ends_with(".rs"))?;
+
+        return Some(Unit {
+            name: package_of_path(script)?.replace("_", "-"),
+            kind: UnitKind::BuildScript,
+        });
+    }
+
+    let kind = if fields.contains(&"--test") {
+        UnitKind::Test
+    } else if fields
+        .windows(2)
+        .any(|pair| pair == ["--crate-type", "bin"])
+    {
+        UnitKind::Bin
+    } else {
+        UnitKind::Lib
+    };
+
+    Some(Unit {
+        name: crate_name.replace("_", "-"),
+        kind,
+    })
+}
+
+pub fn get_units(parent: usize) -> HashSet<Unit> {
     let output = Command::new("pgrep")
         .arg("--list-full")
         .arg("--parent")
@@ -8,26 +58,25 @@ pub fn get_children(parent: usize) -> HashSet<String> {
         .output()
         .expect("Failed to execute pgrep");
 
-    let mut crates = HashSet::<String>::new();
     if output.status.success() {
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        for line in output_str.lines() {
-            let mut fields_iter = line.split_whitespace();
-            for field in &mut fields_iter {
-                if field == "--crate-name" {
-                    break;
-                }
-            }
-
-            if let Some(crate_name) = fields_iter.next() {
-                crates.insert(crate_name.replace("_", "-").to_owned());
-            }
-        }
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(unit_of_command_line)
+            .collect()
+    } else {
+        HashSet::new()
     }
-    crates
 }
 
-pub fn get_active() -> HashSet<String> {
+#[cfg(feature = "gui")]
+pub fn get_children(parent: usize) -> HashSet<String> {
+    get_units(parent)
+        .into_iter()
+        .map(|unit| unit.name)
+        .collect()
+}
+
+pub fn get_active() -> HashSet<Unit> {
     let output = Command::new("pgrep")
         .arg("cargo")
         .arg("--parent")
@@ -41,9 +90,40 @@ pub fn get_active() -> HashSet<String> {
         let trimmed = output_str.trim();
 
         if let Ok(pid) = trimmed.parse::<usize>() {
-            return get_children(pid);
+            return get_units(pid);
         }
     }
 
     HashSet::<_>::new()
 }
+
+// How a process was started, e.g. "cargo build --release"
+#[cfg(feature = "gui")]
+pub fn command_line(pid: u32) -> Option<String> {
+    let output = Command::new("ps")
+        .args(["-o", "args=", "-p"])
+        .arg(pid.to_string())
+        .output()
+        .ok()?;
+
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+        .filter(|_| output.status.success())
+}
+
+// The most recently started cargo on the machine, for watching a build we
+// didn't start ourselves
+#[cfg(feature = "gui")]
+pub fn newest_cargo() -> Option<u32> {
+    let output = Command::new("pgrep")
+        .arg("cargo")
+        .arg("--newest")
+        .arg("--exact")
+        .output()
+        .expect("Failed to execute pgrep");
+
+    if output.status.success() {
+        String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+    } else {
+        None
+    }
+}
@@ -0,0 +1,219 @@
+use crate::drawing::{node_id, Shape};
+use crate::layout::{Placement, Positions};
+use crate::parse_cargo_tree_output::TreeNode;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const DURATION: f32 = 0.3;
+// Moves further than this from one placing to the next are the layout
+// changing, anything less is it settling
+const JUMP: f32 = 40.0;
+
+// Node ids don't survive the tree being reloaded, so nodes are matched up by
+// name instead. The tree repeats shared crates, so also by which occurrence.
+type Key = (String, usize);
+
+fn keys(root: &Arc<TreeNode>) -> HashMap<usize, Key> {
+    fn walk(
+        node: &Arc<TreeNode>,
+        seen: &mut HashMap<String, usize>,
+        keys: &mut HashMap<usize, Key>,
+    ) {
+        if keys.contains_key(&node_id(node)) {
+            return;
+        }
+
+        let occurrence = seen.entry(node.name.clone()).or_insert(0);
+        keys.insert(node_id(node), (node.name.clone(), *occurrence));
+        *occurrence += 1;
+
+        for child in node.children.iter() {
+            walk(child, seen, keys);
+        }
+    }
+
+    let mut keys = HashMap::new();
+    walk(root, &mut HashMap::new(), &mut keys);
+    keys
+}
+
+fn ease(t: f32) -> f32 {
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    }
+}
+
+fn mix(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+fn between(from: Placement, to: Placement, t: f32) -> Placement {
+    let shape = match (from.shape, to.shape) {
+        (
+            Shape::Rect {
+                width: from_width,
+                height: from_height,
+            },
+            Shape::Rect { width, height },
+        ) => Shape::Rect {
+            width: mix(from_width, width, t),
+            height: mix(from_height, height, t),
+        },
+        (
+            Shape::Wedge {
+                origin: from_origin,
+                inner: from_inner,
+                outer: from_outer,
+                start: from_start,
+                end: from_end,
+            },
+            Shape::Wedge {
+                origin,
+                inner,
+                outer,
+                start,
+                end,
+            },
+        ) => Shape::Wedge {
+            origin: (
+                mix(from_origin.0, origin.0, t),
+                mix(from_origin.1, origin.1, t),
+            ),
+            inner: mix(from_inner, inner, t),
+            outer: mix(from_outer, outer, t),
+            start: mix(from_start, start, t),
+            end: mix(from_end, end, t),
+        },
+        // Circles have nothing but the center and radius to move, and a
+        // change of shape just happens at the end
+        (_, shape) => shape,
+    };
+
+    Placement {
+        center: (
+            mix(from.center.0, to.center.0, t),
+            mix(from.center.1, to.center.1, t),
+        ),
+        radius: mix(from.radius, to.radius, t),
+        shape,
+    }
+}
+
+fn jumped(previous: &HashMap<Key, Placement>, next: &HashMap<Key, Placement>) -> bool {
+    previous.len() != next.len()
+        || next.iter().any(|(key, to)| match previous.get(key) {
+            Some(from) => {
+                let (dx, dy) = (to.center.0 - from.center.0, to.center.1 - from.center.1);
+                (dx * dx + dy * dy).sqrt() > JUMP || (to.radius - from.radius).abs() > JUMP
+            }
+            None => true,
+        })
+}
+
+// Either the nodes or the folded aggregates hanging off them
+#[derive(Default)]
+struct Track {
+    shown: HashMap<Key, Placement>,
+    from: HashMap<Key, Placement>,
+    target: HashMap<Key, Placement>,
+}
+
+impl Track {
+    fn retarget(
+        &mut self,
+        placements: &HashMap<usize, Placement>,
+        keys: &HashMap<usize, Key>,
+    ) -> bool {
+        let target = placements
+            .iter()
+            .filter_map(|(id, placement)| Some((keys.get(id)?.clone(), *placement)))
+            .collect();
+        let jumped = jumped(&self.target, &target);
+
+        self.target = target;
+        jumped
+    }
+
+    fn start(&mut self) {
+        self.from = self.shown.clone();
+    }
+
+    // New nodes grow in where they are going rather than flying in
+    fn show(&mut self, t: Option<f32>) {
+        self.shown = match t {
+            Some(t) => self
+                .target
+                .iter()
+                .map(|(key, &to)| {
+                    let from = self
+                        .from
+                        .get(key)
+                        .copied()
+                        .unwrap_or(Placement { radius: 0.0, ..to });
+                    (key.clone(), between(from, to, t))
+                })
+                .collect(),
+            None => self.target.clone(),
+        };
+    }
+
+    fn placements(&self, ids: &HashMap<&Key, usize>) -> HashMap<usize, Placement> {
+        self.shown
+            .iter()
+            .filter_map(|(key, placement)| Some((*ids.get(key)?, *placement)))
+            .collect()
+    }
+}
+
+// Eases between the old and new placements whenever the layout changes
+// enough to notice, e.g. on switching layouts, folding a subtree or
+// reloading the tree
+#[derive(Default)]
+pub struct Animation {
+    nodes: Track,
+    aggregates: Track,
+    started: Option<f32>,
+}
+
+impl Animation {
+    pub fn running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    pub fn step(&mut self, root: &Arc<TreeNode>, positions: Positions, now: f32) -> Positions {
+        let keys = keys(root);
+        let jumped = self.nodes.retarget(&positions.nodes, &keys)
+            | self.aggregates.retarget(&positions.aggregates, &keys);
+
+        // Nothing to ease from on the first placing
+        if jumped && !self.nodes.shown.is_empty() {
+            self.nodes.start();
+            self.aggregates.start();
+            self.started = Some(now);
+        }
+
+        let t = self
+            .started
+            .map(|started| ((now - started) / DURATION).min(1.0))
+            .filter(|t| *t < 1.0);
+        if t.is_none() {
+            self.started = None;
+        }
+
+        self.nodes.show(t.map(ease));
+        self.aggregates.show(t.map(ease));
+
+        let ids = keys
+            .iter()
+            .map(|(id, key)| (key, *id))
+            .collect::<HashMap<_, _>>();
+
+        Positions {
+            nodes: self.nodes.placements(&ids),
+            aggregates: self.aggregates.placements(&ids),
+            ..positions
+        }
+    }
+}
@@ -0,0 +1,3237 @@
+use crate::animation::Animation;
+use crate::audit::{self, Advisories};
+use crate::batch::Batch;
+use crate::camera::Camera;
+use crate::category::{Category, ALL_CATEGORIES};
+use crate::compare::Comparison;
+use crate::config::Config;
+use crate::crates_io::{self, Listing, Listings};
+use crate::dependency_tree::DependencyTree;
+use crate::diff::TreeDiff;
+use crate::drawing::{draw_tree, node_id, DrawCrate, DrawLine, DrawState, Point, Shape};
+use crate::emit::Emitter;
+use crate::events::{BuildEvent, BuildPhase};
+use crate::explore::Exploration;
+use crate::export::BuildExport;
+use crate::filter::{self, Facts, Filter};
+use crate::hulls::{self, Grouping};
+use crate::keymap::{Action, Keymap};
+use crate::layout::{satellite, Positions, SizeMetric, LAYOUT_NAMES};
+use crate::license::LicenseSummary;
+use crate::monitor::LOG_LINES;
+use crate::notify::Notifier;
+use crate::options::{Mode, Options};
+use crate::package_info::PackageInfo;
+use crate::parallelism::CrateGraph;
+use crate::parse_cargo_tree_output::{sort_children, ChildOrder, TreeNode, WORKSPACE_ROOT};
+use crate::placer::{Placer, Request};
+use crate::project::{self, cargo_tree_with, unit_graph, LockfileRevision, CARGO_VERSION};
+use crate::quadtree::Bounds;
+use crate::recorder::Recorder;
+use crate::ring_buffer::RingBuffer;
+use crate::scene::Scene;
+use crate::session::{self, Session};
+use crate::summary::RunSummary;
+use crate::theme::Theme;
+use crate::timeline::{Timeline, TimelineEvent};
+use crate::timings::Timings;
+use crate::unit_graph::build_script_node;
+use crate::units::{UnitKind, Units};
+use crate::utilization::Utilization;
+use crate::{
+    actions, active, cargo_version, category, config, dag, drawing, event_log, export,
+    issue_report, layout, lockfile, lod, manifest, marks, metadata, monitor, outdated, parallelism,
+    power, pulse, report, safe_mode, screenshot, selection, summary, theme, tui, ui, warnings,
+    watch,
+};
+use nannou::draw;
+use nannou::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    time::Duration,
+};
+use std::{
+    env,
+    ops::Sub,
+    sync::mpsc::{channel, Receiver},
+    thread,
+};
+
+// What cargo metadata says, by crate
+type Metadata = HashMap<String, Arc<PackageInfo>>;
+
+const STALE_START: Duration = Duration::from_secs(30 * 60);
+// How far the mouse may move between press and release for it to still count as a click
+const CLICK_SLOP: f32 = 4.0;
+const ZOOM_STEP: f32 = 1.1;
+// As many wheel steps as a key press zooms by
+const KEY_ZOOM_STEPS: f32 = 3.0;
+const DEFAULT_AGGREGATE_DEPTH: usize = 3;
+// How long the cursor has to rest on a crate before its tooltip shows
+const HOVER_DELAY: f32 = 0.4;
+// How much of the way the camera closes in on a followed crate each frame
+const FOLLOW_EASE: f32 = 0.15;
+// Screen pixels a label gets even on a crate smaller than that
+const MIN_LABEL_WIDTH: f32 = 60.0;
+// Screen pixels past the window edge a crate still gets drawn in
+const CULL_MARGIN: f32 = 120.0;
+// Seconds between looking at whether we are on battery
+const POWER_CHECK: f32 = 30.0;
+// Seconds between looking at what cargo is running, which is a process scan
+const ACTIVE_CHECK: f32 = 0.25;
+const LOW_POWER_FPS: f64 = 10.0;
+// Seconds each revision of Cargo.lock stays up while its history plays
+const REVISION_SECS: f32 = 1.0;
+// Of the rings around crates showing how much below them is built
+const RING_WEIGHT: f32 = 2.0;
+// Between one level of dependents going back to pending and the next
+const RESET_STEP_SECS: f32 = 0.15;
+const HISTORY_TITLE: &str = "history of Cargo.lock";
+
+pub struct Model {
+    tree: Arc<DependencyTree>,
+    // Every crate as a single node, when showing the tree as a DAG
+    dag: Option<Arc<DependencyTree>>,
+    mouse_last: Point,
+    mouse_moved_at: f32,
+    // Where the left button went down, while it is held
+    drag_start: Option<Point>,
+    // The crate the button went down on, which moving drags along
+    dragged: Option<String>,
+    camera: Camera,
+    // Whether the camera is where fitting (or starting) put it, rather than
+    // moved by hand since
+    fitted: bool,
+    // Has the layouts, which are cycled through with a hotkey
+    placer: Placer,
+    current_layout: usize,
+    // Arrowheads on edges, pointing at the dependency
+    show_arrows: bool,
+    // Bundled edges from repeated crates to their first occurrence
+    show_bundles: bool,
+    // What the sunburst and treemap size crates by
+    size_metric: SizeMetric,
+    // The other layouts' circles too
+    sized_nodes: bool,
+    positions: Positions,
+    animation: Animation,
+    // Crates deeper than this are folded into one node per parent
+    aggregate_depth: Option<usize>,
+    exploration: Option<Exploration>,
+    selected: Option<Arc<TreeNode>>,
+    // Crates whose paths from the root stay highlighted, for presenting
+    pinned: BTreeSet<String>,
+    // Keeps the camera on the selection after moving it with the keyboard
+    following: bool,
+    metadata: Metadata,
+    categories: HashMap<String, Category>,
+    color_by_category: bool,
+    category_filter: HashSet<Category>,
+    color_by_license: bool,
+    // As of when coloring by license was last turned on
+    licenses: LicenseSummary,
+    show_outdated: bool,
+    // Crates with a newer release on crates.io and what it is
+    outdated: HashMap<String, String>,
+    // The latest versions by crates.io name, while they are being looked up
+    outdated_lookup: Option<Receiver<HashMap<String, String>>>,
+    // cargo metadata and the categories it gives, while it is being run in
+    // the background
+    metadata_refresh: Option<Receiver<(Metadata, HashMap<String, Category>)>>,
+    // What crates.io says about selected crates cargo metadata had no
+    // description of, None for those it doesn't know either
+    listings: Listings,
+    listing_lookup: Option<(String, Receiver<Option<Listing>>)>,
+    show_breakdown: bool,
+    show_minimap: bool,
+    grouping: Grouping,
+    // The group of each node shown, by `node_id`, as of the scene
+    groups: HashMap<usize, String>,
+    crate_graph: CrateGraph,
+    // Doesn't change with the build, and is too slow to redo every frame
+    widest_antichain: usize,
+    show_parallelism: bool,
+    utilization: Utilization,
+    show_utilization: bool,
+    // Warnings cargo tallied per crate this build, and as of earlier builds
+    crate_warnings: HashMap<String, usize>,
+    warning_history: HashMap<String, usize>,
+    show_warning_trends: bool,
+    search: Option<ui::search::Search>,
+    // While a filter is being typed
+    filter_panel: Option<ui::filter::FilterPanel>,
+    filter: Option<Filter>,
+    filter_text: String,
+    // Leave out what the filter doesn't match rather than dim it
+    filter_hides: bool,
+    // Workspace members left out of a build of several, by name
+    hidden_members: BTreeSet<String>,
+    // What the filter leaves of the tree when hiding, and what that was
+    // worked out from
+    filtered: Option<(FilterKey, Arc<TreeNode>)>,
+    // The nodes the filter matches in the tree shown, by `node_id`
+    filter_matches: Option<HashSet<usize>>,
+    // While a note on a crate is being written
+    note_editor: Option<ui::note::NoteEditor>,
+    active_tree: Arc<TreeNode>,
+    completed: HashSet<String>,
+    active: HashSet<String>,
+    failed: HashSet<String>,
+    fresh: HashSet<String>,
+    // False when showing a loaded session, which has no cargo to watch
+    live: bool,
+    // A cargo left running by an earlier treebuild, which isn't our child
+    attached_to: Option<u32>,
+    // Playing a recorded build back, which says itself what is building and
+    // shouldn't end up in the history
+    replaying: bool,
+    // Each package's lib, tests and so on, as seen running
+    units: Units,
+    phase: BuildPhase,
+    theme: Theme,
+    timings: Timings,
+    summary: Option<RunSummary>,
+    previous_run: Option<RunSummary>,
+    hud: ui::hud::Hud,
+    show_hud: bool,
+    keymap: Keymap,
+    // Every key, over everything else until the next key press
+    show_help: bool,
+    recorder: Option<Recorder>,
+    log: RingBuffer<String>,
+    config: Config,
+    // Shown instead of taking input on the first run in a project
+    wizard: Option<ui::wizard::Wizard>,
+    // Started stripped down because the last session crashed
+    safe_mode: bool,
+    // Only the project's own build opens the way it was left, not a shown
+    // export or diff
+    saves_session: bool,
+    // On battery or asked to be, see `set_low_power`
+    low_power: bool,
+    power_checked_at: f32,
+    active_checked_at: f32,
+    timeline: Timeline,
+    show_timeline: bool,
+    // The moment the scrubber is showing, None to follow the build
+    scrub: Option<f32>,
+    scrubbing: bool,
+    // Laid out once and kept until something it was built from changes
+    scene: Scene,
+    scene_key: Option<SceneKey>,
+    receiver: Receiver<BuildEvent>,
+    // Passes the events on as they come, see --emit-events
+    emitter: Option<Emitter>,
+    // See --notify
+    notifier: Option<Notifier>,
+    // The cargo we started, to stop or start over, see `restart_build`
+    build: Option<monitor::Running>,
+    // Changed packages, see --watch
+    watcher: Option<Receiver<Vec<String>>>,
+    // What is yet to go back to pending after a change, the next level last
+    resetting: Vec<HashSet<String>>,
+    reset_since: f32,
+    // Showing how the tree changed rather than a build, see `diff`
+    diff: Option<TreeDiff>,
+    hide_removed: bool,
+    // Showing several projects rather than a build, see `compare`
+    comparison: Option<Comparison>,
+    // Every revision of Cargo.lock when showing its history, see `history`
+    revisions: Vec<LockfileRevision>,
+    revision: usize,
+    playing: bool,
+    // When `revision` came up, to tell when the next one is due
+    revision_since: f32,
+    // Advisories against crates in the tree by name, once cargo audit is done
+    advisories: Advisories,
+    audit: Option<Receiver<Result<Advisories, String>>>,
+}
+
+// Everything the scene is built from, other than the time while nothing is
+// moving
+#[derive(PartialEq)]
+struct SceneKey {
+    root: usize,
+    dag: bool,
+    layout: usize,
+    fixed: HashMap<String, Point>,
+    aggregate_depth: Option<usize>,
+    expanded: Option<HashSet<usize>>,
+    size_metric: SizeMetric,
+    sized_nodes: bool,
+    completed: usize,
+    active: HashSet<String>,
+    failed: usize,
+    fresh: usize,
+    durations: usize,
+    warnings: usize,
+    metadata: usize,
+    scrub: Option<f32>,
+    search: Option<String>,
+    filter: Option<String>,
+    filter_hides: bool,
+    selected: Option<usize>,
+    pinned: BTreeSet<String>,
+    color_by_category: bool,
+    category_filter: HashSet<Category>,
+    color_by_license: bool,
+    outdated: Option<usize>,
+    grouping: Grouping,
+    hide_removed: bool,
+    show_arrows: bool,
+    show_bundles: bool,
+    units: usize,
+    theme: String,
+    time: Option<f32>,
+}
+
+// Everything a filter can ask about, other than the tree
+#[derive(PartialEq)]
+struct FilterKey {
+    text: String,
+    hidden_members: BTreeSet<String>,
+    root: usize,
+    completed: usize,
+    active: usize,
+    failed: usize,
+    durations: usize,
+    metadata: usize,
+}
+
+fn scene_key(_app: &App, _model: &Model) -> SceneKey {
+    // Some layouts take a while to come to rest. Running crates pulse too,
+    // but that happens as they are drawn.
+    let moving = _model.animation.running()
+        || !_model.placer.settled()
+        || (_model.live && _model.timings.build_end.is_none());
+
+    SceneKey {
+        root: node_id(&_model.active_tree),
+        dag: _model.dag.is_some(),
+        layout: _model.current_layout,
+        fixed: fixed_points(_model),
+        aggregate_depth: _model.aggregate_depth,
+        expanded: _model
+            .exploration
+            .as_ref()
+            .map(|exploration| exploration.expanded().clone()),
+        size_metric: _model.size_metric,
+        sized_nodes: _model.sized_nodes,
+        completed: _model.completed.len(),
+        active: _model.active.clone(),
+        failed: _model.failed.len(),
+        fresh: _model.fresh.len(),
+        durations: _model.timings.durations.len(),
+        warnings: _model.crate_warnings.values().sum(),
+        metadata: _model.metadata.len(),
+        scrub: _model.scrub,
+        search: _model
+            .search
+            .as_ref()
+            .map(|search| search.query().to_owned()),
+        filter: Some(_model.filter_text.clone()).filter(|_| _model.filter.is_some()),
+        filter_hides: _model.filter_hides,
+        selected: _model.selected.as_ref().map(node_id),
+        pinned: _model.pinned.clone(),
+        color_by_category: _model.color_by_category,
+        category_filter: _model.category_filter.clone(),
+        color_by_license: _model.color_by_license,
+        outdated: Some(_model.outdated.len()).filter(|_| _model.show_outdated),
+        grouping: _model.grouping,
+        hide_removed: _model.hide_removed,
+        show_arrows: _model.show_arrows,
+        show_bundles: _model.show_bundles,
+        units: _model.units.changes(),
+        theme: _model.config.theme.clone(),
+        time: Some(_app.time).filter(|_| moving),
+    }
+}
+
+lazy_static! {
+    static ref EVENT_RECEIVER: Mutex<Option<std::sync::mpsc::Receiver<BuildEvent>>> =
+        Mutex::new(None);
+    static ref RESUMED: Mutex<Option<event_log::Resumable>> = Mutex::new(None);
+    static ref ATTACHED: Mutex<Option<u32>> = Mutex::new(None);
+    static ref COMMAND: Mutex<Option<String>> = Mutex::new(None);
+    static ref CARGO_COMMAND: Mutex<Vec<&'static str>> = Mutex::new(vec!["build"]);
+    static ref DIFF_AGAINST: Mutex<Option<String>> = Mutex::new(None);
+    static ref HISTORY: Mutex<bool> = Mutex::new(false);
+    static ref REPLAYING: Mutex<bool> = Mutex::new(false);
+    static ref BUILD: Mutex<Option<monitor::Running>> = Mutex::new(None);
+    static ref COMPARE: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+    static ref OPTIONS: Options = Options::from_args();
+}
+
+fn enter_project() {
+    if let Some(ref path) = OPTIONS.path {
+        env::set_current_dir(path)
+            .unwrap_or_else(|e| panic!("Failed to change to {}: {}", path.display(), e));
+    }
+}
+
+pub fn launch(cargo_command: Vec<&'static str>) {
+    enter_project();
+    let cargo_command = OPTIONS.mode.map_or(cargo_command, Mode::command);
+    *CARGO_COMMAND.lock().unwrap() = cargo_command.clone();
+
+    cargo_version::warn_about(*CARGO_VERSION);
+
+    if OPTIONS.tui {
+        let config = config::load().unwrap_or_default();
+        let tree = project_tree(&cargo_command, &config);
+        let previous_secs = if config.history {
+            summary::load_previous().map(|run| run.total_secs)
+        } else {
+            None
+        };
+        let emitter = OPTIONS
+            .emit_events
+            .as_ref()
+            .map(|path| Emitter::create(path, &tree, previous_secs));
+        let notifier = Notifier::configured(OPTIONS.notify, OPTIONS.notify_sound, &config);
+        return tui::run(
+            cargo_command,
+            OPTIONS.cargo_args.clone(),
+            tree,
+            emitter,
+            notifier,
+        );
+    }
+
+    let sender = {
+        let (sender, receiver) = channel();
+
+        *EVENT_RECEIVER.lock().unwrap() = Some(receiver);
+
+        sender
+    };
+
+    if OPTIONS.load.is_none() {
+        // Rather than starting a second cargo next to one we lost track of
+        if let Some(session) = event_log::resumable() {
+            let (cargo_pid, log) = (session.cargo_pid, event_log::Writer::reopen(&session));
+
+            println!("Resuming the build of cargo {}", cargo_pid);
+            *RESUMED.lock().unwrap() = Some(session);
+            thread::spawn(move || monitor::follow(cargo_pid, log, sender));
+        } else {
+            let cargo_args = OPTIONS.cargo_args.clone();
+            *COMMAND.lock().unwrap() = Some(
+                std::iter::once("cargo")
+                    .chain(cargo_command.iter().copied())
+                    .chain(cargo_args.iter().map(String::as_str))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            );
+            *BUILD.lock().unwrap() = Some(monitor::spawn(cargo_command, cargo_args, sender, None));
+        }
+    }
+
+    nannou::app(model).update(update).exit(exit).run();
+}
+
+// The tree of the project, the way the options ask for it
+fn project_tree(cargo_command: &[&str], config: &Config) -> Arc<TreeNode> {
+    let tree = if OPTIONS.unit_graph {
+        unit_graph(cargo_command, &OPTIONS.cargo_args, &config.color_rules)
+    } else if OPTIONS.from_lockfile {
+        project::lockfile_tree(None, config.dev_dependencies, &config.color_rules)
+            .unwrap_or_else(|e| panic!("Failed to read the tree from Cargo.lock: {}", e))
+    } else {
+        cargo_tree_with(
+            config.dev_dependencies,
+            &config.color_rules,
+            &OPTIONS.tree_args,
+        )
+    };
+
+    let order = OPTIONS
+        .sort_children_by
+        .or_else(|| ChildOrder::by_name(&config.sort_children_by))
+        .unwrap_or(ChildOrder::ChildCount);
+    let durations = if order == ChildOrder::BuildTime {
+        event_log::last_durations()
+    } else {
+        HashMap::new()
+    };
+
+    sort_children(&OPTIONS.prune(&tree), order, &durations)
+}
+
+// Shows the project's tree against how it was at a git revision, in another
+// Cargo.lock or in an exported build, instead of building it
+pub fn diff(against: String) {
+    enter_project();
+    *DIFF_AGAINST.lock().unwrap() = Some(against);
+    *EVENT_RECEIVER.lock().unwrap() = Some(channel().1);
+
+    nannou::app(model).update(update).exit(exit).run();
+}
+
+// Plays the tree through every commit that changed Cargo.lock, to see when
+// crates came in, instead of building it
+pub fn history() {
+    enter_project();
+    *HISTORY.lock().unwrap() = true;
+    *EVENT_RECEIVER.lock().unwrap() = Some(channel().1);
+
+    nannou::app(model).update(update).exit(exit).run();
+}
+
+// Shows the trees of the projects in `paths` together, with the crates they
+// share standing out, instead of building any
+pub fn compare(paths: Vec<PathBuf>) {
+    *COMPARE.lock().unwrap() = paths;
+    *EVENT_RECEIVER.lock().unwrap() = Some(channel().1);
+
+    nannou::app(model).update(update).exit(exit).run();
+}
+
+fn compare_projects(paths: &[PathBuf], config: &Config) -> Comparison {
+    let here = env::current_dir().expect("Failed to get the current directory");
+    let trees = paths
+        .iter()
+        .map(|path| {
+            let path = here.join(path);
+            env::set_current_dir(&path)
+                .unwrap_or_else(|e| panic!("Failed to change to {}: {}", path.display(), e));
+            project_tree(&CARGO_COMMAND.lock().unwrap(), config)
+        })
+        .collect::<Vec<_>>();
+    env::set_current_dir(&here).expect("Failed to change back");
+
+    Comparison::new(&trees)
+}
+
+// Nothing built yet, for showing a tree that isn't being built
+fn unbuilt(tree: &Arc<TreeNode>) -> BuildExport {
+    BuildExport::new(
+        Arc::clone(tree),
+        BuildPhase::Waiting,
+        &HashSet::new(),
+        &HashSet::new(),
+        &HashSet::new(),
+        &Timings::new(),
+        &RingBuffer::new(0),
+        &Timeline::default(),
+        &Utilization::default(),
+        &HashMap::new(),
+    )
+}
+
+fn show_revision(_model: &mut Model, idx: usize) {
+    if idx == _model.revision || idx >= _model.revisions.len() {
+        return;
+    }
+
+    let tree = Arc::clone(&_model.revisions[idx].tree);
+    _model.revision = idx;
+    _model.categories = category::classify_tree(&tree, &_model.metadata);
+    _model.crate_graph = CrateGraph::new(&tree);
+    _model.widest_antichain = _model.crate_graph.widest_antichain();
+    _model.hud = ui::hud::Hud::new(HISTORY_TITLE.to_owned(), &tree, None);
+    _model.tree = Arc::new(DependencyTree::new(tree));
+    crates_io::forget_gone(&mut _model.listings, &_model.tree);
+    if _model.dag.is_some() {
+        _model.dag = Some(Arc::new(DependencyTree::new(dag::share(
+            _model.tree.root(),
+        ))));
+    }
+    reset_root(_model);
+}
+
+fn diff_against(against: &str, config: &Config) -> TreeDiff {
+    let new = project_tree(&CARGO_COMMAND.lock().unwrap(), config);
+    let new_versions = project::locked_versions(None);
+
+    // Exports don't say which versions they built
+    let (old, old_versions) = if against.ends_with(".lock") {
+        let path = Path::new(against);
+        let tree = project::lockfile_tree(Some(path), config.dev_dependencies, &config.color_rules)
+            .unwrap_or_else(|e| panic!("Failed to read the tree from {}: {}", against, e));
+        let versions = fs::read_to_string(path)
+            .ok()
+            .and_then(|raw| lockfile::versions(&raw).ok())
+            .unwrap_or_default();
+        (tree, versions)
+    } else if Path::new(against).is_file() {
+        let loaded = export::load(Path::new(against))
+            .unwrap_or_else(|e| panic!("Failed to load build: {}", e));
+        (loaded.tree, HashMap::new())
+    } else {
+        let tree = project::cargo_tree_at(
+            against,
+            config.dev_dependencies,
+            &config.color_rules,
+            &OPTIONS.tree_args,
+        );
+        (tree, project::locked_versions(Some(against)))
+    };
+
+    TreeDiff::new(&OPTIONS.prune(&old), &new, &old_versions, &new_versions)
+}
+
+// Shows a build that was started elsewhere, going by the output cargo writes
+// to `source`
+pub fn attach(source: PathBuf) {
+    let (sender, receiver) = channel();
+    *EVENT_RECEIVER.lock().unwrap() = Some(receiver);
+
+    let cargo_pid = active::newest_cargo();
+    *ATTACHED.lock().unwrap() = cargo_pid;
+    thread::spawn(move || monitor::attach(source, cargo_pid, sender));
+
+    nannou::app(model).update(update).exit(exit).run();
+}
+
+// Plays back a recorded build of the project, the last one treebuild followed
+// without `path`, at the pace it went or `--speed` times as fast
+pub fn replay(path: Option<PathBuf>) {
+    let path = path.unwrap_or_else(event_log::path);
+    let events = crate::replay::read(&path)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e));
+    enter_project();
+
+    let (sender, receiver) = channel();
+    *EVENT_RECEIVER.lock().unwrap() = Some(receiver);
+    *REPLAYING.lock().unwrap() = true;
+    *COMMAND.lock().unwrap() = Some(format!("replay of {}", path.display()));
+
+    let speed = OPTIONS.speed.unwrap_or(1.0);
+    thread::spawn(move || crate::replay::play(events, speed, sender));
+
+    nannou::app(model).update(update).exit(exit).run();
+}
+
+// Typed text only comes through as a raw event
+fn raw_event(_app: &App, _model: &mut Model, event: &nannou::winit::event::WindowEvent) {
+    if let nannou::winit::event::WindowEvent::ReceivedCharacter(c) = event {
+        if let Some(ref mut search) = _model.search {
+            search.push(*c);
+        } else if _model.filter_panel.is_some() {
+            edit_filter(_model, |panel| panel.push(*c));
+        } else if let Some(ref mut editor) = _model.note_editor {
+            editor.push(*c);
+        }
+    }
+}
+
+// Applies what is typed as soon as it parses, keeping the last filter that
+// did until then
+fn edit_filter(_model: &mut Model, edit: impl FnOnce(&mut ui::filter::FilterPanel)) {
+    let panel = match _model.filter_panel {
+        Some(ref mut panel) => panel,
+        None => return,
+    };
+
+    edit(panel);
+    let filter = panel.parse();
+    if !panel.has_error() {
+        _model.filter_text = panel.text().to_owned();
+        _model.filter = filter;
+    }
+}
+
+// Where crates were put by hand in the layout shown
+fn fixed_points(_model: &Model) -> HashMap<String, Point> {
+    _model
+        .config
+        .fixed
+        .get(layout::LAYOUT_NAMES[_model.current_layout])
+        .cloned()
+        .unwrap_or_default()
+}
+
+// Safe mode runs without the saved settings, so it doesn't save any either
+fn save_config(_model: &Model) {
+    if !_model.safe_mode {
+        config::save(&_model.config);
+    }
+}
+
+fn past_slop(from: Point, to: Point) -> bool {
+    (to.0 - from.0).powf(2.0) + (to.1 - from.1).powf(2.0) > CLICK_SLOP.powf(2.0)
+}
+
+// Sends the selected crate back to where the layout puts it
+fn unfix_selected(_model: &mut Model) {
+    let name = match _model.selected {
+        Some(ref selected) => selected.name.clone(),
+        None => return,
+    };
+    let layout = layout::LAYOUT_NAMES[_model.current_layout];
+    let removed = _model
+        .config
+        .fixed
+        .get_mut(layout)
+        .and_then(|fixed| fixed.remove(&name));
+
+    if removed.is_some() {
+        _model.config.fixed.retain(|_, fixed| !fixed.is_empty());
+        save_config(_model);
+    }
+}
+
+// An empty note takes the crate's note away
+fn finish_note(_model: &mut Model, editor: ui::note::NoteEditor) {
+    let note = editor.text().trim();
+    if note.is_empty() {
+        _model.config.notes.remove(editor.name());
+    } else {
+        _model
+            .config
+            .notes
+            .insert(editor.name().to_owned(), note.to_owned());
+    }
+    save_config(_model);
+}
+
+// The DAG when showing shared crates once, the text tree otherwise
+fn shown_tree(_model: &Model) -> &DependencyTree {
+    _model.dag.as_ref().unwrap_or(&_model.tree)
+}
+
+// The first nine members of a workspace build come and go with Ctrl and
+// their place among the root's children
+fn toggle_member(_model: &mut Model, idx: usize) {
+    let root = shown_tree(_model).root();
+    if !root.is_workspace() {
+        return;
+    }
+
+    if let Some(member) = root.children.get(idx) {
+        let name = member.name.clone();
+        if !_model.hidden_members.remove(&name) {
+            _model.hidden_members.insert(name);
+        }
+    }
+}
+
+fn reset_root(_model: &mut Model) {
+    _model.active_tree = Arc::clone(shown_tree(_model).root());
+    _model.selected = None;
+
+    if _model.exploration.is_some() {
+        _model.exploration = Some(Exploration::new(Arc::clone(&_model.active_tree)));
+    }
+}
+
+// Jumps to the best match that is currently on screen
+fn jump_to_match(_app: &App, _model: &mut Model) {
+    let query = match _model.search {
+        Some(ref search) => search.query().to_owned(),
+        None => return,
+    };
+
+    let scene = &_model.scene;
+    let target = shown_tree(_model)
+        .find(&query, &_model.config.aliases)
+        .into_iter()
+        .find_map(|node| {
+            scene
+                .crates()
+                .iter()
+                .find(|draw_crate| draw_crate.tree.name == node.name)
+                .map(|draw_crate| draw_crate.center)
+        });
+
+    if let Some(target) = target {
+        _model.camera.look_at(target);
+        _model.fitted = false;
+    }
+}
+
+// Arrow keys walk the selection: up to the parent, down to the first child and
+// sideways through the siblings
+fn navigate(_model: &mut Model, key: Key) {
+    _model.following = true;
+
+    let current = match _model.selected {
+        Some(ref selected) => Arc::clone(selected),
+        None => {
+            _model.selected = Some(Arc::clone(&_model.active_tree));
+            return;
+        }
+    };
+
+    let next = match key {
+        Key::Up => shown_tree(_model).parent(&current).cloned(),
+        Key::Down => current.children.first().cloned(),
+        Key::Left => shown_tree(_model).sibling(&current, -1).cloned(),
+        Key::Right => shown_tree(_model).sibling(&current, 1).cloned(),
+        _ => None,
+    };
+
+    let next = match next {
+        Some(next) => next,
+        None => return,
+    };
+
+    match _model.exploration {
+        // Walking down peels the next layer
+        Some(ref mut exploration) => {
+            if key == Key::Down && !exploration.expanded().contains(&node_id(&current)) {
+                exploration.toggle(&current);
+            }
+        }
+        // Walking up from the drawn root brings its parent into view
+        None => {
+            if key == Key::Up && node_id(&current) == node_id(&_model.active_tree) {
+                _model.active_tree = Arc::clone(&next);
+            }
+        }
+    }
+
+    _model.selected = Some(next);
+}
+
+// Pins the path down to the selected crate, or without a selection unpins
+// everything
+fn toggle_pin(_model: &mut Model) {
+    match _model.selected {
+        Some(ref selected) => {
+            if !_model.pinned.remove(&selected.name) {
+                _model.pinned.insert(selected.name.clone());
+            }
+        }
+        None => _model.pinned.clear(),
+    }
+}
+
+// What can change without reloading the tree applies right away
+fn finish_wizard(_app: &App, _model: &mut Model, config: Config) {
+    _model.wizard = None;
+    config::save(&config);
+    println!("Saved settings to {}", config::path().display());
+
+    _model.current_layout = layout::index_of(&config.layout);
+    _model.theme = theme::by_name(&config.theme, &config.themes);
+    _model.config = config;
+    _app.set_exit_on_escape(true);
+}
+
+// The view keeps fitting the window as it changes size, until it is moved
+// by hand
+fn fit_to_window(_app: &App, _model: &mut Model) {
+    let window = _app.window_rect();
+    _model
+        .camera
+        .fit(_model.scene.crates(), (window.w(), window.h()));
+    _model.fitted = true;
+}
+
+// What a key does, by what the keymap says it is for
+fn perform(_app: &App, _model: &mut Model, action: Action) {
+    match action {
+        Action::Fit => fit_to_window(_app, _model),
+        Action::ZoomIn | Action::ZoomOut => {
+            let steps = match action {
+                Action::ZoomIn => KEY_ZOOM_STEPS,
+                _ => -KEY_ZOOM_STEPS,
+            };
+            _model.camera.zoom_at((0.0, 0.0), ZOOM_STEP.powf(steps));
+            _model.fitted = false;
+        }
+        Action::ToggleAggregate => {
+            _model.aggregate_depth = match _model.aggregate_depth {
+                Some(_) => None,
+                None => Some(OPTIONS.aggregate_depth.unwrap_or(DEFAULT_AGGREGATE_DEPTH)),
+            }
+        }
+        Action::ToggleExplore => {
+            _model.exploration = match _model.exploration {
+                Some(_) => None,
+                None => Some(Exploration::new(Arc::clone(&_model.active_tree))),
+            }
+        }
+        Action::Filter => {
+            _model.filter_panel = Some(ui::filter::FilterPanel::new(&_model.filter_text));
+            _app.set_exit_on_escape(false);
+        }
+        Action::Note => {
+            if let Some(ref selected) = _model.selected {
+                let note = _model.config.notes.get(&selected.name);
+                _model.note_editor = Some(ui::note::NoteEditor::new(
+                    &selected.name,
+                    note.map_or("", String::as_str),
+                ));
+                _app.set_exit_on_escape(false);
+            }
+        }
+        Action::Unfix => unfix_selected(_model),
+        Action::Search => {
+            _model.search = Some(ui::search::Search::default());
+            _app.set_exit_on_escape(false);
+        }
+        Action::CancelBuild => cancel_build(_model),
+        Action::CopyPath => copy_selected_path(_model),
+        Action::ColorByCategory => {
+            _model.color_by_category = !_model.color_by_category;
+            _model.color_by_license = false;
+        }
+        Action::OpenSource => open_selected_source(_model),
+        Action::ColorByLicense => toggle_licenses(_model),
+        Action::ShowOutdated => toggle_outdated(_model),
+        Action::HideRemoved => _model.hide_removed = !_model.hide_removed,
+        // Again from the start once it has played through
+        Action::PlayHistory if !_model.revisions.is_empty() => {
+            if !_model.playing && _model.revision + 1 == _model.revisions.len() {
+                show_revision(_model, 0);
+            }
+            _model.playing = !_model.playing;
+            _model.revision_since = _app.time;
+        }
+        Action::PreviousRevision if !_model.revisions.is_empty() => {
+            _model.playing = false;
+            show_revision(_model, _model.revision.saturating_sub(1));
+        }
+        Action::NextRevision if !_model.revisions.is_empty() => {
+            _model.playing = false;
+            show_revision(_model, _model.revision + 1);
+        }
+        Action::ToggleBreakdown => _model.show_breakdown = !_model.show_breakdown,
+        Action::ToggleMinimap => _model.show_minimap = !_model.show_minimap,
+        Action::NextGrouping => {
+            _model.grouping = _model.grouping.next();
+            println!("Grouping: {}", _model.grouping.label());
+        }
+        Action::ToggleHud => _model.show_hud = !_model.show_hud,
+        Action::SelectParent => navigate(_model, Key::Up),
+        Action::SelectChild => navigate(_model, Key::Down),
+        Action::SelectPrevious => navigate(_model, Key::Left),
+        Action::SelectNext => navigate(_model, Key::Right),
+        Action::ToggleLayout => {
+            _model.current_layout = (_model.current_layout + 1) % LAYOUT_NAMES.len()
+        }
+        Action::ShareCrates => {
+            _model.dag = match _model.dag {
+                Some(_) => None,
+                None => Some(Arc::new(DependencyTree::new(dag::share(
+                    _model.tree.root(),
+                )))),
+            };
+            reset_root(_model);
+        }
+        Action::ToggleBundles => _model.show_bundles = !_model.show_bundles,
+        Action::OpenDocs => open_selected_docs(_model),
+        Action::ToggleArrows => _model.show_arrows = !_model.show_arrows,
+        Action::NextSizeMetric => _model.size_metric = _model.size_metric.next(),
+        Action::ToggleSizedNodes => _model.sized_nodes = !_model.sized_nodes,
+        Action::RestartBuild => restart_build(_model),
+        Action::IssueReport => write_issue_report(_model),
+        Action::CopyInverseTree => copy_inverse_tree(_model),
+        Action::TogglePin => toggle_pin(_model),
+        Action::NextTheme => {
+            let names = theme::names(&_model.config.themes);
+            let current = names.iter().position(|name| *name == _model.config.theme);
+            let next = current.map_or(0, |idx| (idx + 1) % names.len());
+
+            _model.config.theme = names[next].clone();
+            _model.theme = theme::by_name(&_model.config.theme, &_model.config.themes);
+            println!("Theme: {}", _model.config.theme);
+        }
+        Action::ToggleWarningTrends => _model.show_warning_trends = !_model.show_warning_trends,
+        Action::ToggleUtilization => _model.show_utilization = !_model.show_utilization,
+        Action::ToggleParallelism => _model.show_parallelism = !_model.show_parallelism,
+        Action::ToggleTimeline => {
+            _model.show_timeline = !_model.show_timeline;
+            _model.scrub = None;
+        }
+        Action::Screenshot => save_screenshot(_app, _model),
+        Action::ResetRoot => reset_root(_model),
+        Action::Help => _model.show_help = true,
+        Action::Member(idx) => toggle_member(_model, idx),
+        Action::Category(idx) => {
+            let category = ALL_CATEGORIES[idx];
+
+            if !_model.category_filter.remove(&category) {
+                _model.category_filter.insert(category);
+            }
+        }
+        // Without a lockfile history to go through
+        Action::PlayHistory | Action::PreviousRevision | Action::NextRevision => {}
+    }
+}
+
+fn event(_app: &App, _model: &mut Model, event: WindowEvent) {
+    if let Some(wizard) = _model.wizard.as_mut() {
+        let finished = match event {
+            KeyPressed(Key::Up) => {
+                wizard.move_choice(-1);
+                None
+            }
+            KeyPressed(Key::Down) => {
+                wizard.move_choice(1);
+                None
+            }
+            KeyPressed(Key::Return) => wizard.confirm(),
+            KeyPressed(Key::Escape) => _model.wizard.take().map(ui::wizard::Wizard::skip),
+            _ => None,
+        };
+
+        if let Some(config) = finished {
+            finish_wizard(_app, _model, config);
+        }
+        return;
+    }
+
+    // While searching the keyboard belongs to the search box
+    if _model.search.is_some() {
+        if let KeyPressed(key) = event {
+            match key {
+                Key::Escape => {
+                    _model.search = None;
+                    _app.set_exit_on_escape(true);
+                }
+                Key::Return => jump_to_match(_app, _model),
+                Key::Back => _model.search.as_mut().unwrap().pop(),
+                _ => {}
+            }
+            return;
+        }
+    }
+
+    if _model.filter_panel.is_some() {
+        if let KeyPressed(key) = event {
+            match key {
+                Key::Escape => {
+                    _model.filter_panel = None;
+                    _model.filter = None;
+                    _model.filter_text.clear();
+                    _app.set_exit_on_escape(true);
+                }
+                Key::Return => {
+                    _model.filter_panel = None;
+                    _app.set_exit_on_escape(true);
+                }
+                Key::Tab => _model.filter_hides = !_model.filter_hides,
+                Key::Back => edit_filter(_model, ui::filter::FilterPanel::pop),
+                _ => {}
+            }
+            return;
+        }
+    }
+
+    if _model.note_editor.is_some() {
+        if let KeyPressed(key) = event {
+            match key {
+                Key::Escape => {
+                    _model.note_editor = None;
+                    _app.set_exit_on_escape(true);
+                }
+                Key::Return => {
+                    let editor = _model.note_editor.take().unwrap();
+                    finish_note(_model, editor);
+                    _app.set_exit_on_escape(true);
+                }
+                Key::Back => _model.note_editor.as_mut().unwrap().pop(),
+                _ => {}
+            }
+            return;
+        }
+    }
+
+    // Any key takes the help away again
+    if _model.show_help {
+        if let KeyPressed(_) = event {
+            _model.show_help = false;
+            return;
+        }
+    }
+
+    // We can `match` on the event to do something different depending on the kind of event.
+    match event {
+        // Keyboard events
+        // Keys on no action do nothing, modifiers pressed on their way to a
+        // chord among them
+        KeyPressed(key) => {
+            if let Some(action) = _model.keymap.action(key, _app.keys.mods) {
+                perform(_app, _model, action)
+            }
+        }
+        KeyReleased(_key) => {}
+
+        // Mouse events
+        MouseMoved(_pos) => {
+            if _model.scrubbing && !_model.revisions.is_empty() {
+                let idx = ui::revisions::revision_at(
+                    _app.window_rect(),
+                    (_pos.x, _pos.y),
+                    _model.revisions.len(),
+                );
+                show_revision(_model, idx);
+            } else if _model.scrubbing {
+                _model.scrub = ui::scrubber::position_at(
+                    _app.window_rect(),
+                    (_pos.x, _pos.y),
+                    _model.timings.elapsed().as_secs_f32(),
+                );
+            } else if let (Some(start), Some(name)) = (_model.drag_start, &_model.dragged) {
+                if past_slop(start, (_pos.x, _pos.y)) {
+                    let point = _model.camera.to_world((_pos.x, _pos.y));
+                    _model
+                        .config
+                        .fixed
+                        .entry(layout::LAYOUT_NAMES[_model.current_layout].to_owned())
+                        .or_default()
+                        .insert(name.clone(), point);
+                }
+            } else if _model.drag_start.is_some() {
+                _model.following = false;
+                _model.fitted = false;
+                _model
+                    .camera
+                    .pan((_pos.x - _model.mouse_last.0, _pos.y - _model.mouse_last.1));
+            }
+
+            _model.mouse_last = (_pos.x, _pos.y);
+            _model.mouse_moved_at = _app.time;
+        }
+        MousePressed(MouseButton::Left) => {
+            if _model.show_minimap && ui::minimap::contains(_app.window_rect(), _model.mouse_last) {
+                _model.following = false;
+                _model.fitted = false;
+                _model.camera.look_at(ui::minimap::world_at(
+                    _app.window_rect(),
+                    _model.scene.crates(),
+                    _model.mouse_last,
+                ));
+            } else if !_model.revisions.is_empty()
+                && ui::revisions::contains(_app.window_rect(), _model.mouse_last)
+            {
+                _model.scrubbing = true;
+                _model.playing = false;
+                let idx = ui::revisions::revision_at(
+                    _app.window_rect(),
+                    _model.mouse_last,
+                    _model.revisions.len(),
+                );
+                show_revision(_model, idx);
+            } else if _model.show_timeline
+                && ui::scrubber::contains(_app.window_rect(), _model.mouse_last)
+            {
+                _model.scrubbing = true;
+                _model.scrub = ui::scrubber::position_at(
+                    _app.window_rect(),
+                    _model.mouse_last,
+                    _model.timings.elapsed().as_secs_f32(),
+                );
+            } else {
+                _model.drag_start = Some(_model.mouse_last);
+                _model.dragged = _model
+                    .scene
+                    .pick(_model.camera.to_world(_model.mouse_last))
+                    .map(|draw_crate| draw_crate.tree.name.clone())
+                    .filter(|_| !details_contain(_app, _model, _model.mouse_last));
+            }
+        }
+        MousePressed(_button) => {}
+        MouseReleased(_button) => {
+            if _model.scrubbing {
+                _model.scrubbing = false;
+                return;
+            }
+
+            // The press already moved the camera
+            if _model.show_minimap && ui::minimap::contains(_app.window_rect(), _model.mouse_last) {
+                return;
+            }
+
+            let dragged = _model.dragged.take();
+            if let Some(start) = _model.drag_start.take() {
+                if past_slop(start, _model.mouse_last) {
+                    if dragged.is_some() {
+                        save_config(_model);
+                    }
+                    return;
+                }
+            }
+
+            if let Some(ref run_summary) = _model.summary {
+                if summary::export_button_contains(_model.mouse_last) {
+                    let path = summary::card_path();
+                    summary::export_card(_app, run_summary, _model.previous_run.as_ref(), &path);
+                    write_manifest(_model, &path);
+                    return;
+                }
+            }
+
+            if let Some(ref mut exploration) = _model.exploration {
+                let crumb = ui::breadcrumbs::breadcrumb_at(
+                    _app.window_rect(),
+                    exploration.trail(),
+                    &_model.config.aliases,
+                    _model.mouse_last,
+                );
+
+                if let Some(crumb) = crumb {
+                    exploration.collapse_to(crumb);
+                    return;
+                }
+            }
+
+            if let Some(lines) = selection_details(_model) {
+                let window = _app.window_rect();
+                if ui::details::contains(window, &lines, _model.mouse_last) {
+                    if let Some(url) = ui::details::link_at(window, &lines, _model.mouse_last) {
+                        if let Err(e) = actions::open_url(url) {
+                            eprintln!("{}", e);
+                        }
+                    }
+                    return;
+                }
+            }
+
+            let clicked = _model
+                .scene
+                .pick(_model.camera.to_world(_model.mouse_last))
+                .map(|draw_crate| Arc::clone(&draw_crate.tree));
+
+            // A click selects a crate; in the regular view clicking the
+            // selection again drills into it
+            match (clicked, _model.exploration.as_mut()) {
+                (Some(clicked), Some(exploration)) => {
+                    exploration.toggle(&clicked);
+                    _model.selected = Some(clicked);
+                }
+                (Some(clicked), None) => {
+                    let reselected = _model
+                        .selected
+                        .as_ref()
+                        .is_some_and(|selected| node_id(selected) == node_id(&clicked));
+
+                    if reselected {
+                        _model.active_tree = clicked;
+                        _model.selected = None;
+                    } else {
+                        _model.selected = Some(clicked);
+                    }
+                }
+                (None, _) => _model.selected = None,
+            }
+        }
+        MouseWheel(_amount, _phase) => {
+            let steps = match _amount {
+                MouseScrollDelta::LineDelta(_x, y) => y,
+                MouseScrollDelta::PixelDelta(pos) => pos.y as f32 / 20.0,
+            };
+
+            _model
+                .camera
+                .zoom_at(_model.mouse_last, ZOOM_STEP.powf(steps));
+            _model.fitted = false;
+        }
+        MouseEntered => {}
+        MouseExited => {}
+
+        // Touch events
+        Touch(_touch) => {}
+        TouchPressure(_pressure) => {}
+
+        // Window events
+        Moved(_pos) => {}
+        Resized(_size) if _model.fitted => fit_to_window(_app, _model),
+        Resized(_size) => {}
+        HoveredFile(_path) => {}
+        DroppedFile(_path) => {}
+        HoveredFileCancelled => {}
+        Focused => {}
+        Unfocused => {}
+        Closed => {}
+    }
+}
+
+pub fn model(_app: &App) -> Model {
+    _app.new_window()
+        .event(event)
+        .raw_event(raw_event)
+        .view(view)
+        .build()
+        .unwrap();
+
+    let receiver = EVENT_RECEIVER.lock().unwrap().take().unwrap();
+    // A broken config could be what crashed us, so don't even read it
+    let safe_mode = safe_mode::begin();
+    let low_power = OPTIONS.low_power || power::on_battery();
+    _app.set_loop_mode(loop_mode(low_power));
+    let (config, first_run) = if safe_mode {
+        (Config::safe(), false)
+    } else {
+        let saved_config = config::load();
+        let first_run = saved_config.is_none();
+        (saved_config.unwrap_or_default(), first_run)
+    };
+    let aggregate_depth = if safe_mode {
+        OPTIONS.aggregate_depth.or(Some(DEFAULT_AGGREGATE_DEPTH))
+    } else {
+        OPTIONS.aggregate_depth
+    };
+
+    let revisions = if *HISTORY.lock().unwrap() {
+        let mut revisions = project::lockfile_history(config.dev_dependencies, &config.color_rules)
+            .unwrap_or_else(|e| panic!("Failed to read the history of Cargo.lock: {}", e));
+        revisions
+            .iter_mut()
+            .for_each(|revision| revision.tree = OPTIONS.prune(&revision.tree));
+        revisions
+    } else {
+        Vec::new()
+    };
+
+    let compared = COMPARE.lock().unwrap().clone();
+    let comparison = Some(compared)
+        .filter(|paths| !paths.is_empty())
+        .map(|paths| compare_projects(&paths, &config));
+
+    let against = DIFF_AGAINST.lock().unwrap().clone();
+    let shown = match (against, &OPTIONS.load) {
+        (Some(against), _) => {
+            let diff = diff_against(&against, &config);
+            let export = unbuilt(&diff.tree);
+            Some((export, format!("changes since {}", against), Some(diff)))
+        }
+        // From the oldest on
+        (None, _) if !revisions.is_empty() => {
+            Some((unbuilt(&revisions[0].tree), HISTORY_TITLE.to_owned(), None))
+        }
+        (None, _) if comparison.is_some() => {
+            let comparison = comparison.as_ref().unwrap();
+            Some((
+                unbuilt(&comparison.tree),
+                format!("comparing {}", comparison.projects.join(", ")),
+                None,
+            ))
+        }
+        (None, Some(path)) => {
+            let mut loaded =
+                export::load(path).unwrap_or_else(|e| panic!("Failed to load build: {}", e));
+            loaded.tree = OPTIONS.prune(&loaded.tree);
+            Some((loaded, format!("loaded from {}", path.display()), None))
+        }
+        (None, None) => None,
+    };
+
+    if let Some((loaded, title, diff)) = shown {
+        let durations = loaded
+            .durations
+            .into_iter()
+            .map(|(name, secs)| (name, Duration::from_secs_f32(secs)))
+            .collect::<HashMap<_, _>>();
+
+        // The names the build was shown with, unless ours say otherwise
+        let mut config = config;
+        for (name, alias) in loaded.aliases {
+            config.aliases.entry(name).or_insert(alias);
+        }
+
+        let crate_graph = CrateGraph::new(&loaded.tree);
+        let mut log = RingBuffer::new(LOG_LINES);
+        loaded.log.into_iter().for_each(|line| log.push(line));
+
+        let mut hud = ui::hud::Hud::new(title, &loaded.tree, None);
+        for name in loaded.completed.iter() {
+            hud.observe(&BuildEvent::Completed(name.clone()));
+        }
+        for name in loaded.failed.iter() {
+            hud.observe(&BuildEvent::Failed(name.clone()));
+        }
+        hud.observe(&BuildEvent::Phase(loaded.phase));
+
+        return Model {
+            tree: Arc::new(DependencyTree::new(Arc::clone(&loaded.tree))),
+            dag: None,
+            mouse_last: (0.0, 0.0),
+            mouse_moved_at: 0.0,
+            drag_start: None,
+            dragged: None,
+            camera: Camera::new(),
+            fitted: true,
+            placer: Placer::new(layout::with_sizing(
+                satellite::Sizing::by_name(&config.satellite_sizing).unwrap_or_default(),
+            )),
+            current_layout: layout::index_of(OPTIONS.layout.as_deref().unwrap_or(&config.layout)),
+            size_metric: SizeMetric::Dependencies,
+            sized_nodes: false,
+            show_arrows: false,
+            show_bundles: false,
+            positions: Positions::default(),
+            animation: Animation::default(),
+            aggregate_depth,
+            exploration: None,
+            selected: None,
+            pinned: BTreeSet::new(),
+            following: false,
+            metadata: HashMap::new(),
+            categories: category::classify_tree(&loaded.tree, &HashMap::new()),
+            color_by_category: false,
+            category_filter: HashSet::new(),
+            color_by_license: false,
+            licenses: LicenseSummary::default(),
+            show_outdated: false,
+            outdated: HashMap::new(),
+            outdated_lookup: None,
+            metadata_refresh: None,
+            listings: HashMap::new(),
+            listing_lookup: None,
+            show_breakdown: false,
+            show_minimap: false,
+            grouping: Grouping::Off,
+            groups: HashMap::new(),
+            widest_antichain: crate_graph.widest_antichain(),
+            crate_graph,
+            show_parallelism: false,
+            utilization: loaded.utilization,
+            show_utilization: false,
+            crate_warnings: HashMap::new(),
+            warning_history: HashMap::new(),
+            show_warning_trends: false,
+            search: None,
+            filter_panel: None,
+            note_editor: None,
+            filter: None,
+            filter_text: String::new(),
+            filter_hides: false,
+            hidden_members: BTreeSet::new(),
+            filtered: None,
+            filter_matches: None,
+            active_tree: loaded.tree,
+            completed: loaded.completed.into_iter().collect(),
+            active: loaded.active.into_iter().collect(),
+            failed: loaded.failed.into_iter().collect(),
+            fresh: HashSet::new(),
+            live: false,
+            attached_to: None,
+            replaying: false,
+            units: Units::default(),
+            phase: loaded.phase,
+            theme: theme::by_name(&config.theme, &config.themes),
+            timings: Timings::restore(
+                Duration::from_secs_f32(loaded.elapsed_secs),
+                durations,
+                loaded.warnings,
+            ),
+            summary: None,
+            previous_run: None,
+            hud,
+            show_hud: false,
+            keymap: Keymap::new(&config.keys),
+            show_help: false,
+            recorder: None,
+            log,
+            config,
+            wizard: None,
+            timeline: loaded.timeline,
+            show_timeline: false,
+            scrub: None,
+            scrubbing: false,
+            safe_mode,
+            saves_session: false,
+            low_power,
+            power_checked_at: 0.0,
+            active_checked_at: 0.0,
+            scene: Scene::new(Vec::new(), Vec::new(), Vec::new()),
+            scene_key: None,
+            receiver,
+            diff,
+            hide_removed: false,
+            comparison,
+            playing: !revisions.is_empty(),
+            revisions,
+            revision: 0,
+            revision_since: 0.0,
+            advisories: HashMap::new(),
+            audit: start_audit(),
+            emitter: None,
+            notifier: None,
+            build: None,
+            watcher: None,
+            resetting: Vec::new(),
+            reset_since: 0.0,
+        };
+    }
+
+    let parsed_tree = project_tree(&CARGO_COMMAND.lock().unwrap(), &config);
+    let metadata = if config.enrichment && !low_power {
+        metadata::load()
+    } else {
+        HashMap::new()
+    };
+    let crate_graph = CrateGraph::new(&parsed_tree);
+    let resumed = RESUMED.lock().unwrap().take();
+    let attached_to = resumed
+        .as_ref()
+        .map(|session| session.cargo_pid)
+        .or(*ATTACHED.lock().unwrap());
+    let previous_run = if config.history {
+        summary::load_previous()
+    } else {
+        None
+    };
+    let notifier = Notifier::configured(OPTIONS.notify, OPTIONS.notify_sound, &config);
+    let emitter = OPTIONS.emit_events.as_ref().map(|path| {
+        Emitter::create(
+            path,
+            &parsed_tree,
+            previous_run.as_ref().map(|run| run.total_secs),
+        )
+    });
+    // What cargo was started with, however we came to be watching it
+    let command = attached_to
+        .and_then(active::command_line)
+        .or_else(|| COMMAND.lock().unwrap().clone())
+        .unwrap_or_default();
+
+    let build = BUILD.lock().unwrap().take();
+
+    let mut model = Model {
+        tree: Arc::new(DependencyTree::new(Arc::clone(&parsed_tree))),
+        dag: None,
+        mouse_last: (0.0, 0.0),
+        mouse_moved_at: 0.0,
+        drag_start: None,
+        dragged: None,
+        camera: Camera::new(),
+        fitted: true,
+        placer: Placer::new(layout::with_sizing(
+            satellite::Sizing::by_name(&config.satellite_sizing).unwrap_or_default(),
+        )),
+        current_layout: layout::index_of(OPTIONS.layout.as_deref().unwrap_or(&config.layout)),
+        size_metric: SizeMetric::Dependencies,
+        sized_nodes: false,
+        show_arrows: false,
+        show_bundles: false,
+        positions: Positions::default(),
+        animation: Animation::default(),
+        aggregate_depth,
+        exploration: if OPTIONS.explore {
+            Some(Exploration::new(Arc::clone(&parsed_tree)))
+        } else {
+            None
+        },
+        selected: None,
+        pinned: BTreeSet::new(),
+        following: false,
+        categories: category::classify_tree(&parsed_tree, &metadata),
+        metadata,
+        color_by_category: false,
+        category_filter: HashSet::new(),
+        color_by_license: false,
+        licenses: LicenseSummary::default(),
+        show_outdated: false,
+        outdated: HashMap::new(),
+        outdated_lookup: None,
+        metadata_refresh: None,
+        listings: HashMap::new(),
+        listing_lookup: None,
+        show_breakdown: false,
+        show_minimap: false,
+        grouping: Grouping::Off,
+        groups: HashMap::new(),
+        widest_antichain: crate_graph.widest_antichain(),
+        crate_graph,
+        show_parallelism: false,
+        utilization: Utilization::default(),
+        show_utilization: false,
+        crate_warnings: HashMap::new(),
+        warning_history: if config.history {
+            warnings::load_history()
+        } else {
+            HashMap::new()
+        },
+        show_warning_trends: false,
+        search: None,
+        filter_panel: None,
+        note_editor: None,
+        filter: None,
+        filter_text: String::new(),
+        filter_hides: false,
+        hidden_members: BTreeSet::new(),
+        filtered: None,
+        filter_matches: None,
+        active_tree: Arc::clone(&parsed_tree),
+        completed: HashSet::<_>::new(),
+        active: HashSet::<_>::new(),
+        failed: HashSet::<_>::new(),
+        fresh: HashSet::<_>::new(),
+        live: true,
+        units: Units::default(),
+        attached_to,
+        replaying: *REPLAYING.lock().unwrap(),
+        phase: BuildPhase::Waiting,
+        theme: theme::by_name(&config.theme, &config.themes),
+        timings: Timings::new(),
+        summary: None,
+        hud: ui::hud::Hud::new(
+            command,
+            &parsed_tree,
+            previous_run.as_ref().map(|run| run.total_secs),
+        ),
+        show_hud: false,
+        keymap: Keymap::new(&config.keys),
+        show_help: false,
+        previous_run,
+        // Recording renders offscreen on top of the window, which is one more
+        // thing that can go wrong
+        recorder: OPTIONS
+            .record
+            .as_ref()
+            .filter(|_| !safe_mode)
+            .map(|path| Recorder::start(_app, path, OPTIONS.scale)),
+        log: RingBuffer::new(LOG_LINES),
+        wizard: if first_run {
+            _app.set_exit_on_escape(false);
+            Some(ui::wizard::Wizard::new(config.clone()))
+        } else {
+            None
+        },
+        config,
+        timeline: Timeline::default(),
+        show_timeline: false,
+        scrub: None,
+        scrubbing: false,
+        safe_mode,
+        saves_session: !safe_mode,
+        low_power,
+        power_checked_at: 0.0,
+        active_checked_at: 0.0,
+        scene: Scene::new(Vec::new(), Vec::new(), Vec::new()),
+        scene_key: None,
+        receiver,
+        diff: None,
+        hide_removed: false,
+        comparison: None,
+        revisions: Vec::new(),
+        revision: 0,
+        playing: false,
+        revision_since: 0.0,
+        advisories: HashMap::new(),
+        audit: start_audit(),
+        emitter,
+        notifier,
+        watcher: build
+            .as_ref()
+            .filter(|_| OPTIONS.watch)
+            .map(|_| watch::start()),
+        build,
+        resetting: Vec::new(),
+        reset_since: 0.0,
+    };
+
+    if model.saves_session {
+        if let Some(session) = session::load() {
+            restore_session(&mut model, session);
+        }
+    }
+
+    // Catches up on what happened while nothing was watching
+    if let Some(session) = resumed {
+        model.timings = Timings::resume(session.elapsed);
+        for (at, event) in session.events {
+            apply_event(&mut model, event, at);
+        }
+    }
+
+    model
+}
+
+fn current_session(_model: &Model) -> Session {
+    Session {
+        camera: Some((_model.camera.offset, _model.camera.scale)),
+        layout: Some(layout::LAYOUT_NAMES[_model.current_layout].to_owned()),
+        aggregate_depth: _model.aggregate_depth,
+        explored: _model.exploration.as_ref().map(|exploration| {
+            exploration
+                .trail()
+                .iter()
+                .map(|crumb| crumb.name.clone())
+                .collect()
+        }),
+        filter: Some(_model.filter_text.clone()).filter(|_| _model.filter.is_some()),
+        filter_hides: _model.filter_hides,
+        categories: _model
+            .category_filter
+            .iter()
+            .map(|category| category.label().to_owned())
+            .collect(),
+        hidden_members: _model.hidden_members.clone(),
+    }
+}
+
+// What was given on the command line still goes over what was left
+fn restore_session(_model: &mut Model, session: Session) {
+    if let Some((offset, scale)) = session.camera {
+        _model.camera = Camera { offset, scale };
+        _model.fitted = false;
+    }
+    if let (Some(ref layout), None) = (session.layout, &OPTIONS.layout) {
+        _model.current_layout = layout::index_of(layout);
+    }
+    if OPTIONS.aggregate_depth.is_none() {
+        _model.aggregate_depth = session.aggregate_depth;
+    }
+
+    // The same crates again, as long as they are still where they were
+    if let Some(explored) = session.explored {
+        let mut exploration = Exploration::new(Arc::clone(&_model.active_tree));
+        for name in explored.iter().skip(1) {
+            let next = exploration
+                .trail()
+                .iter()
+                .rev()
+                .flat_map(|crumb| crumb.children.iter())
+                .find(|child| child.name == *name)
+                .cloned();
+            match next {
+                Some(node) => exploration.toggle(&node),
+                None => break,
+            }
+        }
+        _model.exploration = Some(exploration);
+    }
+
+    let categories = &session.categories;
+    if let Some((text, filter)) = session.filter.and_then(|text| {
+        let filter = Filter::parse(&text).ok()?;
+        Some((text, filter))
+    }) {
+        _model.filter_text = text;
+        _model.filter = Some(filter);
+        _model.filter_hides = session.filter_hides;
+    }
+
+    _model.category_filter = ALL_CATEGORIES
+        .iter()
+        .filter(|category| categories.contains(category.label()))
+        .copied()
+        .collect();
+    _model.hidden_members = session.hidden_members;
+}
+
+// For the selected crate if it failed, otherwise the first one that did
+fn write_issue_report(_model: &Model) {
+    let name = match _model.selected {
+        Some(ref selected) if _model.failed.contains(&selected.name) => selected.name.clone(),
+        _ => match _model.failed.iter().min() {
+            Some(name) => name.clone(),
+            None => {
+                println!("Nothing failed to build");
+                return;
+            }
+        },
+    };
+
+    let path = issue_report::write(&name, &_model.tree, &_model.metadata, &_model.log);
+    println!("Saved an issue report for {} to {}", name, path.display());
+    write_manifest(_model, &path);
+}
+
+fn write_manifest(_model: &Model, exported: &Path) {
+    if OPTIONS.export_manifest {
+        let path = manifest::write_beside(exported, &_model.metadata, &OPTIONS.cargo_args);
+        println!("Saved the build manifest to {}", path.display());
+    }
+}
+
+fn write_report(_model: &Model) {
+    if let Some(ref path) = OPTIONS.report {
+        report::write(
+            path,
+            _model.phase,
+            &_model.timings,
+            &category::category_stats(&_model.categories, &_model.timings),
+            &_model.config.aliases,
+        );
+        write_manifest(_model, path);
+    }
+}
+
+// What the filter gets to know about a node `depth` down the tree
+fn crate_facts<'a>(_model: &'a Model, node: &'a TreeNode, depth: usize) -> Facts<'a> {
+    let package = crate::unit_graph::owner(&node.name);
+    let state = if _model.active.contains(&node.name) {
+        "active"
+    } else if _model.failed.contains(package) {
+        "failed"
+    } else if _model.completed.contains(package) {
+        "completed"
+    } else if _model.fresh.contains(package) {
+        "fresh"
+    } else {
+        "pending"
+    };
+    let info = _model.metadata.get(package);
+
+    Facts {
+        node,
+        depth,
+        state,
+        build_time: _model
+            .timings
+            .durations
+            .get(package)
+            .map(Duration::as_secs_f32),
+        category: _model
+            .categories
+            .get(&node.name)
+            .copied()
+            .unwrap_or(Category::Other)
+            .label(),
+        license: info.and_then(|info| info.license.as_deref()),
+        version: info.map(|info| info.version.as_str()),
+        dependencies: shown_tree(_model).transitive_count(node),
+    }
+}
+
+// The tree laid out and drawn, which is what the filter leaves of it when
+// hiding the rest
+fn shown_root(_model: &Model) -> &Arc<TreeNode> {
+    match _model.filtered {
+        Some((_, ref root)) => root,
+        None => &_model.active_tree,
+    }
+}
+
+// The filtering stage before layout, redone only when something the filter
+// could ask about changed, since the nodes it makes are new every time
+fn refresh_filter(_model: &mut Model) {
+    let filter = _model.filter.as_ref().filter(|_| _model.filter_hides);
+    if filter.is_none() && _model.hidden_members.is_empty() {
+        _model.filtered = None;
+        return;
+    }
+
+    let key = FilterKey {
+        text: filter.map_or_else(String::new, |_| _model.filter_text.clone()),
+        hidden_members: _model.hidden_members.clone(),
+        root: node_id(&_model.active_tree),
+        completed: _model.completed.len(),
+        active: _model.active.len(),
+        failed: _model.failed.len(),
+        durations: _model.timings.durations.len(),
+        metadata: _model.metadata.len(),
+    };
+    if _model
+        .filtered
+        .as_ref()
+        .is_some_and(|(from, _)| *from == key)
+    {
+        return;
+    }
+
+    let root = filter::without_members(&_model.active_tree, &_model.hidden_members);
+    let root = match filter {
+        Some(filter) => filter::retain(&root, &|node, depth| {
+            filter.matches(&crate_facts(_model, node, depth))
+        }),
+        None => root,
+    };
+    _model.filtered = Some((key, root));
+}
+
+fn match_filter(_model: &Model) -> Option<HashSet<usize>> {
+    fn visit(
+        _model: &Model,
+        filter: &Filter,
+        node: &Arc<TreeNode>,
+        depth: usize,
+        into: &mut HashSet<usize>,
+    ) {
+        if filter.matches(&crate_facts(_model, node, depth)) {
+            into.insert(node_id(node));
+        }
+        for child in node.children.iter() {
+            visit(_model, filter, child, depth + 1, into);
+        }
+    }
+
+    let filter = _model.filter.as_ref()?;
+    let mut matches = HashSet::new();
+    visit(_model, filter, shown_root(_model), 0, &mut matches);
+    Some(matches)
+}
+
+// Asks for the tree to be placed again, the positions come back in
+// `update` once they're done
+fn place(_app: &App, _model: &mut Model) {
+    let weights: HashMap<String, f32> = match _model.size_metric {
+        SizeMetric::Dependencies => HashMap::new(),
+        SizeMetric::BuildTime => _model
+            .timings
+            .durations
+            .iter()
+            .map(|(name, duration)| (name.clone(), duration.as_secs_f32()))
+            .collect(),
+        SizeMetric::SourceSize => _model
+            .metadata
+            .iter()
+            .map(|(name, info)| (name.clone(), info.source_bytes as f32))
+            .collect(),
+        SizeMetric::SourceLines => _model
+            .metadata
+            .iter()
+            .map(|(name, info)| (name.clone(), info.source_lines as f32))
+            .collect(),
+    };
+
+    let request = Request {
+        tree: Arc::clone(_model.dag.as_ref().unwrap_or(&_model.tree)),
+        root: Arc::clone(shown_root(_model)),
+        layout: _model.current_layout,
+        aggregate_depth: _model.aggregate_depth,
+        expanded: _model
+            .exploration
+            .as_ref()
+            .map(|exploration| exploration.expanded().clone()),
+        time: _app.time,
+        metric: _model.size_metric,
+        weights,
+        fixed: fixed_points(_model),
+        sized_nodes: _model.sized_nodes,
+    };
+    _model.placer.place(request);
+}
+
+// Everything cargo compiled this build, whether or not it had warnings
+fn rebuilt(_model: &Model) -> impl Iterator<Item = &String> {
+    _model.timings.durations.keys().chain(
+        _model
+            .crate_warnings
+            .keys()
+            .filter(move |name| !_model.timings.durations.contains_key(*name)),
+    )
+}
+
+fn save_warning_history(_model: &Model) {
+    if _model.live && !_model.replaying && _model.config.history {
+        warnings::save_history(
+            &_model.warning_history,
+            &_model.crate_warnings,
+            rebuilt(_model),
+        );
+    }
+}
+
+fn save_export(_model: &Model) {
+    if let Some(ref path) = OPTIONS.save {
+        export::save(
+            path,
+            &BuildExport::new(
+                Arc::clone(_model.tree.root()),
+                _model.phase,
+                &_model.completed,
+                &_model.active,
+                &_model.failed,
+                &_model.timings,
+                &_model.log,
+                &_model.timeline,
+                &_model.utilization,
+                &_model.config.aliases,
+            ),
+        );
+        write_manifest(_model, path);
+    }
+}
+
+fn building(_model: &Model) -> bool {
+    !matches!(_model.phase, BuildPhase::Finished | BuildPhase::Failed)
+}
+
+fn cancel_build(_model: &Model) {
+    if let Some(ref build) = _model.build {
+        if building(_model) && !build.is_cancelled() {
+            build.cancel();
+        }
+    }
+}
+
+// Starts over with nothing built yet, stopping the build first if it is still
+// going. Only for a cargo we started ourselves.
+fn restart_build(_model: &mut Model) {
+    if _model.build.is_none() {
+        return;
+    }
+    cancel_build(_model);
+
+    let (sender, receiver) = channel();
+    let cargo_command = CARGO_COMMAND.lock().unwrap().clone();
+    let cargo_args = OPTIONS.cargo_args.clone();
+    _model.build = Some(monitor::spawn(
+        cargo_command,
+        cargo_args,
+        sender,
+        _model.build.take(),
+    ));
+    _model.receiver = receiver;
+    _model.completed.clear();
+    _model.active.clear();
+    _model.failed.clear();
+    _model.fresh.clear();
+    _model.crate_warnings.clear();
+    _model.units = Units::default();
+    _model.phase = BuildPhase::Waiting;
+    _model.timings = Timings::new();
+    _model.timeline = Timeline::default();
+    _model.utilization = Utilization::default();
+    _model.summary = None;
+    _model.hud.restart();
+    _model.scrub = None;
+    _model.scene_key = None;
+    _model.resetting.clear();
+    _model.log.push("restarting the build".to_owned());
+}
+
+// What --watch does on a change. Crates the change doesn't touch stay done,
+// the rest go back to pending from the changed ones up.
+fn rebuild(_app: &App, _model: &mut Model, changed: Vec<String>) {
+    if _model.build.is_none() {
+        return;
+    }
+
+    let completed = std::mem::take(&mut _model.completed);
+    // Saves in quick succession each start a build over the last, which
+    // waits for the one before it to stop, see `monitor::spawn`
+    restart_build(_model);
+    _model.completed = completed;
+
+    _model.resetting = watch::affected(_model.tree.root(), &changed);
+    _model.resetting.reverse();
+    _model.reset_since = _app.time - RESET_STEP_SECS;
+}
+
+// Seconds into the build, which for replayed events is well before now
+fn apply_event(_model: &mut Model, event: BuildEvent, at: f32) {
+    _model.hud.observe(&event);
+
+    match event {
+        BuildEvent::Phase(phase) => {
+            _model.phase = phase;
+
+            if phase == BuildPhase::Finished {
+                _model.timings.finish();
+
+                let run_summary = RunSummary::from_timings(&_model.timings);
+                if _model.config.history && !_model.replaying {
+                    summary::save(&run_summary);
+                }
+                _model.summary = Some(run_summary);
+                save_export(_model);
+                write_report(_model);
+                save_warning_history(_model);
+            } else if phase == BuildPhase::Failed {
+                _model.timings.finish();
+                save_export(_model);
+                write_report(_model);
+                save_warning_history(_model);
+            }
+        }
+        BuildEvent::Started(started_crate) => {
+            _model
+                .timeline
+                .push(at, TimelineEvent::Started(started_crate.clone()));
+            // Otherwise what is building comes from the rustcs running
+            if _model.replaying {
+                _model.active.insert(started_crate.clone());
+            }
+            let at = _model.timings.at(at);
+            _model.timings.start(started_crate, at);
+        }
+        BuildEvent::Completed(completed_crate) => {
+            _model
+                .timeline
+                .push(at, TimelineEvent::Completed(completed_crate.clone()));
+            let at = _model.timings.at(at);
+            _model.timings.complete(&completed_crate, at);
+            _model.active.remove(&completed_crate);
+            _model.completed.insert(completed_crate);
+        }
+        BuildEvent::Failed(failed_crate) => {
+            _model
+                .timeline
+                .push(at, TimelineEvent::Failed(failed_crate.clone()));
+            _model.active.remove(&failed_crate);
+            _model.failed.insert(failed_crate);
+        }
+        BuildEvent::Fresh(fresh_crate) => {
+            _model.fresh.insert(fresh_crate);
+        }
+        BuildEvent::Warning => _model.timings.warnings += 1,
+        BuildEvent::CrateWarnings(name, count) => {
+            *_model.crate_warnings.entry(name).or_insert(0) += count
+        }
+        BuildEvent::Log(line) => _model.log.push(line),
+    }
+}
+
+pub fn update(_app: &App, _model: &mut Model, _update: Update) {
+    if _model.live && !_model.replaying {
+        if _app.time - _model.active_checked_at > ACTIVE_CHECK {
+            _model.active_checked_at = _app.time;
+
+            let running = match _model.attached_to {
+                Some(pid) => active::get_units(pid as usize),
+                None => active::get_active(),
+            };
+            _model.active = running
+                .iter()
+                .map(|unit| match unit.kind {
+                    UnitKind::BuildScript if OPTIONS.unit_graph => build_script_node(&unit.name),
+                    _ => unit.name.clone(),
+                })
+                .collect();
+            _model
+                .units
+                .update(running, _model.timings.elapsed().as_secs_f32());
+        }
+
+        if _model.timings.build_end.is_none() {
+            _model
+                .utilization
+                .record(_model.timings.elapsed().as_secs_f32(), &_model.active);
+        }
+    } else if _model.replaying && _model.timings.build_end.is_none() {
+        _model
+            .utilization
+            .record(_model.timings.elapsed().as_secs_f32(), &_model.active);
+    }
+
+    while let Ok(event) = _model.receiver.try_recv() {
+        if let Some(ref mut emitter) = _model.emitter {
+            emitter.emit(&event);
+        }
+        if let Some(ref mut notifier) = _model.notifier {
+            notifier.observe(&event);
+        }
+        let at = _model.timings.elapsed().as_secs_f32();
+        apply_event(_model, event, at);
+    }
+
+    look_up_listing(_model);
+
+    if let Some(latest) = _model
+        .outdated_lookup
+        .as_ref()
+        .and_then(|lookup| lookup.try_recv().ok())
+    {
+        _model.outdated_lookup = None;
+        _model.outdated = _model
+            .metadata
+            .iter()
+            .filter_map(|(name, info)| {
+                let latest = latest.get(info.published_name.as_ref()?)?;
+                Some((name.clone(), latest.clone()))
+                    .filter(|_| outdated::is_outdated(&info.version, latest))
+            })
+            .collect();
+    }
+
+    if let Some(changed) = _model.watcher.as_ref().and_then(|watcher| {
+        watcher.try_iter().reduce(|mut all, more| {
+            all.extend(more);
+            all
+        })
+    }) {
+        rebuild(_app, _model, changed);
+    }
+    if !_model.resetting.is_empty() && _app.time - _model.reset_since >= RESET_STEP_SECS {
+        for name in _model.resetting.pop().unwrap_or_default() {
+            _model.completed.remove(&name);
+        }
+        _model.reset_since = _app.time;
+    }
+
+    if let Some((metadata, categories)) = _model
+        .metadata_refresh
+        .as_ref()
+        .and_then(|refresh| refresh.try_recv().ok())
+    {
+        _model.metadata_refresh = None;
+        _model.metadata = metadata;
+        _model.categories = categories;
+        if _model.color_by_license {
+            _model.licenses = license_summary(_model);
+        }
+    }
+
+    if let Some(audited) = _model
+        .audit
+        .as_ref()
+        .and_then(|audit| audit.try_recv().ok())
+    {
+        _model.audit = None;
+        match audited {
+            Ok(advisories) => _model.advisories = advisories,
+            Err(e) => {
+                eprintln!("Audit failed: {}", e);
+                _model.log.push(format!("audit failed: {}", e));
+            }
+        }
+    }
+
+    if _model.playing && _app.time - _model.revision_since >= REVISION_SECS {
+        _model.revision_since = _app.time;
+        if _model.revision + 1 < _model.revisions.len() {
+            show_revision(_model, _model.revision + 1);
+        } else {
+            _model.playing = false;
+        }
+    }
+
+    _model.timings.expire_stale(STALE_START);
+
+    if !OPTIONS.low_power && _app.time - _model.power_checked_at > POWER_CHECK {
+        _model.power_checked_at = _app.time;
+        set_low_power(_app, _model, power::on_battery());
+    }
+
+    // The scene is built from every new placement, on whatever else has
+    // changed by then too
+    if let Some(placed) = _model.placer.placed() {
+        _model.positions = _model
+            .animation
+            .step(&placed.root, placed.positions, _app.time);
+        _model.filter_matches = match_filter(_model);
+        _model.scene = build_scene(_app, _model);
+        _model.groups = hulls::groups(shown_root(_model), _model.grouping, &_model.metadata);
+    }
+
+    let key = scene_key(_app, _model);
+    if _model.scene_key.as_ref() != Some(&key) && !_model.placer.busy() {
+        refresh_filter(_model);
+        place(_app, _model);
+        _model.scene_key = Some(key);
+    }
+
+    if _model.following {
+        follow_selection(_app, _model);
+    }
+
+    record_frame(_app, _model);
+}
+
+// cargo audit fetches the advisory database first, so it runs alongside
+fn start_audit() -> Option<Receiver<Result<Advisories, String>>> {
+    if !OPTIONS.audit {
+        return None;
+    }
+
+    let (sender, receiver) = channel();
+    thread::spawn(move || sender.send(audit::load(OPTIONS.audit_report.as_deref())));
+    Some(receiver)
+}
+
+fn loop_mode(low_power: bool) -> LoopMode {
+    if low_power {
+        LoopMode::rate_fps(LOW_POWER_FPS)
+    } else {
+        LoopMode::refresh_sync()
+    }
+}
+
+// On battery we draw less often, keep running crates still and look nothing
+// up beyond the tree. Enrichment skipped for that catches up once plugged in.
+fn set_low_power(_app: &App, _model: &mut Model, low_power: bool) {
+    if low_power == _model.low_power {
+        return;
+    }
+
+    _model.low_power = low_power;
+    _app.set_loop_mode(loop_mode(low_power));
+
+    if !low_power
+        && _model.live
+        && _model.config.enrichment
+        && _model.metadata.is_empty()
+        && _model.metadata_refresh.is_none()
+    {
+        refresh_metadata(_model);
+    }
+}
+
+// Like `load_metadata`, without holding up drawing while cargo metadata runs
+fn refresh_metadata(_model: &mut Model) {
+    let root = Arc::clone(_model.tree.root());
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        let metadata = metadata::load();
+        let categories = category::classify_tree(&root, &metadata);
+        sender.send((metadata, categories))
+    });
+    _model.metadata_refresh = Some(receiver);
+}
+
+fn load_metadata(_model: &mut Model) {
+    _model.metadata = metadata::load();
+    _model.categories = category::classify_tree(_model.tree.root(), &_model.metadata);
+    if _model.color_by_license {
+        _model.licenses = license_summary(_model);
+    }
+}
+
+fn license_summary(_model: &Model) -> LicenseSummary {
+    let licenses = _model
+        .metadata
+        .iter()
+        .filter_map(|(name, info)| Some((name.clone(), info.license.clone()?)))
+        .collect();
+
+    _model.tree.licenses(&licenses)
+}
+
+// Versions are looked up once, in the background, for the crates.io crates in
+// the tree
+fn toggle_outdated(_model: &mut Model) {
+    _model.show_outdated = !_model.show_outdated;
+    if !_model.show_outdated || !_model.outdated.is_empty() || _model.outdated_lookup.is_some() {
+        return;
+    }
+
+    if _model.live && _model.metadata.is_empty() {
+        load_metadata(_model);
+    }
+
+    let names = _model
+        .metadata
+        .iter()
+        .filter(|(name, _)| _model.categories.contains_key(*name))
+        .filter_map(|(_, info)| info.published_name.clone())
+        .collect::<Vec<_>>();
+    let (sender, receiver) = channel();
+    thread::spawn(move || sender.send(outdated::latest_versions(&names, OPTIONS.offline)));
+    _model.outdated_lookup = Some(receiver);
+}
+
+// The side panel on the selected crate
+fn selection_details(_model: &Model) -> Option<Vec<ui::details::Line>> {
+    let selected = _model.selected.as_ref()?;
+    let info = _model.metadata.get(&selected.name).map(Arc::as_ref);
+    let listing = _model.listings.get(&selected.name).and_then(Option::as_ref);
+
+    Some(ui::details::lines(&selected.name, info, listing))
+}
+
+fn details_contain(_app: &App, _model: &Model, point: Point) -> bool {
+    selection_details(_model)
+        .is_some_and(|lines| ui::details::contains(_app.window_rect(), &lines, point))
+}
+
+// Asks crates.io about the selected crate in the background, once, when
+// cargo metadata has no description of it. The root of a tree read from
+// Cargo.lock could be any crate of the same name there.
+fn look_up_listing(_model: &mut Model) {
+    if let Some((name, listing)) = _model
+        .listing_lookup
+        .as_ref()
+        .and_then(|(name, lookup)| Some((name.clone(), lookup.try_recv().ok()?)))
+    {
+        _model.listing_lookup = None;
+        _model.listings.insert(name, listing);
+    }
+
+    if _model.listing_lookup.is_some() || OPTIONS.offline || !_model.config.enrichment {
+        return;
+    }
+    let selected = match _model.selected {
+        Some(ref selected) if node_id(selected) != node_id(_model.tree.root()) => selected,
+        _ => return,
+    };
+    let published_name = match _model.metadata.get(&selected.name) {
+        Some(info) if info.description.is_some() => return,
+        Some(info) => match info.published_name {
+            Some(ref published_name) => published_name.clone(),
+            None => return,
+        },
+        None => selected.name.clone(),
+    };
+    if _model.listings.contains_key(&selected.name) {
+        return;
+    }
+
+    let (sender, receiver) = channel();
+    thread::spawn(move || sender.send(crates_io::listing(&published_name)));
+    _model.listing_lookup = Some((selected.name.clone(), receiver));
+}
+
+fn copy(text: &str) {
+    match actions::copy_to_clipboard(text) {
+        Ok(()) => println!("Copied to the clipboard: {}", text),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+// The fewest crates the selected one comes in through, the way cargo tree
+// names them
+fn copy_selected_path(_model: &Model) {
+    let selected = match _model.selected {
+        Some(ref selected) => selected,
+        None => return,
+    };
+
+    let path = _model
+        .tree
+        .shortest_path(&selected.name)
+        .into_iter()
+        .map(|node| match _model.metadata.get(&node.name) {
+            Some(info) => format!("{} v{}", node.name, info.version),
+            None => node.name.clone(),
+        })
+        .collect::<Vec<_>>();
+    copy(&path.join(" -> "));
+}
+
+// A cargo tree command showing everything that depends on the selected crate
+fn copy_inverse_tree(_model: &Model) {
+    let selected = match _model.selected {
+        Some(ref selected) => selected,
+        None => return,
+    };
+
+    let spec = match _model.metadata.get(&selected.name) {
+        Some(info) => format!(
+            "{}@{}",
+            info.published_name.as_ref().unwrap_or(&selected.name),
+            info.version
+        ),
+        None => selected.name.clone(),
+    };
+    copy(&format!("cargo tree -i {}", spec));
+}
+
+// The selected crate's source in the editor
+fn open_selected_source(_model: &Model) {
+    let selected = match _model.selected {
+        Some(ref selected) => selected,
+        None => return,
+    };
+
+    let opened = match _model.metadata.get(&selected.name) {
+        Some(info) => actions::open_in_editor(&info.directory),
+        None => Err(format!("Don't know where {} is", selected.name)),
+    };
+    if let Err(e) = opened {
+        eprintln!("{}", e);
+    }
+}
+
+// The selected crate's docs.rs page, for the version in the tree when cargo
+// metadata says which
+fn open_selected_docs(_model: &Model) {
+    let selected = match _model.selected {
+        Some(ref selected) => selected,
+        None => return,
+    };
+
+    let opened = match _model.metadata.get(&selected.name) {
+        Some(info) => match info.published_name {
+            Some(ref published_name) => {
+                actions::open_url(&actions::docs_url(published_name, Some(&info.version)))
+            }
+            None => Err(format!("{} isn't on crates.io", selected.name)),
+        },
+        None => actions::open_url(&actions::docs_url(&selected.name, None)),
+    };
+    if let Err(e) = opened {
+        eprintln!("{}", e);
+    }
+}
+
+// Licenses come from cargo metadata, which is fetched for this even when
+// enrichment is off or on battery, since it was asked for
+fn toggle_licenses(_model: &mut Model) {
+    _model.color_by_license = !_model.color_by_license;
+    if !_model.color_by_license {
+        return;
+    }
+
+    _model.color_by_category = false;
+    if _model.live && _model.metadata.is_empty() {
+        load_metadata(_model);
+    } else {
+        _model.licenses = license_summary(_model);
+    }
+}
+
+fn follow_selection(_app: &App, _model: &mut Model) {
+    let selected = match _model.selected {
+        Some(ref selected) => node_id(selected),
+        None => return,
+    };
+
+    let target = _model
+        .scene
+        .crates()
+        .iter()
+        .find(|draw_crate| node_id(&draw_crate.tree) == selected)
+        .map(|draw_crate| draw_crate.center);
+
+    if let Some(target) = target {
+        _model.camera.ease_towards(target, FOLLOW_EASE);
+    }
+}
+
+// One line on what the screenshot shows, for sharing it without more words
+fn screenshot_banner(_model: &Model) -> String {
+    let root = _model.tree.root();
+    let project = if root.is_workspace() {
+        project::workspace_name().unwrap_or_else(|| root.name.clone())
+    } else {
+        root.name.clone()
+    };
+    let commit = project::git_commit().map_or_else(String::new, |commit| format!(" @ {}", commit));
+
+    let phase = match _model.phase {
+        BuildPhase::Waiting => "waiting",
+        BuildPhase::Downloading => "downloading",
+        BuildPhase::Building => "building",
+        BuildPhase::Failed => "failed",
+        BuildPhase::Finished => "finished",
+    };
+    // Build scripts and the like aren't crates of the tree, and a package can
+    // be fresh and built at once, by its different targets
+    let crates = root.crates();
+    let crates = crates.iter().filter(|name| **name != WORKSPACE_ROOT);
+    let counted =
+        |set: &HashSet<String>| crates.clone().filter(|name| set.contains(**name)).count();
+    let pending = crates
+        .clone()
+        .filter(|name| {
+            ![
+                &_model.completed,
+                &_model.failed,
+                &_model.fresh,
+                &_model.active,
+            ]
+            .iter()
+            .any(|set| set.contains(**name))
+        })
+        .count();
+    format!(
+        "{}{}  |  {}  |  {:.1}s  |  {} built, {} fresh, {} building, {} failed, {} pending",
+        project,
+        commit,
+        phase,
+        _model.timings.elapsed().as_secs_f32(),
+        counted(&_model.completed),
+        counted(&_model.fresh),
+        counted(&_model.active),
+        counted(&_model.failed),
+        pending,
+    )
+}
+
+// What the window shows, without the tooltip, like recordings
+fn save_screenshot(_app: &App, _model: &Model) {
+    let draw = Draw::new();
+    draw_scene(_app, _model, &draw);
+
+    let banner = Some(screenshot_banner(_model)).filter(|_| _model.config.screenshot_banner);
+    let path = screenshot::save(_app, &draw, banner.as_deref(), OPTIONS.scale);
+    write_manifest(_model, &path);
+}
+
+fn record_frame(_app: &App, _model: &mut Model) {
+    if let Some(mut recorder) = _model.recorder.take() {
+        if _model.phase == BuildPhase::Finished || _model.phase == BuildPhase::Failed {
+            recorder.stop_soon(_app.time);
+        }
+
+        if recorder.frame_due(_app.time) {
+            let draw = Draw::new();
+            draw_scene(_app, _model, &draw);
+            recorder.capture(_app, &draw);
+        }
+
+        _model.recorder = Some(recorder);
+    }
+}
+
+fn exit(_app: &App, _model: Model) {
+    // Cargo is in a process group of its own, so it would keep going
+    cancel_build(&_model);
+    if let Some(ref path) = OPTIONS.record {
+        write_manifest(&_model, path);
+    }
+    if _model.saves_session {
+        session::save(&current_session(&_model));
+    }
+    if let Some(recorder) = _model.recorder {
+        recorder.finish();
+    }
+
+    safe_mode::end();
+}
+
+// The scene in world coordinates, before the camera is applied
+fn build_scene(_app: &App, _model: &Model) -> Scene {
+    let actually_completed = _model.completed.sub(&_model.active);
+    let scrubbed = _model.scrub.map(|at| _model.timeline.state_at(at));
+    let (completed, active, failed) = match scrubbed {
+        Some(ref state) => (&state.completed, &state.active, &state.failed),
+        None => (&actually_completed, &_model.active, &_model.failed),
+    };
+    let matches = _model
+        .search
+        .as_ref()
+        .filter(|search| !search.query().is_empty())
+        .map(|search| {
+            _model
+                .tree
+                .find(search.query(), &_model.config.aliases)
+                .into_iter()
+                .map(|node| node.name.clone())
+                .collect::<HashSet<_>>()
+        });
+    let warning_deltas = warnings::deltas(
+        &_model.warning_history,
+        &_model.crate_warnings,
+        rebuilt(_model),
+    );
+    let root = shown_root(_model);
+    let pinned = selection::pinned_paths(root, &_model.pinned);
+    let highlighted = match _model.selected {
+        Some(ref selected) => Some(selection::highlighted(root, selected)),
+        None => Some(pinned.clone()).filter(|pinned| !pinned.is_empty()),
+    };
+    // Only dims by itself, what it hides is already gone
+    let filtered = _model
+        .filter_matches
+        .as_ref()
+        .filter(|_| !_model.filter_hides);
+    let highlighted = match (highlighted, filtered) {
+        (Some(highlighted), Some(filtered)) => Some(&highlighted & filtered),
+        (highlighted, filtered) => highlighted.or_else(|| filtered.cloned()),
+    };
+
+    // Only builds have progress to show
+    let building =
+        _model.diff.is_none() && _model.comparison.is_none() && _model.revisions.is_empty();
+    let (draw_crates, draw_lines) = draw_tree(
+        root,
+        &_model.positions,
+        &DrawState {
+            completed,
+            active,
+            failed,
+            fresh: &_model.fresh,
+            highlighted: highlighted.as_ref(),
+            pinned: &pinned,
+            categories: &_model.categories,
+            color_by_category: _model.color_by_category,
+            category_filter: &_model.category_filter,
+            color_by_license: _model.color_by_license,
+            outdated: Some(&_model.outdated).filter(|_| _model.show_outdated),
+            diff: _model.diff.as_ref(),
+            hide_removed: _model.hide_removed,
+            comparison: _model.comparison.as_ref(),
+            metadata: &_model.metadata,
+            matches: matches.as_ref(),
+            warning_deltas: &warning_deltas,
+            arrows: _model.show_arrows,
+            bundles: _model.show_bundles,
+            theme: &_model.theme,
+            aliases: &_model.config.aliases,
+            units: &_model.units,
+            edge_states: building,
+        },
+    );
+
+    let draw_arcs = if building {
+        drawing::progress_arcs(&draw_crates, completed, &_model.theme)
+    } else {
+        Vec::new()
+    };
+
+    Scene::new(draw_crates, draw_lines, draw_arcs)
+}
+
+// Only what is in the window is drawn, with room to spare for labels that
+// stick out of their crates
+fn visible_world(_app: &App, _model: &Model) -> Bounds {
+    let window = _app.window_rect().pad(-CULL_MARGIN);
+    let corners = [
+        _model.camera.to_world((window.left(), window.bottom())),
+        _model.camera.to_world((window.right(), window.top())),
+    ];
+
+    Bounds::around(corners[0], 0.0).union(Bounds::around(corners[1], 0.0))
+}
+
+// nannou draws in points, which already come out crisp at whatever scale
+// factor the display has, so lines and text are only drawn bigger or smaller
+// when --scale says the display should have another
+fn line_scale(_app: &App) -> f32 {
+    OPTIONS
+        .scale
+        .map_or(1.0, |scale| scale / _app.main_window().scale_factor())
+}
+
+fn draw_dep(_app: &App, _model: &Model, draw: &draw::Draw) {
+    // A group's hull goes around all of it, on screen or not
+    if _model.grouping != Grouping::Off {
+        let (mut all_crates, mut all_lines, _) = _model.scene.to_parts();
+        _model.camera.apply(&mut all_crates, &mut all_lines);
+        hulls::draw_hulls(draw, &all_crates, &_model.groups);
+    }
+
+    let (mut tree_crates, mut tree_lines, mut tree_arcs) =
+        _model.scene.visible_parts(&visible_world(_app, _model));
+    _model.camera.apply(&mut tree_crates, &mut tree_lines);
+    _model.camera.apply_arcs(&mut tree_arcs);
+
+    let scale = line_scale(_app);
+    let mut rings = Batch::default();
+    for arc in tree_arcs.iter() {
+        let color = rgb(arc.color.0, arc.color.1, arc.color.2);
+        rings.polyline(&arc.path(), RING_WEIGHT * scale, color);
+    }
+    rings.draw(draw);
+
+    // Once per crate, next to where it first shows up
+    let mut noted = HashSet::new();
+    let notes = tree_crates
+        .iter()
+        .filter_map(|draw_crate| {
+            let note = _model.config.notes.get(&draw_crate.tree.name)?;
+            noted.insert(draw_crate.tree.name.clone()).then_some((
+                draw_crate.center,
+                draw_crate.radius,
+                note.as_str(),
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    draw_shapes(
+        draw,
+        tree_crates,
+        tree_lines,
+        &_model.theme,
+        Some(_app.time).filter(|_| !_model.low_power),
+        _model.config.state_shapes,
+        &_model.advisories,
+        scale,
+    );
+    ui::note::draw_notes(draw, &notes);
+}
+
+// Crates and edges already in screen coordinates
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn draw_shapes(
+    draw: &draw::Draw,
+    tree_crates: Vec<DrawCrate>,
+    mut tree_lines: Vec<DrawLine>,
+    theme: &Theme,
+    // The frame clock, which running crates pulse by, None to keep them still
+    time: Option<f32>,
+    // Tell states apart by shape as well as color
+    state_shapes: bool,
+    advisories: &Advisories,
+    // Of line widths and text, see `line_scale`
+    scale: f32,
+) {
+    let outline = rgb(theme.outline.0, theme.outline.1, theme.outline.2);
+    let text = rgb(theme.text.0, theme.text.1, theme.text.2);
+
+    lod::simplify(&mut tree_lines);
+
+    let mut edges = Batch::default();
+
+    let phase = time.map_or(1.0, pulse::phase);
+
+    for draw_line in tree_lines {
+        let color = srgba(draw_line.color.0, draw_line.color.1, draw_line.color.2, 127);
+
+        let (color, weight) = if draw_line.pinned {
+            (
+                srgba(draw_line.color.0, draw_line.color.1, draw_line.color.2, 255),
+                4.0 * scale,
+            )
+        } else {
+            (color, 2.0 * scale)
+        };
+
+        if draw_line.flowing {
+            for dash in pulse::dashes(&draw_line.path(), time.unwrap_or(0.0)) {
+                edges.polyline(&dash, weight, color);
+            }
+        } else {
+            edges.polyline(&draw_line.path(), weight, color);
+        }
+
+        if draw_line.arrow {
+            let (ux, uy) = draw_line.heading();
+            let (x, y) = draw_line.p2;
+            let (length, width) = (10.0 * scale, 4.0 * scale);
+
+            edges.tri(
+                (x, y),
+                (x - ux * length - uy * width, y - uy * length + ux * width),
+                (x - ux * length + uy * width, y - uy * length - ux * width),
+                color,
+            );
+        }
+    }
+
+    edges.draw(draw);
+
+    let mut glows = Batch::default();
+    let mut nodes = Batch::default();
+    let mut badges = Batch::default();
+    let mut marks = Batch::default();
+    let mut labels = Vec::new();
+    let mut warning_icons = Vec::new();
+
+    for mut draw_crate in tree_crates {
+        if let Some(active) = draw_crate.pulse {
+            draw_crate.color = pulse::blend(draw_crate.color, active, phase);
+
+            // The other shapes fill their space exactly, so only change color
+            if let (Shape::Circle, Some(_)) = (draw_crate.shape, time) {
+                draw_crate.radius = pulse::radius(draw_crate.radius, phase);
+                pulse::glow(
+                    &mut glows,
+                    draw_crate.center,
+                    draw_crate.radius,
+                    active,
+                    phase,
+                );
+            }
+        }
+
+        let color = srgba(
+            draw_crate.color.0,
+            draw_crate.color.1,
+            draw_crate.color.2,
+            127,
+        );
+        // Pinned crates keep their labels however far out the view is
+        let detail = if draw_crate.pinned {
+            lod::detail(&draw_crate).max(lod::Detail::Labelled)
+        } else {
+            lod::detail(&draw_crate)
+        };
+
+        if detail == lod::Detail::Dot {
+            nodes.square(draw_crate.center, draw_crate.radius, color);
+            continue;
+        }
+
+        // Labels may spill out of small circles, but only so far
+        let label_width = f32::max(draw_crate.radius * 2.0, MIN_LABEL_WIDTH);
+        let mut label = (draw_crate.center, (label_width, draw_crate.radius * 2.0));
+
+        // Only the space filling layouts use the other shapes, and they are
+        // drawn parents first so they go out right away
+        match draw_crate.shape {
+            Shape::Circle => nodes.circle(draw_crate.center, draw_crate.radius, color),
+            Shape::Rect { width, height } => {
+                draw.rect()
+                    .color(color)
+                    .x_y(draw_crate.center.0, draw_crate.center.1)
+                    .w_h(width, height)
+                    .stroke(outline)
+                    .stroke_weight(scale);
+
+                // Along the top, where the children leave room for it
+                label = (
+                    (
+                        draw_crate.center.0,
+                        draw_crate.center.1 + height / 2.0 - 10.0,
+                    ),
+                    (width, 20.0),
+                );
+            }
+            Shape::Wedge {
+                origin,
+                inner,
+                outer,
+                start,
+                end,
+            } => {
+                let steps = ((end - start) * 16.0).ceil().max(1.0) as usize;
+                let arc = |radius: f32, step: usize| {
+                    let angle = start + (end - start) * step as f32 / steps as f32;
+                    pt2(
+                        origin.0 + radius * angle.cos(),
+                        origin.1 + radius * angle.sin(),
+                    )
+                };
+
+                draw.polygon()
+                    .color(color)
+                    .stroke(outline)
+                    .stroke_weight(scale)
+                    .points(
+                        (0..=steps)
+                            .map(|step| arc(outer, step))
+                            .chain((0..=steps).rev().map(|step| arc(inner, step))),
+                    );
+            }
+        }
+
+        // A small triangle off the top right: up and red when the warnings
+        // grew, down and green when they shrank
+        if draw_crate.warning_delta != 0 {
+            let size = f32::max(4.0, draw_crate.radius * 0.3);
+            let (x, y) = (
+                draw_crate.center.0 + draw_crate.radius * 0.8,
+                draw_crate.center.1 + draw_crate.radius * 0.8,
+            );
+            let (tip, color) = if draw_crate.warning_delta > 0 {
+                (size, theme.crate_failed)
+            } else {
+                (-size, theme.completed)
+            };
+
+            badges.tri(
+                (x - size, y - tip / 2.0),
+                (x + size, y - tip / 2.0),
+                (x, y + tip / 2.0),
+                rgb(color.0, color.1, color.2),
+            );
+        }
+
+        // A triangle off the top left in the color of the worst advisory
+        // against the crate, with an exclamation mark when there is room
+        if let Some(advisory) = advisories
+            .get(&draw_crate.tree.name)
+            .and_then(|advisories| advisories.first())
+        {
+            let size = f32::max(5.0, draw_crate.radius * 0.35);
+            let (x, y) = (
+                draw_crate.center.0 - draw_crate.radius * 0.8,
+                draw_crate.center.1 + draw_crate.radius * 0.8,
+            );
+            let color = advisory.severity.color();
+
+            badges.tri(
+                (x - size, y - size * 0.8),
+                (x + size, y - size * 0.8),
+                (x, y + size * 0.9),
+                rgb(color.0, color.1, color.2),
+            );
+            if size >= 8.0 {
+                warning_icons.push(((x, y - size * 0.15), size));
+            }
+        }
+
+        if !draw_crate.units.is_empty() {
+            marks::unit_segments(&mut marks, &draw_crate, theme);
+        }
+        if !draw_crate.members.is_empty() {
+            marks::member_segments(&mut marks, &draw_crate, theme);
+        }
+
+        if state_shapes {
+            marks::mark(&mut marks, &draw_crate, theme, time.unwrap_or(0.0));
+        }
+
+        if detail >= lod::Detail::Labelled {
+            let font_size =
+                (drawing::label_font_size(draw_crate.radius) as f32 * scale).max(1.0) as u32;
+            let (center, size) = label;
+            let mut lines = vec![drawing::truncate(&draw_crate.name, size.0, font_size)];
+
+            if detail == lod::Detail::Versioned {
+                if let Some(ref info) = draw_crate.info {
+                    lines.push(drawing::truncate(&info.version, size.0, font_size));
+                }
+            }
+
+            let height = f32::max(size.1, lines.len() as f32 * drawing::line_height(font_size));
+            labels.push((lines.join("\n"), font_size, center, (size.0, height)));
+        }
+    }
+
+    glows.draw(draw);
+    nodes.draw(draw);
+    marks.draw(draw);
+    badges.draw(draw);
+
+    for ((x, y), size) in warning_icons {
+        draw.text("!")
+            .font_size(size as u32)
+            .color(BLACK)
+            .x_y(x, y)
+            .w_h(size, size);
+    }
+
+    for (label, font_size, center, size) in labels {
+        draw.text(&label)
+            .font_size(font_size)
+            .color(text)
+            .x_y(center.0, center.1)
+            .w_h(size.0, size.1);
+    }
+}
+
+fn draw_scene(_app: &App, _model: &Model, draw: &draw::Draw) {
+    let background = _model.theme.background(_model.phase);
+    draw.background()
+        .color(rgb(background.0, background.1, background.2));
+
+    draw_dep(_app, _model, draw);
+
+    if let Some(ref exploration) = _model.exploration {
+        ui::breadcrumbs::draw_breadcrumbs(
+            draw,
+            _app.window_rect(),
+            exploration.trail(),
+            &_model.config.aliases,
+        );
+    }
+
+    if _model.color_by_category || !_model.category_filter.is_empty() {
+        ui::legend::draw_legend(draw, _app.window_rect(), &_model.category_filter);
+    } else if _model.color_by_license {
+        ui::legend::draw_license_legend(draw, _app.window_rect(), &_model.licenses);
+    }
+
+    if !_model.revisions.is_empty() {
+        ui::revisions::draw_revisions(
+            draw,
+            _app.window_rect(),
+            &_model.revisions,
+            _model.revision,
+            _model.playing,
+        );
+    } else if _model.show_timeline {
+        ui::scrubber::draw_scrubber(
+            draw,
+            _app.window_rect(),
+            _model.scrub,
+            _model.timings.elapsed().as_secs_f32(),
+        );
+    }
+
+    if _model.safe_mode {
+        let window = _app.window_rect();
+        draw.text("safe mode: the last session crashed, enrichment and history are off")
+            .color(rgb(
+                _model.theme.notice.0,
+                _model.theme.notice.1,
+                _model.theme.notice.2,
+            ))
+            .x_y(0.0, window.top() - 40.0)
+            .w_h(window.w(), 20.0);
+    }
+
+    if let (false, Some(keys)) = (
+        _model.failed.is_empty(),
+        _model.keymap.keys(Action::IssueReport),
+    ) {
+        let window = _app.window_rect();
+        draw.text(&format!(
+            "{} writes an issue report for the failed crate",
+            keys
+        ))
+        .color(rgb(
+            _model.theme.notice.0,
+            _model.theme.notice.1,
+            _model.theme.notice.2,
+        ))
+        .x_y(0.0, window.top() - 60.0)
+        .w_h(window.w(), 20.0);
+    }
+
+    // The space filling layouts always size crates by the metric
+    if _model.positions.hide_edges || _model.sized_nodes {
+        let window = _app.window_rect();
+        let label = match _model.keymap.keys(Action::NextSizeMetric) {
+            Some(keys) => format!("sized by {} ({})", _model.size_metric.label(), keys),
+            None => format!("sized by {}", _model.size_metric.label()),
+        };
+        draw.text(&label)
+            .color(rgb(
+                _model.theme.text.0,
+                _model.theme.text.1,
+                _model.theme.text.2,
+            ))
+            .x_y(0.0, window.top() - 20.0)
+            .w_h(window.w(), 20.0);
+    }
+
+    if let Some(ref diff) = _model.diff {
+        let window = _app.window_rect();
+        let removed = if _model.hide_removed {
+            "shown"
+        } else {
+            "hidden"
+        };
+        let removed = match _model.keymap.keys(Action::HideRemoved) {
+            Some(keys) => format!("{} with {}", removed, keys),
+            None => removed.to_owned(),
+        };
+        draw.text(&format!(
+            "{} added, {} removed ({}), {} at other versions",
+            diff.added.len(),
+            diff.removed.len(),
+            removed,
+            diff.versions.len()
+        ))
+        .color(rgb(
+            _model.theme.text.0,
+            _model.theme.text.1,
+            _model.theme.text.2,
+        ))
+        .x_y(0.0, window.top() - 40.0)
+        .w_h(window.w(), 20.0);
+    }
+
+    if let Some(ref comparison) = _model.comparison {
+        let window = _app.window_rect();
+        let (common, shared) = (comparison.common().len(), comparison.shared().len());
+        let own = comparison
+            .projects
+            .iter()
+            .enumerate()
+            .map(|(idx, project)| format!("{} {}", project, comparison.only_in(idx).len()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let summary = if common == shared {
+            format!("{} crates in common, of their own: {}", common, own)
+        } else {
+            format!(
+                "{} crates in all of them, {} in more than one, of their own: {}",
+                common, shared, own
+            )
+        };
+        draw.text(&summary)
+            .color(rgb(
+                _model.theme.text.0,
+                _model.theme.text.1,
+                _model.theme.text.2,
+            ))
+            .x_y(0.0, window.top() - 40.0)
+            .w_h(window.w(), 20.0);
+    }
+
+    if let Some(lines) = selection_details(_model) {
+        ui::details::draw_details(draw, _app.window_rect(), &lines);
+    }
+
+    if let Some(ref panel) = _model.filter_panel {
+        ui::filter::draw_filter(
+            draw,
+            _app.window_rect(),
+            panel,
+            _model.filter_matches.as_ref().map(HashSet::len),
+            _model.filter_hides,
+        );
+    }
+
+    if let Some(ref editor) = _model.note_editor {
+        ui::note::draw_note_editor(draw, _app.window_rect(), editor);
+    }
+
+    if let Some(ref search) = _model.search {
+        let matches = shown_tree(_model)
+            .find(search.query(), &_model.config.aliases)
+            .len();
+        ui::search::draw_search(draw, _app.window_rect(), search, matches);
+    }
+
+    if _model.show_parallelism {
+        ui::parallelism::draw_parallelism(
+            draw,
+            _app.window_rect(),
+            &parallelism::compare(
+                &_model.crate_graph,
+                _model.widest_antichain,
+                parallelism::jobs(&OPTIONS.cargo_args),
+                &_model.timings,
+                &_model.timeline,
+            ),
+        );
+    }
+
+    if _model.show_utilization {
+        ui::utilization::draw_utilization(
+            draw,
+            _app.window_rect(),
+            &_model.utilization,
+            parallelism::jobs(&OPTIONS.cargo_args),
+            _model.timings.elapsed().as_secs_f32(),
+        );
+    }
+
+    if _model.show_warning_trends {
+        let deltas = warnings::deltas(
+            &_model.warning_history,
+            &_model.crate_warnings,
+            rebuilt(_model),
+        );
+        ui::warnings::draw_leaderboard(draw, _app.window_rect(), &deltas, &_model.crate_warnings);
+    }
+
+    if _model.show_breakdown {
+        ui::breakdown::draw_breakdown(
+            draw,
+            _app.window_rect(),
+            &category::category_stats(&_model.categories, &_model.timings),
+        );
+    }
+
+    if _model.show_hud {
+        ui::hud::draw_hud(
+            draw,
+            _app.window_rect(),
+            &_model.hud,
+            _model.timings.elapsed().as_secs_f32(),
+        );
+    }
+
+    if _model.show_minimap {
+        ui::minimap::draw_minimap(
+            draw,
+            _app.window_rect(),
+            _model.scene.crates(),
+            _model.camera,
+            &_model.theme,
+        );
+    }
+
+    if let Some(ref run_summary) = _model.summary {
+        summary::draw_summary(draw, run_summary, _model.previous_run.as_ref());
+    }
+
+    if let Some(ref wizard) = _model.wizard {
+        ui::wizard::draw_wizard(draw, wizard);
+    }
+
+    if _model.show_help {
+        ui::help::draw_help(draw, _app.window_rect(), &_model.keymap.help());
+    }
+}
+
+fn view(_app: &App, _model: &Model, frame: Frame) {
+    let draw = _app.draw();
+
+    draw_scene(_app, _model, &draw);
+
+    // Only on screen, recordings get no tooltips
+    if _model.drag_start.is_none() && _app.time - _model.mouse_moved_at > HOVER_DELAY {
+        let scene = &_model.scene;
+
+        if let Some(hovered) = scene.pick(_model.camera.to_world(_model.mouse_last)) {
+            ui::tooltip::draw_tooltip(
+                &draw,
+                _app.window_rect(),
+                _model.mouse_last,
+                hovered,
+                &_model.timings,
+                &_model.units,
+                &ui::tooltip::Findings {
+                    advisories: _model
+                        .advisories
+                        .get(&hovered.tree.name)
+                        .map_or(&[], Vec::as_slice),
+                    latest: _model.outdated.get(&hovered.tree.name).map(String::as_str),
+                },
+            );
+        }
+    }
+
+    draw.to_frame(_app, &frame).unwrap();
+}
@@ -0,0 +1,244 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    // Unmaintained or unsound crates, which aren't vulnerabilities as such
+    Informational,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Informational => "informational",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+
+    pub fn color(self) -> (u8, u8, u8) {
+        match self {
+            Severity::Informational => (0x80, 0x90, 0xb0),
+            Severity::Low => (0xe0, 0xc8, 0x40),
+            Severity::Medium => (0xf0, 0x90, 0x30),
+            Severity::High => (0xe0, 0x40, 0x30),
+            Severity::Critical => (0xd0, 0x20, 0x90),
+        }
+    }
+
+    // From the CVSS 3 base score, the way the NVD buckets it
+    fn of_score(score: f32) -> Self {
+        match score {
+            score if score >= 9.0 => Severity::Critical,
+            score if score >= 7.0 => Severity::High,
+            score if score >= 4.0 => Severity::Medium,
+            _ => Severity::Low,
+        }
+    }
+}
+
+// An advisory against the version of a crate the project locks
+#[derive(Debug, Clone)]
+pub struct Advisory {
+    pub id: String,
+    pub title: String,
+    pub version: String,
+    pub severity: Severity,
+    // Version requirements that are fixed, empty when nothing is yet
+    pub patched: Vec<String>,
+}
+
+// By crate name, the worst first
+pub type Advisories = HashMap<String, Vec<Advisory>>;
+
+#[derive(Deserialize)]
+struct Report {
+    vulnerabilities: Vulnerabilities,
+    #[serde(default)]
+    warnings: HashMap<String, Vec<Finding>>,
+}
+
+#[derive(Deserialize)]
+struct Vulnerabilities {
+    #[serde(default)]
+    list: Vec<Finding>,
+}
+
+#[derive(Deserialize)]
+struct Finding {
+    // Yanked crates come without one
+    advisory: Option<RawAdvisory>,
+    package: LockedPackage,
+    versions: Option<Versions>,
+}
+
+#[derive(Deserialize)]
+struct RawAdvisory {
+    id: String,
+    title: String,
+    cvss: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: String,
+}
+
+#[derive(Deserialize)]
+struct Versions {
+    #[serde(default)]
+    patched: Vec<String>,
+}
+
+// The CVSS 3 base score of a vector like CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H,
+// None when a metric is missing or unknown
+fn cvss_score(vector: &str) -> Option<f32> {
+    let metrics = vector
+        .split('/')
+        .filter_map(|metric| metric.split_once(':'))
+        .collect::<HashMap<_, _>>();
+    let changed = match *metrics.get("S")? {
+        "U" => false,
+        "C" => true,
+        _ => return None,
+    };
+
+    let impact_of = |metric| match *metrics.get(metric)? {
+        "H" => Some(0.56),
+        "L" => Some(0.22),
+        "N" => Some(0.0),
+        _ => None,
+    };
+    let base = 1.0 - (1.0 - impact_of("C")?) * (1.0 - impact_of("I")?) * (1.0 - impact_of("A")?);
+    let impact = if changed {
+        7.52 * (base - 0.029) - 3.25 * f32::powi(base - 0.02, 15)
+    } else {
+        6.42 * base
+    };
+
+    let attack_vector = match *metrics.get("AV")? {
+        "N" => 0.85,
+        "A" => 0.62,
+        "L" => 0.55,
+        "P" => 0.2,
+        _ => return None,
+    };
+    let attack_complexity = match *metrics.get("AC")? {
+        "L" => 0.77,
+        "H" => 0.44,
+        _ => return None,
+    };
+    let privileges = match (*metrics.get("PR")?, changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        _ => return None,
+    };
+    let interaction = match *metrics.get("UI")? {
+        "N" => 0.85,
+        "R" => 0.62,
+        _ => return None,
+    };
+    let exploitability = 8.22 * attack_vector * attack_complexity * privileges * interaction;
+
+    if impact <= 0.0 {
+        return Some(0.0);
+    }
+
+    let score = if changed {
+        1.08 * (impact + exploitability)
+    } else {
+        impact + exploitability
+    };
+    // Rounded up to one decimal
+    Some((score.min(10.0) * 10.0).ceil() / 10.0)
+}
+
+// From the JSON cargo audit --json prints
+pub fn parse(raw: &str) -> Result<Advisories, String> {
+    let report = serde_json::from_str::<Report>(raw).map_err(|e| e.to_string())?;
+    let vulnerabilities = report
+        .vulnerabilities
+        .list
+        .into_iter()
+        .map(|finding| (finding, None));
+    let warnings = report
+        .warnings
+        .into_values()
+        .flatten()
+        .map(|finding| (finding, Some(Severity::Informational)));
+
+    let mut advisories = Advisories::new();
+    for (finding, severity) in vulnerabilities.chain(warnings) {
+        let advisory = match finding.advisory {
+            Some(advisory) => advisory,
+            None => continue,
+        };
+
+        // RustSec doesn't rate every advisory, those are taken as medium
+        let severity = severity.unwrap_or_else(|| {
+            advisory
+                .cvss
+                .as_deref()
+                .and_then(cvss_score)
+                .map_or(Severity::Medium, Severity::of_score)
+        });
+
+        advisories
+            .entry(finding.package.name)
+            .or_default()
+            .push(Advisory {
+                id: advisory.id,
+                title: advisory.title,
+                version: finding.package.version,
+                severity,
+                patched: finding
+                    .versions
+                    .map(|versions| versions.patched)
+                    .unwrap_or_default(),
+            });
+    }
+
+    for found in advisories.values_mut() {
+        found.sort_by(|a, b| b.severity.cmp(&a.severity).then(a.id.cmp(&b.id)));
+    }
+    Ok(advisories)
+}
+
+// Audits the project's Cargo.lock against the RustSec advisory database
+// with cargo audit, which fetches the database first, or reads what an
+// earlier cargo audit --json left in `report`
+pub fn load(report: Option<&Path>) -> Result<Advisories, String> {
+    let raw = match report {
+        Some(report) => fs::read_to_string(report)
+            .map_err(|e| format!("Failed to read {}: {}", report.display(), e))?,
+        None => {
+            let output = crate::project::cargo()
+                .args(["audit", "--json"])
+                .output()
+                .map_err(|e| e.to_string())?;
+
+            // It fails when it finds anything, but still reports it
+            if output.stdout.is_empty() {
+                return Err(format!(
+                    "cargo audit failed, is it installed (cargo install cargo-audit)? {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        }
+    };
+
+    parse(&raw)
+}
@@ -0,0 +1,133 @@
+use crate::drawing::Point;
+use nannou::color::IntoLinSrgba;
+use nannou::draw;
+use nannou::prelude::*;
+
+// Segments in the circle of the given on screen radius, enough to look round
+fn segments(radius: f32) -> usize {
+    ((radius * 0.8) as usize).clamp(8, 48)
+}
+
+// Colored triangles collected over a whole frame and handed to nannou as a
+// single mesh. Drawing each circle and line on its own costs far more in
+// per-primitive overhead than the triangles themselves do.
+#[derive(Default)]
+pub struct Batch {
+    points: Vec<(Point3, LinSrgba)>,
+    indices: Vec<usize>,
+}
+
+impl Batch {
+    fn vertex(&mut self, point: Point, color: LinSrgba) -> usize {
+        self.points.push((pt3(point.0, point.1, 0.0), color));
+        self.points.len() - 1
+    }
+
+    pub fn tri(&mut self, a: Point, b: Point, c: Point, color: impl IntoLinSrgba<f32>) {
+        let color = color.into_lin_srgba();
+        let a = self.vertex(a, color);
+        let b = self.vertex(b, color);
+        let c = self.vertex(c, color);
+
+        self.indices.extend([a, b, c].iter());
+    }
+
+    // Axis aligned, centered on `center`
+    pub fn square(&mut self, center: Point, half: f32, color: impl IntoLinSrgba<f32>) {
+        let color = color.into_lin_srgba();
+        let corners = [
+            (center.0 - half, center.1 - half),
+            (center.0 + half, center.1 - half),
+            (center.0 + half, center.1 + half),
+            (center.0 - half, center.1 + half),
+        ];
+
+        self.quad(corners, color);
+    }
+
+    fn quad(&mut self, corners: [Point; 4], color: LinSrgba) {
+        let first = self.points.len();
+        for &corner in corners.iter() {
+            self.vertex(corner, color);
+        }
+
+        self.indices
+            .extend([first, first + 1, first + 2, first, first + 2, first + 3].iter());
+    }
+
+    pub fn circle(&mut self, center: Point, radius: f32, color: impl IntoLinSrgba<f32>) {
+        let color = color.into_lin_srgba();
+        let segments = segments(radius);
+        let middle = self.vertex(center, color);
+
+        for segment in 0..segments {
+            let angle = segment as f32 / segments as f32 * std::f32::consts::TAU;
+            self.vertex(
+                (
+                    center.0 + radius * angle.cos(),
+                    center.1 + radius * angle.sin(),
+                ),
+                color,
+            );
+        }
+
+        for segment in 0..segments {
+            self.indices.extend(
+                [
+                    middle,
+                    middle + 1 + segment,
+                    middle + 1 + (segment + 1) % segments,
+                ]
+                .iter(),
+            );
+        }
+    }
+
+    // Just the outline of a circle
+    pub fn ring(&mut self, center: Point, radius: f32, weight: f32, color: impl IntoLinSrgba<f32>) {
+        let segments = segments(radius);
+        let points = (0..=segments)
+            .map(|segment| {
+                let angle = segment as f32 / segments as f32 * std::f32::consts::TAU;
+                (
+                    center.0 + radius * angle.cos(),
+                    center.1 + radius * angle.sin(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        self.polyline(&points, weight, color.into_lin_srgba());
+    }
+
+    // One quad per segment, the joints are too small to see at these widths
+    pub fn polyline(&mut self, points: &[Point], weight: f32, color: impl IntoLinSrgba<f32>) {
+        let color = color.into_lin_srgba();
+
+        for segment in points.windows(2) {
+            let (a, b) = (segment[0], segment[1]);
+            let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+            let length = (dx * dx + dy * dy).sqrt();
+
+            if length <= f32::EPSILON {
+                continue;
+            }
+
+            let (nx, ny) = (-dy / length * weight / 2.0, dx / length * weight / 2.0);
+            self.quad(
+                [
+                    (a.0 + nx, a.1 + ny),
+                    (b.0 + nx, b.1 + ny),
+                    (b.0 - nx, b.1 - ny),
+                    (a.0 - nx, a.1 - ny),
+                ],
+                color,
+            );
+        }
+    }
+
+    pub fn draw(self, draw: &draw::Draw) {
+        if !self.indices.is_empty() {
+            draw.mesh().indexed_colored(self.points, self.indices);
+        }
+    }
+}
@@ -0,0 +1,5 @@
+extern crate treebuild;
+
+fn main() {
+    treebuild::cli::main();
+}
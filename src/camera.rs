@@ -0,0 +1,138 @@
+use crate::drawing::{DrawArc, DrawCrate, DrawLine, Point, Shape};
+
+const FIT_MARGIN: f32 = 0.9;
+
+// Maps the layout's world coordinates onto the window: screen = world * scale + offset
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    pub offset: Point,
+    pub scale: f32,
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        Camera {
+            offset: (0.0, 0.0),
+            scale: 1.0,
+        }
+    }
+
+    pub fn to_screen(self, point: Point) -> Point {
+        (
+            point.0 * self.scale + self.offset.0,
+            point.1 * self.scale + self.offset.1,
+        )
+    }
+
+    pub fn to_world(self, point: Point) -> Point {
+        (
+            (point.0 - self.offset.0) / self.scale,
+            (point.1 - self.offset.1) / self.scale,
+        )
+    }
+
+    pub fn pan(&mut self, delta: Point) {
+        self.offset.0 += delta.0;
+        self.offset.1 += delta.1;
+    }
+
+    // Keeps whatever is under `anchor` (in screen space) in place while zooming
+    pub fn zoom_at(&mut self, anchor: Point, factor: f32) {
+        let world = self.to_world(anchor);
+
+        self.scale *= factor;
+        self.offset = (
+            anchor.0 - world.0 * self.scale,
+            anchor.1 - world.1 * self.scale,
+        );
+    }
+
+    // Centers the window on a point in world space, keeping the zoom
+    pub fn look_at(&mut self, point: Point) {
+        self.offset = (-point.0 * self.scale, -point.1 * self.scale);
+    }
+
+    // Moves part of the way towards centering on a point, for following one
+    // smoothly over several frames
+    pub fn ease_towards(&mut self, point: Point, amount: f32) {
+        let target = (-point.0 * self.scale, -point.1 * self.scale);
+
+        self.offset = (
+            self.offset.0 + (target.0 - self.offset.0) * amount,
+            self.offset.1 + (target.1 - self.offset.1) * amount,
+        );
+    }
+
+    pub fn fit(&mut self, crates: &[DrawCrate], viewport: (f32, f32)) {
+        if crates.is_empty() {
+            return;
+        }
+
+        let (min_x, min_y, max_x, max_y) = crates.iter().fold(
+            (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+            |(min_x, min_y, max_x, max_y), draw_crate| {
+                let bounds = draw_crate.bounds();
+                (
+                    min_x.min(bounds.min.0),
+                    min_y.min(bounds.min.1),
+                    max_x.max(bounds.max.0),
+                    max_y.max(bounds.max.1),
+                )
+            },
+        );
+
+        let (width, height) = (max_x - min_x, max_y - min_y);
+        let center = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+
+        self.scale = FIT_MARGIN * f32::min(viewport.0 / width, viewport.1 / height);
+        self.offset = (-center.0 * self.scale, -center.1 * self.scale);
+    }
+
+    pub fn apply(&self, crates: &mut [DrawCrate], lines: &mut [DrawLine]) {
+        for draw_crate in crates.iter_mut() {
+            draw_crate.center = self.to_screen(draw_crate.center);
+            draw_crate.radius *= self.scale;
+            draw_crate.shape = match draw_crate.shape {
+                Shape::Circle => Shape::Circle,
+                Shape::Rect { width, height } => Shape::Rect {
+                    width: width * self.scale,
+                    height: height * self.scale,
+                },
+                Shape::Wedge {
+                    origin,
+                    inner,
+                    outer,
+                    start,
+                    end,
+                } => Shape::Wedge {
+                    origin: self.to_screen(origin),
+                    inner: inner * self.scale,
+                    outer: outer * self.scale,
+                    start,
+                    end,
+                },
+            };
+        }
+
+        for draw_line in lines.iter_mut() {
+            draw_line.p1 = self.to_screen(draw_line.p1);
+            draw_line.p2 = self.to_screen(draw_line.p2);
+            for control in draw_line.controls.iter_mut() {
+                *control = self.to_screen(*control);
+            }
+        }
+    }
+
+    pub fn apply_arcs(&self, arcs: &mut [DrawArc]) {
+        for arc in arcs.iter_mut() {
+            arc.center = self.to_screen(arc.center);
+            arc.radius *= self.scale;
+        }
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,287 @@
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CargoVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+// Before this `cargo tree` was the separate cargo-tree plugin, with its own
+// flags for the same things
+const BUILTIN_TREE: u32 = 44;
+
+impl CargoVersion {
+    // "cargo 1.76.0 (c84b36747 2024-01-18)" or "cargo 1.78.0-nightly (...)"
+    fn parse(output: &str) -> Option<Self> {
+        let version = output.split_whitespace().nth(1)?;
+        let numbers = version.split('-').next()?;
+        let mut numbers = numbers.split('.').map(|part| part.parse().ok());
+
+        Some(CargoVersion {
+            major: numbers.next()??,
+            minor: numbers.next()??,
+            patch: numbers.next().flatten().unwrap_or(0),
+        })
+    }
+
+    fn at_least(self, minor: u32) -> bool {
+        self.major > 1 || (self.major == 1 && self.minor >= minor)
+    }
+
+    pub fn has_builtin_tree(self) -> bool {
+        self.at_least(BUILTIN_TREE)
+    }
+}
+
+// The cargo that would run in `dir`, which a rust-toolchain file can make
+// different from the one on the path. None when it can't be told, in which
+// case everything assumes a recent one.
+pub fn detect(dir: &Path) -> Option<CargoVersion> {
+    let output = crate::project::cargo()
+        .arg("--version")
+        .current_dir(dir)
+        .output()
+        .ok()?;
+
+    CargoVersion::parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+// Every crate on its own line, each prefixed by its depth
+pub fn tree_args(version: Option<CargoVersion>, dev_dependencies: bool) -> Vec<&'static str> {
+    if version.is_none_or(CargoVersion::has_builtin_tree) {
+        let mut args = vec!["tree", "--prefix", "depth", "--no-dedupe"];
+        if !dev_dependencies {
+            args.push("-e=no-dev");
+        }
+        args
+    } else {
+        let mut args = vec!["tree", "--prefix-depth", "--all"];
+        if !dev_dependencies {
+            args.push("--no-dev-dependencies");
+        }
+        args
+    }
+}
+
+// Tells about anything the found cargo is too old for, once at startup
+#[cfg(feature = "gui")]
+pub fn warn_about(version: Option<CargoVersion>) {
+    let version = match version {
+        Some(version) => version,
+        None => {
+            eprintln!("treebuild: couldn't tell which cargo this is, assuming a recent one");
+            return;
+        }
+    };
+
+    if !version.has_builtin_tree() {
+        eprintln!(
+            "treebuild: cargo {}.{}.{} has no built-in `cargo tree`, falling back to the cargo-tree plugin",
+            version.major, version.minor, version.patch
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_cargo_tree_output::parse_tree;
+    use itertools::Itertools;
+
+    // What `cargo --version` prints and then `cargo tree` with the flags
+    // picked for it, for the same small project. The newest is as cargo
+    // printed it, the older ones are written out the way those print.
+    const FIXTURES: [(&str, CargoVersion, &str); 4] = [
+        (
+            "cargo 1.43.1 (8fefb4d1c 2020-04-20)",
+            CargoVersion {
+                major: 1,
+                minor: 43,
+                patch: 1,
+            },
+            "0fx v0.1.0 (/tmp/fx)
+1itertools v0.9.0
+2either v1.5.3
+1serde v1.0.106
+2serde_derive v1.0.106
+3proc-macro2 v1.0.10
+4unicode-xid v0.2.0
+3quote v1.0.3
+4proc-macro2 v1.0.10
+5unicode-xid v0.2.0
+3syn v1.0.17
+4proc-macro2 v1.0.10
+5unicode-xid v0.2.0
+4quote v1.0.3
+5proc-macro2 v1.0.10
+6unicode-xid v0.2.0
+4unicode-xid v0.2.0
+1serde_json v1.0.51
+2itoa v0.4.5
+2ryu v1.0.3
+2serde v1.0.106
+",
+        ),
+        (
+            "cargo 1.44.0 (05d080faa 2020-05-06)",
+            CargoVersion {
+                major: 1,
+                minor: 44,
+                patch: 0,
+            },
+            "0fx v0.1.0 (/tmp/fx)
+1itertools v0.9.0
+2either v1.5.3
+1serde v1.0.110
+2serde_derive v1.0.110
+3proc-macro2 v1.0.13
+4unicode-xid v0.2.0
+3quote v1.0.6
+4proc-macro2 v1.0.13
+5unicode-xid v0.2.0
+3syn v1.0.22
+4proc-macro2 v1.0.13
+5unicode-xid v0.2.0
+4quote v1.0.6
+5proc-macro2 v1.0.13
+6unicode-xid v0.2.0
+4unicode-xid v0.2.0
+1serde_json v1.0.53
+2itoa v0.4.5
+2ryu v1.0.4
+2serde v1.0.110
+",
+        ),
+        (
+            "cargo 1.78.0-nightly (7bb7b5395 2024-01-20)",
+            CargoVersion {
+                major: 1,
+                minor: 78,
+                patch: 0,
+            },
+            "0fx v0.1.0 (/tmp/fx)
+1itertools v0.9.0
+2either v1.9.0
+1serde v1.0.195
+2serde_derive v1.0.195 (proc-macro)
+3proc-macro2 v1.0.76
+4unicode-ident v1.0.12
+3quote v1.0.35
+4proc-macro2 v1.0.76
+5unicode-ident v1.0.12
+3syn v2.0.48
+4proc-macro2 v1.0.76
+5unicode-ident v1.0.12
+4quote v1.0.35
+5proc-macro2 v1.0.76
+6unicode-ident v1.0.12
+4unicode-ident v1.0.12
+1serde_json v1.0.111
+2itoa v1.0.10
+2ryu v1.0.16
+2serde v1.0.195
+",
+        ),
+        (
+            "cargo 1.95.0 (f2d3ce0bd 2026-03-21)",
+            CargoVersion {
+                major: 1,
+                minor: 95,
+                patch: 0,
+            },
+            "0fx v0.1.0 (/tmp/fx)
+1itertools v0.9.0
+2either v1.19.0
+1serde v1.0.229
+2serde_core v1.0.229
+2serde_derive v1.0.229 (proc-macro)
+3proc-macro2 v1.0.107
+4unicode-ident v1.0.26
+3quote v1.0.47
+4proc-macro2 v1.0.107
+5unicode-ident v1.0.26
+3syn v3.0.7
+4proc-macro2 v1.0.107
+5unicode-ident v1.0.26
+4quote v1.0.47
+5proc-macro2 v1.0.107
+6unicode-ident v1.0.26
+4unicode-ident v1.0.26
+1serde_json v1.0.152
+2itoa v1.0.18
+2memchr v2.8.3
+2serde_core v1.0.229
+2zmij v1.0.23
+",
+        ),
+    ];
+
+    #[test]
+    fn versions_are_read_from_what_cargo_prints() {
+        for (printed, version, _) in FIXTURES.iter() {
+            assert_eq!(CargoVersion::parse(printed), Some(*version), "{}", printed);
+        }
+
+        assert_eq!(
+            CargoVersion::parse("cargo 2.0 (abc 2030-01-01)"),
+            Some(CargoVersion {
+                major: 2,
+                minor: 0,
+                patch: 0
+            })
+        );
+        assert_eq!(CargoVersion::parse(""), None);
+        assert_eq!(CargoVersion::parse("cargo"), None);
+        assert_eq!(
+            CargoVersion::parse("error: no such command: `--version`"),
+            None
+        );
+    }
+
+    #[test]
+    fn flags_match_the_version() {
+        let [plugin, builtin, ..] = FIXTURES.map(|(_, version, _)| Some(version));
+        assert!(!plugin.unwrap().has_builtin_tree());
+        assert!(builtin.unwrap().has_builtin_tree());
+
+        assert_eq!(
+            tree_args(plugin, false),
+            ["tree", "--prefix-depth", "--all", "--no-dev-dependencies"]
+        );
+        assert_eq!(tree_args(plugin, true), ["tree", "--prefix-depth", "--all"]);
+        assert_eq!(
+            tree_args(builtin, false),
+            ["tree", "--prefix", "depth", "--no-dedupe", "-e=no-dev"]
+        );
+        assert_eq!(
+            tree_args(builtin, true),
+            ["tree", "--prefix", "depth", "--no-dedupe"]
+        );
+        // Assumed recent when it can't be told
+        assert_eq!(tree_args(None, true), tree_args(builtin, true));
+    }
+
+    #[test]
+    fn every_version_prints_a_tree_that_parses() {
+        for (printed, _, output) in FIXTURES.iter() {
+            let tree = parse_tree(output.to_string());
+            let crates = tree.crates();
+
+            assert_eq!(tree.name, "fx", "{}", printed);
+            assert!(crates.contains("serde-derive"), "{}", printed);
+            assert!(crates.contains("serde-json"), "{}", printed);
+            assert!(!crates.iter().any(|name| name.contains('(')), "{}", printed);
+            assert_eq!(
+                tree.children
+                    .iter()
+                    .map(|child| child.name.as_str())
+                    .sorted()
+                    .collect::<Vec<_>>(),
+                ["itertools", "serde", "serde-json"],
+                "{}",
+                printed
+            );
+        }
+    }
+}
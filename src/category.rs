@@ -0,0 +1,248 @@
+use crate::drawing::Color;
+#[cfg(feature = "gui")]
+use {
+    crate::package_info::PackageInfo, crate::parse_cargo_tree_output::TreeNode,
+    crate::timings::Timings, std::collections::HashMap, std::sync::Arc, std::time::Duration,
+};
+
+// Only the window classifies crates, on wasm32 every one of them is Other
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    AsyncRuntime,
+    Serialization,
+    Web,
+    Ffi,
+    ProcMacro,
+    DevTooling,
+    Other,
+}
+
+#[cfg(feature = "gui")]
+pub const ALL_CATEGORIES: [Category; 7] = [
+    Category::AsyncRuntime,
+    Category::Serialization,
+    Category::Web,
+    Category::Ffi,
+    Category::ProcMacro,
+    Category::DevTooling,
+    Category::Other,
+];
+
+impl Category {
+    #[cfg(feature = "gui")]
+    pub fn label(self) -> &'static str {
+        match self {
+            Category::AsyncRuntime => "async runtime",
+            Category::Serialization => "serialization",
+            Category::Web => "web",
+            Category::Ffi => "FFI / -sys",
+            Category::ProcMacro => "proc-macro",
+            Category::DevTooling => "dev tooling",
+            Category::Other => "other",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Category::AsyncRuntime => (0x4e, 0x9a, 0xf1),
+            Category::Serialization => (0xf1, 0xa3, 0x40),
+            Category::Web => (0x3c, 0xc4, 0xa0),
+            Category::Ffi => (0xd9, 0x5c, 0x5c),
+            Category::ProcMacro => (0xb0, 0x7c, 0xe8),
+            Category::DevTooling => (0xd8, 0xd0, 0x5a),
+            Category::Other => (0x70, 0x70, 0x78),
+        }
+    }
+}
+
+// crates.io category slugs, matched by prefix so subcategories count too
+#[cfg(feature = "gui")]
+const CATEGORY_SLUGS: [(&str, Category); 9] = [
+    ("asynchronous", Category::AsyncRuntime),
+    ("encoding", Category::Serialization),
+    ("parser-implementations", Category::Serialization),
+    ("web-programming", Category::Web),
+    ("network-programming", Category::Web),
+    ("external-ffi-bindings", Category::Ffi),
+    (
+        "development-tools::procedural-macro-helpers",
+        Category::ProcMacro,
+    ),
+    ("development-tools", Category::DevTooling),
+    ("config", Category::DevTooling),
+];
+
+#[cfg(feature = "gui")]
+const KEYWORDS: [(&str, Category); 12] = [
+    ("async", Category::AsyncRuntime),
+    ("futures", Category::AsyncRuntime),
+    ("runtime", Category::AsyncRuntime),
+    ("serde", Category::Serialization),
+    ("serialization", Category::Serialization),
+    ("json", Category::Serialization),
+    ("http", Category::Web),
+    ("web", Category::Web),
+    ("ffi", Category::Ffi),
+    ("bindings", Category::Ffi),
+    ("derive", Category::ProcMacro),
+    ("build", Category::DevTooling),
+];
+
+// For crates cargo told us nothing about (e.g. a loaded session)
+#[cfg(feature = "gui")]
+fn guess_from_name(name: &str) -> Category {
+    const ASYNC: [&str; 7] = [
+        "tokio",
+        "async-std",
+        "smol",
+        "futures",
+        "mio",
+        "async-io",
+        "async-executor",
+    ];
+    const SERIALIZATION: [&str; 7] = [
+        "serde", "bincode", "toml", "ron", "rmp", "prost", "postcard",
+    ];
+    const WEB: [&str; 8] = [
+        "hyper", "http", "reqwest", "actix", "warp", "axum", "tower", "h2",
+    ];
+    const DEV_TOOLING: [&str; 8] = [
+        "cc",
+        "cmake",
+        "bindgen",
+        "pkg-config",
+        "autocfg",
+        "version-check",
+        "rustc-version",
+        "vcpkg",
+    ];
+
+    let matches = |prefixes: &[&str]| {
+        prefixes
+            .iter()
+            .any(|prefix| name == *prefix || name.starts_with(&format!("{}-", prefix)))
+    };
+
+    if name.ends_with("-sys") {
+        Category::Ffi
+    } else if name.ends_with("-derive") || name.ends_with("-macros") || name.ends_with("-macro") {
+        Category::ProcMacro
+    } else if matches(&ASYNC) {
+        Category::AsyncRuntime
+    } else if matches(&SERIALIZATION) || name.contains("json") {
+        Category::Serialization
+    } else if matches(&WEB) {
+        Category::Web
+    } else if matches(&DEV_TOOLING) {
+        Category::DevTooling
+    } else {
+        Category::Other
+    }
+}
+
+#[cfg(feature = "gui")]
+pub fn classify(name: &str, info: Option<&PackageInfo>) -> Category {
+    if let Some(info) = info {
+        if info.proc_macro {
+            return Category::ProcMacro;
+        }
+
+        if info.links || name.ends_with("-sys") {
+            return Category::Ffi;
+        }
+
+        let by_slug = info.categories.iter().find_map(|slug| {
+            CATEGORY_SLUGS
+                .iter()
+                .find(|(prefix, _)| slug.starts_with(prefix))
+                .map(|(_, category)| *category)
+        });
+
+        let by_keyword = || {
+            info.keywords.iter().find_map(|keyword| {
+                KEYWORDS
+                    .iter()
+                    .find(|(known, _)| keyword.eq_ignore_ascii_case(known))
+                    .map(|(_, category)| *category)
+            })
+        };
+
+        if let Some(category) = by_slug.or_else(by_keyword) {
+            return category;
+        }
+    }
+
+    guess_from_name(name)
+}
+
+#[cfg(feature = "gui")]
+pub fn classify_tree(
+    tree: &TreeNode,
+    metadata: &HashMap<String, Arc<PackageInfo>>,
+) -> HashMap<String, Category> {
+    fn collect(
+        node: &TreeNode,
+        metadata: &HashMap<String, Arc<PackageInfo>>,
+        categories: &mut HashMap<String, Category>,
+    ) {
+        if !categories.contains_key(&node.name) {
+            categories.insert(
+                node.name.clone(),
+                classify(&node.name, metadata.get(&node.name).map(|info| &**info)),
+            );
+        }
+
+        for child in node.children.iter() {
+            collect(child, metadata, categories);
+        }
+    }
+
+    let mut categories = HashMap::new();
+    collect(tree, metadata, &mut categories);
+    categories
+}
+
+#[cfg(feature = "gui")]
+pub struct CategoryStats {
+    pub category: Category,
+    pub crates: usize,
+    pub compile: Duration,
+}
+
+// Where the build time went, biggest share first. Categories with no crates
+// in the tree are left out.
+#[cfg(feature = "gui")]
+pub fn category_stats(
+    categories: &HashMap<String, Category>,
+    timings: &Timings,
+) -> Vec<CategoryStats> {
+    let mut stats = ALL_CATEGORIES
+        .iter()
+        .map(|&category| CategoryStats {
+            category,
+            crates: 0,
+            compile: Duration::default(),
+        })
+        .collect::<Vec<_>>();
+
+    let index = |category: Category| {
+        ALL_CATEGORIES
+            .iter()
+            .position(|&known| known == category)
+            .unwrap()
+    };
+
+    for category in categories.values() {
+        stats[index(*category)].crates += 1;
+    }
+
+    for (name, duration) in timings.durations.iter() {
+        let category = categories.get(name).copied().unwrap_or(Category::Other);
+        stats[index(category)].compile += *duration;
+    }
+
+    stats.retain(|stat| stat.crates > 0);
+    stats.sort_by(|a, b| b.compile.cmp(&a.compile).then(b.crates.cmp(&a.crates)));
+    stats
+}
@@ -0,0 +1,190 @@
+//! The `treebuild` command, which is also `cargo treebuild`.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+
+// The ones that open the window, and so take its options too
+#[cfg(feature = "gui")]
+const WINDOW_SUBCOMMANDS: [&str; 5] = ["attach", "compare", "diff", "history", "replay"];
+
+/// The arguments after our own name. Cargo runs `cargo treebuild ARGS` as
+/// `cargo-treebuild treebuild ARGS`, and the same for every other `cargo-*`
+/// binary, so the name it passes along is skipped too.
+///
+/// A leading `+toolchain` and `--cargo PATH` anywhere before a `--` select
+/// the cargo everything runs, see [`crate::project::select_cargo`], and are
+/// taken out.
+pub fn args() -> impl Iterator<Item = String> {
+    let mut args = env::args();
+    let through_cargo = args
+        .next()
+        .and_then(|binary| {
+            Path::new(&binary)
+                .file_name()
+                .map(|name| name.to_string_lossy().starts_with("cargo-"))
+        })
+        .unwrap_or(false);
+    if through_cargo {
+        args.next();
+    }
+
+    let mut args = args.peekable();
+    let toolchain = match args.peek() {
+        Some(arg) if arg.starts_with('+') => args.next().map(|arg| arg[1..].to_owned()),
+        _ => None,
+    };
+
+    let mut program = None;
+    let mut rest = Vec::new();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            rest.push(arg);
+            rest.extend(args.by_ref());
+        } else if arg == "--cargo" {
+            program = args.next();
+        } else if let Some(path) = arg.strip_prefix("--cargo=") {
+            program = Some(path.to_owned());
+        } else {
+            rest.push(arg);
+        }
+    }
+
+    crate::project::select_cargo(program, toolchain);
+    rest.into_iter()
+}
+
+// How many of the words after one of the window's subcommands are its own
+// rather than options, e.g. the paths `compare` compares
+#[cfg(feature = "gui")]
+fn own_words(subcommand: &str, rest: &[String]) -> usize {
+    match subcommand {
+        "attach" | "diff" => rest.len().min(1),
+        "replay" => rest
+            .iter()
+            .take(1)
+            .filter(|arg| !arg.starts_with('-'))
+            .count(),
+        "compare" => rest.iter().take_while(|arg| !arg.starts_with('-')).count(),
+        _ => 0,
+    }
+}
+
+/// The window's options, which are [`args`] without the subcommand and the
+/// words it takes.
+#[cfg(feature = "gui")]
+pub fn option_args() -> impl Iterator<Item = String> {
+    let mut args = args().collect::<Vec<_>>();
+
+    if let Some(subcommand) = args.first().cloned() {
+        if WINDOW_SUBCOMMANDS.contains(&subcommand.as_str()) {
+            let words = own_words(&subcommand, &args[1..]);
+            args.drain(..=words);
+        }
+    }
+    args.into_iter()
+}
+
+/// Runs the subcommand in [`args`], or treebuild's window on the build of the
+/// project without one.
+pub fn main() {
+    let mut args = args();
+
+    match args.next().as_deref() {
+        Some("daemon") => crate::daemon::run(args.map(PathBuf::from).collect()),
+        Some("doctor") => process::exit(if crate::doctor::run_checks() { 0 } else { 1 }),
+        // e.g. `treebuild query heaviest --by build-time -n 5` in CI
+        Some("query") => crate::query::run(args.collect()),
+        // e.g. `cargo build --message-format=json > build.json` in another
+        // terminal, or `cargo build 2>&1 | treebuild attach -`
+        #[cfg(feature = "gui")]
+        Some("attach") => {
+            crate::attach(PathBuf::from(args.next().unwrap_or_else(|| "-".to_owned())))
+        }
+        // e.g. `treebuild diff HEAD~10`, `treebuild diff Cargo.lock.orig` or
+        // `treebuild diff last-week.json`
+        #[cfg(feature = "gui")]
+        Some("diff") => match args.next() {
+            Some(against) => crate::diff(against),
+            None => {
+                eprintln!("usage: treebuild diff REVISION|FILE.lock|EXPORT.json [OPTIONS] [PATH]");
+                process::exit(2);
+            }
+        },
+        #[cfg(feature = "gui")]
+        Some("history") => crate::history(),
+        // e.g. `treebuild replay build.jsonl --speed 4` for a demo, the last
+        // build treebuild followed without a file
+        #[cfg(feature = "gui")]
+        Some("replay") => crate::replay(
+            args.next()
+                .filter(|arg| !arg.starts_with('-'))
+                .map(PathBuf::from),
+        ),
+        // e.g. `treebuild compare services/api services/worker`, the options
+        // apply to every project
+        #[cfg(feature = "gui")]
+        Some("compare") => {
+            let rest = args.collect::<Vec<_>>();
+            let paths = rest[..own_words("compare", &rest)]
+                .iter()
+                .map(PathBuf::from)
+                .collect::<Vec<_>>();
+            if let Some(path) = paths.iter().find(|path| !path.is_dir()) {
+                eprintln!("error: {} isn't a directory", path.display());
+                process::exit(2);
+            }
+            if paths.len() < 2 {
+                eprintln!("usage: treebuild compare PATH PATH... [OPTIONS]");
+                process::exit(2);
+            }
+            crate::compare(paths)
+        }
+        // e.g. `treebuild serve 0.0.0.0:8080 -- --release` on a build server
+        Some("serve") => {
+            let mut args = args.peekable();
+            let addr = match args.peek() {
+                Some(addr) if !addr.starts_with('-') => args.next().unwrap(),
+                _ => crate::serve::DEFAULT_ADDR.to_owned(),
+            };
+            crate::serve::run(&addr, args.filter(|arg| arg != "--").collect())
+        }
+        // `treebuild [OPTIONS] [PATH]`, see `treebuild --help`
+        #[cfg(feature = "gui")]
+        _ => crate::launch(vec!["build"]),
+        #[cfg(not(feature = "gui"))]
+        _ => {
+            eprintln!("usage: treebuild [+TOOLCHAIN] [--cargo PATH] daemon [PROJECT_DIR...]");
+            eprintln!("       treebuild [+TOOLCHAIN] [--cargo PATH] doctor");
+            eprintln!("       treebuild [+TOOLCHAIN] [--cargo PATH] query QUERY ...");
+            eprintln!(
+                "       treebuild [+TOOLCHAIN] [--cargo PATH] serve [ADDR] [-- CARGO_ARGS...]"
+            );
+            process::exit(2);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "gui"))]
+mod tests {
+    use super::*;
+
+    fn words(subcommand: &str, rest: &str) -> usize {
+        let rest = rest
+            .split_whitespace()
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        own_words(subcommand, &rest)
+    }
+
+    #[test]
+    fn subcommands_take_their_words_and_leave_the_options() {
+        assert_eq!(words("compare", "api worker --layout force --release"), 2);
+        assert_eq!(words("compare", "api typo"), 2);
+        assert_eq!(words("diff", "HEAD~10 --explore ."), 1);
+        assert_eq!(words("attach", "- --tui"), 1);
+        assert_eq!(words("replay", "build.jsonl --speed 4"), 1);
+        assert_eq!(words("replay", "--speed 4"), 0);
+        assert_eq!(words("history", "--layout force"), 0);
+    }
+}
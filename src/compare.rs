@@ -0,0 +1,85 @@
+//! The dependency trees of several projects at once, e.g. to see what
+//! merging them into one workspace would share.
+
+use crate::palette;
+use crate::parse_cargo_tree_output::TreeNode;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+/// Every project under one root, for showing them side by side. The crates
+/// they have in common are repeated in each project's subtree, the DAG view
+/// shows them once.
+pub struct Comparison {
+    pub tree: Arc<TreeNode>,
+    /// The root crate of each project, in the order they were given
+    pub projects: Vec<String>,
+    // The projects depending on each crate, by index into `projects`
+    users: HashMap<String, BTreeSet<usize>>,
+}
+
+fn names(tree: &TreeNode, project: usize, users: &mut HashMap<String, BTreeSet<usize>>) {
+    users.entry(tree.name.clone()).or_default().insert(project);
+    for child in tree.children.iter() {
+        names(child, project, users);
+    }
+}
+
+impl Comparison {
+    /// Puts the trees under a root named after all of them.
+    pub fn new(trees: &[Arc<TreeNode>]) -> Self {
+        let mut users = HashMap::new();
+        for (idx, tree) in trees.iter().enumerate() {
+            names(tree, idx, &mut users);
+        }
+
+        let projects = trees
+            .iter()
+            .map(|tree| tree.name.clone())
+            .collect::<Vec<_>>();
+        let name = projects.join(" + ");
+
+        Comparison {
+            tree: Arc::new(TreeNode {
+                color: palette::color_for(&name),
+                name,
+                children: trees.to_vec(),
+            }),
+            projects,
+            users,
+        }
+    }
+
+    /// How many of the projects have the crate in their tree.
+    pub fn users(&self, name: &str) -> usize {
+        self.users.get(name).map_or(0, BTreeSet::len)
+    }
+
+    /// The crates more than one project has, by name.
+    pub fn shared(&self) -> BTreeSet<&str> {
+        self.users
+            .iter()
+            .filter(|(_, users)| users.len() > 1)
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// The crates every project has, by name.
+    pub fn common(&self) -> BTreeSet<&str> {
+        self.users
+            .iter()
+            .filter(|(_, users)| users.len() == self.projects.len())
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// The crates only the project at `project` has, by name.
+    pub fn only_in(&self, project: usize) -> BTreeSet<&str> {
+        let alone = BTreeSet::from([project]);
+
+        self.users
+            .iter()
+            .filter(|(_, users)| **users == alone)
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use {
+    crate::theme::{ColorRule, Theme},
+    serde::{Deserialize, Serialize},
+    std::fs,
+    std::path::PathBuf,
+};
+
+// Per-project settings, kept next to Cargo.toml so they can be shared
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub layout: String,
+    pub theme: String,
+    pub dev_dependencies: bool,
+    // Looking crates up beyond the dependency tree itself, e.g. their
+    // categories and licenses
+    pub enrichment: bool,
+    // Keeping timings and warnings around to compare against next time
+    pub history: bool,
+    // Marks build states by shape too, for when the colors are hard to tell
+    // apart
+    pub state_shapes: bool,
+    // Shorter names to show for crates, e.g. for long internal ones
+    pub aliases: HashMap<String, String>,
+    // Extra themes by name, as [themes.<name>] tables of colors
+    pub themes: HashMap<String, Theme>,
+    // As [[color_rules]] entries, checked in order
+    pub color_rules: Vec<ColorRule>,
+    // Crates dragged somewhere by hand, by layout and then crate, in the
+    // layout's coordinates
+    pub fixed: HashMap<String, HashMap<String, (f32, f32)>>,
+    // Notes shown next to crates, by crate
+    pub notes: HashMap<String, String>,
+    // A desktop notification when the build is over or a crate fails, with
+    // a sound too if notify_sound is on
+    pub notify: bool,
+    pub notify_sound: bool,
+    // child-count, name or build-time, see `ChildOrder`
+    pub sort_children_by: String,
+    // How big satellites are drawn, subtree or depth, see `Sizing`
+    pub satellite_sizing: String,
+    // As a [keys] table of action = key, e.g. search = "/", for the keys
+    // that should differ from the built-in ones
+    pub keys: HashMap<String, String>,
+    // Stamp screenshots with the project, commit, build time and how many
+    // crates are in each state
+    pub screenshot_banner: bool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            layout: "satellites".to_owned(),
+            theme: "dark".to_owned(),
+            dev_dependencies: false,
+            enrichment: true,
+            history: true,
+            state_shapes: false,
+            aliases: HashMap::new(),
+            themes: HashMap::new(),
+            color_rules: Vec::new(),
+            fixed: HashMap::new(),
+            notes: HashMap::new(),
+            notify: false,
+            notify_sound: false,
+            sort_children_by: "child-count".to_owned(),
+            satellite_sizing: "subtree".to_owned(),
+            keys: HashMap::new(),
+            screenshot_banner: true,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Config {
+    // The least that can go wrong, for starting up after a crash
+    pub fn safe() -> Self {
+        Config {
+            layout: "satellites".to_owned(),
+            enrichment: false,
+            history: false,
+            ..Config::default()
+        }
+    }
+}
+
+// What a crate is called on screen, everything else keeps the real name
+pub fn display_name<'a>(aliases: &'a HashMap<String, String>, name: &'a str) -> &'a str {
+    aliases.get(name).map_or(name, String::as_str)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn path() -> PathBuf {
+    PathBuf::from("treebuild.toml")
+}
+
+// None when the project has no config yet, which is how a first run is told
+// apart
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load() -> Option<Config> {
+    let raw = fs::read_to_string(path()).ok()?;
+
+    Some(
+        toml::from_str(&raw)
+            .unwrap_or_else(|e| panic!("Failed to parse {}: {}", path().display(), e)),
+    )
+}
+
+#[cfg(feature = "gui")]
+pub fn save(config: &Config) {
+    fs::write(
+        path(),
+        toml::to_string(config).expect("Failed to serialize config"),
+    )
+    .expect("Failed to write config");
+}
@@ -0,0 +1,49 @@
+use crate::dependency_tree::DependencyTree;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+
+const API: &str = "https://crates.io/api/v1/crates";
+// crates.io turns away API requests that don't say who is asking
+const USER_AGENT: &str = concat!("treebuild/", env!("CARGO_PKG_VERSION"));
+
+// What crates.io shows on a crate's page, for crates cargo metadata said
+// nothing about
+#[derive(Debug, Clone, Deserialize)]
+pub struct Listing {
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    pub repository: Option<String>,
+    pub documentation: Option<String>,
+}
+
+// Looked up so far by the tree's name for the crate, None for the ones
+// crates.io didn't have
+pub type Listings = HashMap<String, Option<Listing>>;
+
+#[derive(Deserialize)]
+struct Response {
+    #[serde(rename = "crate")]
+    listing: Listing,
+}
+
+// None when crates.io can't be reached or doesn't have the crate
+pub fn listing(name: &str) -> Option<Listing> {
+    let output = Command::new("curl")
+        .args(["-sf", "--max-time", "10", "-A", USER_AGENT])
+        .arg(format!("{}/{}", API, name))
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    serde_json::from_slice::<Response>(&output.stdout)
+        .ok()
+        .map(|response| response.listing)
+}
+
+// Once the tree changes, e.g. to another revision, what's no longer in it
+// won't be selected again
+pub fn forget_gone(listings: &mut Listings, tree: &DependencyTree) {
+    listings.retain(|name, _| tree.by_name(name).is_some());
+}
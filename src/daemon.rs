@@ -0,0 +1,173 @@
+use crate::cargo_version;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// A line of JSON each way, one request per connection
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Request {
+    Tree {
+        project: PathBuf,
+        dev_dependencies: bool,
+    },
+    Metadata {
+        project: PathBuf,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Response {
+    // What cargo printed to stdout
+    Output(String),
+    Failed(String),
+}
+
+impl Request {
+    fn project(&self) -> &Path {
+        match self {
+            Request::Tree { project, .. } | Request::Metadata { project } => project,
+        }
+    }
+
+    fn cargo_args(&self) -> Vec<&'static str> {
+        match self {
+            Request::Tree {
+                project,
+                dev_dependencies,
+            } => cargo_version::tree_args(cargo_version::detect(project), *dev_dependencies),
+            Request::Metadata { .. } => vec!["metadata", "--format-version", "1"],
+        }
+    }
+
+    // Asks cargo, which is what the daemon is there to save everyone else from
+    fn run(&self) -> Result<String, String> {
+        let output = crate::project::cargo()
+            .args(self.cargo_args())
+            .current_dir(self.project())
+            .output()
+            .map_err(|e| e.to_string())?;
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).into_owned())
+        }
+    }
+}
+
+pub fn socket_path() -> PathBuf {
+    let user = env::var("USER").unwrap_or_else(|_| "default".to_owned());
+    env::temp_dir().join(format!("treebuild-{}.sock", user))
+}
+
+// Both the manifest and the lock file change whenever the graph does
+fn stamp(project: &Path) -> Option<SystemTime> {
+    ["Cargo.toml", "Cargo.lock"]
+        .iter()
+        .filter_map(|name| fs::metadata(project.join(name)).ok()?.modified().ok())
+        .max()
+}
+
+struct Cached {
+    stamp: Option<SystemTime>,
+    output: String,
+}
+
+#[derive(Default)]
+struct Cache {
+    entries: HashMap<Request, Cached>,
+}
+
+impl Cache {
+    fn get(&mut self, request: &Request) -> Response {
+        let stamp = stamp(request.project());
+
+        if let Some(cached) = self.entries.get(request) {
+            if cached.stamp == stamp {
+                return Response::Output(cached.output.clone());
+            }
+        }
+
+        match request.run() {
+            Ok(output) => {
+                self.entries.insert(
+                    request.clone(),
+                    Cached {
+                        stamp,
+                        output: output.clone(),
+                    },
+                );
+                Response::Output(output)
+            }
+            Err(e) => Response::Failed(e),
+        }
+    }
+}
+
+fn serve(stream: UnixStream, cache: &mut Cache) -> Option<()> {
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line).ok()?;
+
+    let response = match serde_json::from_str::<Request>(&line) {
+        Ok(request) => cache.get(&request),
+        Err(e) => Response::Failed(format!("Bad request: {}", e)),
+    };
+
+    let mut stream = stream;
+    serde_json::to_writer(&mut stream, &response).ok()?;
+    stream.write_all(b"\n").ok()
+}
+
+// Stays up keeping cargo's answers for every project anyone asked about,
+// starting with the ones given here
+pub fn run(projects: Vec<PathBuf>) {
+    let path = socket_path();
+    let _ = fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)
+        .unwrap_or_else(|e| panic!("Failed to listen on {}: {}", path.display(), e));
+
+    let mut cache = Cache::default();
+
+    for project in projects {
+        let project = project.canonicalize().expect("No such project directory");
+
+        cache.get(&Request::Tree {
+            project: project.clone(),
+            dev_dependencies: false,
+        });
+        cache.get(&Request::Metadata { project });
+    }
+
+    eprintln!("treebuild daemon listening on {}", path.display());
+
+    for stream in listener.incoming().flatten() {
+        serve(stream, &mut cache);
+    }
+}
+
+// None when there is no daemon around, or it couldn't answer
+pub fn ask(request: &Request) -> Option<String> {
+    let mut stream = UnixStream::connect(socket_path()).ok()?;
+
+    serde_json::to_writer(&mut stream, request).ok()?;
+    stream.write_all(b"\n").ok()?;
+
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line).ok()?;
+
+    match serde_json::from_str(&line).ok()? {
+        Response::Output(output) => Some(output),
+        Response::Failed(_) => None,
+    }
+}
+
+// Asks about the package in the current directory
+pub fn ask_here(request: impl FnOnce(PathBuf) -> Request) -> Option<String> {
+    let project = env::current_dir().ok()?.canonicalize().ok()?;
+    ask(&request(project))
+}
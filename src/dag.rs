@@ -0,0 +1,30 @@
+use crate::parse_cargo_tree_output::TreeNode;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// The same tree with each crate made into a single node that all of its
+// dependents point at, which turns it into the dependency DAG it really is.
+// Everything keyed by `node_id` then sees a shared crate as one node.
+pub fn share(root: &Arc<TreeNode>) -> Arc<TreeNode> {
+    fn visit(node: &Arc<TreeNode>, shared: &mut HashMap<String, Arc<TreeNode>>) -> Arc<TreeNode> {
+        if let Some(existing) = shared.get(&node.name) {
+            return Arc::clone(existing);
+        }
+
+        let children = node
+            .children
+            .iter()
+            .map(|child| visit(child, shared))
+            .collect();
+        let node = Arc::new(TreeNode {
+            name: node.name.clone(),
+            children,
+            color: node.color,
+        });
+
+        shared.insert(node.name.clone(), Arc::clone(&node));
+        node
+    }
+
+    visit(root, &mut HashMap::new())
+}
@@ -0,0 +1,372 @@
+//! The tree `cargo tree` prints, with the way back up from every node that
+//! the nodes themselves don't have. It holds the very nodes drawing and
+//! layout walk, so the two never need converting between.
+
+use crate::drawing::node_id;
+use crate::license::{LicenseFamily, LicenseSummary};
+use crate::parse_cargo_tree_output::TreeNode;
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+// Marks the root's parent in `Node::parent`
+const NO_PARENT: u32 = u32::MAX;
+
+// A node's place in the tree, all of it by index
+struct Node {
+    tree: Arc<TreeNode>,
+    parent: u32,
+    depth: u32,
+    // Where its children start and end in `DependencyTree::children`
+    children: (u32, u32),
+}
+
+/// A crate shows up once for every crate depending on it, so names repeat,
+/// and a node is told apart from others of the same crate by `node_id`.
+///
+/// Every node is kept once, shallowest first, and addressed by its index
+/// into that order, which is what [`Dependency`] goes by. Parents, children
+/// and depths are all kept by index too, so going around the tree this way
+/// never follows a pointer into a node.
+pub struct DependencyTree {
+    nodes: Vec<Node>,
+    // Every node's children one after the other, see `Node::children`
+    children: Vec<u32>,
+    // Index by `node_id`
+    index: HashMap<usize, u32>,
+    // The shallowest node for each crate
+    first: HashMap<String, u32>,
+    // Of every node, counted the first time one is asked for
+    transitive_counts: OnceLock<Vec<u32>>,
+}
+
+/// One node of a [`DependencyTree`], cheap to copy around.
+#[derive(Clone, Copy)]
+pub struct Dependency<'a> {
+    tree: &'a DependencyTree,
+    index: u32,
+}
+
+impl<'a> Dependency<'a> {
+    fn at(self, index: u32) -> Dependency<'a> {
+        Dependency {
+            tree: self.tree,
+            index,
+        }
+    }
+
+    fn entry(self) -> &'a Node {
+        &self.tree.nodes[self.index as usize]
+    }
+
+    pub fn node(self) -> &'a Arc<TreeNode> {
+        &self.entry().tree
+    }
+
+    pub fn name(self) -> &'a str {
+        &self.node().name
+    }
+
+    /// None for the root.
+    pub fn parent(self) -> Option<Dependency<'a>> {
+        match self.entry().parent {
+            NO_PARENT => None,
+            index => Some(self.at(index)),
+        }
+    }
+
+    /// In the order the node has them. A child shared with other nodes (see
+    /// `dag::share`) is the same [`Dependency`] under each of them.
+    pub fn children(self) -> impl ExactSizeIterator<Item = Dependency<'a>> + 'a {
+        let (start, end) = self.entry().children;
+
+        self.tree.children[start as usize..end as usize]
+            .iter()
+            .map(move |&index| self.at(index))
+    }
+
+    /// How far down from the root, along the shallowest way there.
+    pub fn depth(self) -> usize {
+        self.entry().depth as usize
+    }
+}
+
+// Every pattern character has to show up in order. Runs of consecutive
+// characters and matches at the start of a word score higher, skipped
+// characters cost a little.
+fn fuzzy_score(pattern: &str, name: &str) -> Option<i32> {
+    let name = name.to_lowercase().chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+
+    for wanted in pattern.to_lowercase().chars() {
+        let found = (next..name.len()).find(|&idx| name[idx] == wanted)?;
+
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || name[found - 1] == '-' {
+            score += 3;
+        }
+        score -= (found - next) as i32;
+
+        previous = Some(found);
+        next = found + 1;
+    }
+
+    Some(score)
+}
+
+impl DependencyTree {
+    /// Links every node under `root` to its parent. A node shared by
+    /// several parents (see `dag::share`) goes back up to the shallowest.
+    pub fn new(root: Arc<TreeNode>) -> Self {
+        let mut tree = DependencyTree {
+            nodes: Vec::new(),
+            children: Vec::new(),
+            index: HashMap::new(),
+            first: HashMap::new(),
+            transitive_counts: OnceLock::new(),
+        };
+        tree.index.insert(node_id(&root), 0);
+        tree.first.insert(root.name.clone(), 0);
+        tree.nodes.push(Node {
+            tree: root,
+            parent: NO_PARENT,
+            depth: 0,
+            children: (0, 0),
+        });
+
+        // Breadth first, so every level comes after the one above it, and
+        // each node's children are listed right after the node before it
+        let mut next = 0;
+        while next < tree.nodes.len() {
+            let node = Arc::clone(&tree.nodes[next].tree);
+            let depth = tree.nodes[next].depth + 1;
+            let start = tree.children.len() as u32;
+
+            for child in node.children.iter() {
+                let index = match tree.index.get(&node_id(child)) {
+                    Some(&index) => index,
+                    None => {
+                        let index = tree.nodes.len() as u32;
+                        tree.index.insert(node_id(child), index);
+                        tree.first.entry(child.name.clone()).or_insert(index);
+                        tree.nodes.push(Node {
+                            tree: Arc::clone(child),
+                            parent: next as u32,
+                            depth,
+                            children: (0, 0),
+                        });
+                        index
+                    }
+                };
+                tree.children.push(index);
+            }
+
+            tree.nodes[next].children = (start, tree.children.len() as u32);
+            next += 1;
+        }
+
+        tree
+    }
+
+    fn at(&self, index: u32) -> Dependency<'_> {
+        Dependency { tree: self, index }
+    }
+
+    /// How many nodes there are, a node shared by several parents once.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Never, there is always the root.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Whether any node is a child of more than one, as in the trees
+    /// `dag::share` makes. A tree straight from `cargo tree` has none.
+    pub fn is_shared(&self) -> bool {
+        self.children.len() + 1 > self.nodes.len()
+    }
+
+    /// Every node once, shallowest first, the root first of all.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = Dependency<'_>> {
+        (0..self.nodes.len() as u32).map(move |index| self.at(index))
+    }
+
+    /// The handle on a node of this tree.
+    pub fn get(&self, node: &Arc<TreeNode>) -> Option<Dependency<'_>> {
+        Some(self.at(*self.index.get(&node_id(node))?))
+    }
+
+    /// None for the root.
+    pub fn parent(&self, node: &Arc<TreeNode>) -> Option<&Arc<TreeNode>> {
+        Some(self.get(node)?.parent()?.node())
+    }
+
+    /// The node `offset` places over among its parent's children, wrapping
+    /// around at either end
+    pub fn sibling(&self, node: &Arc<TreeNode>, offset: isize) -> Option<&Arc<TreeNode>> {
+        let node = self.get(node)?;
+        let siblings = node.parent()?.children().collect::<Vec<_>>();
+        let idx = siblings
+            .iter()
+            .position(|sibling| sibling.index == node.index)?;
+        let len = siblings.len() as isize;
+
+        Some(siblings[(idx as isize + offset).rem_euclid(len) as usize].node())
+    }
+
+    pub fn root(&self) -> &Arc<TreeNode> {
+        &self.nodes[0].tree
+    }
+
+    /// [`TreeNode::transitive_count`] of a node of this tree, which is only
+    /// worked out once for all of them, so it can be asked over and over, e.g.
+    /// by filters. None for nodes of other trees.
+    pub fn transitive_count(&self, node: &TreeNode) -> Option<usize> {
+        // The same address `node_id` gives for the node's Arc
+        let index = *self.index.get(&(node as *const TreeNode as usize))?;
+        let counts = self.transitive_counts.get_or_init(|| {
+            self.nodes
+                .iter()
+                .map(|node| node.tree.transitive_count() as u32)
+                .collect()
+        });
+
+        Some(counts[index as usize] as usize)
+    }
+
+    /// The first node for a crate, shallowest first
+    pub fn by_name(&self, name: &str) -> Option<&Arc<TreeNode>> {
+        Some(self.at(*self.first.get(name)?).node())
+    }
+
+    /// The nodes from the root down to the first node for a crate, both
+    /// included, so the fewest crates it is pulled in through. Empty when the
+    /// crate isn't in the tree.
+    pub fn shortest_path(&self, name: &str) -> Vec<&Arc<TreeNode>> {
+        let first = self.first.get(name).map(|&index| self.at(index));
+        let mut path = std::iter::successors(first, |found| found.parent())
+            .map(Dependency::node)
+            .collect::<Vec<_>>();
+
+        path.reverse();
+        path
+    }
+
+    /// One node per matching crate, the shallowest, best match first. A
+    /// crate matches by its alias as well as its name.
+    pub fn find(&self, pattern: &str, aliases: &HashMap<String, String>) -> Vec<Arc<TreeNode>> {
+        if pattern.is_empty() {
+            return Vec::new();
+        }
+
+        self.first
+            .iter()
+            .filter_map(|(name, &index)| {
+                let alias_score = aliases
+                    .get(name)
+                    .and_then(|alias| fuzzy_score(pattern, alias));
+                let score = fuzzy_score(pattern, name).max(alias_score)?;
+                Some((name, score, index))
+            })
+            .sorted_by(|(a_name, a, _), (b_name, b, _)| b.cmp(a).then(a_name.cmp(b_name)))
+            .map(|(_, _, index)| Arc::clone(self.at(index).node()))
+            .collect()
+    }
+
+    /// Every crate in the tree by its license and by the family of it, given
+    /// each crate's license expression, as [`project::licenses`] gets them.
+    /// Crates missing from `licenses` count as unknown.
+    ///
+    /// [`project::licenses`]: crate::project::licenses
+    pub fn licenses(&self, licenses: &HashMap<String, String>) -> LicenseSummary {
+        let mut summary = LicenseSummary::default();
+
+        for name in self.first.keys() {
+            let license = licenses.get(name);
+            summary
+                .by_license
+                .entry(license.cloned().unwrap_or_else(|| "none".to_owned()))
+                .or_default()
+                .insert(name.clone());
+            summary
+                .by_family
+                .entry(LicenseFamily::of(license.map(String::as_str)))
+                .or_default()
+                .insert(name.clone());
+        }
+
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, children: Vec<Arc<TreeNode>>) -> Arc<TreeNode> {
+        Arc::new(TreeNode {
+            name: name.to_owned(),
+            children,
+            color: (0, 0, 0),
+        })
+    }
+
+    #[test]
+    fn children_come_in_order_and_shared_ones_once() {
+        let shared = node("shared", Vec::new());
+        let deep = node("deep", vec![Arc::clone(&shared)]);
+        let root = node("root", vec![deep, Arc::clone(&shared)]);
+        let tree = DependencyTree::new(Arc::clone(&root));
+
+        assert_eq!(tree.len(), 3);
+        let names = |of: Dependency| {
+            of.children()
+                .map(|child| child.name().to_owned())
+                .collect::<Vec<_>>()
+        };
+        let root = tree.get(&root).unwrap();
+        assert_eq!(names(root), ["deep", "shared"]);
+        assert_eq!(names(root.children().next().unwrap()), ["shared"]);
+
+        // Back up along the shallowest way there
+        let shared = tree.get(&shared).unwrap();
+        assert_eq!(shared.depth(), 1);
+        assert_eq!(shared.parent().map(Dependency::name), Some("root"));
+    }
+
+    #[test]
+    fn siblings_wrap_around() {
+        let (a, b, c) = (
+            node("a", Vec::new()),
+            node("b", Vec::new()),
+            node("c", Vec::new()),
+        );
+        let tree = DependencyTree::new(node("root", vec![Arc::clone(&a), b, Arc::clone(&c)]));
+
+        assert_eq!(tree.sibling(&a, -1).map(|n| n.name.as_str()), Some("c"));
+        assert_eq!(tree.sibling(&c, 1).map(|n| n.name.as_str()), Some("a"));
+        assert_eq!(tree.sibling(tree.root(), 1).map(|n| n.name.as_str()), None);
+    }
+
+    #[test]
+    fn transitive_counts_are_those_of_the_nodes() {
+        let shared = node("shared", vec![node("leaf", Vec::new())]);
+        let deep = node("deep", vec![Arc::clone(&shared)]);
+        let root = node("root", vec![Arc::clone(&deep), Arc::clone(&shared)]);
+        let tree = DependencyTree::new(Arc::clone(&root));
+
+        for node in [&root, &deep, &shared] {
+            assert_eq!(tree.transitive_count(node), Some(node.transitive_count()));
+        }
+        assert_eq!(tree.transitive_count(&root), Some(3));
+        // A lookalike from elsewhere isn't counted
+        assert_eq!(tree.transitive_count(&node("deep", Vec::new())), None);
+    }
+}
@@ -0,0 +1,229 @@
+//! What changed between two dependency trees of a project, e.g. before and
+//! after an upgrade.
+
+use crate::parse_cargo_tree_output::TreeNode;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// How a crate fared from the old tree to the new one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    /// Locked at other versions, from the old ones to the new
+    VersionChanged(Vec<String>, Vec<String>),
+    Unchanged,
+}
+
+/// Both trees in one. The new tree has the crates that are gone hanging where
+/// they used to be, so the crates the two have in common are laid out the
+/// same whether or not those are shown.
+pub struct TreeDiff {
+    pub tree: Arc<TreeNode>,
+    pub added: HashSet<String>,
+    pub removed: HashSet<String>,
+    /// The versions locked before and after, of the crates in both that moved
+    pub versions: HashMap<String, (Vec<String>, Vec<String>)>,
+}
+
+fn names(tree: &TreeNode, into: &mut HashSet<String>) {
+    into.insert(tree.name.clone());
+    for child in tree.children.iter() {
+        names(child, into);
+    }
+}
+
+// The part of an old subtree that is gone, crates that are still around
+// having moved on to wherever the new tree has them
+fn ghost(old: &Arc<TreeNode>, removed: &HashSet<String>) -> Arc<TreeNode> {
+    Arc::new(TreeNode {
+        name: old.name.clone(),
+        color: old.color,
+        children: old
+            .children
+            .iter()
+            .filter(|child| removed.contains(&child.name))
+            .map(|child| ghost(child, removed))
+            .collect(),
+    })
+}
+
+// The new node's children, those of the old node that are gone slotted in
+// after the sibling they followed before
+fn merge(new: &Arc<TreeNode>, old: &Arc<TreeNode>, removed: &HashSet<String>) -> Arc<TreeNode> {
+    let mut children = new
+        .children
+        .iter()
+        .map(
+            |child| match old.children.iter().find(|old| old.name == child.name) {
+                Some(old_child) => merge(child, old_child, removed),
+                None => Arc::clone(child),
+            },
+        )
+        .collect::<Vec<_>>();
+
+    for (idx, old_child) in old.children.iter().enumerate() {
+        if !removed.contains(&old_child.name) {
+            continue;
+        }
+
+        let after = old.children[..idx]
+            .iter()
+            .rev()
+            .find_map(|before| children.iter().position(|child| child.name == before.name));
+        children.insert(
+            after.map_or(0, |after| after + 1),
+            ghost(old_child, removed),
+        );
+    }
+
+    Arc::new(TreeNode {
+        name: new.name.clone(),
+        color: new.color,
+        children,
+    })
+}
+
+impl TreeDiff {
+    /// Compares two trees, along with the versions each locks by crate name,
+    /// which may be empty when they aren't known.
+    pub fn new(
+        old: &Arc<TreeNode>,
+        new: &Arc<TreeNode>,
+        old_versions: &HashMap<String, Vec<String>>,
+        new_versions: &HashMap<String, Vec<String>>,
+    ) -> Self {
+        let (mut old_names, mut new_names) = (HashSet::new(), HashSet::new());
+        names(old, &mut old_names);
+        names(new, &mut new_names);
+
+        let removed = &old_names - &new_names;
+        let versions = old_names
+            .intersection(&new_names)
+            .filter_map(|name| {
+                let (before, after) = (old_versions.get(name)?, new_versions.get(name)?);
+                Some((name.clone(), (before.clone(), after.clone()))).filter(|_| before != after)
+            })
+            .collect();
+
+        TreeDiff {
+            tree: merge(new, old, &removed),
+            added: &new_names - &old_names,
+            removed,
+            versions,
+        }
+    }
+
+    pub fn change(&self, name: &str) -> Change {
+        if self.added.contains(name) {
+            Change::Added
+        } else if self.removed.contains(name) {
+            Change::Removed
+        } else if let Some((before, after)) = self.versions.get(name) {
+            Change::VersionChanged(before.clone(), after.clone())
+        } else {
+            Change::Unchanged
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, children: Vec<Arc<TreeNode>>) -> Arc<TreeNode> {
+        Arc::new(TreeNode {
+            name: name.to_owned(),
+            children,
+            color: (0, 0, 0),
+        })
+    }
+
+    fn leaf(name: &str) -> Arc<TreeNode> {
+        node(name, Vec::new())
+    }
+
+    // The tree in one line in its order, every crate followed by what's
+    // below it
+    fn render(node: &TreeNode) -> String {
+        if node.children.is_empty() {
+            return node.name.clone();
+        }
+
+        let children = node.children.iter().map(|child| render(child));
+        format!("{}({})", node.name, children.collect::<Vec<_>>().join(" "))
+    }
+
+    fn versions(of: &[(&str, &str)]) -> HashMap<String, Vec<String>> {
+        of.iter()
+            .map(|&(name, version)| (name.to_owned(), vec![version.to_owned()]))
+            .collect()
+    }
+
+    #[test]
+    fn removed_crates_hang_where_they_were() {
+        // a goes along with x, m moves from under a to under b, and c takes
+        // on y
+        let old = node(
+            "app",
+            vec![
+                node("a", vec![leaf("x"), leaf("m")]),
+                leaf("b"),
+                node("c", vec![leaf("z")]),
+            ],
+        );
+        let new = node(
+            "app",
+            vec![
+                node("b", vec![leaf("m")]),
+                node("c", vec![leaf("z"), leaf("y")]),
+                leaf("d"),
+            ],
+        );
+        let diff = TreeDiff::new(&old, &new, &HashMap::new(), &HashMap::new());
+
+        assert_eq!(render(&diff.tree), "app(a(x) b(m) c(z y) d)");
+        assert_eq!(
+            diff.removed,
+            ["a", "x"].iter().map(|name| name.to_string()).collect()
+        );
+        assert_eq!(
+            diff.added,
+            ["d", "y"].iter().map(|name| name.to_string()).collect()
+        );
+    }
+
+    #[test]
+    fn removed_crates_go_after_the_sibling_they_followed() {
+        let old = node("app", vec![leaf("a"), leaf("gone"), leaf("b")]);
+        let new = node("app", vec![leaf("b"), leaf("new"), leaf("a")]);
+        let diff = TreeDiff::new(&old, &new, &HashMap::new(), &HashMap::new());
+
+        assert_eq!(render(&diff.tree), "app(b new a gone)");
+    }
+
+    #[test]
+    fn every_crate_has_a_change() {
+        let old = node("app", vec![leaf("log"), leaf("rand"), leaf("time")]);
+        let new = node("app", vec![leaf("log"), leaf("rand"), leaf("serde")]);
+        let diff = TreeDiff::new(
+            &old,
+            &new,
+            &versions(&[("log", "0.4.8"), ("rand", "0.7.3"), ("time", "0.1.0")]),
+            &versions(&[("log", "0.4.11"), ("rand", "0.7.3"), ("serde", "1.0.0")]),
+        );
+
+        assert_eq!(
+            diff.change("log"),
+            Change::VersionChanged(vec!["0.4.8".to_owned()], vec!["0.4.11".to_owned()])
+        );
+        assert_eq!(diff.change("rand"), Change::Unchanged);
+        assert_eq!(diff.change("time"), Change::Removed);
+        assert_eq!(diff.change("serde"), Change::Added);
+        // Without versions to go by a crate in both is the same
+        assert_eq!(
+            TreeDiff::new(&old, &new, &HashMap::new(), &HashMap::new()).change("log"),
+            Change::Unchanged
+        );
+    }
+}
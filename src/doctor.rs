@@ -0,0 +1,343 @@
+use crate::cargo_version;
+use crate::config::{self, Config};
+use crate::parse_cargo_tree_output::{parse_tree, TreeNode};
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+
+// Discrepancies listed before the rest are only counted
+const LISTED: usize = 10;
+
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+    resolve: Option<Resolve>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Resolve {
+    nodes: Vec<Node>,
+}
+
+#[derive(Deserialize)]
+struct Node {
+    id: String,
+    #[serde(default)]
+    deps: Vec<Dep>,
+}
+
+#[derive(Deserialize)]
+struct Dep {
+    pkg: String,
+    #[serde(default)]
+    dep_kinds: Vec<DepKind>,
+}
+
+#[derive(Deserialize)]
+struct DepKind {
+    kind: Option<String>,
+}
+
+type Edge = (String, String);
+
+struct Report {
+    failures: usize,
+}
+
+impl Report {
+    fn ok(&mut self, what: &str, detail: &str) {
+        println!("ok    {}: {}", what, detail);
+    }
+
+    fn warn(&mut self, what: &str, detail: &str) {
+        println!("warn  {}: {}", what, detail);
+    }
+
+    fn fail(&mut self, what: &str, detail: &str) {
+        println!("FAIL  {}: {}", what, detail);
+        self.failures += 1;
+    }
+
+    fn listed(&mut self, what: &str, items: &BTreeSet<String>) {
+        if items.is_empty() {
+            return;
+        }
+
+        self.fail(what, &format!("{}", items.len()));
+        for item in items.iter().take(LISTED) {
+            println!("        {}", item);
+        }
+        if items.len() > LISTED {
+            println!("        and {} more", items.len() - LISTED);
+        }
+    }
+}
+
+fn run(mut command: Command, args: &[&str]) -> Result<String, String> {
+    let output = command.args(args).output().map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_owned())
+    }
+}
+
+fn check_toolchain(report: &mut Report) -> Option<String> {
+    match run(crate::project::cargo(), &["--version"]) {
+        Ok(version) => report.ok("cargo", version.trim()),
+        Err(e) => {
+            report.fail("cargo", &format!("not runnable ({})", e));
+            return None;
+        }
+    }
+
+    match cargo_version::detect(Path::new(".")) {
+        Some(version) if !version.has_builtin_tree() => report.warn(
+            "cargo tree",
+            "not built in, the cargo-tree plugin will be used",
+        ),
+        Some(_) => report.ok("cargo tree", "built in"),
+        None => report.warn("cargo tree", "couldn't tell the cargo version apart"),
+    }
+
+    match run(Command::new("rustc"), &["-vV"]) {
+        Ok(version) => {
+            report.ok("rustc", version.lines().next().unwrap_or(""));
+            version
+                .lines()
+                .find_map(|line| line.strip_prefix("host: "))
+                .map(str::to_owned)
+        }
+        Err(e) => {
+            report.fail("rustc", &format!("not runnable ({})", e));
+            None
+        }
+    }
+}
+
+fn check_config(report: &mut Report) -> Config {
+    let path = config::path();
+
+    match fs::read_to_string(&path) {
+        Err(_) => {
+            report.ok(
+                "config",
+                &format!("no {}, using the defaults", path.display()),
+            );
+            Config::default()
+        }
+        Ok(raw) => match toml::from_str::<Config>(&raw) {
+            Ok(config) => {
+                report.ok("config", &format!("{} parses", path.display()));
+                config
+            }
+            Err(e) => {
+                report.fail("config", &format!("{}: {}", path.display(), e));
+                Config::default()
+            }
+        },
+    }
+}
+
+fn check_state_dir(report: &mut Report) {
+    let dir = crate::project::state_dir();
+    let probe = dir.join(".doctor");
+    let written = fs::create_dir_all(&dir)
+        .and_then(|_| fs::write(&probe, b""))
+        .and_then(|_| fs::remove_file(&probe));
+
+    match written {
+        Ok(()) => report.ok("state directory", &format!("{} is writable", dir.display())),
+        Err(e) => report.fail("state directory", &format!("{}: {}", dir.display(), e)),
+    }
+}
+
+// The parser panics on lines it doesn't understand, which is exactly what a
+// change in cargo's output would look like
+fn check_tree(report: &mut Report, dev_dependencies: bool) -> Option<Arc<TreeNode>> {
+    let args = cargo_version::tree_args(cargo_version::detect(Path::new(".")), dev_dependencies);
+    let out = match run(crate::project::cargo(), &args) {
+        Ok(out) => out,
+        Err(e) => {
+            report.fail("cargo tree", &e);
+            return None;
+        }
+    };
+
+    match panic::catch_unwind(AssertUnwindSafe(|| parse_tree(out))) {
+        Ok(tree) => {
+            report.ok(
+                "dependency tree",
+                &format!("{} with {} crates", tree.name, tree.transitive_count()),
+            );
+            Some(tree)
+        }
+        Err(_) => {
+            report.fail("dependency tree", "cargo tree's output didn't parse");
+            None
+        }
+    }
+}
+
+// The tree repeats shared crates in full, so each is only walked once. Two
+// versions of a crate share a name but not their dependencies, which is what
+// tells them apart here.
+fn tree_graph(
+    tree: &Arc<TreeNode>,
+    walked: &mut HashSet<(String, Vec<String>)>,
+    edges: &mut HashSet<Edge>,
+) {
+    let children = tree
+        .children
+        .iter()
+        .map(|child| child.name.clone())
+        .collect();
+    if !walked.insert((tree.name.clone(), children)) {
+        return;
+    }
+
+    for child in tree.children.iter() {
+        edges.insert((tree.name.clone(), child.name.clone()));
+        tree_graph(child, walked, edges);
+    }
+}
+
+// Everything reachable from the root the way cargo tree walks it: normal and
+// build dependencies, dev ones only when asked for and only from the root
+fn metadata_graph(
+    metadata: &Metadata,
+    root: &str,
+    dev_dependencies: bool,
+) -> Option<(HashSet<String>, HashSet<Edge>)> {
+    let names = metadata
+        .packages
+        .iter()
+        .map(|package| (package.id.as_str(), package.name.replace("_", "-")))
+        .collect::<HashMap<_, _>>();
+    let nodes = metadata
+        .resolve
+        .as_ref()?
+        .nodes
+        .iter()
+        .map(|node| (node.id.as_str(), node))
+        .collect::<HashMap<_, _>>();
+    let root_id = names.iter().find(|(_, name)| *name == root)?.0;
+
+    let (mut seen, mut edges) = (HashSet::new(), HashSet::new());
+    let mut queue = vec![*root_id];
+
+    while let Some(id) = queue.pop() {
+        let is_root = id == *root_id;
+        if !seen.insert(id) {
+            continue;
+        }
+
+        for dep in nodes.get(id).map_or(&[][..], |node| &node.deps[..]) {
+            let wanted = dep.dep_kinds.iter().any(|kind| match kind.kind.as_deref() {
+                Some("dev") => dev_dependencies && is_root,
+                _ => true,
+            });
+
+            if wanted {
+                edges.insert((names[id].clone(), names[dep.pkg.as_str()].clone()));
+                queue.push(dep.pkg.as_str());
+            }
+        }
+    }
+
+    Some((seen.iter().map(|id| names[id].clone()).collect(), edges))
+}
+
+fn cross_check(report: &mut Report, tree: &Arc<TreeNode>, host: Option<&str>, dev: bool) {
+    let mut args = vec!["metadata", "--format-version", "1"];
+    if let Some(host) = host {
+        // cargo tree only follows dependencies for the host by default
+        args.extend(["--filter-platform", host]);
+    }
+
+    let metadata = match run(crate::project::cargo(), &args)
+        .map(|out| serde_json::from_str::<Metadata>(&out))
+    {
+        Ok(Ok(metadata)) => metadata,
+        Ok(Err(e)) => return report.fail("cargo metadata", &format!("didn't parse: {}", e)),
+        Err(e) => return report.fail("cargo metadata", &e),
+    };
+
+    let (expected_names, expected_edges) = match metadata_graph(&metadata, &tree.name, dev) {
+        Some(graph) => graph,
+        None => {
+            return report.fail(
+                "cargo metadata",
+                &format!("no resolved package called {}", tree.name),
+            )
+        }
+    };
+
+    let (mut walked, mut edges) = (HashSet::new(), HashSet::new());
+    tree_graph(tree, &mut walked, &mut edges);
+    let names = walked
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect::<HashSet<_>>();
+
+    let missing_names = expected_names.difference(&names).cloned().collect();
+    let extra_names = names.difference(&expected_names).cloned().collect();
+    let edge_list = |edges: Vec<&Edge>| {
+        edges
+            .into_iter()
+            .map(|(from, to)| format!("{} -> {}", from, to))
+            .collect::<BTreeSet<_>>()
+    };
+    let missing_edges = edge_list(expected_edges.difference(&edges).collect());
+    let extra_edges = edge_list(edges.difference(&expected_edges).collect());
+
+    let failures = report.failures;
+    report.listed("crates missing from the tree", &missing_names);
+    report.listed("crates cargo metadata doesn't know", &extra_names);
+    report.listed("dependencies missing from the tree", &missing_edges);
+    report.listed("dependencies cargo metadata doesn't know", &extra_edges);
+
+    if report.failures == failures {
+        report.ok(
+            "cross-check",
+            &format!(
+                "{} crates and {} dependencies agree with cargo metadata",
+                names.len(),
+                edges.len()
+            ),
+        );
+    }
+}
+
+// Checks everything treebuild depends on in the current project, printing a
+// line per check. False when any of them failed.
+pub fn run_checks() -> bool {
+    let mut report = Report { failures: 0 };
+
+    let host = check_toolchain(&mut report);
+    let config = check_config(&mut report);
+    check_state_dir(&mut report);
+
+    if let Some(tree) = check_tree(&mut report, config.dev_dependencies) {
+        cross_check(&mut report, &tree, host.as_deref(), config.dev_dependencies);
+    }
+
+    if report.failures == 0 {
+        println!("\nEverything looks fine");
+    } else {
+        println!("\n{} problem(s) found", report.failures);
+    }
+
+    report.failures == 0
+}
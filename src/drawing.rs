@@ -1,173 +1,944 @@
 use crate::parse_cargo_tree_output::TreeNode;
-use std::{cmp, collections::HashSet, rc::Rc};
+#[cfg(feature = "gui")]
+use crate::quadtree::Bounds;
+use serde::Serialize;
+use std::sync::Arc;
+// Without the window only the types the layouts share are of use
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+use {
+    crate::category::Category,
+    crate::compare::Comparison,
+    crate::config,
+    crate::diff::{Change, TreeDiff},
+    crate::layout::{parallel, Placement, Positions},
+    crate::license::LicenseFamily,
+    crate::package_info::PackageInfo,
+    crate::theme::Theme,
+    crate::unit_graph,
+    crate::units::Units,
+    std::collections::{HashMap, HashSet},
+};
 
 pub type Point = (f32, f32);
 pub type Color = (u8, u8, u8);
-fn get_satellites(
-    center: Point,
-    root_radius: f32,
-    in_radius: f32,
-    amount: usize,
-    phase: f32,
-    sky: f32,
-) -> (f32, Vec<(Point, f32)>) {
-    let diff_angle = sky / (amount as f32);
 
-    (
-        if diff_angle > std::f32::consts::PI {
-            root_radius * 0.7
-        } else {
-            f32::min(
-                in_radius * (2.0f32.sqrt()) * (1.0 - diff_angle.cos()).sqrt() / 2.0,
-                root_radius * 0.7,
-            )
-        },
-        (0u32..(amount as u32))
-            // 0 1 2 3 5 6 7 ... to 0 1 1 2 2 3 3 ...
-            .map(|idx| ((idx as f32) / 2.0).ceil())
-            // Alternate sign
-            .zip([1, -1].iter().cycle())
-            .map(|(idx, &sign)| idx * (sign as f32))
-            // Get final angle for this satellite
-            .map(|alternating_idx| phase + alternating_idx * diff_angle)
-            // Get final cartesian coordinates of this satellite, also append angle
-            .map(|angle: f32| {
-                (
-                    (
-                        center.0 + angle.cos() * in_radius,
-                        center.1 + angle.sin() * in_radius,
-                    ),
-                    angle,
-                )
-            })
-            .collect::<Vec<_>>(),
-    )
+// What a crate is drawn as. Space filling layouts give up circles so that
+// area can stand for something.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum Shape {
+    Circle,
+    // Centered on the crate
+    Rect {
+        width: f32,
+        height: f32,
+    },
+    // A slice of the ring between `inner` and `outer` around `origin`,
+    // angles in radians counterclockwise
+    Wedge {
+        origin: Point,
+        inner: f32,
+        outer: f32,
+        start: f32,
+        end: f32,
+    },
 }
 
+// wasm32 hands the page only the shape, color and name of each
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+#[derive(Clone)]
 pub struct DrawCrate {
     pub center: Point,
+    // For shapes other than circles this is only a rough size, used to decide
+    // whether there is room for a label
     pub radius: f32,
+    pub shape: Shape,
     pub color: Color,
     pub name: String,
-    pub tree: Rc<TreeNode>,
+    pub tree: Arc<TreeNode>,
+    pub state: CrateState,
+    pub info: Option<Arc<PackageInfo>>,
+    // Change in warnings since the crate's last build
+    pub warning_delta: i64,
+    // Whether each of the package's compile units is done, when it has more
+    // than one
+    pub units: Vec<bool>,
+    // How much of each workspace member is built, on the workspace root
+    pub members: Vec<f32>,
+    // What a running crate's color beats towards, see `pulse`
+    pub pulse: Option<Color>,
+    // On a pinned path, so labelled however small
+    pub pinned: bool,
 }
 
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrateState {
+    Pending,
+    Active,
+    Completed,
+    Failed,
+}
+
+#[cfg(feature = "gui")]
+impl CrateState {
+    pub fn label(self) -> &'static str {
+        match self {
+            CrateState::Pending => "pending",
+            CrateState::Active => "building",
+            CrateState::Completed => "built",
+            CrateState::Failed => "failed",
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl DrawCrate {
+    pub fn bounds(&self) -> Bounds {
+        match self.shape {
+            Shape::Circle => Bounds::around(self.center, self.radius),
+            Shape::Rect { width, height } => Bounds {
+                min: (self.center.0 - width / 2.0, self.center.1 - height / 2.0),
+                max: (self.center.0 + width / 2.0, self.center.1 + height / 2.0),
+            },
+            // Generous, but wedges are only ever tested point by point
+            Shape::Wedge { origin, outer, .. } => Bounds::around(origin, outer),
+        }
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        match self.shape {
+            Shape::Circle => {
+                (self.center.0 - point.0).powf(2.0) + (self.center.1 - point.1).powf(2.0)
+                    < self.radius.powf(2.0)
+            }
+            Shape::Rect { .. } => self.bounds().contains_point(point),
+            Shape::Wedge {
+                origin,
+                inner,
+                outer,
+                start,
+                end,
+            } => {
+                let (dx, dy) = (point.0 - origin.0, point.1 - origin.1);
+                let distance = (dx * dx + dy * dy).sqrt();
+                let angle = dy.atan2(dx).rem_euclid(std::f32::consts::TAU);
+
+                distance >= inner && distance < outer && (start..end).contains(&angle)
+            }
+        }
+    }
+}
+
+// Runs from a crate to one of its dependencies. On wasm32 pinned paths are
+// drawn like any other.
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+#[derive(Clone)]
 pub struct DrawLine {
     pub p1: Point,
     pub p2: Point,
+    // A single point bends the line into a quadratic Bezier curve, more
+    // route it along a B-spline that passes near each of them in turn
+    pub controls: Vec<Point>,
+    // Points the line's end at the dependency
+    pub arrow: bool,
     pub color: Color,
+    // Leads into a running crate, so gets drawn as crawling dashes
+    pub flowing: bool,
+    // Part of a pinned path, so drawn heavier
+    pub pinned: bool,
 }
 
-pub fn draw_tree(
-    center: Point,
-    tree: Rc<TreeNode>,
-    radius: f32,
-    phase: f32,
-    depth: usize,
-    sky: f32,
-    phase_accum: f32,
-    color: Color,
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+const CURVE_STEPS: usize = 16;
+
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+impl DrawLine {
+    // Curves stay within their control points, so those bound them too
+    #[cfg(feature = "gui")]
+    pub fn bounds(&self) -> Bounds {
+        self.controls
+            .iter()
+            .fold(Bounds::around(self.p1, 0.0), |bounds, &control| {
+                bounds.union(Bounds::around(control, 0.0))
+            })
+            .union(Bounds::around(self.p2, 0.0))
+    }
+
+    pub fn path(&self) -> Vec<Point> {
+        match self.controls[..] {
+            [] => vec![self.p1, self.p2],
+            [control] => (0..=CURVE_STEPS)
+                .map(|step| {
+                    let t = step as f32 / CURVE_STEPS as f32;
+                    let (a, b, c) = ((1.0 - t) * (1.0 - t), 2.0 * (1.0 - t) * t, t * t);
+
+                    (
+                        a * self.p1.0 + b * control.0 + c * self.p2.0,
+                        a * self.p1.1 + b * control.1 + c * self.p2.1,
+                    )
+                })
+                .collect(),
+            _ => self.b_spline(),
+        }
+    }
+
+    // Uniform cubic, with the ends repeated so it starts and stops right on them
+    fn b_spline(&self) -> Vec<Point> {
+        let points = [self.p1, self.p1]
+            .iter()
+            .chain(std::iter::once(&self.p1))
+            .chain(self.controls.iter())
+            .chain([self.p2, self.p2, self.p2].iter())
+            .copied()
+            .collect::<Vec<_>>();
+        let steps = CURVE_STEPS / 2;
+
+        let mut path = points
+            .windows(4)
+            .flat_map(|segment| {
+                (0..steps).map(move |step| {
+                    let t = step as f32 / steps as f32;
+                    let weights = [
+                        (1.0 - t).powi(3) / 6.0,
+                        (3.0 * t.powi(3) - 6.0 * t.powi(2) + 4.0) / 6.0,
+                        (-3.0 * t.powi(3) + 3.0 * t.powi(2) + 3.0 * t + 1.0) / 6.0,
+                        t.powi(3) / 6.0,
+                    ];
+
+                    segment
+                        .iter()
+                        .zip(weights.iter())
+                        .fold((0.0, 0.0), |(x, y), (point, weight)| {
+                            (x + point.0 * weight, y + point.1 * weight)
+                        })
+                })
+            })
+            .collect::<Vec<_>>();
+
+        path.push(self.p2);
+        path
+    }
+
+    // Which way the line is going where it ends, as a unit vector
+    #[cfg(feature = "gui")]
+    pub fn heading(&self) -> Point {
+        let from = self.controls.last().copied().unwrap_or(self.p1);
+        let (dx, dy) = (self.p2.0 - from.0, self.p2.1 - from.1);
+        let length = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+
+        (dx / length, dy / length)
+    }
+}
+
+// Part of a circle, going clockwise from `start` through `sweep` radians,
+// with 0 pointing right
+#[cfg(feature = "gui")]
+#[derive(Clone)]
+pub struct DrawArc {
+    pub center: Point,
+    pub radius: f32,
+    pub start: f32,
+    pub sweep: f32,
+    pub color: Color,
+}
+
+#[cfg(feature = "gui")]
+const ARC_STEPS: usize = 32;
+
+#[cfg(feature = "gui")]
+impl DrawArc {
+    pub fn bounds(&self) -> Bounds {
+        Bounds::around(self.center, self.radius)
+    }
+
+    pub fn path(&self) -> Vec<Point> {
+        let steps =
+            ((ARC_STEPS as f32 * self.sweep / std::f32::consts::TAU).ceil() as usize).max(2);
+
+        (0..=steps)
+            .map(|step| {
+                let angle = self.start - self.sweep * step as f32 / steps as f32;
+                (
+                    self.center.0 + self.radius * angle.cos(),
+                    self.center.1 + self.radius * angle.sin(),
+                )
+            })
+            .collect()
+    }
+}
+
+// How far out of its crate a progress ring goes
+#[cfg(feature = "gui")]
+const RING_SPACING: f32 = 1.3;
+
+// A ring around every crate with dependencies, filled clockwise from the
+// top as far as they are built, for reading progress when zoomed out too far
+// for much else. The workspace root has its members' rings instead, and done
+// crates need none.
+#[cfg(feature = "gui")]
+pub fn progress_arcs(
+    crates: &[DrawCrate],
     completed: &HashSet<String>,
-    active: &HashSet<String>,
-    transition: f32,
+    theme: &Theme,
+) -> Vec<DrawArc> {
+    let top = std::f32::consts::FRAC_PI_2;
+    let mut arcs = Vec::new();
+
+    for draw_crate in crates {
+        if draw_crate.tree.children.is_empty()
+            || !draw_crate.members.is_empty()
+            || draw_crate.state == CrateState::Completed
+            || !matches!(draw_crate.shape, Shape::Circle)
+        {
+            continue;
+        }
+
+        let mut below = draw_crate.tree.crates();
+        below.remove(draw_crate.tree.name.as_str());
+        let built = below
+            .iter()
+            .filter(|name| completed.contains(unit_graph::owner(name)))
+            .count() as f32
+            / below.len().max(1) as f32;
+
+        let sweep = std::f32::consts::TAU * built;
+        let ring = |start, sweep, color| DrawArc {
+            center: draw_crate.center,
+            radius: draw_crate.radius * RING_SPACING,
+            start,
+            sweep,
+            color,
+        };
+        if built < 1.0 {
+            arcs.push(ring(
+                top - sweep,
+                std::f32::consts::TAU - sweep,
+                theme.outline,
+            ));
+        }
+        if built > 0.0 {
+            arcs.push(ring(top, sweep, theme.completed));
+        }
+    }
+
+    arcs
+}
+
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+const ADDED_COLOR: Color = (0x50, 0xc8, 0x60);
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+const REMOVED_COLOR: Color = (0xd0, 0x40, 0x40);
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+const SHARED_COLOR: Color = (0xe0, 0xb0, 0x40);
+
+// Everything that stays the same across the whole recursion
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+pub struct DrawState<'a> {
+    pub completed: &'a HashSet<String>,
+    pub active: &'a HashSet<String>,
+    pub failed: &'a HashSet<String>,
+    // Crates cargo found up to date, dimmed unless they build after all
+    pub fresh: &'a HashSet<String>,
+    // When set, everything else is dimmed
+    pub highlighted: Option<&'a HashSet<usize>>,
+    // Nodes on the pinned paths, which are never dimmed
+    pub pinned: &'a HashSet<usize>,
+    pub categories: &'a HashMap<String, Category>,
+    pub color_by_category: bool,
+    // Crates outside these categories are dimmed, unless it is empty
+    pub category_filter: &'a HashSet<Category>,
+    // By the license family of each crate's package, from `metadata`
+    pub color_by_license: bool,
+    // When showing upgrade candidates, the crates with a newer release out
+    // and what it is
+    pub outdated: Option<&'a HashMap<String, String>>,
+    // When showing two trees in one, see `TreeDiff`
+    pub diff: Option<&'a TreeDiff>,
+    // Leaves the crates that are gone out, where they would be
+    pub hide_removed: bool,
+    // When showing several projects in one, see `Comparison`
+    pub comparison: Option<&'a Comparison>,
+    pub metadata: &'a HashMap<String, Arc<PackageInfo>>,
+    // Crates matching the current search, when there is one
+    pub matches: Option<&'a HashSet<String>>,
+    pub warning_deltas: &'a HashMap<String, i64>,
+    pub arrows: bool,
+    // Also link repeated crates to their first occurrence, see `bundled_edges`
+    pub bundles: bool,
+    pub theme: &'a Theme,
+    pub aliases: &'a HashMap<String, String>,
+    pub units: &'a Units,
+    // Color edges by how far the build got, rather than all the same
+    pub edge_states: bool,
+}
+
+// Rough metrics of the default font, good enough to size boxes around text
+#[cfg(feature = "gui")]
+pub fn char_width(font_size: u32) -> f32 {
+    font_size as f32 * 0.6
+}
+
+#[cfg(feature = "gui")]
+pub fn line_height(font_size: u32) -> f32 {
+    font_size as f32 * 1.4
+}
+
+// Labels grow with their crate, within reason
+#[cfg(feature = "gui")]
+pub fn label_font_size(radius: f32) -> u32 {
+    (radius * 0.4).clamp(8.0, 20.0) as u32
+}
+
+// Cuts text that doesn't fit in `width` short, ending it with an ellipsis
+#[cfg(feature = "gui")]
+pub fn truncate(text: &str, width: f32, font_size: u32) -> String {
+    let fits = (width / char_width(font_size)).floor() as usize;
+
+    if text.chars().count() <= fits {
+        text.to_owned()
+    } else if fits == 0 {
+        String::new()
+    } else {
+        text.chars()
+            .take(fits - 1)
+            .chain(std::iter::once('\u{2026}'))
+            .collect()
+    }
+}
+
+// The width and height a block of left justified lines takes up
+#[cfg(feature = "gui")]
+pub fn measure_lines(lines: &[String], font_size: u32) -> (f32, f32) {
+    let longest = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    (
+        longest as f32 * char_width(font_size),
+        lines.len() as f32 * line_height(font_size),
+    )
+}
+
+// The text tree repeats shared crates, so a node is identified by its
+// allocation rather than by name
+pub fn node_id(tree: &Arc<TreeNode>) -> usize {
+    Arc::as_ptr(tree) as usize
+}
+
+#[cfg(feature = "gui")]
+pub fn subtree_ids(tree: &Arc<TreeNode>, ids: &mut HashSet<usize>) {
+    ids.insert(node_id(tree));
+
+    for child in tree.children.iter() {
+        subtree_ids(child, ids);
+    }
+}
+
+// How far curved edges bow out to the side, relative to their length
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+const BEND: f32 = 0.2;
+
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+fn towards(from: Point, to: Point) -> Point {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let distance = (dx * dx + dy * dy).sqrt();
+
+    (dx / distance, dy / distance)
+}
+
+// Joins two circles edge to edge, either straight between their centers or
+// bowed out to the side so the edge goes around whatever sits in between
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+fn connect(
+    from: Placement,
+    to: Placement,
+    curved: bool,
+    arrow: bool,
+    color: Color,
+) -> Option<DrawLine> {
+    let (dx, dy) = (to.center.0 - from.center.0, to.center.1 - from.center.1);
+    let distance = (dx * dx + dy * dy).sqrt();
+
+    if distance <= from.radius + to.radius {
+        return None;
+    }
+
+    let control = if curved {
+        Some((
+            (from.center.0 + to.center.0) / 2.0 - dy * BEND,
+            (from.center.1 + to.center.1) / 2.0 + dx * BEND,
+        ))
+    } else {
+        None
+    };
+
+    // Curves leave and arrive along their tangents rather than the centerline
+    let (start, end) = match control {
+        Some(control) => (towards(from.center, control), towards(control, to.center)),
+        None => (
+            (dx / distance, dy / distance),
+            (dx / distance, dy / distance),
+        ),
+    };
+
+    Some(DrawLine {
+        p1: (
+            from.center.0 + start.0 * from.radius,
+            from.center.1 + start.1 * from.radius,
+        ),
+        p2: (
+            to.center.0 - end.0 * to.radius,
+            to.center.1 - end.1 * to.radius,
+        ),
+        controls: control.into_iter().collect(),
+        arrow,
+        color,
+        flowing: false,
+        pinned: false,
+    })
+}
+
+// Styles whatever part of the tree the layout placed
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+pub fn draw_tree(
+    root: &Arc<TreeNode>,
+    positions: &Positions,
+    state: &DrawState,
 ) -> (Vec<DrawCrate>, Vec<DrawLine>) {
-    let mut crate_draws = Vec::<DrawCrate>::new();
-    let mut line_draws = Vec::<DrawLine>::new();
+    let order = draw_order(root, positions, state);
 
-    // Draw a red outline if active
-    crate_draws.push(DrawCrate {
-        center,
-        radius,
-        color: if active.contains(&tree.name) {
-            let active_color = (0x98, 0xfb, 0x98);
-
-            let base_r = cmp::min(color.0, active_color.0);
-            let base_g = cmp::min(color.1, active_color.1);
-            let base_b = cmp::min(color.2, active_color.2);
-
-            let diff_r = cmp::max(color.0, active_color.0) - base_r;
-            let diff_g = cmp::max(color.1, active_color.1) - base_g;
-            let diff_b = cmp::max(color.2, active_color.2) - base_b;
-
-            (
-                base_r.saturating_add((diff_r as f32 * transition) as u8),
-                base_g.saturating_add((diff_g as f32 * transition) as u8),
-                base_b.saturating_add((diff_b as f32 * transition) as u8),
-            )
-        } else if completed.contains(&tree.name) {
-            (0x98, 0xfb, 0x98)
+    // Each crate is styled on its own, so they all are at once
+    let drawn = parallel::map_indices(order.len(), |idx| {
+        let (node, color) = order[idx];
+        let (mut crate_draws, mut line_draws) = (Vec::new(), Vec::new());
+        draw_node(
+            node,
+            color,
+            positions,
+            state,
+            &mut crate_draws,
+            &mut line_draws,
+        );
+        (crate_draws, line_draws)
+    });
+    let mut crate_draws = Vec::<DrawCrate>::with_capacity(order.len());
+    let mut line_draws = Vec::<DrawLine>::with_capacity(order.len());
+    for (crates, lines) in drawn {
+        crate_draws.extend(crates);
+        line_draws.extend(lines);
+    }
+
+    if state.bundles && !positions.hide_edges {
+        bundled_edges(
+            root,
+            positions,
+            state.arrows,
+            state.theme.bundle,
+            &mut line_draws,
+        );
+    }
+
+    (crate_draws, line_draws)
+}
+
+// How closely bundled edges follow the tree, where 0 is a straight line
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+const BUNDLE_STRENGTH: f32 = 0.85;
+
+// The text tree repeats a crate under everything that depends on it. Each
+// repeat becomes an edge from its parent to the crate's first occurrence,
+// routed up the tree to their closest common ancestor and back down, so edges
+// converging on the same crate share most of their way there instead of
+// crossing all over.
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+fn bundled_edges(
+    root: &Arc<TreeNode>,
+    positions: &Positions,
+    arrows: bool,
+    color: Color,
+    line_draws: &mut Vec<DrawLine>,
+) {
+    let mut parents = HashMap::<usize, usize>::new();
+    let mut first = HashMap::<&str, usize>::new();
+    let mut repeats = Vec::<(usize, usize)>::new();
+
+    let mut pending = vec![(root, None)];
+    while let Some((node, parent)) = pending.pop() {
+        let id = node_id(node);
+
+        if !positions.nodes.contains_key(&id) {
+            continue;
+        }
+        if let Some(parent) = parent {
+            parents.insert(id, parent);
+        }
+
+        // Everything below a repeat repeats as well, one edge covers it all
+        if let Some(&canonical) = first.get(node.name.as_str()) {
+            // Already shared, in which case the edge is there
+            if canonical == id {
+                continue;
+            }
+            if let Some(parent) = parent {
+                repeats.push((parent, canonical));
+            }
+            continue;
+        }
+        first.insert(&node.name, id);
+
+        // Reversed so the stack hands them out in order
+        pending.extend(node.children.iter().rev().map(|child| (child, Some(id))));
+    }
+
+    let ancestry = |mut id: usize| {
+        let mut chain = vec![id];
+        while let Some(&parent) = parents.get(&id) {
+            chain.push(parent);
+            id = parent;
+        }
+        chain
+    };
+
+    for (from, to) in repeats {
+        let up = ancestry(from);
+        let down = ancestry(to);
+        let common = match down.iter().position(|id| up.contains(id)) {
+            Some(common) => common,
+            None => continue,
+        };
+        let route = up
+            .iter()
+            .take_while(|&&id| id != down[common])
+            .chain(down[..=common].iter().rev())
+            .map(|id| positions.nodes[id].center)
+            .collect::<Vec<_>>();
+
+        if route.len() < 3 {
+            continue;
+        }
+
+        // Straightened a little, or every bundle would just trace the tree
+        let (start, end) = (route[0], route[route.len() - 1]);
+        let last = route.len() - 1;
+        let controls = route[1..last]
+            .iter()
+            .enumerate()
+            .map(|(idx, point)| {
+                let t = (idx + 1) as f32 / last as f32;
+                (
+                    BUNDLE_STRENGTH * point.0
+                        + (1.0 - BUNDLE_STRENGTH) * (start.0 + (end.0 - start.0) * t),
+                    BUNDLE_STRENGTH * point.1
+                        + (1.0 - BUNDLE_STRENGTH) * (start.1 + (end.1 - start.1) * t),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let (from, to) = (positions.nodes[&from], positions.nodes[&to]);
+        let leaving = towards(from.center, controls[0]);
+        let arriving = towards(controls[controls.len() - 1], to.center);
+
+        line_draws.push(DrawLine {
+            p1: (
+                from.center.0 + leaving.0 * from.radius,
+                from.center.1 + leaving.1 * from.radius,
+            ),
+            p2: (
+                to.center.0 - arriving.0 * to.radius,
+                to.center.1 - arriving.1 * to.radius,
+            ),
+            controls,
+            arrow: arrows,
+            color,
+            flowing: false,
+            pinned: false,
+        });
+    }
+}
+
+// Every placed crate once, with the color it is drawn in unless something
+// else decides it, in the order they are drawn
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+fn draw_order<'a>(
+    root: &'a Arc<TreeNode>,
+    positions: &Positions,
+    state: &DrawState,
+) -> Vec<(&'a Arc<TreeNode>, Color)> {
+    let is_removed = |node: &Arc<TreeNode>| {
+        state.hide_removed
+            && state
+                .diff
+                .is_some_and(|diff| diff.removed.contains(&node.name))
+    };
+    let mut order = Vec::new();
+    let mut drawn = HashSet::new();
+
+    // Depth first off a stack rather than by recursing, since trees can go
+    // deeper than the stack does
+    let mut pending = vec![(root, state.theme.root)];
+    while let Some((node, color)) = pending.pop() {
+        // Shared crates are reached once per dependent, but only drawn once
+        if !positions.nodes.contains_key(&node_id(node)) || !drawn.insert(node_id(node)) {
+            continue;
+        }
+        order.push((node, color));
+
+        // Still laid out, so everything else stays where it is. Pushed last
+        // first, so the stack hands the children out in order.
+        pending.extend(
+            node.children
+                .iter()
+                .rev()
+                .filter(|child| !is_removed(child) && positions.nodes.contains_key(&node_id(child)))
+                .map(|child| (child, child.color)),
+        );
+    }
+
+    order
+}
+
+// Styles one crate and the edges to its children
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+fn draw_node(
+    tree: &Arc<TreeNode>,
+    color: Color,
+    positions: &Positions,
+    state: &DrawState,
+    crate_draws: &mut Vec<DrawCrate>,
+    line_draws: &mut Vec<DrawLine>,
+) {
+    let DrawState {
+        completed,
+        active,
+        failed,
+        fresh,
+        highlighted,
+        pinned,
+        categories,
+        color_by_category,
+        category_filter,
+        color_by_license,
+        outdated,
+        diff,
+        hide_removed,
+        comparison,
+        metadata,
+        matches,
+        warning_deltas,
+        arrows,
+        bundles: _,
+        theme,
+        aliases,
+        units,
+        edge_states,
+    } = *state;
+
+    let placement = positions.nodes[&node_id(tree)];
+
+    let category_of = |node: &Arc<TreeNode>| {
+        categories
+            .get(&node.name)
+            .copied()
+            .unwrap_or(Category::Other)
+    };
+    let is_dimmed = |node: &Arc<TreeNode>| {
+        !pinned.contains(&node_id(node))
+            && (highlighted.is_some_and(|highlighted| !highlighted.contains(&node_id(node)))
+                || (!category_filter.is_empty() && !category_filter.contains(&category_of(node)))
+                || matches.is_some_and(|matches| !matches.contains(&node.name)))
+    };
+    let color = if color_by_category {
+        category_of(tree).color()
+    } else if color_by_license {
+        let license = metadata
+            .get(unit_graph::owner(&tree.name))
+            .and_then(|info| info.license.as_deref());
+        LicenseFamily::of(license).color()
+    } else {
+        color
+    };
+    let is_removed =
+        |node: &Arc<TreeNode>| diff.is_some_and(|diff| diff.removed.contains(&node.name));
+    // A build script is done when its package is
+    let state_of = |node: &TreeNode| {
+        let package = unit_graph::owner(&node.name);
+        if active.contains(&node.name) {
+            CrateState::Active
+        } else if failed.contains(package) {
+            CrateState::Failed
+        } else if completed.contains(package) {
+            CrateState::Completed
+        } else {
+            CrateState::Pending
+        }
+    };
+    // Edges take the state of the dependency they lead to, so what is built
+    // can be seen working its way up towards the root
+    let edge_color = |from: &Arc<TreeNode>, to: &Arc<TreeNode>| {
+        let edge = if !edge_states {
+            theme.edge
+        } else {
+            match state_of(to) {
+                CrateState::Pending => theme.dim(theme.edge),
+                CrateState::Active => theme.active,
+                CrateState::Completed => theme.completed,
+                CrateState::Failed => theme.crate_failed,
+            }
+        };
+
+        if is_removed(to) {
+            theme.dim(REMOVED_COLOR)
+        } else if is_dimmed(from) || is_dimmed(to) {
+            theme.dim(edge)
+        } else {
+            edge
+        }
+    };
+
+    let package = unit_graph::owner(&tree.name);
+    let crate_state = state_of(tree);
+
+    // Running crates keep their own color here and beat towards the active
+    // one as they are drawn, so the scene needn't be rebuilt every frame
+    let crate_color = match crate_state {
+        CrateState::Failed => theme.crate_failed,
+        CrateState::Completed => theme.completed,
+        CrateState::Active | CrateState::Pending => color,
+    };
+    let crate_color = if outdated.is_some_and(|outdated| outdated.contains_key(package)) {
+        theme.tint(crate_color)
+    } else {
+        crate_color
+    };
+    let change = diff.map_or(Change::Unchanged, |diff| diff.change(&tree.name));
+    let crate_color = match change {
+        Change::Added => ADDED_COLOR,
+        // Ghosts of what was
+        Change::Removed => theme.dim(REMOVED_COLOR),
+        Change::VersionChanged(..) | Change::Unchanged => crate_color,
+    };
+    let users = comparison.map_or(0, |comparison| comparison.users(&tree.name));
+    let crate_color = if users > 1 { SHARED_COLOR } else { crate_color };
+    let name = config::display_name(aliases, &tree.name);
+    let name = match change {
+        Change::VersionChanged(before, after) => {
+            format!("{} {} -> {}", name, before.join(", "), after.join(", "))
+        }
+        // Between two projects shared is all there is to say
+        _ => match comparison {
+            Some(comparison) if users > 1 && comparison.projects.len() > 2 => {
+                format!("{} ({}/{})", name, users, comparison.projects.len())
+            }
+            _ => name.to_owned(),
+        },
+    };
+    let shade = |color: Color| {
+        if is_dimmed(tree) || (crate_state == CrateState::Pending && fresh.contains(package)) {
+            theme.dim(color)
         } else {
             color
+        }
+    };
+
+    crate_draws.push(DrawCrate {
+        center: placement.center,
+        radius: placement.radius,
+        shape: placement.shape,
+        color: shade(crate_color),
+        name,
+        tree: Arc::clone(tree),
+        state: crate_state,
+        info: metadata.get(&tree.name).cloned(),
+        warning_delta: warning_deltas.get(&tree.name).copied().unwrap_or(0),
+        units: units
+            .of(&tree.name)
+            .filter(|units| units.len() > 1)
+            .map(|units| {
+                units
+                    .values()
+                    .map(|timing| timing.finished.is_some())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        members: if tree.is_workspace() {
+            tree.children
+                .iter()
+                .map(|member| {
+                    let crates = member.crates();
+                    let built = crates
+                        .iter()
+                        .filter(|name| completed.contains(unit_graph::owner(name)))
+                        .count();
+                    built as f32 / crates.len() as f32
+                })
+                .collect()
+        } else {
+            Vec::new()
         },
-        name: tree.name.clone(),
-        tree: Rc::clone(&tree),
+        pulse: Some(shade(theme.active)).filter(|_| crate_state == CrateState::Active),
+        pinned: pinned.contains(&node_id(tree)),
     });
 
-    let child_count = tree.children.len();
-
-    let (new_radius, sats) = get_satellites(
-        (center.0, center.1),
-        radius,
-        radius * 2.0,
-        child_count,
-        phase + phase_accum,
-        sky,
-    );
-
-    sats.into_iter()
-        .zip(tree.children.iter())
-        .for_each(|((point, point_phase), child)| {
-            let child_center = if child.children.len() < 5 {
-                point
+    // A folded subtree still points at this crate so clicking it drills in
+    if let Some(&aggregate) = positions.aggregates.get(&node_id(tree)) {
+        crate_draws.push(DrawCrate {
+            center: aggregate.center,
+            radius: aggregate.radius,
+            shape: aggregate.shape,
+            color: if is_dimmed(tree) {
+                theme.dim(theme.aggregate)
             } else {
-                (
-                    point.0 + new_radius * point_phase.cos() * 1.5,
-                    point.1 + new_radius * point_phase.sin() * 1.5,
-                )
-            };
-
-            let child_sky = {
-                if Rc::clone(&child).children.len() < 5 {
-                    std::f32::consts::PI / 2.0
-                } else {
-                    std::f32::consts::PI * 1.5
-                }
-            };
-
-            let (child_crate_draws, child_line_draws) = draw_tree(
-                child_center,
-                Rc::clone(&child),
-                new_radius,
-                point_phase,
-                depth + 1,
-                child_sky,
-                phase_accum,
-                child.color,
-                &completed,
-                &active,
-                transition,
-            );
-
-            // Make sure the line starts from the circle and not from the center
-            let line_start = (
-                center.0 + point_phase.cos() * radius,
-                center.1 + point_phase.sin() * radius,
-            );
-
-            let line_end = (
-                child_center.0 - (point_phase).cos() * new_radius,
-                child_center.1 - (point_phase).sin() * new_radius,
-            );
-
-            line_draws.push(DrawLine {
-                p1: line_start,
-                p2: line_end,
-                color: (255, 255, 255),
-            });
-
-            crate_draws.extend(child_crate_draws);
-            line_draws.extend(child_line_draws);
+                theme.aggregate
+            },
+            name: format!("+{} transitive", tree.transitive_count()),
+            tree: Arc::clone(tree),
+            state: crate_state,
+            info: None,
+            warning_delta: 0,
+            units: Vec::new(),
+            members: Vec::new(),
+            pulse: None,
+            pinned: false,
         });
 
-    (crate_draws, line_draws)
+        if !positions.hide_edges {
+            line_draws.extend(connect(
+                placement,
+                aggregate,
+                positions.curved_edges,
+                arrows,
+                edge_color(tree, tree),
+            ));
+        }
+    }
+
+    for child in tree.children.iter() {
+        // Still laid out, so everything else stays where it is
+        if hide_removed && is_removed(child) {
+            continue;
+        }
+
+        if let Some(&child_placement) = positions.nodes.get(&node_id(child)) {
+            if !positions.hide_edges {
+                line_draws.extend(
+                    connect(
+                        placement,
+                        child_placement,
+                        positions.curved_edges,
+                        arrows,
+                        edge_color(tree, child),
+                    )
+                    .map(|line| DrawLine {
+                        flowing: active.contains(&child.name),
+                        pinned: pinned.contains(&node_id(tree)) && pinned.contains(&node_id(child)),
+                        ..line
+                    }),
+                );
+            }
+        }
+    }
 }
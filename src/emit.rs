@@ -0,0 +1,231 @@
+use crate::events::{BuildEvent, BuildPhase};
+use crate::parse_cargo_tree_output::TreeNode;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Instant;
+
+// One JSON object per line, told apart by "type", with seconds since the
+// build started in "at"
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum Line<'a> {
+    Phase {
+        at: f32,
+        phase: BuildPhase,
+    },
+    Started {
+        at: f32,
+        name: &'a str,
+    },
+    Finished {
+        at: f32,
+        name: &'a str,
+        // None when it was already building when we started following
+        secs: Option<f32>,
+    },
+    Failed {
+        at: f32,
+        name: &'a str,
+    },
+    Fresh {
+        at: f32,
+        name: &'a str,
+    },
+    Warning {
+        at: f32,
+    },
+    CrateWarnings {
+        at: f32,
+        name: &'a str,
+        count: usize,
+    },
+    Log {
+        at: f32,
+        line: &'a str,
+    },
+    // After every crate that is done, for those showing a progress bar
+    Progress {
+        at: f32,
+        done: usize,
+        total: usize,
+        remaining_secs: Option<f32>,
+    },
+}
+
+// Writes every build event as it comes for other tools to follow, see
+// --emit-events
+pub struct Emitter {
+    out: Box<dyn Write>,
+    start: Instant,
+    started: HashMap<String, Instant>,
+    // Everything in the tree, and what of it needn't or won't build
+    crates: HashSet<String>,
+    fresh: HashSet<String>,
+    done: HashSet<String>,
+    // How long the last build took, to tell how long this one has left
+    previous_secs: Option<f32>,
+}
+
+fn names(node: &TreeNode, into: &mut HashSet<String>) {
+    if into.insert(node.name.clone()) {
+        for child in node.children.iter() {
+            names(child, into);
+        }
+    }
+}
+
+impl Emitter {
+    // To stdout when `path` is -
+    pub fn create(path: &Path, tree: &TreeNode, previous_secs: Option<f32>) -> Self {
+        let out: Box<dyn Write> = if path == Path::new("-") {
+            Box::new(io::stdout())
+        } else {
+            Box::new(
+                File::create(path)
+                    .unwrap_or_else(|e| panic!("Failed to create {}: {}", path.display(), e)),
+            )
+        };
+
+        let mut crates = HashSet::new();
+        names(tree, &mut crates);
+
+        Emitter {
+            out,
+            start: Instant::now(),
+            started: HashMap::new(),
+            crates,
+            fresh: HashSet::new(),
+            done: HashSet::new(),
+            previous_secs,
+        }
+    }
+
+    fn write(&mut self, line: &Line) {
+        let mut raw = serde_json::to_string(line).expect("Failed to serialize event");
+        raw.push('\n');
+
+        // Whoever reads it going away is no reason to stop the build
+        let _ = self.out.write_all(raw.as_bytes());
+        let _ = self.out.flush();
+    }
+
+    // The same way the HUD has it, going by the last build when there was
+    // one and by how fast crates have been getting done otherwise
+    fn progress(&self, at: f32) -> Line<'static> {
+        let total = self
+            .crates
+            .len()
+            .saturating_sub(self.fresh.len())
+            .max(self.done.len());
+        let done = self.done.len();
+
+        let remaining_secs = match self.previous_secs {
+            Some(previous) if previous > at => Some(previous - at),
+            _ if done > 0 => Some(at / done as f32 * (total - done) as f32),
+            _ => None,
+        };
+
+        Line::Progress {
+            at,
+            done,
+            total,
+            remaining_secs,
+        }
+    }
+
+    pub fn emit(&mut self, event: &BuildEvent) {
+        let at = self.start.elapsed().as_secs_f32();
+
+        match event {
+            BuildEvent::Phase(phase) => self.write(&Line::Phase { at, phase: *phase }),
+            BuildEvent::Started(name) => {
+                self.started.insert(name.clone(), Instant::now());
+                self.write(&Line::Started { at, name })
+            }
+            // Build scripts being run and the like are only noise here, the
+            // way the progress has it
+            BuildEvent::Completed(name) | BuildEvent::Failed(name)
+                if !self.crates.contains(name) => {}
+            BuildEvent::Completed(name) | BuildEvent::Failed(name) => {
+                let secs = self
+                    .started
+                    .remove(name)
+                    .map(|started| started.elapsed().as_secs_f32());
+                match event {
+                    BuildEvent::Completed(_) => self.write(&Line::Finished { at, name, secs }),
+                    _ => self.write(&Line::Failed { at, name }),
+                }
+
+                if self.done.insert(name.clone()) {
+                    let progress = self.progress(at);
+                    self.write(&progress);
+                }
+            }
+            BuildEvent::Fresh(name) => {
+                self.fresh.insert(name.clone());
+                self.write(&Line::Fresh { at, name })
+            }
+            BuildEvent::Warning => self.write(&Line::Warning { at }),
+            BuildEvent::CrateWarnings(name, count) => self.write(&Line::CrateWarnings {
+                at,
+                name,
+                count: *count,
+            }),
+            BuildEvent::Log(line) => self.write(&Line::Log { at, line }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::Arc;
+
+    fn node(name: &str, children: Vec<Arc<TreeNode>>) -> Arc<TreeNode> {
+        Arc::new(TreeNode {
+            name: name.to_owned(),
+            children,
+            color: (0, 0, 0),
+        })
+    }
+
+    // The types of the lines written for `events`
+    fn emitted(events: &[BuildEvent]) -> Vec<String> {
+        let path = std::env::temp_dir().join(format!("treebuild-emit-{}", std::process::id()));
+        let tree = node("app", vec![node("log", Vec::new())]);
+        let mut emitter = Emitter::create(&path, &tree, None);
+        for event in events {
+            emitter.emit(event);
+        }
+        drop(emitter);
+
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        written
+            .lines()
+            .map(|line| {
+                let line: serde_json::Value = serde_json::from_str(line).unwrap();
+                line["type"].as_str().unwrap().to_owned()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn only_crates_of_the_tree_are_done() {
+        assert_eq!(
+            emitted(&[
+                BuildEvent::Started("log".to_owned()),
+                BuildEvent::Completed("log".to_owned()),
+                BuildEvent::Completed("log(build)".to_owned()),
+                BuildEvent::Failed("somewhere-else".to_owned()),
+                BuildEvent::Failed("app".to_owned()),
+            ]),
+            ["started", "finished", "progress", "failed", "progress"]
+        );
+        assert!(emitted(&[BuildEvent::Completed("log(build)".to_owned())]).is_empty());
+    }
+}
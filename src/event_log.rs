@@ -0,0 +1,190 @@
+use crate::events::BuildEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use sysinfo::{Pid, System, SystemExt};
+
+// One per line, the session first and then every event as it happens
+#[derive(Debug, Serialize, Deserialize)]
+enum Line {
+    Session { cargo_pid: u32, started: u64 },
+    // Seconds into the session
+    Event { at: f32, event: BuildEvent },
+}
+
+pub fn path() -> PathBuf {
+    crate::project::state_dir().join("events.ndjson")
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or(0)
+}
+
+pub struct Writer {
+    file: File,
+    start: Instant,
+}
+
+impl Writer {
+    // Starts over for a new cargo
+    pub fn create(cargo_pid: u32) -> Self {
+        fs::create_dir_all(crate::project::state_dir()).expect("Failed to create state directory");
+        let mut writer = Writer {
+            file: File::create(path()).expect("Failed to create event log"),
+            start: Instant::now(),
+        };
+
+        writer.write(&Line::Session {
+            cargo_pid,
+            started: unix_now(),
+        });
+        writer
+    }
+
+    // Carries on with a session that was resumed
+    #[cfg(feature = "gui")]
+    pub fn reopen(session: &Resumable) -> Self {
+        Writer {
+            file: fs::OpenOptions::new()
+                .append(true)
+                .open(path())
+                .expect("Failed to open event log"),
+            start: Instant::now() - session.elapsed,
+        }
+    }
+
+    fn write(&mut self, line: &Line) {
+        let mut raw = serde_json::to_string(line).expect("Failed to serialize event");
+        raw.push('\n');
+
+        // Losing the log is no reason to stop following the build
+        let _ = self.file.write_all(raw.as_bytes());
+    }
+
+    pub fn append(&mut self, event: &BuildEvent) {
+        let at = self.start.elapsed().as_secs_f32();
+        self.write(&Line::Event {
+            at,
+            event: event.clone(),
+        });
+    }
+}
+
+// A session whose cargo is still running without anyone watching it
+#[cfg(feature = "gui")]
+pub struct Resumable {
+    pub cargo_pid: u32,
+    pub elapsed: std::time::Duration,
+    pub events: Vec<(f32, BuildEvent)>,
+}
+
+pub fn is_running(pid: u32) -> bool {
+    let mut system = System::new();
+
+    // What refresh_process returns is whether it knew the process before
+    system.refresh_process(pid as Pid);
+    system.get_process(pid as Pid).is_some()
+}
+
+// Seconds into the session of each
+type Events = Vec<(f32, BuildEvent)>;
+
+// The session's cargo, when it started and what happened since
+fn read() -> Option<(u32, u64, Events)> {
+    let file = File::open(path()).ok()?;
+    let mut lines = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<Line>(&line).ok());
+
+    let (cargo_pid, started) = match lines.next()? {
+        Line::Session { cargo_pid, started } => (cargo_pid, started),
+        Line::Event { .. } => return None,
+    };
+
+    let events = lines
+        .filter_map(|line| match line {
+            Line::Event { at, event } => Some((at, event)),
+            Line::Session { .. } => None,
+        })
+        .collect::<Vec<_>>();
+
+    Some((cargo_pid, started, events))
+}
+
+// An event line of a log, with the seconds into its session, e.g. for
+// replaying it
+#[cfg(feature = "gui")]
+pub fn event(raw: &str) -> Option<(f32, BuildEvent)> {
+    match serde_json::from_str::<Line>(raw).ok()? {
+        Line::Event { at, event } => Some((at, event)),
+        Line::Session { .. } => None,
+    }
+}
+
+#[cfg(feature = "gui")]
+pub fn resumable() -> Option<Resumable> {
+    let (cargo_pid, started, events) = read()?;
+    let finished = events.iter().any(|(_, event)| {
+        matches!(
+            event,
+            BuildEvent::Phase(crate::events::BuildPhase::Finished)
+                | BuildEvent::Phase(crate::events::BuildPhase::Failed)
+        )
+    });
+
+    if finished || !is_running(cargo_pid) {
+        return None;
+    }
+
+    Some(Resumable {
+        cargo_pid,
+        elapsed: std::time::Duration::from_secs(unix_now().saturating_sub(started)),
+        events,
+    })
+}
+
+// Seconds each crate of the last session took to build, finished or not
+pub fn last_durations() -> HashMap<String, f32> {
+    let events = read().map(|(_, _, events)| events).unwrap_or_default();
+    let mut started = HashMap::new();
+    let mut durations = HashMap::new();
+
+    for (at, event) in events {
+        match event {
+            BuildEvent::Started(name) => {
+                started.insert(name, at);
+            }
+            BuildEvent::Completed(name) => {
+                if let Some(start) = started.remove(&name) {
+                    durations.insert(name, at - start);
+                }
+            }
+            _ => {}
+        }
+    }
+    durations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn running_processes_are_told_from_gone_ones() {
+        let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+        assert!(is_running(child.id()));
+        assert!(is_running(std::process::id()));
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(!is_running(child.id()));
+    }
+}
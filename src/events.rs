@@ -0,0 +1,29 @@
+//! What a build goes through, as the [`monitor`](crate::monitor) reports it.
+
+use serde::{Deserialize, Serialize};
+
+/// Where the build as a whole is at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BuildPhase {
+    Waiting,
+    Downloading,
+    Building,
+    Failed,
+    Finished,
+}
+
+/// One thing that happened during the build. Crates go by their names with
+/// dashes, the way `cargo tree` prints them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BuildEvent {
+    Phase(BuildPhase),
+    Started(String),
+    Completed(String),
+    Failed(String),
+    /// Cargo found it up to date, it won't be built this time.
+    Fresh(String),
+    Warning,
+    // Cargo's own count of warnings for a crate once it is done with it
+    CrateWarnings(String, usize),
+    Log(String),
+}
@@ -0,0 +1,65 @@
+use crate::drawing::{node_id, subtree_ids};
+use crate::parse_cargo_tree_output::TreeNode;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+// "Peel the onion": only expanded nodes show their children, starting from
+// the root alone, with the expansions kept in order as breadcrumbs
+pub struct Exploration {
+    trail: Vec<Arc<TreeNode>>,
+    expanded: HashSet<usize>,
+}
+
+impl Exploration {
+    pub fn new(root: Arc<TreeNode>) -> Self {
+        let mut exploration = Exploration {
+            trail: vec![root],
+            expanded: HashSet::new(),
+        };
+        exploration.sync();
+        exploration
+    }
+
+    fn sync(&mut self) {
+        self.expanded = self.trail.iter().map(node_id).collect();
+    }
+
+    pub fn expanded(&self) -> &HashSet<usize> {
+        &self.expanded
+    }
+
+    pub fn trail(&self) -> &[Arc<TreeNode>] {
+        &self.trail
+    }
+
+    // Expands a collapsed node, or collapses an expanded one together with
+    // anything that was expanded below it. The root always stays expanded.
+    pub fn toggle(&mut self, node: &Arc<TreeNode>) {
+        let id = node_id(node);
+
+        if !self.expanded.contains(&id) {
+            if !node.children.is_empty() {
+                self.trail.push(Arc::clone(node));
+                self.sync();
+            }
+            return;
+        }
+
+        if id == node_id(&self.trail[0]) {
+            return;
+        }
+
+        let mut collapsed = HashSet::new();
+        subtree_ids(node, &mut collapsed);
+
+        self.trail
+            .retain(|crumb| !collapsed.contains(&node_id(crumb)));
+        self.sync();
+    }
+
+    // Undoes every expansion made after the given breadcrumb
+    pub fn collapse_to(&mut self, crumb: usize) {
+        self.trail.truncate(crumb + 1);
+        self.sync();
+    }
+}
@@ -0,0 +1,118 @@
+use crate::events::BuildPhase;
+use crate::parse_cargo_tree_output::TreeNode;
+use crate::timeline::Timeline;
+use crate::utilization::Utilization;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+// wasm32 is only ever handed an export, see `wasm`
+#[cfg(not(target_arch = "wasm32"))]
+use {
+    crate::ring_buffer::RingBuffer,
+    crate::timings::Timings,
+    std::collections::{HashMap, HashSet},
+    std::fs,
+    std::path::Path,
+};
+
+// Bump whenever the layout of `BuildExport` changes incompatibly
+#[cfg(not(target_arch = "wasm32"))]
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BuildExport {
+    pub version: u32,
+    pub phase: BuildPhase,
+    pub tree: Arc<TreeNode>,
+    pub completed: BTreeSet<String>,
+    pub active: BTreeSet<String>,
+    pub failed: BTreeSet<String>,
+    pub elapsed_secs: f32,
+    pub warnings: usize,
+    pub durations: BTreeMap<String, f32>,
+    // The tail of cargo's output, older files simply don't have it
+    #[serde(default)]
+    pub log: Vec<String>,
+    #[serde(default)]
+    pub timeline: Timeline,
+    #[serde(default)]
+    pub utilization: Utilization,
+    // Display names from the config it was saved with
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl BuildExport {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        tree: Arc<TreeNode>,
+        phase: BuildPhase,
+        completed: &HashSet<String>,
+        active: &HashSet<String>,
+        failed: &HashSet<String>,
+        timings: &Timings,
+        log: &RingBuffer<String>,
+        timeline: &Timeline,
+        utilization: &Utilization,
+        aliases: &HashMap<String, String>,
+    ) -> Self {
+        BuildExport {
+            version: SCHEMA_VERSION,
+            phase,
+            tree,
+            completed: completed.iter().cloned().collect(),
+            active: active.iter().cloned().collect(),
+            failed: failed.iter().cloned().collect(),
+            elapsed_secs: timings.elapsed().as_secs_f32(),
+            warnings: timings.warnings,
+            durations: timings
+                .durations
+                .iter()
+                .map(|(name, duration)| (name.clone(), duration.as_secs_f32()))
+                .collect(),
+            log: log.iter().cloned().collect(),
+            timeline: timeline.clone(),
+            utilization: utilization.clone(),
+            aliases: aliases
+                .iter()
+                .map(|(name, alias)| (name.clone(), alias.clone()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+pub fn save(path: &Path, export: &BuildExport) {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).expect("Failed to create export directory");
+    }
+
+    fs::write(
+        path,
+        serde_json::to_string_pretty(export).expect("Failed to serialize build"),
+    )
+    .expect("Failed to write build export");
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load(path: &Path) -> Result<BuildExport, String> {
+    let raw = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    // Check the version on its own first so an old or newer file gets a clear
+    // message instead of whatever field happened to not match
+    let value: serde_json::Value =
+        serde_json::from_str(&raw).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let version = value.get("version").and_then(|v| v.as_u64());
+
+    if version != Some(SCHEMA_VERSION as u64) {
+        return Err(format!(
+            "{}: unsupported schema version {:?} (expected {})",
+            path.display(),
+            version,
+            SCHEMA_VERSION
+        ));
+    }
+
+    serde_json::from_value(value).map_err(|e| format!("{}: {}", path.display(), e))
+}
@@ -0,0 +1,244 @@
+// A C ABI over the parsing and layout engine. Build it as a shared library with
+//
+//     cargo rustc --lib --release --features ffi --crate-type cdylib
+//
+// Every pointer handed out is owned by the caller until passed back to the
+// matching `_free` function, and every pointer passed in has to be one of
+// those (or null, which is always rejected). Nothing here is thread safe.
+#![allow(clippy::missing_safety_doc)]
+
+use crate::dependency_tree::DependencyTree;
+use crate::drawing::node_id;
+use crate::layout::{self, LayoutView, Placement, SizeMetric};
+use crate::parse_cargo_tree_output::{parse_tree, TreeNode};
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::Arc;
+
+pub struct TreebuildGraph {
+    tree: DependencyTree,
+    // Preorder, so a node's parent always comes before it
+    nodes: Vec<(Arc<TreeNode>, CString, usize)>,
+    edges: Vec<TreebuildEdge>,
+}
+
+pub struct TreebuildLayout {
+    // Lined up with the graph's nodes
+    placements: Vec<Option<Placement>>,
+}
+
+#[repr(C)]
+pub struct TreebuildNode {
+    // Valid for as long as the graph is
+    pub name: *const c_char,
+    pub depth: usize,
+}
+
+// Indices into the graph's nodes, from a crate to one of its dependencies
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TreebuildEdge {
+    pub from: usize,
+    pub to: usize,
+}
+
+#[repr(C)]
+pub struct TreebuildPosition {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+}
+
+// Panics can't cross into C, so they become a null result instead
+fn catch<T>(f: impl FnOnce() -> T) -> Option<T> {
+    panic::catch_unwind(AssertUnwindSafe(f)).ok()
+}
+
+fn into_raw<T>(value: Option<T>) -> *mut T {
+    value.map_or(ptr::null_mut(), |value| Box::into_raw(Box::new(value)))
+}
+
+fn graph(root: Arc<TreeNode>) -> TreebuildGraph {
+    fn visit(
+        node: &Arc<TreeNode>,
+        depth: usize,
+        parent: Option<usize>,
+        graph: &mut TreebuildGraph,
+    ) {
+        let idx = graph.nodes.len();
+        let name = CString::new(node.name.clone()).unwrap_or_default();
+
+        graph.nodes.push((Arc::clone(node), name, depth));
+        if let Some(from) = parent {
+            graph.edges.push(TreebuildEdge { from, to: idx });
+        }
+
+        for child in node.children.iter() {
+            visit(child, depth + 1, Some(idx), graph);
+        }
+    }
+
+    let mut graph = TreebuildGraph {
+        tree: DependencyTree::new(Arc::clone(&root)),
+        nodes: Vec::new(),
+        edges: Vec::new(),
+    };
+    visit(&root, 0, None, &mut graph);
+    graph
+}
+
+// Runs `cargo tree` for the package in the current directory
+#[no_mangle]
+pub extern "C" fn treebuild_parse_project(dev_dependencies: bool) -> *mut TreebuildGraph {
+    into_raw(catch(|| {
+        graph(crate::project::cargo_tree(
+            dev_dependencies,
+            &crate::config::load().unwrap_or_default().color_rules,
+        ))
+    }))
+}
+
+// Takes `cargo tree --prefix depth --no-dedupe` output that was captured
+// some other way
+#[no_mangle]
+pub unsafe extern "C" fn treebuild_parse_tree(raw: *const c_char) -> *mut TreebuildGraph {
+    if raw.is_null() {
+        return ptr::null_mut();
+    }
+
+    let raw = CStr::from_ptr(raw).to_string_lossy().into_owned();
+    into_raw(catch(|| graph(parse_tree(raw))))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn treebuild_graph_free(graph: *mut TreebuildGraph) {
+    if !graph.is_null() {
+        drop(Box::from_raw(graph));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn treebuild_node_count(graph: *const TreebuildGraph) -> usize {
+    graph.as_ref().map_or(0, |graph| graph.nodes.len())
+}
+
+// False once `idx` runs past the last node
+#[no_mangle]
+pub unsafe extern "C" fn treebuild_node(
+    graph: *const TreebuildGraph,
+    idx: usize,
+    out: *mut TreebuildNode,
+) -> bool {
+    match (graph.as_ref(), out.as_mut()) {
+        (Some(graph), Some(out)) => match graph.nodes.get(idx) {
+            Some((_, name, depth)) => {
+                *out = TreebuildNode {
+                    name: name.as_ptr(),
+                    depth: *depth,
+                };
+                true
+            }
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn treebuild_edge_count(graph: *const TreebuildGraph) -> usize {
+    graph.as_ref().map_or(0, |graph| graph.edges.len())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn treebuild_edge(
+    graph: *const TreebuildGraph,
+    idx: usize,
+    out: *mut TreebuildEdge,
+) -> bool {
+    match (graph.as_ref(), out.as_mut()) {
+        (Some(graph), Some(out)) => match graph.edges.get(idx) {
+            Some(edge) => {
+                *out = *edge;
+                true
+            }
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+// Lays the whole graph out with one of `layout::LAYOUT_NAMES`, falling back to
+// the default for names it doesn't know
+#[no_mangle]
+pub unsafe extern "C" fn treebuild_layout(
+    graph: *const TreebuildGraph,
+    name: *const c_char,
+) -> *mut TreebuildLayout {
+    let graph = match graph.as_ref() {
+        Some(graph) => graph,
+        None => return ptr::null_mut(),
+    };
+    let name = if name.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(name).to_string_lossy().into_owned()
+    };
+
+    into_raw(catch(|| {
+        let weights = HashMap::new();
+        let view = LayoutView {
+            root: graph.tree.root(),
+            aggregate_depth: None,
+            expanded: None,
+            time: 0.0,
+            metric: SizeMetric::Dependencies,
+            weights: &weights,
+            fixed: &HashMap::new(),
+        };
+        let positions = layout::all()
+            .remove(layout::index_of(&name))
+            .place(&graph.tree, &view);
+
+        TreebuildLayout {
+            placements: graph
+                .nodes
+                .iter()
+                .map(|(node, _, _)| positions.nodes.get(&node_id(node)).copied())
+                .collect(),
+        }
+    }))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn treebuild_layout_free(layout: *mut TreebuildLayout) {
+    if !layout.is_null() {
+        drop(Box::from_raw(layout));
+    }
+}
+
+// Where the layout put node `idx`. False for nodes it left out, and once
+// `idx` runs past the last node.
+#[no_mangle]
+pub unsafe extern "C" fn treebuild_position(
+    layout: *const TreebuildLayout,
+    idx: usize,
+    out: *mut TreebuildPosition,
+) -> bool {
+    match (layout.as_ref(), out.as_mut()) {
+        (Some(layout), Some(out)) => match layout.placements.get(idx) {
+            Some(Some(placement)) => {
+                *out = TreebuildPosition {
+                    x: placement.center.0,
+                    y: placement.center.1,
+                    radius: placement.radius,
+                };
+                true
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
@@ -0,0 +1,602 @@
+// Predicates on crates such as `depth <= 3 and name ~ "tokio*"`, for the
+// filter panel. Comparisons go `field op value`, combine with and, or and not
+// (or &&, || and !) and group with parentheses. Names compare ignoring case,
+// and ~ matches a glob, where * is any run of characters and ? any one.
+use crate::parse_cargo_tree_output::TreeNode;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+// What a predicate can ask about a node, besides its name and how many
+// crates are below it
+pub struct Facts<'a> {
+    pub node: &'a TreeNode,
+    // The root being at 0
+    pub depth: usize,
+    // pending, active, completed, failed or fresh
+    pub state: &'a str,
+    // In seconds, None until it has built
+    pub build_time: Option<f32>,
+    pub category: &'a str,
+    pub license: Option<&'a str>,
+    pub version: Option<&'a str>,
+    // Distinct crates below, when they were counted ahead of time, see
+    // `DependencyTree::transitive_count`
+    pub dependencies: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Name,
+    Depth,
+    State,
+    BuildTime,
+    Dependencies,
+    Category,
+    License,
+    Version,
+}
+
+impl Field {
+    fn named(name: &str) -> Option<Self> {
+        Some(match name {
+            "name" => Field::Name,
+            "depth" => Field::Depth,
+            "state" => Field::State,
+            "build_time" | "time" => Field::BuildTime,
+            "dependencies" | "deps" => Field::Dependencies,
+            "category" => Field::Category,
+            "license" => Field::License,
+            "version" => Field::Version,
+            _ => return None,
+        })
+    }
+
+    fn numeric(self) -> bool {
+        matches!(self, Field::Depth | Field::BuildTime | Field::Dependencies)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Glob,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(f32),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Compare(Field, Op, Value),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    // Quoted, so never a keyword or a number
+    Text(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Word(word) => word.clone(),
+            Token::Text(text) => format!("\"{}\"", text),
+            Token::Op(op) => match op {
+                Op::Eq => "==",
+                Op::Ne => "!=",
+                Op::Lt => "<",
+                Op::Le => "<=",
+                Op::Gt => ">",
+                Op::Ge => ">=",
+                Op::Glob => "~",
+            }
+            .to_owned(),
+            Token::And => "and".to_owned(),
+            Token::Or => "or".to_owned(),
+            Token::Not => "not".to_owned(),
+            Token::Open => "(".to_owned(),
+            Token::Close => ")".to_owned(),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let token = match c {
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '~' => Token::Op(Op::Glob),
+            '"' => {
+                chars.next();
+                let mut quoted = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => quoted.push(c),
+                        None => return Err("unterminated string".to_owned()),
+                    }
+                }
+                tokens.push(Token::Text(quoted));
+                continue;
+            }
+            '&' | '|' | '=' | '!' | '<' | '>' => {
+                chars.next();
+                let (token, pair) = match (c, chars.peek()) {
+                    ('&', Some('&')) => (Token::And, true),
+                    ('|', Some('|')) => (Token::Or, true),
+                    ('=', Some('=')) => (Token::Op(Op::Eq), true),
+                    ('=', _) => (Token::Op(Op::Eq), false),
+                    ('!', Some('=')) => (Token::Op(Op::Ne), true),
+                    ('!', _) => (Token::Not, false),
+                    ('<', Some('=')) => (Token::Op(Op::Le), true),
+                    ('<', _) => (Token::Op(Op::Lt), false),
+                    ('>', Some('=')) => (Token::Op(Op::Ge), true),
+                    ('>', _) => (Token::Op(Op::Gt), false),
+                    (c, _) => return Err(format!("unexpected {}", c)),
+                };
+                if pair {
+                    chars.next();
+                }
+                tokens.push(token);
+                continue;
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "()~\"&|=!<>".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(match word.to_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Word(word),
+                });
+                continue;
+            }
+        };
+
+        chars.next();
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+// Seconds for times, like 5s, 200ms or 2m
+fn number(word: &str) -> Option<f32> {
+    let (digits, scale) = if let Some(digits) = word.strip_suffix("ms") {
+        (digits, 0.001)
+    } else if let Some(digits) = word.strip_suffix('s') {
+        (digits, 1.0)
+    } else if let Some(digits) = word.strip_suffix('m') {
+        (digits, 60.0)
+    } else {
+        (word, 1.0)
+    };
+
+    digits.parse::<f32>().ok().map(|value| value * scale)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn take(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.next).cloned();
+        self.next += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.next += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.take() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                match self.take() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err("missing )".to_owned()),
+                }
+            }
+            Some(Token::Word(name)) => self.comparison(&name),
+            Some(token) => Err(format!("expected a field, not {}", token.describe())),
+            None => Err("expected a field".to_owned()),
+        }
+    }
+
+    fn comparison(&mut self, name: &str) -> Result<Expr, String> {
+        let field =
+            Field::named(&name.to_lowercase()).ok_or_else(|| format!("unknown field {}", name))?;
+        let op = match self.take() {
+            Some(Token::Op(op)) => op,
+            _ => return Err(format!("expected a comparison after {}", name)),
+        };
+        let value = match self.take() {
+            Some(Token::Word(word)) if field.numeric() => {
+                Value::Number(number(&word).ok_or_else(|| format!("{} is not a number", word))?)
+            }
+            Some(Token::Word(word)) | Some(Token::Text(word)) if !field.numeric() => {
+                Value::Text(word.to_lowercase())
+            }
+            _ => return Err(format!("expected a value for {}", name)),
+        };
+
+        match (field.numeric(), op) {
+            (true, Op::Glob) => Err(format!("~ only goes with names, not {}", name)),
+            (false, Op::Lt | Op::Le | Op::Gt | Op::Ge) if field != Field::Version => {
+                Err(format!("{} can only be compared with ==, != or ~", name))
+            }
+            _ => Ok(Expr::Compare(field, op, value)),
+        }
+    }
+}
+
+fn glob(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|skip| glob(rest, &text[skip..])),
+        Some(('?', rest)) => !text.is_empty() && glob(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && glob(rest, &text[1..]),
+    }
+}
+
+// Versions compare part by part, as numbers where they are. Like semver has
+// it, a pre-release comes before its release and build metadata doesn't count.
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let parts = |parts: &str| {
+        parts
+            .split('.')
+            .map(|part| part.parse::<u64>().map_err(|_| part.to_owned()))
+            .collect::<Vec<_>>()
+    };
+    let split = |version: &str| {
+        let version = version.split('+').next().unwrap_or_default();
+        match version.split_once('-') {
+            Some((release, pre)) => (parts(release), Some(parts(pre))),
+            None => (parts(version), None),
+        }
+    };
+
+    let ((a, a_pre), (b, b_pre)) = (split(a), split(b));
+    a.cmp(&b).then_with(|| match (a_pre, b_pre) {
+        (Some(a_pre), Some(b_pre)) => a_pre.cmp(&b_pre),
+        (a_pre, b_pre) => b_pre.is_some().cmp(&a_pre.is_some()),
+    })
+}
+
+fn holds(op: Op, ordering: std::cmp::Ordering) -> bool {
+    use std::cmp::Ordering::*;
+
+    match op {
+        Op::Eq => ordering == Equal,
+        Op::Ne => ordering != Equal,
+        Op::Lt => ordering == Less,
+        Op::Le => ordering != Greater,
+        Op::Gt => ordering == Greater,
+        Op::Ge => ordering != Less,
+        Op::Glob => false,
+    }
+}
+
+impl Expr {
+    fn eval(&self, facts: &Facts) -> bool {
+        match self {
+            Expr::Not(expr) => !expr.eval(facts),
+            Expr::And(a, b) => a.eval(facts) && b.eval(facts),
+            Expr::Or(a, b) => a.eval(facts) || b.eval(facts),
+            Expr::Compare(field, op, Value::Number(wanted)) => {
+                let value = match field {
+                    Field::Depth => Some(facts.depth as f32),
+                    Field::BuildTime => facts.build_time,
+                    _ => Some(
+                        facts
+                            .dependencies
+                            .unwrap_or_else(|| facts.node.transitive_count())
+                            as f32,
+                    ),
+                };
+                value
+                    .and_then(|value| value.partial_cmp(wanted))
+                    .is_some_and(|ordering| holds(*op, ordering))
+            }
+            Expr::Compare(field, op, Value::Text(wanted)) => {
+                let value = match field {
+                    Field::Name => Some(facts.node.name.as_str()),
+                    Field::State => Some(facts.state),
+                    Field::Category => Some(facts.category),
+                    Field::License => facts.license,
+                    _ => facts.version,
+                };
+                let value = match value {
+                    Some(value) => value.to_lowercase(),
+                    // Nothing known is nothing alike
+                    None => return *op == Op::Ne,
+                };
+
+                match op {
+                    Op::Glob => glob(
+                        &wanted.chars().collect::<Vec<_>>(),
+                        &value.chars().collect::<Vec<_>>(),
+                    ),
+                    _ if *field == Field::Version => holds(*op, compare_versions(&value, wanted)),
+                    _ => holds(*op, value.cmp(wanted)),
+                }
+            }
+        }
+    }
+}
+
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            next: 0,
+        };
+        let expr = parser.or()?;
+
+        match parser.take() {
+            None => Ok(Filter { expr }),
+            Some(token) => Err(format!("unexpected {}", token.describe())),
+        }
+    }
+
+    pub fn matches(&self, facts: &Facts) -> bool {
+        self.expr.eval(facts)
+    }
+}
+
+// The tree with only the nodes `keep` accepts and the way down to them, the
+// root always staying. Subtrees that stay whole are the same nodes as before.
+pub fn retain(tree: &Arc<TreeNode>, keep: &dyn Fn(&Arc<TreeNode>, usize) -> bool) -> Arc<TreeNode> {
+    fn below(
+        node: &Arc<TreeNode>,
+        depth: usize,
+        keep: &dyn Fn(&Arc<TreeNode>, usize) -> bool,
+    ) -> Option<Arc<TreeNode>> {
+        let children = node
+            .children
+            .iter()
+            .filter_map(|child| below(child, depth + 1, keep))
+            .collect::<Vec<_>>();
+
+        let whole = children.len() == node.children.len()
+            && children
+                .iter()
+                .zip(node.children.iter())
+                .all(|(kept, child)| Arc::ptr_eq(kept, child));
+
+        if whole && (depth == 0 || keep(node, depth) || !node.children.is_empty()) {
+            Some(Arc::clone(node))
+        } else if depth == 0 || !children.is_empty() || keep(node, depth) {
+            Some(Arc::new(TreeNode {
+                name: node.name.clone(),
+                color: node.color,
+                children,
+            }))
+        } else {
+            None
+        }
+    }
+
+    below(tree, 0, keep).expect("The root always stays")
+}
+
+// The workspace root without the members in `hidden`, anything else as it is
+pub fn without_members(tree: &Arc<TreeNode>, hidden: &BTreeSet<String>) -> Arc<TreeNode> {
+    if !tree.is_workspace() || hidden.is_empty() {
+        return Arc::clone(tree);
+    }
+
+    Arc::new(TreeNode {
+        name: tree.name.clone(),
+        color: tree.color,
+        children: tree
+            .children
+            .iter()
+            .filter(|member| !hidden.contains(&member.name))
+            .cloned()
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, children: Vec<Arc<TreeNode>>) -> Arc<TreeNode> {
+        Arc::new(TreeNode {
+            name: name.to_owned(),
+            children,
+            color: (0, 0, 0),
+        })
+    }
+
+    fn facts<'a>(node: &'a TreeNode, version: Option<&'a str>) -> Facts<'a> {
+        Facts {
+            node,
+            depth: 1,
+            state: "completed",
+            build_time: Some(0.5),
+            category: "other",
+            license: None,
+            version,
+            dependencies: None,
+        }
+    }
+
+    fn compare(field: Field, op: Op, value: &str) -> Box<Expr> {
+        Box::new(Expr::Compare(field, op, Value::Text(value.to_owned())))
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and_and_and_than_or() {
+        let filter = Filter::parse("not name == a or name == b and state == active").unwrap();
+
+        assert_eq!(
+            filter.expr,
+            Expr::Or(
+                Box::new(Expr::Not(compare(Field::Name, Op::Eq, "a"))),
+                Box::new(Expr::And(
+                    compare(Field::Name, Op::Eq, "b"),
+                    compare(Field::State, Op::Eq, "active")
+                ))
+            )
+        );
+        assert_eq!(
+            Filter::parse("!name == a || name == b && state == active")
+                .unwrap()
+                .expr,
+            filter.expr
+        );
+    }
+
+    #[test]
+    fn times_take_units() {
+        let time = |text: &str| match Filter::parse(text).unwrap().expr {
+            Expr::Compare(Field::BuildTime, Op::Gt, Value::Number(secs)) => secs,
+            expr => panic!("{:?}", expr),
+        };
+
+        assert_eq!(time("time > 200ms"), 0.2);
+        assert_eq!(time("build_time > 5s"), 5.0);
+        assert_eq!(time("time > 2m"), 120.0);
+        assert_eq!(time("time > 1.5"), 1.5);
+        assert!(Filter::parse("time > 2h").is_err());
+
+        let node = node("a", Vec::new());
+        assert!(Filter::parse("time < 600ms")
+            .unwrap()
+            .matches(&facts(&node, None)));
+        assert!(!Filter::parse("time >= 1s")
+            .unwrap()
+            .matches(&facts(&node, None)));
+    }
+
+    #[test]
+    fn malformed_filters_are_errors() {
+        assert_eq!(
+            Filter::parse("name ~ \"tokio*").err().as_deref(),
+            Some("unterminated string")
+        );
+        assert!(Filter::parse("depth ~ 3").is_err());
+        assert!(Filter::parse("deps ~ \"1*\"").is_err());
+        assert!(Filter::parse("name > a").is_err());
+        assert!(Filter::parse("(depth < 3").is_err());
+        assert!(Filter::parse("depth < 3)").is_err());
+        assert!(Filter::parse("colour == red").is_err());
+    }
+
+    #[test]
+    fn versions_order_like_semver() {
+        let node = node("a", Vec::new());
+        let holds = |filter: &str, version: &str| {
+            Filter::parse(filter)
+                .unwrap()
+                .matches(&facts(&node, Some(version)))
+        };
+
+        assert!(holds("version > 1.9.0", "1.10.0"));
+        assert!(!holds("version < 1.9.0", "1.10.0"));
+        assert!(holds("version < 1.0.0", "1.0.0-alpha"));
+        assert!(holds("version > 1.0.0-alpha", "1.0.0-alpha.1"));
+        assert!(holds("version < 1.0.0-beta", "1.0.0-alpha.2"));
+        assert!(holds("version > 1.0.0-rc.9", "1.0.0-rc.10"));
+        assert!(holds("version == 1.0.0", "1.0.0+build.5"));
+        assert!(!holds("version >= 0.1.0", "0.0.9"));
+    }
+
+    #[test]
+    fn retain_keeps_the_way_down_and_shares_what_stays_whole() {
+        let whole = node("whole", vec![node("leaf", Vec::new())]);
+        let matched = node("match", Vec::new());
+        let path = node(
+            "path",
+            vec![
+                Arc::clone(&matched),
+                node("dropped", vec![node("gone", Vec::new())]),
+            ],
+        );
+        let root = node("root", vec![Arc::clone(&path), Arc::clone(&whole)]);
+
+        let kept = retain(&root, &|node, _| {
+            node.name == "match" || node.name == "leaf"
+        });
+
+        assert!(!Arc::ptr_eq(&kept, &root));
+        let names = |node: &TreeNode| {
+            node.children
+                .iter()
+                .map(|child| child.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&kept), ["path", "whole"]);
+        assert_eq!(names(&kept.children[0]), ["match"]);
+
+        // A new node for the path, which lost a child, but the same ones for
+        // what kept all of theirs
+        assert!(!Arc::ptr_eq(&kept.children[0], &path));
+        assert!(Arc::ptr_eq(&kept.children[0].children[0], &matched));
+        assert!(Arc::ptr_eq(&kept.children[1], &whole));
+
+        // Nothing dropped is the tree as it was
+        assert!(Arc::ptr_eq(&retain(&root, &|_, _| true), &root));
+        assert!(retain(&root, &|_, _| false).children.is_empty());
+    }
+}
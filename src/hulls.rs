@@ -0,0 +1,184 @@
+use crate::drawing::{node_id, DrawCrate, Point};
+use crate::package_info::PackageInfo;
+use crate::palette;
+use crate::parse_cargo_tree_output::TreeNode;
+use nannou::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+// Screen pixels between a crate and the hull around it
+const PADDING: f32 = 8.0;
+// Points on each crate's circle the hull goes around
+const SAMPLES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    Off,
+    // Under the same workspace member, or the same dependency of the root
+    // when the root is the only member
+    Members,
+    // Kept in the same GitHub (or other forge) organization
+    Owners,
+}
+
+impl Grouping {
+    pub fn next(self) -> Self {
+        match self {
+            Grouping::Off => Grouping::Members,
+            Grouping::Members => Grouping::Owners,
+            Grouping::Owners => Grouping::Off,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Grouping::Off => "off",
+            Grouping::Members => "by workspace member",
+            Grouping::Owners => "by owner",
+        }
+    }
+}
+
+// The organization in a repository URL, with the forge unless it is GitHub
+fn owner(repository: &str) -> Option<String> {
+    let path = repository
+        .split_once("://")
+        .map_or(repository, |(_, path)| path);
+    let mut parts = path.split('/').filter(|part| !part.is_empty());
+    let host = parts.next()?.trim_start_matches("www.");
+    let org = parts.next()?.to_lowercase();
+
+    if host == "github.com" {
+        Some(org)
+    } else {
+        Some(format!("{}/{}", host, org))
+    }
+}
+
+// The group of every node under `root`, by `node_id`. The root and crates
+// nothing says anything about are in none.
+pub fn groups(
+    root: &Arc<TreeNode>,
+    grouping: Grouping,
+    metadata: &HashMap<String, Arc<PackageInfo>>,
+) -> HashMap<usize, String> {
+    fn members(
+        node: &Arc<TreeNode>,
+        group: &str,
+        metadata: &HashMap<String, Arc<PackageInfo>>,
+        into: &mut HashMap<usize, String>,
+    ) {
+        let local = metadata.get(&node.name).is_some_and(|info| info.local);
+        let group = if local { &node.name } else { group };
+
+        if into.insert(node_id(node), group.to_owned()).is_none() {
+            for child in node.children.iter() {
+                members(child, group, metadata, into);
+            }
+        }
+    }
+
+    let mut into = HashMap::new();
+    match grouping {
+        Grouping::Off => {}
+        Grouping::Members => {
+            for child in root.children.iter() {
+                members(child, &child.name, metadata, &mut into);
+            }
+        }
+        Grouping::Owners => {
+            let mut level = root.children.iter().collect::<Vec<_>>();
+            while !level.is_empty() {
+                for node in level.iter() {
+                    let owner = metadata
+                        .get(&node.name)
+                        .and_then(|info| owner(info.repository.as_deref()?));
+                    if let Some(owner) = owner {
+                        into.insert(node_id(node), owner);
+                    }
+                }
+                level = level
+                    .into_iter()
+                    .flat_map(|node| node.children.iter())
+                    .collect();
+            }
+        }
+    }
+    into
+}
+
+fn cross(o: Point, a: Point, b: Point) -> f32 {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
+
+// Counterclockwise, by Andrew's monotone chain
+fn convex_hull(mut points: Vec<Point>) -> Vec<Point> {
+    points.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    if points.len() < 3 {
+        return points;
+    }
+
+    let mut hull: Vec<Point> = Vec::with_capacity(points.len() * 2);
+    for pass in 0..2 {
+        let start = hull.len();
+        for &point in points.iter() {
+            while hull.len() >= start + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        // Each chain ends where the other starts
+        hull.pop();
+        if pass == 0 {
+            points.reverse();
+        }
+    }
+    hull
+}
+
+// A translucent blob under each group of two or more crates, labelled above
+// it, with crates already in screen coordinates
+pub fn draw_hulls(draw: &Draw, crates: &[DrawCrate], groups: &HashMap<usize, String>) {
+    let mut members = BTreeMap::<&str, Vec<&DrawCrate>>::new();
+    for draw_crate in crates {
+        if let Some(group) = groups.get(&node_id(&draw_crate.tree)) {
+            members.entry(group).or_default().push(draw_crate);
+        }
+    }
+
+    for (group, crates) in members {
+        if crates.len() < 2 {
+            continue;
+        }
+
+        let outline = crates
+            .iter()
+            .flat_map(|draw_crate| {
+                let (x, y) = draw_crate.center;
+                let radius = draw_crate.radius + PADDING;
+                (0..SAMPLES).map(move |idx| {
+                    let angle = idx as f32 * TAU / SAMPLES as f32;
+                    (x + radius * angle.cos(), y + radius * angle.sin())
+                })
+            })
+            .collect();
+        let hull = convex_hull(outline);
+
+        let (r, g, b) = palette::color_for(group);
+        draw.polygon()
+            .color(srgba(r, g, b, 40))
+            .points(hull.iter().map(|&(x, y)| pt2(x, y)));
+
+        let top = hull
+            .iter()
+            .copied()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap_or_default();
+        draw.text(group)
+            .color(srgba(r, g, b, 200))
+            .x_y(top.0, top.1 + 10.0)
+            .w_h(300.0, 20.0);
+    }
+}
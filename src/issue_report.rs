@@ -0,0 +1,130 @@
+use crate::dependency_tree::DependencyTree;
+use crate::package_info::PackageInfo;
+use crate::ring_buffer::RingBuffer;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+
+fn command_output(mut command: Command, args: &[&str]) -> String {
+    let program = command.get_program().to_string_lossy().into_owned();
+
+    match command.args(args).output() {
+        Ok(output) if output.status.success() => {
+            String::from_utf8_lossy(&output.stdout).trim().to_owned()
+        }
+        _ => format!("({} {} failed)", program, args.join(" ")),
+    }
+}
+
+// Everything cargo printed at the error level, from the crate's "Compiling"
+// line up to its "could not compile". Builds run in parallel, so other crates'
+// warnings may sit in between, those are left out.
+fn diagnostics(name: &str, log: &RingBuffer<String>) -> Vec<String> {
+    let underscored = name.replace("-", "_");
+    let lines = log.iter().collect::<Vec<_>>();
+    let end = lines
+        .iter()
+        .rposition(|line| {
+            line.starts_with("error: could not compile `")
+                && (line.contains(&format!("`{}`", name))
+                    || line.contains(&format!("`{}`", underscored)))
+        })
+        .unwrap_or(lines.len());
+    let start = lines[..end]
+        .iter()
+        .rposition(|line| {
+            let mut words = line.trim().split(' ');
+            matches!(words.next(), Some("Compiling") | Some("Checking"))
+                && matches!(words.next(), Some(word) if word == name || word == underscored)
+        })
+        .unwrap_or(0);
+
+    let mut kept = Vec::new();
+    let mut in_error = false;
+    for line in lines[start..end.min(lines.len())].iter() {
+        if line.starts_with("error") {
+            in_error = true;
+        } else if line.starts_with("warning") || line.trim_start().starts_with("Compiling") {
+            in_error = false;
+        }
+
+        if in_error {
+            kept.push((*line).clone());
+        }
+    }
+
+    kept
+}
+
+fn version_row(name: &str, metadata: &HashMap<String, Arc<PackageInfo>>) -> String {
+    match metadata.get(name) {
+        Some(info) => format!(
+            "| {} | {} | {} |",
+            name,
+            info.version,
+            if info.features.is_empty() {
+                "-".to_owned()
+            } else {
+                info.features.join(", ")
+            }
+        ),
+        None => format!("| {} | ? | ? |", name),
+    }
+}
+
+// A markdown bug report for a crate that failed to build, with what the
+// upstream maintainers are likely to ask for first
+pub fn write(
+    name: &str,
+    tree: &DependencyTree,
+    metadata: &HashMap<String, Arc<PackageInfo>>,
+    log: &RingBuffer<String>,
+) -> PathBuf {
+    let version = metadata
+        .get(name)
+        .map(|info| format!(" {}", info.version))
+        .unwrap_or_default();
+    let mut report = String::new();
+
+    writeln!(report, "# `{}{}` fails to build\n", name, version).unwrap();
+
+    report.push_str("## Error\n\n```text\n");
+    for line in diagnostics(name, log) {
+        report.push_str(&line);
+        report.push('\n');
+    }
+    report.push_str("```\n\n");
+
+    report.push_str("## Versions\n\n| crate | version | features |\n|---|---|---|\n");
+    writeln!(report, "{}", version_row(name, metadata)).unwrap();
+    if let Some(node) = tree.by_name(name) {
+        let mut children = node
+            .children
+            .iter()
+            .map(|child| child.name.as_str())
+            .collect::<Vec<_>>();
+        children.sort_unstable();
+        children.dedup();
+
+        for child in children {
+            writeln!(report, "{}", version_row(child, metadata)).unwrap();
+        }
+    }
+
+    writeln!(
+        report,
+        "\n## Toolchain\n\n```text\n{}\n{}\n```",
+        command_output(Command::new("rustc"), &["-vV"]),
+        command_output(crate::project::cargo(), &["-V"])
+    )
+    .unwrap();
+
+    let path = crate::project::state_dir().join(format!("issue-{}.md", name));
+    fs::create_dir_all(crate::project::state_dir()).expect("Failed to create state directory");
+    fs::write(&path, report).expect("Failed to write issue report");
+
+    path
+}
@@ -0,0 +1,708 @@
+use nannou::prelude::*;
+use nannou::winit::event::ModifiersState;
+use std::collections::HashMap;
+
+// Everything the window does from the keyboard, outside of typing into the
+// search, filter and note boxes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Fit,
+    ZoomIn,
+    ZoomOut,
+    ToggleAggregate,
+    ToggleExplore,
+    Filter,
+    Note,
+    Unfix,
+    Search,
+    CancelBuild,
+    RestartBuild,
+    CopyPath,
+    CopyInverseTree,
+    ColorByCategory,
+    ColorByLicense,
+    OpenSource,
+    OpenDocs,
+    ShowOutdated,
+    HideRemoved,
+    PlayHistory,
+    PreviousRevision,
+    NextRevision,
+    ToggleBreakdown,
+    ToggleMinimap,
+    NextGrouping,
+    ToggleHud,
+    SelectParent,
+    SelectChild,
+    SelectPrevious,
+    SelectNext,
+    ToggleLayout,
+    ShareCrates,
+    ToggleBundles,
+    ToggleArrows,
+    NextSizeMetric,
+    ToggleSizedNodes,
+    IssueReport,
+    TogglePin,
+    NextTheme,
+    ToggleWarningTrends,
+    ToggleUtilization,
+    ToggleParallelism,
+    ToggleTimeline,
+    Screenshot,
+    ResetRoot,
+    Help,
+    // By their place in `ALL_CATEGORIES`
+    Category(usize),
+    // By their place among the workspace root's children
+    Member(usize),
+}
+
+// Each action's name in the config, what the help says it does and the keys
+// it is on unless the config says otherwise
+const ACTIONS: &[(Action, &str, &str, &[&str])] = &[
+    (Action::Fit, "fit", "fit the tree to the window", &["f"]),
+    (Action::ZoomIn, "zoom_in", "zoom in", &["+", "="]),
+    (Action::ZoomOut, "zoom_out", "zoom out", &["-"]),
+    (
+        Action::ToggleAggregate,
+        "toggle_aggregate",
+        "fold deep crates",
+        &["a"],
+    ),
+    (
+        Action::ToggleExplore,
+        "toggle_explore",
+        "expand crates one at a time",
+        &["e"],
+    ),
+    (Action::Filter, "filter", "filter crates", &["q"]),
+    (
+        Action::Note,
+        "note",
+        "write a note on the selection",
+        &["y"],
+    ),
+    (
+        Action::Unfix,
+        "unfix",
+        "let the selection move again",
+        &["delete"],
+    ),
+    (Action::Search, "search", "search crates", &["/"]),
+    (
+        Action::CancelBuild,
+        "cancel_build",
+        "cancel the build",
+        &["ctrl+c"],
+    ),
+    (
+        Action::RestartBuild,
+        "restart_build",
+        "build again from the start",
+        &["ctrl+r"],
+    ),
+    (
+        Action::CopyPath,
+        "copy_path",
+        "copy the path to the selection",
+        &["shift+c"],
+    ),
+    (
+        Action::CopyInverseTree,
+        "copy_inverse_tree",
+        "copy what depends on the selection",
+        &["shift+i"],
+    ),
+    (
+        Action::ColorByCategory,
+        "color_by_category",
+        "color crates by category",
+        &["c"],
+    ),
+    (
+        Action::ColorByLicense,
+        "color_by_license",
+        "color crates by license",
+        &["o"],
+    ),
+    (
+        Action::OpenSource,
+        "open_source",
+        "open the selection's source",
+        &["shift+o"],
+    ),
+    (
+        Action::OpenDocs,
+        "open_docs",
+        "open the selection's docs",
+        &["shift+d"],
+    ),
+    (
+        Action::ShowOutdated,
+        "show_outdated",
+        "mark outdated crates",
+        &["n"],
+    ),
+    (
+        Action::HideRemoved,
+        "hide_removed",
+        "hide crates a diff removed",
+        &["x"],
+    ),
+    (
+        Action::PlayHistory,
+        "play_history",
+        "play or pause the lockfile history",
+        &["space"],
+    ),
+    (
+        Action::PreviousRevision,
+        "previous_revision",
+        "the lockfile before",
+        &["["],
+    ),
+    (
+        Action::NextRevision,
+        "next_revision",
+        "the lockfile after",
+        &["]"],
+    ),
+    (
+        Action::ToggleBreakdown,
+        "toggle_breakdown",
+        "show the breakdown",
+        &["b"],
+    ),
+    (
+        Action::ToggleMinimap,
+        "toggle_minimap",
+        "show the minimap",
+        &["v"],
+    ),
+    (
+        Action::NextGrouping,
+        "next_grouping",
+        "group crates differently",
+        &["j"],
+    ),
+    (Action::ToggleHud, "toggle_hud", "show the HUD", &["s"]),
+    (
+        Action::SelectParent,
+        "select_parent",
+        "select what depends on it",
+        &["up"],
+    ),
+    (
+        Action::SelectChild,
+        "select_child",
+        "select its first dependency",
+        &["down"],
+    ),
+    (
+        Action::SelectPrevious,
+        "select_previous",
+        "select the sibling before",
+        &["left"],
+    ),
+    (
+        Action::SelectNext,
+        "select_next",
+        "select the sibling after",
+        &["right"],
+    ),
+    (Action::ToggleLayout, "toggle_layout", "next layout", &["l"]),
+    (
+        Action::ShareCrates,
+        "share_crates",
+        "show shared crates once",
+        &["g"],
+    ),
+    (
+        Action::ToggleBundles,
+        "toggle_bundles",
+        "bundle edges to repeated crates",
+        &["h"],
+    ),
+    (
+        Action::ToggleArrows,
+        "toggle_arrows",
+        "show arrowheads",
+        &["d"],
+    ),
+    (
+        Action::NextSizeMetric,
+        "next_size_metric",
+        "size crates differently",
+        &["m"],
+    ),
+    (
+        Action::ToggleSizedNodes,
+        "toggle_sized_nodes",
+        "size every layout's crates",
+        &["z"],
+    ),
+    (
+        Action::IssueReport,
+        "issue_report",
+        "write an issue report",
+        &["r"],
+    ),
+    (
+        Action::TogglePin,
+        "toggle_pin",
+        "pin the selection's path",
+        &["i"],
+    ),
+    (Action::NextTheme, "next_theme", "next theme", &["k"]),
+    (
+        Action::ToggleWarningTrends,
+        "toggle_warning_trends",
+        "show warning trends",
+        &["w"],
+    ),
+    (
+        Action::ToggleUtilization,
+        "toggle_utilization",
+        "show core utilization",
+        &["u"],
+    ),
+    (
+        Action::ToggleParallelism,
+        "toggle_parallelism",
+        "show parallelism",
+        &["p"],
+    ),
+    (
+        Action::ToggleTimeline,
+        "toggle_timeline",
+        "show the timeline",
+        &["t"],
+    ),
+    (
+        Action::Screenshot,
+        "screenshot",
+        "save a screenshot",
+        &["ctrl+s"],
+    ),
+    (
+        Action::ResetRoot,
+        "reset_root",
+        "show the whole tree again",
+        &["backspace"],
+    ),
+    (Action::Help, "help", "show these keys", &["?"]),
+    (
+        Action::Category(0),
+        "category_1",
+        "show the 1st category",
+        &["1"],
+    ),
+    (
+        Action::Category(1),
+        "category_2",
+        "show the 2nd category",
+        &["2"],
+    ),
+    (
+        Action::Category(2),
+        "category_3",
+        "show the 3rd category",
+        &["3"],
+    ),
+    (
+        Action::Category(3),
+        "category_4",
+        "show the 4th category",
+        &["4"],
+    ),
+    (
+        Action::Category(4),
+        "category_5",
+        "show the 5th category",
+        &["5"],
+    ),
+    (
+        Action::Category(5),
+        "category_6",
+        "show the 6th category",
+        &["6"],
+    ),
+    (
+        Action::Category(6),
+        "category_7",
+        "show the 7th category",
+        &["7"],
+    ),
+    (
+        Action::Member(0),
+        "member_1",
+        "hide the 1st member",
+        &["ctrl+1"],
+    ),
+    (
+        Action::Member(1),
+        "member_2",
+        "hide the 2nd member",
+        &["ctrl+2"],
+    ),
+    (
+        Action::Member(2),
+        "member_3",
+        "hide the 3rd member",
+        &["ctrl+3"],
+    ),
+    (
+        Action::Member(3),
+        "member_4",
+        "hide the 4th member",
+        &["ctrl+4"],
+    ),
+    (
+        Action::Member(4),
+        "member_5",
+        "hide the 5th member",
+        &["ctrl+5"],
+    ),
+    (
+        Action::Member(5),
+        "member_6",
+        "hide the 6th member",
+        &["ctrl+6"],
+    ),
+    (
+        Action::Member(6),
+        "member_7",
+        "hide the 7th member",
+        &["ctrl+7"],
+    ),
+    (
+        Action::Member(7),
+        "member_8",
+        "hide the 8th member",
+        &["ctrl+8"],
+    ),
+    (
+        Action::Member(8),
+        "member_9",
+        "hide the 9th member",
+        &["ctrl+9"],
+    ),
+];
+
+// Keys named by what they type, or by name for the rest
+const KEYS: &[(&str, Key)] = &[
+    ("a", Key::A),
+    ("b", Key::B),
+    ("c", Key::C),
+    ("d", Key::D),
+    ("e", Key::E),
+    ("f", Key::F),
+    ("g", Key::G),
+    ("h", Key::H),
+    ("i", Key::I),
+    ("j", Key::J),
+    ("k", Key::K),
+    ("l", Key::L),
+    ("m", Key::M),
+    ("n", Key::N),
+    ("o", Key::O),
+    ("p", Key::P),
+    ("q", Key::Q),
+    ("r", Key::R),
+    ("s", Key::S),
+    ("t", Key::T),
+    ("u", Key::U),
+    ("v", Key::V),
+    ("w", Key::W),
+    ("x", Key::X),
+    ("y", Key::Y),
+    ("z", Key::Z),
+    ("0", Key::Key0),
+    ("1", Key::Key1),
+    ("2", Key::Key2),
+    ("3", Key::Key3),
+    ("4", Key::Key4),
+    ("5", Key::Key5),
+    ("6", Key::Key6),
+    ("7", Key::Key7),
+    ("8", Key::Key8),
+    ("9", Key::Key9),
+    ("/", Key::Slash),
+    ("=", Key::Equals),
+    ("-", Key::Minus),
+    ("[", Key::LBracket),
+    ("]", Key::RBracket),
+    (",", Key::Comma),
+    (".", Key::Period),
+    (";", Key::Semicolon),
+    ("'", Key::Apostrophe),
+    ("`", Key::Grave),
+    ("\\", Key::Backslash),
+    ("space", Key::Space),
+    ("delete", Key::Delete),
+    ("backspace", Key::Back),
+    ("tab", Key::Tab),
+    ("home", Key::Home),
+    ("end", Key::End),
+    ("pageup", Key::PageUp),
+    ("pagedown", Key::PageDown),
+    ("up", Key::Up),
+    ("down", Key::Down),
+    ("left", Key::Left),
+    ("right", Key::Right),
+    ("f1", Key::F1),
+    ("f2", Key::F2),
+    ("f3", Key::F3),
+    ("f4", Key::F4),
+    ("f5", Key::F5),
+    ("f6", Key::F6),
+    ("f7", Key::F7),
+    ("f8", Key::F8),
+    ("f9", Key::F9),
+    ("f10", Key::F10),
+    ("f11", Key::F11),
+    ("f12", Key::F12),
+];
+
+// What shift makes of a key on a US layout, for binding them by what they type
+const SHIFTED: &[(&str, Key)] = &[("?", Key::Slash), ("+", Key::Equals)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Chord {
+    key: Key,
+    ctrl: bool,
+    shift: bool,
+}
+
+impl Chord {
+    // Such as "k", "shift+c", "ctrl+r" or "?". A key that is a + itself goes
+    // last, as in "ctrl++".
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().to_lowercase();
+        let (modifiers, key) = if text == "+" {
+            ("", "+")
+        } else if let Some(modifiers) = text.strip_suffix("++") {
+            (modifiers, "+")
+        } else {
+            text.rsplit_once('+').unwrap_or(("", &text))
+        };
+
+        let mut chord = match SHIFTED.iter().find(|(name, _)| *name == key) {
+            Some(&(_, key)) => Chord {
+                key,
+                ctrl: false,
+                shift: true,
+            },
+            None => Chord {
+                key: KEYS.iter().find(|(name, _)| *name == key)?.1,
+                ctrl: false,
+                shift: false,
+            },
+        };
+
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            match modifier {
+                "ctrl" | "control" => chord.ctrl = true,
+                "shift" => chord.shift = true,
+                _ => return None,
+            }
+        }
+        Some(chord)
+    }
+}
+
+impl Action {
+    pub fn name(self) -> &'static str {
+        ACTIONS
+            .iter()
+            .find(|(action, ..)| *action == self)
+            .map_or("", |(_, name, ..)| name)
+    }
+}
+
+// With each key as written, for the help
+pub struct Keymap {
+    bindings: Vec<(Chord, String, Action)>,
+}
+
+impl Keymap {
+    // The built-in keys, with those in the config's [keys] table (a key or
+    // several, space separated, by action name) instead of the built-in ones
+    // for the actions it names
+    pub fn new(overrides: &HashMap<String, String>) -> Self {
+        let mut bindings = Vec::new();
+
+        for &(action, name, _, defaults) in ACTIONS {
+            let keys = match overrides.get(name) {
+                Some(keys) => keys.split_whitespace().collect::<Vec<_>>(),
+                None => defaults.to_vec(),
+            };
+
+            for key in keys {
+                match Chord::parse(key) {
+                    Some(chord) => bindings.push((chord, key.to_owned(), action)),
+                    None => eprintln!("Ignoring unknown key {} for {}", key, name),
+                }
+            }
+        }
+
+        for name in overrides.keys() {
+            if !ACTIONS.iter().any(|(_, known, ..)| known == name) {
+                eprintln!("Ignoring keys for unknown action {}", name);
+            }
+        }
+
+        // What the config binds wins over a built-in key it takes over
+        let taken = bindings
+            .iter()
+            .filter(|(_, _, action)| overrides.contains_key(action.name()))
+            .map(|&(chord, ..)| chord)
+            .collect::<Vec<_>>();
+        bindings.retain(|(chord, _, action)| {
+            overrides.contains_key(action.name()) || !taken.contains(chord)
+        });
+
+        Keymap { bindings }
+    }
+
+    pub fn action(&self, key: Key, mods: ModifiersState) -> Option<Action> {
+        let pressed = Chord {
+            key,
+            ctrl: mods.ctrl(),
+            shift: mods.shift(),
+        };
+
+        self.bindings
+            .iter()
+            .find(|(chord, ..)| *chord == pressed)
+            .map(|&(_, _, action)| action)
+    }
+
+    // As written in the config, space separated, None when it is on none
+    pub fn keys(&self, action: Action) -> Option<String> {
+        let keys = self
+            .bindings
+            .iter()
+            .filter(|(_, _, bound)| *bound == action)
+            .map(|(_, key, _)| key.as_str())
+            .collect::<Vec<_>>();
+        Some(keys.join(" ")).filter(|_| !keys.is_empty())
+    }
+
+    // Each action that is on a key, with its keys and what it does
+    pub fn help(&self) -> Vec<(String, &'static str)> {
+        ACTIONS
+            .iter()
+            .filter_map(|&(action, _, description, _)| Some((self.keys(action)?, description)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modifiers_alone_do_nothing_on_the_way_to_a_chord() {
+        let keymap = Keymap::new(&HashMap::new());
+
+        for key in [Key::LShift, Key::RShift, Key::LControl, Key::RControl] {
+            assert_eq!(keymap.action(key, ModifiersState::SHIFT), None);
+            assert_eq!(keymap.action(key, ModifiersState::CTRL), None);
+        }
+        assert_eq!(
+            keymap.action(Key::C, ModifiersState::SHIFT),
+            Some(Action::CopyPath)
+        );
+        assert_eq!(
+            keymap.action(Key::I, ModifiersState::SHIFT),
+            Some(Action::CopyInverseTree)
+        );
+        assert_eq!(
+            keymap.action(Key::O, ModifiersState::SHIFT),
+            Some(Action::OpenSource)
+        );
+        assert_eq!(
+            keymap.action(Key::D, ModifiersState::SHIFT),
+            Some(Action::OpenDocs)
+        );
+    }
+
+    #[test]
+    fn only_its_own_key_resets_the_root() {
+        let keymap = Keymap::new(&HashMap::new());
+
+        assert_eq!(
+            keymap.action(Key::Back, ModifiersState::empty()),
+            Some(Action::ResetRoot)
+        );
+        assert_eq!(keymap.action(Key::F9, ModifiersState::empty()), None);
+        assert_eq!(
+            keymap.action(Key::R, ModifiersState::CTRL),
+            Some(Action::RestartBuild)
+        );
+    }
+
+    #[test]
+    fn shift_tells_apart_what_it_types() {
+        let keymap = Keymap::new(&HashMap::new());
+
+        assert_eq!(
+            keymap.action(Key::Slash, ModifiersState::empty()),
+            Some(Action::Search)
+        );
+        assert_eq!(
+            keymap.action(Key::Slash, ModifiersState::SHIFT),
+            Some(Action::Help)
+        );
+        assert_eq!(
+            keymap.action(Key::Equals, ModifiersState::SHIFT),
+            Some(Action::ZoomIn)
+        );
+    }
+
+    #[test]
+    fn config_keys_replace_the_defaults_and_take_others_over() {
+        let overrides = vec![("issue_report".to_owned(), "ctrl+i f2".to_owned())]
+            .into_iter()
+            .collect();
+        let keymap = Keymap::new(&overrides);
+
+        assert_eq!(keymap.action(Key::R, ModifiersState::empty()), None);
+        assert_eq!(
+            keymap.action(Key::F2, ModifiersState::empty()),
+            Some(Action::IssueReport)
+        );
+        assert_eq!(
+            keymap.keys(Action::IssueReport).as_deref(),
+            Some("ctrl+i f2")
+        );
+
+        let overrides = vec![("search".to_owned(), "f".to_owned())]
+            .into_iter()
+            .collect();
+        let keymap = Keymap::new(&overrides);
+        assert_eq!(
+            keymap.action(Key::F, ModifiersState::empty()),
+            Some(Action::Search)
+        );
+        assert_eq!(keymap.keys(Action::Fit), None);
+    }
+
+    #[test]
+    fn chords_parse_like_they_are_written() {
+        let plus = Chord::parse("ctrl++").unwrap();
+        assert_eq!(
+            plus,
+            Chord {
+                key: Key::Equals,
+                ctrl: true,
+                shift: true
+            }
+        );
+        assert_eq!(Chord::parse("Shift+C"), Chord::parse("shift+c"));
+        assert_eq!(Chord::parse("hyper+c"), None);
+        assert_eq!(Chord::parse("nope"), None);
+    }
+}
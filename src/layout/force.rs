@@ -0,0 +1,209 @@
+use super::{parallel, Expansion, Layout, LayoutView, Placement, Positions};
+use crate::dependency_tree::DependencyTree;
+use crate::drawing::{node_id, Point};
+use std::collections::{HashMap, HashSet};
+
+// Distance an edge settles at
+const IDEAL_EDGE: f32 = 90.0;
+const START_TEMPERATURE: f32 = 60.0;
+const COOLING: f32 = 0.95;
+const MIN_TEMPERATURE: f32 = 0.5;
+// Pair visits per frame, the repulsion is quadratic so big trees get fewer
+// iterations per frame and settle over a longer time
+const ITERATION_BUDGET: usize = 2_000_000;
+const MAX_ITERATIONS: usize = 10;
+
+// Fruchterman-Reingold: every node pushes every other away, edges pull their
+// ends together, and moves shrink as the layout cools. Positions carry over
+// between frames so the layout settles over time instead of being solved from
+// scratch, and reheats whenever the visible nodes change.
+pub struct ForceLayout {
+    positions: HashMap<usize, Point>,
+    visible: HashSet<usize>,
+    temperature: f32,
+}
+
+impl ForceLayout {
+    pub fn new() -> Self {
+        ForceLayout {
+            positions: HashMap::new(),
+            visible: HashSet::new(),
+            temperature: START_TEMPERATURE,
+        }
+    }
+}
+
+impl Default for ForceLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn radius_at(depth: usize) -> f32 {
+    if depth == 0 {
+        40.0
+    } else {
+        f32::max(8.0, 24.0 - depth as f32 * 4.0)
+    }
+}
+
+// Spreads new nodes around their parent by id so they don't start stacked
+fn spawn_offset(id: usize) -> Point {
+    let angle = (id / 8) as f32 * 2.399_963;
+    (
+        angle.cos() * IDEAL_EDGE * 0.5,
+        angle.sin() * IDEAL_EDGE * 0.5,
+    )
+}
+
+impl Layout for ForceLayout {
+    fn place(&mut self, _tree: &DependencyTree, view: &LayoutView) -> Positions {
+        let (nodes, edges) = view.graph();
+        let ids = nodes
+            .iter()
+            .map(|(node, _)| node_id(node))
+            .collect::<Vec<_>>();
+        let parents = edges
+            .iter()
+            .map(|&(parent, child)| (child, parent))
+            .collect::<HashMap<_, _>>();
+
+        let visible = ids.iter().copied().collect::<HashSet<_>>();
+        if visible != self.visible {
+            self.visible = visible;
+            self.temperature = START_TEMPERATURE;
+        }
+
+        // A parent is usually reached first, so its point is known by the
+        // time a child needs a starting point
+        let mut points: Vec<Point> = Vec::with_capacity(nodes.len());
+        for (idx, &id) in ids.iter().enumerate() {
+            let point = match self.positions.get(&id) {
+                Some(&point) => point,
+                None => {
+                    let parent = parents
+                        .get(&idx)
+                        .and_then(|&parent| points.get(parent))
+                        .copied()
+                        .unwrap_or((0.0, 0.0));
+                    let offset = spawn_offset(id);
+                    (parent.0 + offset.0, parent.1 + offset.1)
+                }
+            };
+            points.push(point);
+        }
+        if let Some(root) = points.first_mut() {
+            *root = (0.0, 0.0);
+        }
+
+        // Crates placed by hand don't move, and the rest settle around them
+        // again whenever one does
+        let mut fixed = vec![false; points.len()];
+        for (idx, (node, _)) in nodes.iter().enumerate() {
+            if let Some(&point) = view.fixed.get(&node.name) {
+                if points[idx] != point {
+                    points[idx] = point;
+                    self.temperature = START_TEMPERATURE;
+                }
+                fixed[idx] = true;
+            }
+        }
+
+        let iterations =
+            (ITERATION_BUDGET / (points.len() * points.len()).max(1)).clamp(1, MAX_ITERATIONS);
+
+        for _ in 0..iterations {
+            if self.temperature <= MIN_TEMPERATURE {
+                break;
+            }
+
+            // Every pair twice, once from either end, so each node's push
+            // can be added up on its own
+            let mut moves = parallel::map_indices(points.len(), |a| {
+                let mut push = (0.0f32, 0.0f32);
+                for (b, other) in points.iter().enumerate() {
+                    if a == b {
+                        continue;
+                    }
+                    let (dx, dy) = (points[a].0 - other.0, points[a].1 - other.1);
+                    let distance = (dx * dx + dy * dy).sqrt().max(0.01);
+                    let force = IDEAL_EDGE * IDEAL_EDGE / distance;
+
+                    push.0 += dx / distance * force;
+                    push.1 += dy / distance * force;
+                }
+                push
+            });
+
+            for &(a, b) in edges.iter() {
+                let (dx, dy) = (points[a].0 - points[b].0, points[a].1 - points[b].1);
+                let distance = (dx * dx + dy * dy).sqrt().max(0.01);
+                let force = distance * distance / IDEAL_EDGE;
+                let (fx, fy) = (dx / distance * force, dy / distance * force);
+
+                moves[a].0 -= fx;
+                moves[a].1 -= fy;
+                moves[b].0 += fx;
+                moves[b].1 += fy;
+            }
+
+            // The root stays pinned at the origin so the layout doesn't drift
+            for (idx, (mx, my)) in moves.into_iter().enumerate().skip(1) {
+                if fixed[idx] {
+                    continue;
+                }
+                let length = (mx * mx + my * my).sqrt().max(0.01);
+                let step = length.min(self.temperature);
+
+                points[idx].0 += mx / length * step;
+                points[idx].1 += my / length * step;
+            }
+
+            self.temperature *= COOLING;
+        }
+
+        // Nothing keeps nodes out of the way of unrelated edges here
+        let mut positions = Positions {
+            curved_edges: true,
+            ..Positions::default()
+        };
+
+        for ((node, depth), point) in nodes.iter().zip(points.iter()) {
+            let id = node_id(node);
+            let radius = radius_at(*depth);
+
+            self.positions.insert(id, *point);
+            positions
+                .nodes
+                .insert(id, Placement::circle(*point, radius));
+
+            // Folded subtrees sit just outside their node, away from the root
+            if let Expansion::Aggregate = view.expansion(node, *depth) {
+                let distance = (point.0 * point.0 + point.1 * point.1).sqrt().max(0.01);
+                let (ux, uy) = if *depth == 0 {
+                    (1.0, 0.0)
+                } else {
+                    (point.0 / distance, point.1 / distance)
+                };
+
+                positions.aggregates.insert(
+                    id,
+                    Placement::circle(
+                        (point.0 + ux * radius * 2.5, point.1 + uy * radius * 2.5),
+                        radius * 0.7,
+                    ),
+                );
+            }
+        }
+
+        // Forget nodes that went out of view so coming back starts fresh
+        let visible = &self.visible;
+        self.positions.retain(|id, _| visible.contains(id));
+
+        positions
+    }
+
+    fn settled(&self) -> bool {
+        self.temperature <= MIN_TEMPERATURE
+    }
+}
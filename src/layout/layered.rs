@@ -0,0 +1,165 @@
+use super::{Expansion, Layout, LayoutView, Placement, Positions};
+use crate::dependency_tree::DependencyTree;
+use crate::drawing::node_id;
+
+const LAYER_GAP: f32 = 120.0;
+const NODE_GAP: f32 = 50.0;
+const NODE_RADIUS: f32 = 18.0;
+const ROOT_RADIUS: f32 = 30.0;
+// Alternating down and up passes of the barycenter heuristic
+const SWEEPS: usize = 4;
+
+// Sugiyama style: one row per dependency depth going down from the root,
+// rows reordered to cut down on crossing edges, then nodes in each row moved
+// towards their neighbors as far as the spacing allows
+pub struct LayeredLayout;
+
+// Reorders `layer` by where each node's neighbors sit in the row they are
+// in, leaving nodes without neighbors where they were
+fn sort_by_barycenter(layer: &mut Vec<usize>, neighbors: &[Vec<usize>], order: &[f32]) {
+    let mut sorted = layer
+        .iter()
+        .enumerate()
+        .map(|(position, &node)| {
+            let linked = &neighbors[node];
+            let barycenter = if linked.is_empty() {
+                position as f32
+            } else {
+                linked.iter().map(|&other| order[other]).sum::<f32>() / linked.len() as f32
+            };
+            (barycenter, node)
+        })
+        .collect::<Vec<_>>();
+
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+    *layer = sorted.into_iter().map(|(_, node)| node).collect();
+}
+
+fn index_in_layers(layers: &[Vec<usize>], order: &mut [f32]) {
+    for layer in layers.iter() {
+        for (position, &node) in layer.iter().enumerate() {
+            order[node] = position as f32;
+        }
+    }
+}
+
+// Moves every node of a row towards where it wants to be, left to right,
+// without letting it get closer than NODE_GAP to the one before it
+fn pack(layer: &[usize], desired: &[Option<f32>], x: &mut [f32]) {
+    let mut previous: Option<f32> = None;
+
+    for &node in layer.iter() {
+        let want = desired[node].unwrap_or(x[node]);
+        let placed = match previous {
+            Some(previous) => want.max(previous + NODE_GAP),
+            None => want,
+        };
+
+        x[node] = placed;
+        previous = Some(placed);
+    }
+}
+
+impl Layout for LayeredLayout {
+    fn place(&mut self, _tree: &DependencyTree, view: &LayoutView) -> Positions {
+        let (nodes, links) = view.graph();
+
+        // Shared crates have several parents, and may sit more than one row
+        // below some of them
+        let mut up = vec![Vec::new(); nodes.len()];
+        let mut down = vec![Vec::new(); nodes.len()];
+        for &(parent, child) in links.iter() {
+            up[child].push(parent);
+            down[parent].push(child);
+        }
+
+        let depth = nodes.iter().map(|(_, depth)| *depth).max().unwrap_or(0);
+        let mut layers = vec![Vec::new(); depth + 1];
+        for (idx, (_, depth)) in nodes.iter().enumerate() {
+            layers[*depth].push(idx);
+        }
+
+        let mut order = vec![0.0; nodes.len()];
+        index_in_layers(&layers, &mut order);
+
+        for sweep in 0..SWEEPS {
+            if sweep % 2 == 0 {
+                for row in 1..layers.len() {
+                    sort_by_barycenter(&mut layers[row], &up, &order);
+                    index_in_layers(&layers[row..=row], &mut order);
+                }
+            } else {
+                for row in (0..layers.len().saturating_sub(1)).rev() {
+                    sort_by_barycenter(&mut layers[row], &down, &order);
+                    index_in_layers(&layers[row..=row], &mut order);
+                }
+            }
+        }
+
+        let mut x = order
+            .iter()
+            .map(|order| order * NODE_GAP)
+            .collect::<Vec<_>>();
+
+        let mean = |linked: &[usize], x: &[f32]| {
+            if linked.is_empty() {
+                None
+            } else {
+                Some(linked.iter().map(|&other| x[other]).sum::<f32>() / linked.len() as f32)
+            }
+        };
+
+        // Parents over the middle of their children, then childless nodes
+        // under their parent where there is room
+        for layer in layers.iter().rev() {
+            let desired = (0..nodes.len())
+                .map(|node| mean(&down[node], &x))
+                .collect::<Vec<_>>();
+            pack(layer, &desired, &mut x);
+        }
+        for layer in layers.iter().skip(1) {
+            let desired = (0..nodes.len())
+                .map(|node| {
+                    if down[node].is_empty() {
+                        mean(&up[node], &x)
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+            pack(layer, &desired, &mut x);
+        }
+
+        let offset = x.first().copied().unwrap_or(0.0);
+        // Straight edges skipping a row would run right through it
+        let mut positions = Positions {
+            curved_edges: links
+                .iter()
+                .any(|&(parent, child)| nodes[child].1 > nodes[parent].1 + 1),
+            ..Positions::default()
+        };
+
+        for (idx, (node, depth)) in nodes.iter().enumerate() {
+            let center = (x[idx] - offset, -(*depth as f32) * LAYER_GAP);
+            let radius = if *depth == 0 {
+                ROOT_RADIUS
+            } else {
+                NODE_RADIUS
+            };
+
+            positions
+                .nodes
+                .insert(node_id(node), Placement::circle(center, radius));
+
+            if let Expansion::Aggregate = view.expansion(node, *depth) {
+                positions.aggregates.insert(
+                    node_id(node),
+                    Placement::circle((center.0, center.1 - LAYER_GAP / 2.0), radius * 0.7),
+                );
+            }
+        }
+
+        super::apply_fixed(&mut positions, view);
+        positions
+    }
+}
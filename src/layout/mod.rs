@@ -0,0 +1,322 @@
+//! Places a [`DependencyTree`] in the plane. Every layout gets the same
+//! [`LayoutView`] of which part of the tree to show and hands back
+//! [`Positions`] for the nodes it shows.
+
+use crate::dependency_tree::DependencyTree;
+use crate::drawing::node_id;
+use crate::parse_cargo_tree_output::TreeNode;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+pub use crate::drawing::{Point, Shape};
+
+pub mod force;
+pub mod layered;
+pub(crate) mod parallel;
+mod relax;
+pub mod satellite;
+pub mod sunburst;
+pub mod treemap;
+
+/// Where a node goes and how big it is there.
+#[derive(Debug, Clone, Copy)]
+pub struct Placement {
+    pub center: Point,
+    pub radius: f32,
+    pub shape: Shape,
+}
+
+impl Placement {
+    pub fn circle(center: Point, radius: f32) -> Self {
+        Placement {
+            center,
+            radius,
+            shape: Shape::Circle,
+        }
+    }
+}
+
+/// Where every visible node goes, keyed by `node_id`
+#[derive(Default)]
+pub struct Positions {
+    pub nodes: HashMap<usize, Placement>,
+    /// The folded remainder hanging off a node at the aggregation depth
+    pub aggregates: HashMap<usize, Placement>,
+    /// Nesting already shows who depends on whom
+    pub hide_edges: bool,
+    /// For layouts where straight edges would run through other nodes
+    pub curved_edges: bool,
+}
+
+impl Positions {
+    // Takes in what was placed apart from the rest, e.g. a subtree on a core
+    // of its own, over anything of the same nodes
+    fn merge(&mut self, other: Positions) {
+        self.nodes.extend(other.nodes);
+        self.aggregates.extend(other.aggregates);
+    }
+}
+
+/// What the space filling layouts size crates by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeMetric {
+    Dependencies,
+    BuildTime,
+    SourceSize,
+    SourceLines,
+}
+
+impl SizeMetric {
+    pub fn label(self) -> &'static str {
+        match self {
+            SizeMetric::Dependencies => "transitive dependencies",
+            SizeMetric::BuildTime => "build time",
+            SizeMetric::SourceSize => "source size",
+            SizeMetric::SourceLines => "lines of code",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            SizeMetric::Dependencies => SizeMetric::BuildTime,
+            SizeMetric::BuildTime => SizeMetric::SourceSize,
+            SizeMetric::SourceSize => SizeMetric::SourceLines,
+            SizeMetric::SourceLines => SizeMetric::Dependencies,
+        }
+    }
+}
+
+/// Which part of the tree is on screen, shared by every layout
+pub struct LayoutView<'a> {
+    pub root: &'a Arc<TreeNode>,
+    pub aggregate_depth: Option<usize>,
+    /// When set, only these nodes show their children
+    pub expanded: Option<&'a HashSet<usize>>,
+    pub time: f32,
+    pub metric: SizeMetric,
+    /// Per crate amounts for the metrics that aren't just counting crates
+    pub weights: &'a HashMap<String, f32>,
+    /// Crates the user put somewhere by hand, by name, wherever the layout
+    /// would have them
+    pub fixed: &'a HashMap<String, Point>,
+}
+
+/// A node along with its depth
+pub type VisibleNode = (Arc<TreeNode>, usize);
+
+pub enum Expansion {
+    Children,
+    // Past the aggregation depth the whole subtree becomes a single node
+    Aggregate,
+    Collapsed,
+}
+
+impl LayoutView<'_> {
+    pub fn expansion(&self, node: &Arc<TreeNode>, depth: usize) -> Expansion {
+        if node.children.is_empty() {
+            Expansion::Collapsed
+        } else if self.aggregate_depth.is_some_and(|max| depth >= max) {
+            Expansion::Aggregate
+        } else if self
+            .expanded
+            .is_some_and(|expanded| !expanded.contains(&node_id(node)))
+        {
+            Expansion::Collapsed
+        } else {
+            Expansion::Children
+        }
+    }
+
+    /// The visible nodes with their depth, in the order they are first reached,
+    /// along with the edges between them as indices into the nodes going from
+    /// parent to child. A crate shared by several parents
+    /// (see `dag::share`) shows up once, as deep as its longest way down.
+    pub fn graph(&self) -> (Vec<VisibleNode>, Vec<(usize, usize)>) {
+        struct Walk {
+            nodes: Vec<VisibleNode>,
+            index: HashMap<usize, usize>,
+            links: HashSet<(usize, usize)>,
+        }
+
+        fn visit(view: &LayoutView, node: &Arc<TreeNode>, depth: usize, walk: &mut Walk) -> usize {
+            let idx = match walk.index.get(&node_id(node)) {
+                Some(&idx) => {
+                    walk.nodes[idx].1 = walk.nodes[idx].1.max(depth);
+                    idx
+                }
+                None => {
+                    walk.nodes.push((Arc::clone(node), depth));
+                    walk.index.insert(node_id(node), walk.nodes.len() - 1);
+                    walk.nodes.len() - 1
+                }
+            };
+
+            if let Expansion::Children = view.expansion(node, depth) {
+                for child in node.children.iter() {
+                    let child_idx = visit(view, child, depth + 1, walk);
+                    walk.links.insert((idx, child_idx));
+                }
+            }
+
+            idx
+        }
+
+        let mut walk = Walk {
+            nodes: Vec::new(),
+            index: HashMap::new(),
+            links: HashSet::new(),
+        };
+        visit(self, self.root, 0, &mut walk);
+
+        let mut links = walk.links.into_iter().collect::<Vec<_>>();
+        links.sort_unstable();
+        (walk.nodes, links)
+    }
+
+    /// What every node's whole subtree adds up to under the chosen metric,
+    /// folded and collapsed parts included. Crates we know nothing about yet
+    /// still get a sliver so they can be found.
+    pub fn totals(&self) -> HashMap<usize, f32> {
+        fn visit(view: &LayoutView, node: &Arc<TreeNode>, totals: &mut HashMap<usize, f32>) -> f32 {
+            let own = match view.metric {
+                SizeMetric::Dependencies => 1.0,
+                _ => view.weights.get(&node.name).copied().unwrap_or(0.0),
+            };
+
+            let total = node
+                .children
+                .iter()
+                .map(|child| visit(view, child, totals))
+                .sum::<f32>()
+                + own.max(MIN_WEIGHT);
+
+            totals.insert(node_id(node), total);
+            total
+        }
+
+        let mut totals = HashMap::new();
+        visit(self, self.root, &mut totals);
+        totals
+    }
+}
+
+const MIN_WEIGHT: f32 = 1e-3;
+// How far `scale_radii` strays from the size a layout picked
+const MIN_SCALE: f32 = 0.5;
+const MAX_SCALE: f32 = 2.0;
+
+/// Scales the circles of the layouts that size nodes alike by how each
+/// node's amount under the view's metric compares to the median, by area.
+/// Counting dependencies, a node's amount is its whole subtree.
+pub fn scale_radii(positions: &mut Positions, view: &LayoutView) {
+    let totals = match view.metric {
+        SizeMetric::Dependencies => view.totals(),
+        _ => HashMap::new(),
+    };
+    let amounts = view
+        .graph()
+        .0
+        .into_iter()
+        .map(|(node, _)| {
+            let amount = match view.metric {
+                SizeMetric::Dependencies => totals.get(&node_id(&node)).copied(),
+                _ => view.weights.get(&node.name).copied(),
+            };
+            (node_id(&node), amount.unwrap_or(0.0))
+        })
+        .collect::<Vec<_>>();
+
+    let mut known = amounts
+        .iter()
+        .map(|&(_, amount)| amount)
+        .filter(|&amount| amount > 0.0)
+        .collect::<Vec<_>>();
+    if known.is_empty() {
+        return;
+    }
+    known.sort_by(f32::total_cmp);
+    let median = known[known.len() / 2];
+
+    for (id, amount) in amounts {
+        if let Some(placement) = positions.nodes.get_mut(&id) {
+            if let Shape::Circle = placement.shape {
+                placement.radius *= (amount / median).sqrt().clamp(MIN_SCALE, MAX_SCALE);
+            }
+        }
+    }
+}
+
+/// Moves every node with a fixed point there, along with its folded
+/// remainder. Layouts call this last so hand placed crates win over
+/// whatever they worked out.
+pub fn apply_fixed(positions: &mut Positions, view: &LayoutView) {
+    if view.fixed.is_empty() {
+        return;
+    }
+
+    for (node, _) in view.graph().0 {
+        let point = match view.fixed.get(&node.name) {
+            Some(&point) => point,
+            None => continue,
+        };
+        let id = node_id(&node);
+        let placement = match positions.nodes.get_mut(&id) {
+            Some(placement) => placement,
+            None => continue,
+        };
+        let delta = (point.0 - placement.center.0, point.1 - placement.center.1);
+
+        placement.center = point;
+        if let Shape::Wedge { ref mut origin, .. } = placement.shape {
+            *origin = (origin.0 + delta.0, origin.1 + delta.1);
+        }
+        if let Some(aggregate) = positions.aggregates.get_mut(&id) {
+            aggregate.center = (aggregate.center.0 + delta.0, aggregate.center.1 + delta.1);
+        }
+    }
+}
+
+/// In the order `all` returns them, which is also what the hotkey cycles
+pub const LAYOUT_NAMES: [&str; 5] = [
+    "satellites",
+    "force-directed",
+    "layered",
+    "sunburst",
+    "treemap",
+];
+
+/// A fresh one of each layout.
+pub fn all() -> Vec<Box<dyn Layout>> {
+    with_sizing(satellite::Sizing::default())
+}
+
+/// Like [`all`], with satellites sized by `sizing`.
+pub fn with_sizing(sizing: satellite::Sizing) -> Vec<Box<dyn Layout>> {
+    vec![
+        Box::new(satellite::SatelliteLayout { sizing }),
+        Box::new(force::ForceLayout::new()),
+        Box::new(layered::LayeredLayout),
+        Box::new(sunburst::SunburstLayout),
+        Box::new(treemap::TreemapLayout),
+    ]
+}
+
+/// Where `name` is in [`LAYOUT_NAMES`], the first layout when it is none of them.
+pub fn index_of(name: &str) -> usize {
+    LAYOUT_NAMES
+        .iter()
+        .position(|known| *known == name)
+        .unwrap_or(0)
+}
+
+/// Some layouts keep state between calls, the force-directed one settles
+/// a little more with every call. The window places on a thread of its own,
+/// so layouts have to be able to go there.
+pub trait Layout: Send {
+    fn place(&mut self, tree: &DependencyTree, view: &LayoutView) -> Positions;
+
+    // Whether placing again with the same view would move anything
+    fn settled(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,17 @@
+// Threads aren't there on wasm32, where this all runs in turn instead
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+
+// What `f` makes of every index below `amount`, in order, worked out on
+// every core there is
+pub fn map_indices<T, F>(amount: usize, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync + Send,
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    return (0..amount).into_par_iter().map(f).collect();
+
+    #[cfg(target_arch = "wasm32")]
+    return (0..amount).map(f).collect();
+}
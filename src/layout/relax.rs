@@ -0,0 +1,118 @@
+use super::{parallel, Placement, Positions};
+use crate::drawing::Point;
+use crate::quadtree::{Bounds, QuadTree};
+
+const ITERATIONS: usize = 8;
+// Leave some air between circles so neighbouring labels stay apart
+const GAP: f32 = 4.0;
+
+fn index(circles: &[Placement]) -> Option<QuadTree> {
+    let bounds = circles
+        .iter()
+        .map(|circle| Bounds::around(circle.center, circle.radius + GAP))
+        .fold(None, |acc: Option<Bounds>, bounds| {
+            Some(acc.map_or(bounds, |acc| acc.union(bounds)))
+        })?;
+
+    let mut index = QuadTree::new(bounds);
+
+    for (idx, circle) in circles.iter().enumerate() {
+        index.insert(idx, Bounds::around(circle.center, circle.radius + GAP));
+    }
+
+    Some(index)
+}
+
+// Pushes overlapping circles apart, a few rounds at a time so one push doesn't
+// just shove a circle into the next one over. Lines are drawn from the
+// placements, so they follow along.
+pub fn separate(positions: &mut Positions, pinned: usize) {
+    // Aggregates are keyed by the node they hang off, so keep the two apart
+    let keys = positions
+        .nodes
+        .keys()
+        .map(|&id| (false, id))
+        .chain(positions.aggregates.keys().map(|&id| (true, id)))
+        .collect::<Vec<_>>();
+    let mut circles = keys
+        .iter()
+        .map(|&(aggregate, id)| {
+            if aggregate {
+                positions.aggregates[&id]
+            } else {
+                positions.nodes[&id]
+            }
+        })
+        .collect::<Vec<_>>();
+    let is_pinned = |idx: usize| keys[idx] == (false, pinned);
+
+    for _ in 0..ITERATIONS {
+        let index = match index(&circles) {
+            Some(index) => index,
+            None => return,
+        };
+
+        // Each circle works out its own way out of everything it overlaps,
+        // so they can all be worked out at once
+        let moves = parallel::map_indices(circles.len(), |i| {
+            let a = circles[i];
+            let mut found = Vec::new();
+            index.query(&Bounds::around(a.center, a.radius + GAP), &mut found);
+
+            let mut away: Option<Point> = None;
+            for &j in found.iter().filter(|&&j| j != i) {
+                let b = circles[j];
+                let (dx, dy) = (b.center.0 - a.center.0, b.center.1 - a.center.1);
+                let distance = (dx * dx + dy * dy).sqrt();
+                let overlap = a.radius + b.radius + GAP - distance;
+
+                if overlap <= 0.0 {
+                    continue;
+                }
+
+                // Any direction will do for circles right on top of each
+                // other, as long as the two of them pick opposite ones
+                let (ux, uy) = if distance > f32::EPSILON {
+                    (dx / distance, dy / distance)
+                } else if i < j {
+                    (1.0, 0.0)
+                } else {
+                    (-1.0, 0.0)
+                };
+
+                // The smaller circle gives way more, and the pinned one not at all
+                let share = if is_pinned(i) {
+                    0.0
+                } else if is_pinned(j) {
+                    1.0
+                } else {
+                    b.radius / (a.radius + b.radius)
+                };
+
+                let away = away.get_or_insert((0.0, 0.0));
+                away.0 -= ux * overlap * share / 2.0;
+                away.1 -= uy * overlap * share / 2.0;
+            }
+            away
+        });
+
+        if moves.iter().all(Option::is_none) {
+            break;
+        }
+
+        for (circle, (dx, dy)) in circles
+            .iter_mut()
+            .zip(moves.into_iter().map(|away| away.unwrap_or((0.0, 0.0))))
+        {
+            circle.center = (circle.center.0 + dx, circle.center.1 + dy);
+        }
+    }
+
+    for ((aggregate, id), circle) in keys.into_iter().zip(circles) {
+        if aggregate {
+            positions.aggregates.insert(id, circle);
+        } else {
+            positions.nodes.insert(id, circle);
+        }
+    }
+}
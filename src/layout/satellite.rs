@@ -0,0 +1,303 @@
+use super::{parallel, relax, Expansion, Layout, LayoutView, Placement, Positions};
+use crate::dependency_tree::DependencyTree;
+use crate::drawing::{node_id, Point};
+use crate::parse_cargo_tree_output::TreeNode;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// However small a subtree, it gets at least this much of an equal share of
+// the sky, so leaves next to a big subtree don't vanish
+const MIN_SHARE: f32 = 0.25;
+
+// How big `Sizing::Subtree` draws crates, by their dependencies
+const MIN_RADIUS: f32 = 12.0;
+const MAX_RADIUS: f32 = 105.0;
+const RADIUS_PER_LOG: f32 = 14.0;
+
+// An amount for each node of its subtree, by node
+type Sizes = HashMap<usize, f32>;
+
+/// How big satellites are drawn.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Sizing {
+    /// Smaller with every level down, each at most 0.7 of its parent
+    Depth,
+    /// By the logarithm of how many crates are below, so crates many
+    /// others hang off stand out wherever they are
+    #[default]
+    Subtree,
+}
+
+impl Sizing {
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "depth" => Some(Sizing::Depth),
+            "subtree" => Some(Sizing::Subtree),
+            _ => None,
+        }
+    }
+}
+
+// What placing satellites goes by, worked out once per layout
+struct Subtrees {
+    sizing: Sizing,
+    // The crates shown in each subtree, itself included, for how much room
+    // to give it
+    shown: Sizes,
+    // Every crate below each one, shown or not
+    dependencies: Sizes,
+    // Whether some crates have more than one parent
+    shared: bool,
+}
+
+impl Subtrees {
+    // How big a satellite of a `parent` sized crate may be, when `fits` is
+    // as big as its slice of the sky allows
+    fn radius(&self, node: &Arc<TreeNode>, parent: f32, fits: f32) -> f32 {
+        match self.sizing {
+            Sizing::Depth => fits.min(parent * 0.7),
+            Sizing::Subtree => {
+                let below = self
+                    .dependencies
+                    .get(&node_id(node))
+                    .copied()
+                    .unwrap_or(0.0);
+                let wanted = (MIN_RADIUS + RADIUS_PER_LOG * below.ln_1p()).min(MAX_RADIUS);
+                wanted.min(fits).max(MIN_RADIUS)
+            }
+        }
+    }
+}
+
+fn dependency_counts(tree: &Arc<TreeNode>, counts: &mut Sizes) -> f32 {
+    if let Some(&count) = counts.get(&node_id(tree)) {
+        return count;
+    }
+
+    let count = tree
+        .children
+        .iter()
+        .map(|child| 1.0 + dependency_counts(child, counts))
+        .sum::<f32>();
+
+    counts.insert(node_id(tree), count);
+    count
+}
+
+fn subtree_sizes(tree: &Arc<TreeNode>, depth: usize, view: &LayoutView, sizes: &mut Sizes) -> f32 {
+    if let Some(&size) = sizes.get(&node_id(tree)) {
+        return size;
+    }
+
+    let size = match view.expansion(tree, depth) {
+        Expansion::Collapsed => 1.0,
+        Expansion::Aggregate => 2.0,
+        Expansion::Children => {
+            1.0 + tree
+                .children
+                .iter()
+                .map(|child| subtree_sizes(child, depth + 1, view, sizes))
+                .sum::<f32>()
+        }
+    };
+
+    sizes.insert(node_id(tree), size);
+    size
+}
+
+// Where each satellite goes, at which angle and how big it can be without
+// leaving its slice of `sky`, each getting a slice by its weight. The first
+// is at `phase` and the rest alternate to either side of it.
+fn get_satellites(
+    center: Point,
+    in_radius: f32,
+    weights: &[f32],
+    phase: f32,
+    sky: f32,
+) -> Vec<(Point, f32, f32)> {
+    let amount = weights.len() as f32;
+    let total = weights.iter().sum::<f32>().max(f32::EPSILON);
+    let slice = |weight: f32| sky * (MIN_SHARE / amount + (1.0 - MIN_SHARE) * weight / total);
+
+    // How far the slices taken so far reach to either side
+    let mut ahead = phase;
+    let mut behind = phase;
+
+    weights
+        .iter()
+        .enumerate()
+        .map(|(idx, &weight)| {
+            let slice = slice(weight);
+            let angle = if idx == 0 {
+                ahead += slice / 2.0;
+                behind -= slice / 2.0;
+                phase
+            } else if idx % 2 == 1 {
+                ahead += slice;
+                ahead - slice / 2.0
+            } else {
+                behind -= slice;
+                behind + slice / 2.0
+            };
+
+            let fits = if slice > std::f32::consts::PI {
+                f32::INFINITY
+            } else {
+                in_radius * (2.0f32.sqrt()) * (1.0 - slice.cos()).sqrt() / 2.0
+            };
+
+            (
+                (
+                    center.0 + angle.cos() * in_radius,
+                    center.1 + angle.sin() * in_radius,
+                ),
+                angle,
+                fits,
+            )
+        })
+        .collect()
+}
+
+// Children orbit their parent, each subtree in the slice of sky it was given
+#[derive(Default)]
+pub struct SatelliteLayout {
+    pub sizing: Sizing,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn place_tree(
+    center: Point,
+    tree: &Arc<TreeNode>,
+    radius: f32,
+    phase: f32,
+    depth: usize,
+    sky: f32,
+    phase_accum: f32,
+    view: &LayoutView,
+    subtrees: &Subtrees,
+    positions: &mut Positions,
+) {
+    // A crate shared by several parents orbits the first one to reach it
+    if positions.nodes.contains_key(&node_id(tree)) {
+        return;
+    }
+
+    positions
+        .nodes
+        .insert(node_id(tree), Placement::circle(center, radius));
+
+    match view.expansion(tree, depth) {
+        Expansion::Collapsed => {}
+        Expansion::Aggregate => {
+            let sats = get_satellites(center, radius * 2.0, &[1.0], phase + phase_accum, sky);
+
+            // Folded crates are sized like a leaf would be
+            for (point, _, fits) in sats {
+                let new_radius = match subtrees.sizing {
+                    Sizing::Depth => fits.min(radius * 0.7),
+                    Sizing::Subtree => MIN_RADIUS,
+                };
+                positions
+                    .aggregates
+                    .insert(node_id(tree), Placement::circle(point, new_radius));
+            }
+        }
+        Expansion::Children => {
+            let weights = tree
+                .children
+                .iter()
+                .map(|child| subtrees.shown.get(&node_id(child)).copied().unwrap_or(1.0))
+                .collect::<Vec<_>>();
+            let sats = get_satellites(center, radius * 2.0, &weights, phase + phase_accum, sky);
+            let children = sats
+                .into_iter()
+                .zip(tree.children.iter())
+                .map(|((point, point_phase, fits), child)| {
+                    let new_radius = subtrees.radius(child, radius, fits);
+                    let child_center = if child.children.len() < 5 {
+                        point
+                    } else {
+                        (
+                            point.0 + new_radius * point_phase.cos() * 1.5,
+                            point.1 + new_radius * point_phase.sin() * 1.5,
+                        )
+                    };
+
+                    let child_sky = if child.children.len() < 5 {
+                        std::f32::consts::PI / 2.0
+                    } else {
+                        std::f32::consts::PI * 1.5
+                    };
+
+                    (child, child_center, new_radius, point_phase, child_sky)
+                })
+                .collect::<Vec<_>>();
+            let place_child = |idx: usize, positions: &mut Positions| {
+                let (child, child_center, new_radius, point_phase, child_sky) = children[idx];
+                place_tree(
+                    child_center,
+                    child,
+                    new_radius,
+                    point_phase,
+                    depth + 1,
+                    child_sky,
+                    phase_accum,
+                    view,
+                    subtrees,
+                    positions,
+                );
+            };
+
+            // The root's subtrees each get a core, unless a crate shared
+            // between two of them has to orbit whichever reaches it first
+            if depth == 0 && !subtrees.shared {
+                let placed = parallel::map_indices(children.len(), |idx| {
+                    let mut placed = Positions::default();
+                    place_child(idx, &mut placed);
+                    placed
+                });
+                for subtree in placed {
+                    positions.merge(subtree);
+                }
+            } else {
+                for idx in 0..children.len() {
+                    place_child(idx, positions);
+                }
+            }
+        }
+    }
+}
+
+impl Layout for SatelliteLayout {
+    fn place(&mut self, tree: &DependencyTree, view: &LayoutView) -> Positions {
+        let mut positions = Positions::default();
+        let mut subtrees = Subtrees {
+            sizing: self.sizing,
+            shown: Sizes::new(),
+            dependencies: Sizes::new(),
+            shared: tree.is_shared(),
+        };
+        subtree_sizes(view.root, 0, view, &mut subtrees.shown);
+        if self.sizing == Sizing::Subtree {
+            dependency_counts(view.root, &mut subtrees.dependencies);
+        }
+
+        place_tree(
+            (0.0, 0.0),
+            view.root,
+            150.0,
+            1.0,
+            0,
+            2.0 * std::f32::consts::PI,
+            view.time.sin() * 0.1,
+            view,
+            &subtrees,
+            &mut positions,
+        );
+
+        relax::separate(&mut positions, node_id(view.root));
+        super::apply_fixed(&mut positions, view);
+
+        positions
+    }
+}
@@ -0,0 +1,105 @@
+use super::{parallel, Expansion, Layout, LayoutView, Placement, Positions};
+use crate::dependency_tree::DependencyTree;
+use crate::drawing::{node_id, Point, Shape};
+use crate::parse_cargo_tree_output::TreeNode;
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+const RING: f32 = 80.0;
+
+// The root in the middle, each depth a ring around it, and every crate
+// getting a share of its parent's angle by its subtree's share of the metric
+pub struct SunburstLayout;
+
+fn wedge(depth: usize, start: f32, end: f32) -> Placement {
+    let (inner, outer) = (depth as f32 * RING, (depth + 1) as f32 * RING);
+    let (middle, angle) = ((inner + outer) / 2.0, (start + end) / 2.0);
+
+    Placement {
+        center: (middle * angle.cos(), middle * angle.sin()),
+        radius: f32::min(RING / 2.0, middle * (end - start) / 2.0),
+        shape: Shape::Wedge {
+            origin: (0.0, 0.0),
+            inner,
+            outer,
+            start,
+            end,
+        },
+    }
+}
+
+fn place_tree(
+    tree: &Arc<TreeNode>,
+    depth: usize,
+    (start, end): Point,
+    view: &LayoutView,
+    totals: &HashMap<usize, f32>,
+    positions: &mut Positions,
+) {
+    match view.expansion(tree, depth) {
+        Expansion::Collapsed => {}
+        Expansion::Aggregate => {
+            positions
+                .aggregates
+                .insert(node_id(tree), wedge(depth + 1, start, end));
+        }
+        Expansion::Children => {
+            // The crate's own share is left as a gap after its children
+            let scale = (end - start) / totals[&node_id(tree)];
+            let slices = tree
+                .children
+                .iter()
+                .scan(start, |angle, child| {
+                    let slice = (*angle, *angle + totals[&node_id(child)] * scale);
+                    *angle = slice.1;
+                    Some((child, slice))
+                })
+                .collect::<Vec<_>>();
+            let place_child = |idx: usize, positions: &mut Positions| {
+                let (child, (start, end)) = slices[idx];
+
+                positions
+                    .nodes
+                    .insert(node_id(child), wedge(depth + 1, start, end));
+                place_tree(child, depth + 1, (start, end), view, totals, positions);
+            };
+
+            // Each of the root's subtrees on a core of its own. Merged in the
+            // same order they'd be placed in, a crate under several of them
+            // ends up where the last one puts it either way.
+            if depth == 0 {
+                let placed = parallel::map_indices(slices.len(), |idx| {
+                    let mut placed = Positions::default();
+                    place_child(idx, &mut placed);
+                    placed
+                });
+                for subtree in placed {
+                    positions.merge(subtree);
+                }
+            } else {
+                for idx in 0..slices.len() {
+                    place_child(idx, positions);
+                }
+            }
+        }
+    }
+}
+
+impl Layout for SunburstLayout {
+    fn place(&mut self, _tree: &DependencyTree, view: &LayoutView) -> Positions {
+        let mut positions = Positions {
+            hide_edges: true,
+            ..Positions::default()
+        };
+        let totals = view.totals();
+
+        positions
+            .nodes
+            .insert(node_id(view.root), Placement::circle((0.0, 0.0), RING));
+        place_tree(view.root, 0, (0.0, TAU), view, &totals, &mut positions);
+        super::apply_fixed(&mut positions, view);
+
+        positions
+    }
+}
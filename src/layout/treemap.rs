@@ -0,0 +1,132 @@
+use super::{Expansion, Layout, LayoutView, Placement, Positions};
+use crate::dependency_tree::DependencyTree;
+use crate::drawing::{node_id, Point, Shape};
+use crate::parse_cargo_tree_output::TreeNode;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const WIDTH: f32 = 1200.0;
+const HEIGHT: f32 = 800.0;
+const PADDING: f32 = 4.0;
+// Room along the top of each box for its label
+const HEADER: f32 = 20.0;
+
+// Nested boxes, each child taking a slice of its parent's box by its
+// subtree's share of the metric. Slicing along the longer side each time
+// keeps the boxes from becoming slivers too quickly.
+pub struct TreemapLayout;
+
+fn boxed(center: Point, (width, height): (f32, f32)) -> Placement {
+    Placement {
+        center,
+        radius: f32::min(width, height) / 2.0,
+        shape: Shape::Rect { width, height },
+    }
+}
+
+// What's left inside a box once its label and border are taken out
+fn inner(center: Point, (width, height): (f32, f32)) -> Option<(Point, (f32, f32))> {
+    let size = (width - 2.0 * PADDING, height - 2.0 * PADDING - HEADER);
+
+    if size.0 <= 0.0 || size.1 <= 0.0 {
+        None
+    } else {
+        Some(((center.0, center.1 - HEADER / 2.0), size))
+    }
+}
+
+fn place_tree(
+    tree: &Arc<TreeNode>,
+    depth: usize,
+    center: Point,
+    size: (f32, f32),
+    view: &LayoutView,
+    totals: &HashMap<usize, f32>,
+    positions: &mut Positions,
+) {
+    let (center, size) = match inner(center, size) {
+        Some(inside) => inside,
+        None => return,
+    };
+
+    match view.expansion(tree, depth) {
+        Expansion::Collapsed => {}
+        Expansion::Aggregate => {
+            positions
+                .aggregates
+                .insert(node_id(tree), boxed(center, size));
+        }
+        Expansion::Children => {
+            let sum = tree
+                .children
+                .iter()
+                .map(|child| totals[&node_id(child)])
+                .sum::<f32>();
+            let horizontal = size.0 >= size.1;
+            let mut offset = 0.0;
+
+            for child in tree.children.iter() {
+                let share = totals[&node_id(child)] / sum;
+
+                let (child_center, child_size) = if horizontal {
+                    let width = size.0 * share;
+                    (
+                        (center.0 - size.0 / 2.0 + offset + width / 2.0, center.1),
+                        (width, size.1),
+                    )
+                } else {
+                    let height = size.1 * share;
+                    (
+                        (center.0, center.1 + size.1 / 2.0 - offset - height / 2.0),
+                        (size.0, height),
+                    )
+                };
+
+                offset += if horizontal {
+                    child_size.0
+                } else {
+                    child_size.1
+                };
+
+                positions
+                    .nodes
+                    .insert(node_id(child), boxed(child_center, child_size));
+                place_tree(
+                    child,
+                    depth + 1,
+                    child_center,
+                    child_size,
+                    view,
+                    totals,
+                    positions,
+                );
+            }
+        }
+    }
+}
+
+impl Layout for TreemapLayout {
+    fn place(&mut self, _tree: &DependencyTree, view: &LayoutView) -> Positions {
+        let mut positions = Positions {
+            hide_edges: true,
+            ..Positions::default()
+        };
+        let totals = view.totals();
+
+        positions
+            .nodes
+            .insert(node_id(view.root), boxed((0.0, 0.0), (WIDTH, HEIGHT)));
+        place_tree(
+            view.root,
+            0,
+            (0.0, 0.0),
+            (WIDTH, HEIGHT),
+            view,
+            &totals,
+            &mut positions,
+        );
+        super::apply_fixed(&mut positions, view);
+
+        positions
+    }
+}
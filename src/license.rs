@@ -0,0 +1,122 @@
+//! Sorting crates by what their licenses ask of whoever ships them.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// How much a license expression asks for, the most demanding first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum LicenseFamily {
+    /// GPL, LGPL, MPL and the like, which come with conditions on sharing
+    /// the source.
+    Copyleft,
+    /// MIT, Apache, BSD and the like.
+    Permissive,
+    /// No license given, or one this doesn't recognize.
+    Unknown,
+}
+
+pub const ALL_FAMILIES: [LicenseFamily; 3] = [
+    LicenseFamily::Permissive,
+    LicenseFamily::Copyleft,
+    LicenseFamily::Unknown,
+];
+
+// SPDX identifiers by prefix, so versions and -only/-or-later count too
+const PERMISSIVE: [&str; 14] = [
+    "MIT",
+    "Apache-",
+    "BSD-",
+    "0BSD",
+    "ISC",
+    "Zlib",
+    "Unlicense",
+    "CC0-",
+    "BSL-",
+    "Unicode-",
+    "WTFPL",
+    "Python-",
+    "PSF-",
+    "NCSA",
+];
+const COPYLEFT: [&str; 9] = [
+    "GPL-", "LGPL-", "AGPL-", "MPL-", "EPL-", "CDDL-", "EUPL-", "OSL-", "CECILL",
+];
+
+fn family_of_id(id: &str) -> LicenseFamily {
+    if PERMISSIVE.iter().any(|prefix| id.starts_with(prefix)) {
+        LicenseFamily::Permissive
+    } else if COPYLEFT.iter().any(|prefix| id.starts_with(prefix)) {
+        LicenseFamily::Copyleft
+    } else {
+        LicenseFamily::Unknown
+    }
+}
+
+impl LicenseFamily {
+    pub fn label(self) -> &'static str {
+        match self {
+            LicenseFamily::Copyleft => "copyleft",
+            LicenseFamily::Permissive => "permissive",
+            LicenseFamily::Unknown => "unknown",
+        }
+    }
+
+    pub fn color(self) -> (u8, u8, u8) {
+        match self {
+            LicenseFamily::Copyleft => (0xe0, 0x6c, 0x4c),
+            LicenseFamily::Permissive => (0x5c, 0xb8, 0x6c),
+            LicenseFamily::Unknown => (0x80, 0x80, 0x88),
+        }
+    }
+
+    /// The family of a Cargo.toml license expression. Any one alternative of
+    /// an OR can be picked, so the least demanding counts, while every part
+    /// of an AND applies, so the most demanding does. An exception along
+    /// with a license only ever loosens it. Older crates use / for OR.
+    pub fn of(expression: Option<&str>) -> Self {
+        let expression = match expression {
+            Some(expression) => expression.replace(['(', ')'], " "),
+            None => return LicenseFamily::Unknown,
+        };
+
+        expression
+            .split(" OR ")
+            .flat_map(|alternative| alternative.split('/'))
+            .map(|alternative| {
+                alternative
+                    .split(" AND ")
+                    .map(|part| {
+                        let license = part.split(" WITH ").next().unwrap_or(part);
+                        family_of_id(license.trim())
+                    })
+                    .min_by_key(|family| match family {
+                        LicenseFamily::Copyleft => 0,
+                        LicenseFamily::Unknown => 1,
+                        LicenseFamily::Permissive => 2,
+                    })
+                    .unwrap_or(LicenseFamily::Unknown)
+            })
+            .min_by_key(|family| match family {
+                LicenseFamily::Permissive => 0,
+                LicenseFamily::Copyleft => 1,
+                LicenseFamily::Unknown => 2,
+            })
+            .unwrap_or(LicenseFamily::Unknown)
+    }
+}
+
+/// What the crates of a tree are licensed under, see
+/// [`DependencyTree::licenses`](crate::dependency_tree::DependencyTree::licenses).
+#[derive(Debug, Default, Serialize)]
+pub struct LicenseSummary {
+    /// Crates by their license expression as Cargo.toml gives it, the ones
+    /// without one under "none".
+    pub by_license: BTreeMap<String, BTreeSet<String>>,
+    pub by_family: BTreeMap<LicenseFamily, BTreeSet<String>>,
+}
+
+impl LicenseSummary {
+    pub fn count(&self, family: LicenseFamily) -> usize {
+        self.by_family.get(&family).map_or(0, BTreeSet::len)
+    }
+}
@@ -0,0 +1,327 @@
+use crate::drawing::Color;
+use crate::palette;
+use crate::parse_cargo_tree_output::TreeNode;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+    // Where in `package` every version of each is
+    #[serde(skip)]
+    by_name: HashMap<String, Vec<usize>>,
+}
+
+#[derive(Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: String,
+    // None for the workspace's own packages and other path dependencies
+    source: Option<String>,
+    // As "name", or "name version" and "name version (source)" when the name
+    // alone isn't enough to tell which package it is
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+// The trees below packages, by where they are in the lockfile, for those
+// that don't go in circles and so are the same wherever they show up
+type Subtrees = HashMap<usize, Arc<TreeNode>>;
+
+impl Lockfile {
+    fn parse(raw: &str) -> Result<Self, String> {
+        let mut lockfile: Lockfile = toml::from_str(raw).map_err(|e| e.to_string())?;
+
+        for (idx, package) in lockfile.package.iter().enumerate() {
+            lockfile
+                .by_name
+                .entry(package.name.clone())
+                .or_default()
+                .push(idx);
+        }
+        Ok(lockfile)
+    }
+
+    fn find(&self, dependency: &str) -> Option<usize> {
+        let mut parts = dependency.splitn(3, ' ');
+        let name = parts.next()?;
+        let version = parts.next();
+        let source = parts
+            .next()
+            .map(|source| source.trim_start_matches('(').trim_end_matches(')'));
+
+        self.by_name.get(name)?.iter().copied().find(|&idx| {
+            let package = &self.package[idx];
+
+            version.is_none_or(|version| package.version == version)
+                && source.is_none_or(|source| package.source.as_deref() == Some(source))
+        })
+    }
+
+    // Along with whether anything below was left out for going in a circle
+    fn node(
+        &self,
+        idx: usize,
+        skip: &HashSet<String>,
+        path: &mut Vec<usize>,
+        subtrees: &mut Subtrees,
+        color_of: &dyn Fn(&str) -> Option<Color>,
+    ) -> (Arc<TreeNode>, bool) {
+        if let Some(subtree) = subtrees.get(&idx) {
+            return (Arc::clone(subtree), false);
+        }
+
+        let name = self.package[idx].name.replace("_", "-");
+        path.push(idx);
+
+        let mut children = Vec::new();
+        let mut cut = false;
+        for dependency in self.package[idx]
+            .dependencies
+            .iter()
+            .filter_map(|dependency| self.find(dependency))
+            .filter(|dependency| !skip.contains(&self.package[*dependency].name))
+        {
+            // Dev-dependencies of the other workspace members can go in
+            // circles
+            if path.contains(&dependency) {
+                cut = true;
+                continue;
+            }
+
+            let (child, child_cut) =
+                self.node(dependency, &HashSet::new(), path, subtrees, color_of);
+            cut |= child_cut;
+            children.push(child);
+        }
+        children.sort_by_key(|child| child.children.len());
+
+        path.pop();
+        let node = Arc::new(TreeNode {
+            color: color_of(&name).unwrap_or_else(|| palette::color_for(&name)),
+            name,
+            children,
+        });
+        if !cut && skip.is_empty() {
+            subtrees.insert(idx, Arc::clone(&node));
+        }
+        (node, cut)
+    }
+}
+
+// Every version of each package a Cargo.lock locks, named the way cargo tree
+// names them
+pub fn versions(raw: &str) -> Result<HashMap<String, Vec<String>>, String> {
+    let mut versions = HashMap::<String, Vec<String>>::new();
+
+    for package in Lockfile::parse(raw)?.package {
+        versions
+            .entry(package.name.replace("_", "-"))
+            .or_default()
+            .push(package.version);
+    }
+    for locked in versions.values_mut() {
+        locked.sort();
+    }
+
+    Ok(versions)
+}
+
+// The tree below the workspace package `root` the way cargo tree --no-dedupe
+// has it, except that a Cargo.lock doesn't say which dependencies are only
+// for other platforms or behind features that are off, so all of them are
+// there. The root's own dependencies named in `skip` are left out, which is
+// how its dev-dependencies go, Cargo.lock having no others'.
+pub fn parse_lockfile(
+    raw: &str,
+    root: &str,
+    skip: &HashSet<String>,
+    color_of: &dyn Fn(&str) -> Option<Color>,
+) -> Result<Arc<TreeNode>, String> {
+    let lockfile = Lockfile::parse(raw)?;
+    let root = lockfile
+        .package
+        .iter()
+        .position(|package| package.name == root && package.source.is_none())
+        .ok_or_else(|| format!("{} isn't in Cargo.lock", root))?;
+
+    Ok(lockfile
+        .node(root, skip, &mut Vec::new(), &mut Subtrees::new(), color_of)
+        .0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    // A workspace of app, helper and tool, with helper having app as a
+    // dev-dependency and helper and tool each other, two versions of log and
+    // two packages of rand 0.7.3
+    const LOCKFILE: &str = r#"
+version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "dep_a",
+ "helper",
+ "log 0.4.8",
+ "pretty",
+ "rand 0.7.3 (git+https://github.com/rust-random/rand#0f7e4b5)",
+ "tool",
+]
+
+[[package]]
+name = "cfg-if"
+version = "0.1.10"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "dep_a"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "log 0.3.9",
+ "rand 0.7.3 (registry+https://github.com/rust-lang/crates.io-index)",
+]
+
+[[package]]
+name = "helper"
+version = "0.1.0"
+dependencies = [
+ "app",
+ "log 0.4.8",
+ "tool",
+]
+
+[[package]]
+name = "log"
+version = "0.3.9"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "log 0.4.8",
+]
+
+[[package]]
+name = "log"
+version = "0.4.8"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "cfg-if",
+]
+
+[[package]]
+name = "pretty"
+version = "0.2.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "rand"
+version = "0.7.3"
+source = "git+https://github.com/rust-random/rand#0f7e4b5"
+dependencies = [
+ "rand_core",
+]
+
+[[package]]
+name = "rand"
+version = "0.7.3"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "rand_core"
+version = "0.5.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "tool"
+version = "0.1.0"
+dependencies = [
+ "helper",
+]
+"#;
+
+    fn tree(skip: &[&str]) -> Arc<TreeNode> {
+        let skip = skip.iter().map(|name| name.to_string()).collect();
+        parse_lockfile(LOCKFILE, "app", &skip, &|_| None).unwrap()
+    }
+
+    // The tree in one line, every crate followed by what's below it
+    fn render(node: &TreeNode) -> String {
+        if node.children.is_empty() {
+            return node.name.clone();
+        }
+
+        let children = node.children.iter().map(|child| render(child));
+        format!("{}({})", node.name, children.sorted().join(" "))
+    }
+
+    fn child<'a>(node: &'a Arc<TreeNode>, name: &str) -> &'a Arc<TreeNode> {
+        node.children
+            .iter()
+            .find(|child| child.name == name)
+            .unwrap_or_else(|| panic!("{} has no {}", node.name, name))
+    }
+
+    #[test]
+    fn versions_and_sources_tell_packages_of_the_same_name_apart() {
+        assert_eq!(
+            render(&tree(&["pretty", "helper", "tool"])),
+            "app(dep-a(log(log(cfg-if)) rand) log(cfg-if) rand(rand-core))"
+        );
+    }
+
+    #[test]
+    fn skipping_is_only_for_the_roots_own_dependencies() {
+        assert!(tree(&[])
+            .children
+            .iter()
+            .any(|child| child.name == "pretty"));
+        // log 0.3.9 is below dep-a, and its log is 0.4.8
+        assert_eq!(
+            render(child(&tree(&["log"]), "dep-a")),
+            "dep-a(log(log(cfg-if)) rand)"
+        );
+    }
+
+    #[test]
+    fn packages_below_more_than_one_are_built_once() {
+        let root = tree(&[]);
+        let log = child(&root, "log");
+
+        assert!(Arc::ptr_eq(log, child(child(&root, "helper"), "log")));
+        assert!(Arc::ptr_eq(
+            log,
+            child(child(child(&root, "dep-a"), "log"), "log")
+        ));
+    }
+
+    // Below helper, tool is cut short where it goes back to helper, which it
+    // isn't right below app
+    #[test]
+    fn subtrees_cut_short_by_a_circle_are_not_kept() {
+        let root = tree(&["pretty"]);
+
+        assert_eq!(render(child(&root, "helper")), "helper(log(cfg-if) tool)");
+        assert_eq!(render(child(&root, "tool")), "tool(helper(log(cfg-if)))");
+    }
+
+    #[test]
+    fn every_version_of_a_package_is_listed() {
+        let versions = versions(LOCKFILE).unwrap();
+
+        assert_eq!(versions["log"], ["0.3.9", "0.4.8"]);
+        assert_eq!(versions["rand"], ["0.7.3", "0.7.3"]);
+        assert_eq!(versions["rand-core"], ["0.5.1"]);
+    }
+
+    #[test]
+    fn a_root_not_in_the_workspace_is_an_error() {
+        assert!(parse_lockfile(LOCKFILE, "log", &HashSet::new(), &|_| None).is_err());
+        assert!(parse_lockfile("package = 3", "app", &HashSet::new(), &|_| None).is_err());
+    }
+}
@@ -0,0 +1,48 @@
+use crate::drawing::{DrawCrate, DrawLine};
+
+// Screen sizes, in pixels, where crates stop being worth more than a dot and
+// start being worth a label
+const DOT_BELOW: f32 = 3.0;
+const LABEL_ABOVE: f32 = 5.0;
+const VERSION_ABOVE: f32 = 48.0;
+// Curves this short look the same drawn straight
+const STRAIGHT_BELOW: f32 = 24.0;
+const HIDDEN_BELOW: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Detail {
+    // A plain square, far cheaper than tessellating a tiny circle
+    Dot,
+    Shape,
+    Labelled,
+    // The version goes under the name
+    Versioned,
+}
+
+pub fn detail(draw_crate: &DrawCrate) -> Detail {
+    if draw_crate.radius < DOT_BELOW {
+        Detail::Dot
+    } else if draw_crate.radius > VERSION_ABOVE {
+        Detail::Versioned
+    } else if draw_crate.radius > LABEL_ABOVE {
+        Detail::Labelled
+    } else {
+        Detail::Shape
+    }
+}
+
+// Runs on screen coordinates, between the camera and drawing. Zoomed out on a
+// big tree most edges are a few pixels long, so they get drawn straight or
+// not at all.
+pub fn simplify(lines: &mut Vec<DrawLine>) {
+    lines.retain_mut(|line| {
+        let (dx, dy) = (line.p2.0 - line.p1.0, line.p2.1 - line.p1.1);
+        let length = (dx * dx + dy * dy).sqrt();
+
+        if length < STRAIGHT_BELOW {
+            line.controls.clear();
+        }
+
+        length >= HIDDEN_BELOW
+    });
+}
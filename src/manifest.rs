@@ -0,0 +1,165 @@
+use crate::package_info::PackageInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+// Exactly what went into a build, saved next to anything exported from it so
+// a picture or report can be traced back to it
+#[derive(Serialize)]
+struct Manifest {
+    rustc: String,
+    commit: Option<String>,
+    host: Option<String>,
+    cargo: String,
+    target: String,
+    // As asked for on the command line, the resolved ones are per package
+    features: Vec<String>,
+    packages: Vec<Resolved>,
+}
+
+#[derive(Serialize)]
+struct Resolved {
+    name: String,
+    version: String,
+    source: Option<String>,
+    checksum: Option<String>,
+    features: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<Locked>,
+}
+
+#[derive(Deserialize)]
+struct Locked {
+    name: String,
+    version: String,
+    source: Option<String>,
+    // Only sources that can change under us have one, path dependencies don't
+    checksum: Option<String>,
+}
+
+fn command_output(mut command: Command, args: &[&str]) -> String {
+    command
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+        .unwrap_or_default()
+}
+
+// The lockfile of the workspace we are in, which may be a few directories up
+fn find_lockfile() -> Option<PathBuf> {
+    let dir = env::current_dir().ok()?;
+
+    dir.ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.is_file())
+}
+
+fn locked_packages() -> Vec<Locked> {
+    find_lockfile()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| toml::from_str::<Lockfile>(&contents).ok())
+        .map(|lockfile| lockfile.package)
+        .unwrap_or_default()
+}
+
+fn flag_value(cargo_args: &[String], flags: &[&str]) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut args = cargo_args.iter();
+
+    while let Some(arg) = args.next() {
+        for flag in flags {
+            if arg == flag {
+                values.extend(args.next().cloned());
+            } else if let Some(value) = arg.strip_prefix(&format!("{}=", flag)) {
+                values.push(value.to_owned());
+            }
+        }
+    }
+
+    values
+}
+
+fn requested_features(cargo_args: &[String]) -> Vec<String> {
+    let mut features = flag_value(cargo_args, &["--features", "-F"])
+        .iter()
+        .flat_map(|list| list.split([',', ' ']))
+        .filter(|feature| !feature.is_empty())
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+
+    for flag in &["--all-features", "--no-default-features"] {
+        if cargo_args.iter().any(|arg| arg == flag) {
+            features.push((*flag).to_owned());
+        }
+    }
+
+    features
+}
+
+fn manifest(metadata: &HashMap<String, Arc<PackageInfo>>, cargo_args: &[String]) -> Manifest {
+    let rustc = command_output(Command::new("rustc"), &["-vV"]);
+    let field = |name: &str| {
+        rustc
+            .lines()
+            .find_map(|line| line.strip_prefix(&format!("{}: ", name)))
+            .map(str::to_owned)
+    };
+    let host = field("host");
+
+    let packages = locked_packages()
+        .into_iter()
+        .map(|locked| {
+            let features = metadata
+                .get(&locked.name.replace("_", "-"))
+                .filter(|info| info.version == locked.version)
+                .map(|info| info.features.clone())
+                .unwrap_or_default();
+
+            Resolved {
+                name: locked.name,
+                version: locked.version,
+                source: locked.source,
+                checksum: locked.checksum,
+                features,
+            }
+        })
+        .collect();
+
+    Manifest {
+        rustc: rustc.lines().next().unwrap_or("").to_owned(),
+        commit: field("commit-hash"),
+        target: flag_value(cargo_args, &["--target"])
+            .pop()
+            .or_else(|| host.clone())
+            .unwrap_or_default(),
+        host,
+        cargo: command_output(crate::project::cargo(), &["-V"]),
+        features: requested_features(cargo_args),
+        packages,
+    }
+}
+
+// e.g. build.manifest.json for build.json
+pub fn write_beside(
+    path: &Path,
+    metadata: &HashMap<String, Arc<PackageInfo>>,
+    cargo_args: &[String],
+) -> PathBuf {
+    let manifest_path = path.with_extension("manifest.json");
+    let json = serde_json::to_string_pretty(&manifest(metadata, cargo_args))
+        .expect("Failed to serialize manifest");
+
+    fs::write(&manifest_path, json).expect("Failed to write manifest");
+
+    manifest_path
+}
@@ -0,0 +1,101 @@
+use crate::batch::Batch;
+use crate::drawing::{CrateState, DrawCrate};
+use crate::theme::Theme;
+use nannou::prelude::*;
+
+const WEIGHT: f32 = 2.0;
+const HATCHES: usize = 3;
+
+// Tells build states apart by shape as well as color: pending crates get a
+// ring inside them, running ones a pulsing outline and failed ones are
+// hatched across. Completed crates stay plainly filled.
+pub fn mark(batch: &mut Batch, draw_crate: &DrawCrate, theme: &Theme, time: f32) {
+    let (center, radius) = (draw_crate.center, draw_crate.radius);
+    let outline = rgb(theme.outline.0, theme.outline.1, theme.outline.2);
+
+    match draw_crate.state {
+        CrateState::Pending => batch.ring(center, radius * 0.6, WEIGHT, outline),
+        CrateState::Active => {
+            let pulse = 1.1 + 0.1 * (time * 4.0).sin();
+            let color = rgb(theme.text.0, theme.text.1, theme.text.2);
+
+            batch.ring(center, radius * pulse, WEIGHT, color);
+        }
+        CrateState::Failed => {
+            // Chords at 45 degrees, evenly spaced across the circle
+            let diagonal = std::f32::consts::FRAC_1_SQRT_2;
+
+            for idx in 0..HATCHES {
+                let offset = radius * ((idx as f32 + 1.0) / (HATCHES as f32 + 1.0) * 2.0 - 1.0);
+                let half = (radius * radius - offset * offset).sqrt() * diagonal;
+                let middle = (center.0 - offset * diagonal, center.1 + offset * diagonal);
+
+                batch.polyline(
+                    &[
+                        (middle.0 - half, middle.1 - half),
+                        (middle.0 + half, middle.1 + half),
+                    ],
+                    WEIGHT,
+                    outline,
+                );
+            }
+        }
+        CrateState::Completed => {}
+    }
+}
+
+// The workspace root gets a ring split into one arc per member, each filled
+// around as far as that member is built
+pub fn member_segments(batch: &mut Batch, draw_crate: &DrawCrate, theme: &Theme) {
+    let count = draw_crate.members.len();
+    let radius = draw_crate.radius + WEIGHT * 2.0;
+    let (gap, steps) = (0.15, 24);
+    let span = TAU / count as f32;
+
+    for (idx, &built) in draw_crate.members.iter().enumerate() {
+        let start = PI / 2.0 - idx as f32 * span - gap / 2.0;
+        let arc = |from: f32, to: f32| {
+            (0..=steps)
+                .map(|step| {
+                    let angle =
+                        start - (span - gap) * (from + (to - from) * step as f32 / steps as f32);
+                    (
+                        draw_crate.center.0 + radius * angle.cos(),
+                        draw_crate.center.1 + radius * angle.sin(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let (done, left) = (theme.completed, theme.outline);
+        batch.polyline(&arc(built, 1.0), WEIGHT, rgb(left.0, left.1, left.2));
+        if built > 0.0 {
+            batch.polyline(&arc(0.0, built), WEIGHT, rgb(done.0, done.1, done.2));
+        }
+    }
+}
+
+// A package built as several units (say a lib and its tests) gets a ring
+// split into one arc per unit, so it doesn't look done while one is left
+pub fn unit_segments(batch: &mut Batch, draw_crate: &DrawCrate, theme: &Theme) {
+    let count = draw_crate.units.len();
+    let radius = draw_crate.radius + WEIGHT * 2.0;
+    let (gap, steps) = (0.15, 12);
+
+    for (idx, &done) in draw_crate.units.iter().enumerate() {
+        let color = if done { theme.completed } else { theme.active };
+        let span = TAU / count as f32;
+        let start = PI / 2.0 - idx as f32 * span - gap / 2.0;
+        let points = (0..=steps)
+            .map(|step| {
+                let angle = start - (span - gap) * step as f32 / steps as f32;
+                (
+                    draw_crate.center.0 + radius * angle.cos(),
+                    draw_crate.center.1 + radius * angle.sin(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        batch.polyline(&points, WEIGHT, rgb(color.0, color.1, color.2));
+    }
+}
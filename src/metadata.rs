@@ -0,0 +1,175 @@
+use crate::daemon;
+use crate::package_info::PackageInfo;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+    resolve: Option<Resolve>,
+}
+
+#[derive(Deserialize)]
+struct Resolve {
+    nodes: Vec<ResolveNode>,
+}
+
+#[derive(Deserialize)]
+struct ResolveNode {
+    id: String,
+    #[serde(default)]
+    features: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    id: String,
+    name: String,
+    version: String,
+    // Where the package comes from, None for local ones
+    source: Option<String>,
+    license: Option<String>,
+    repository: Option<String>,
+    description: Option<String>,
+    homepage: Option<String>,
+    documentation: Option<String>,
+    #[serde(default)]
+    authors: Vec<String>,
+    #[serde(default)]
+    features: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    categories: Vec<String>,
+    #[serde(default)]
+    keywords: Vec<String>,
+    #[serde(default)]
+    targets: Vec<Target>,
+    links: Option<String>,
+    manifest_path: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct Target {
+    kind: Vec<String>,
+}
+
+fn from_crates_io(source: &str) -> bool {
+    source == "registry+https://github.com/rust-lang/crates.io-index"
+        || source == "sparse+https://index.crates.io/"
+}
+
+// Bytes of everything in a directory and lines of the Rust files in it
+fn directory_size(path: &Path) -> (u64, u64) {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return (0, 0),
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(kind) if kind.is_dir() => directory_size(&entry.path()),
+            _ if entry.path().extension().is_some_and(|ext| ext == "rs") => {
+                match fs::read(entry.path()) {
+                    Ok(source) => (
+                        source.len() as u64,
+                        source.iter().filter(|&&byte| byte == b'\n').count() as u64,
+                    ),
+                    Err(_) => (0, 0),
+                }
+            }
+            _ => (entry.metadata().map_or(0, |meta| meta.len()), 0),
+        })
+        .fold((0, 0), |(bytes, lines), (more_bytes, more_lines)| {
+            (bytes + more_bytes, lines + more_lines)
+        })
+}
+
+// Keyed the way the tree names crates. This is only used to enrich the view,
+// so when cargo can't tell us we just go without.
+pub fn load() -> HashMap<String, Arc<PackageInfo>> {
+    let stdout = match daemon::ask_here(|project| daemon::Request::Metadata { project }) {
+        Some(stdout) => stdout.into_bytes(),
+        None => match crate::project::cargo()
+            .args(["metadata", "--format-version", "1"])
+            .output()
+        {
+            Ok(output) if output.status.success() => output.stdout,
+            _ => return HashMap::new(),
+        },
+    };
+
+    let metadata: Metadata = match serde_json::from_slice(&stdout) {
+        Ok(metadata) => metadata,
+        Err(_) => return HashMap::new(),
+    };
+
+    let mut features = metadata
+        .resolve
+        .map(|resolve| {
+            resolve
+                .nodes
+                .into_iter()
+                .map(|node| (node.id, node.features))
+                .collect::<HashMap<_, _>>()
+        })
+        .unwrap_or_default();
+
+    metadata
+        .packages
+        .into_iter()
+        .map(|package| {
+            let published_name = Some(package.name.clone())
+                .filter(|_| package.source.as_deref().is_some_and(from_crates_io));
+            // Registry crates are unpacked under ~/.cargo/registry/src
+            let (source_bytes, source_lines) = package
+                .manifest_path
+                .parent()
+                .map_or((0, 0), |root| directory_size(&root.join("src")));
+
+            (
+                package.name.replace("_", "-"),
+                Arc::new(PackageInfo {
+                    version: package.version,
+                    published_name,
+                    local: package.source.is_none(),
+                    directory: package
+                        .manifest_path
+                        .parent()
+                        .map(Path::to_path_buf)
+                        .unwrap_or_default(),
+                    repository: package.repository,
+                    description: package.description,
+                    homepage: package.homepage,
+                    documentation: package.documentation,
+                    authors: package
+                        .authors
+                        .iter()
+                        .map(|author| {
+                            author
+                                .split('<')
+                                .next()
+                                .unwrap_or_default()
+                                .trim()
+                                .to_owned()
+                        })
+                        .collect(),
+                    license: package.license,
+                    categories: package.categories,
+                    keywords: package.keywords,
+                    proc_macro: package
+                        .targets
+                        .iter()
+                        .any(|target| target.kind.iter().any(|kind| kind == "proc-macro")),
+                    links: package.links.is_some(),
+                    source_bytes,
+                    source_lines,
+                    features: features.remove(&package.id).unwrap_or_default(),
+                    declared_features: package.features.into_keys().collect(),
+                }),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,141 @@
+use crate::events::BuildPhase;
+use crate::timings::Timings;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+const PHASES: [(BuildPhase, &str); 5] = [
+    (BuildPhase::Waiting, "waiting"),
+    (BuildPhase::Downloading, "downloading"),
+    (BuildPhase::Building, "building"),
+    (BuildPhase::Failed, "failed"),
+    (BuildPhase::Finished, "finished"),
+];
+
+// What a build is at, for anything that reads the Prometheus text format
+pub struct BuildMetrics<'a> {
+    pub phase: BuildPhase,
+    pub crates: usize,
+    pub completed: &'a HashSet<String>,
+    pub active: &'a HashSet<String>,
+    pub failed: &'a HashSet<String>,
+    pub timings: &'a Timings,
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: f64) {
+    header(out, name, "gauge", help);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+// Crates by name, in order so scrapes line up
+fn per_crate<'a>(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    values: impl Iterator<Item = (&'a String, f64)>,
+) {
+    let mut values = values.collect::<Vec<_>>();
+    values.sort_by(|a, b| a.0.cmp(b.0));
+
+    header(out, name, "gauge", help);
+    for (crate_name, value) in values {
+        let _ = writeln!(
+            out,
+            "{}{{crate=\"{}\"}} {}",
+            name,
+            escape(crate_name),
+            value
+        );
+    }
+}
+
+impl BuildMetrics<'_> {
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "treebuild_phase",
+            "gauge",
+            "1 for the phase the build is in, 0 for the rest.",
+        );
+        for (phase, label) in PHASES.iter() {
+            let _ = writeln!(
+                out,
+                "treebuild_phase{{phase=\"{}\"}} {}",
+                label,
+                (*phase == self.phase) as u8
+            );
+        }
+
+        gauge(
+            &mut out,
+            "treebuild_crates",
+            "Crates in the dependency tree.",
+            self.crates as f64,
+        );
+        gauge(
+            &mut out,
+            "treebuild_crates_completed",
+            "Crates built so far.",
+            self.completed.len() as f64,
+        );
+        gauge(
+            &mut out,
+            "treebuild_crates_active",
+            "Crates building right now.",
+            self.active.len() as f64,
+        );
+        gauge(
+            &mut out,
+            "treebuild_crates_failed",
+            "Crates that failed to build.",
+            self.failed.len() as f64,
+        );
+        gauge(
+            &mut out,
+            "treebuild_elapsed_seconds",
+            "Seconds since the build started, until it ended.",
+            self.timings.elapsed().as_secs_f64(),
+        );
+
+        header(
+            &mut out,
+            "treebuild_warnings_total",
+            "counter",
+            "Warnings so far.",
+        );
+        let _ = writeln!(out, "treebuild_warnings_total {}", self.timings.warnings);
+
+        per_crate(
+            &mut out,
+            "treebuild_crate_duration_seconds",
+            "Seconds each crate built took.",
+            self.timings
+                .durations
+                .iter()
+                .map(|(name, duration)| (name, duration.as_secs_f64())),
+        );
+        per_crate(
+            &mut out,
+            "treebuild_crate_running_seconds",
+            "Seconds each crate building right now has been at it.",
+            self.active
+                .iter()
+                .filter_map(|name| Some((name, self.timings.running_for(name)?.as_secs_f64()))),
+        );
+
+        out
+    }
+}
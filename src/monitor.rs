@@ -0,0 +1,857 @@
+//! Runs a build, or follows one already running, turning what cargo prints
+//! into [`BuildEvent`]s on a channel. Everything sent is also appended to
+//! the project's event log, so a restarted treebuild can pick up from it.
+
+#[cfg(feature = "gui")]
+use crate::active;
+use crate::event_log::{self, Writer};
+use crate::events::{BuildEvent, BuildPhase};
+use crate::unit_graph::package_name;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// How much of cargo's output is kept around
+pub(crate) const LOG_LINES: usize = 5000;
+
+// The arguments of cargo build that choose what gets built, all of them
+// understood by cargo tree too, and whether each takes a value
+const SELECTION_FLAGS: [(&str, bool); 11] = [
+    ("--package", true),
+    ("-p", true),
+    ("--workspace", false),
+    ("--all", false),
+    ("--exclude", true),
+    ("--features", true),
+    ("-F", true),
+    ("--all-features", false),
+    ("--no-default-features", false),
+    ("--target", true),
+    ("--manifest-path", true),
+];
+
+// Cargo prints a status verb as the first word of each progress line
+fn phase_of_line(line: &str) -> Option<BuildPhase> {
+    match line.trim().split(' ').next() {
+        Some("Updating") | Some("Locking") | Some("Downloading") | Some("Downloaded") => {
+            Some(BuildPhase::Downloading)
+        }
+        Some("Compiling") | Some("Checking") | Some("Installing") => Some(BuildPhase::Building),
+        _ => None,
+    }
+}
+
+fn started_crate(line: &str) -> Option<String> {
+    let mut words = line.trim().split(' ');
+
+    match words.next() {
+        Some("Compiling") | Some("Checking") => words.next().map(|name| name.replace("_", "-")),
+        _ => None,
+    }
+}
+
+fn failed_crate(line: &str) -> Option<String> {
+    let rest = line.strip_prefix("error: could not compile `")?;
+
+    rest.split('`').next().map(|name| name.replace("_", "-"))
+}
+
+// Skip the "warning: `foo` (lib) generated 3 warnings" tallies
+fn is_warning(line: &str) -> bool {
+    line.starts_with("warning:") && !line.starts_with("warning: `")
+}
+
+// "warning: `foo` (lib) generated 3 warnings (run `cargo fix` ...)"
+fn warning_tally(line: &str) -> Option<(String, usize)> {
+    let rest = line.strip_prefix("warning: `")?;
+    let name = rest.split('`').next()?;
+
+    let count = rest
+        .split("generated ")
+        .nth(1)?
+        .split(' ')
+        .next()?
+        .parse()
+        .ok()?;
+
+    Some((name.replace("_", "-"), count))
+}
+
+// Everything goes to the log first so a restarted treebuild can pick up
+fn emit(sender: &Sender<BuildEvent>, log: &mut Writer, event: BuildEvent) {
+    log.append(&event);
+    // Nobody is listening to a build that was restarted over
+    sender.send(event).ok();
+}
+
+/// The part of `cargo_args` that chooses the packages, features and target
+/// of the build, `--release` and the like left out. Passed on to `cargo tree`
+/// it shows just the crates the build compiles.
+pub fn selection_args(cargo_args: &[String]) -> Vec<String> {
+    let mut selection = Vec::new();
+    let mut args = cargo_args.iter();
+
+    while let Some(arg) = args.next() {
+        for (flag, takes_value) in SELECTION_FLAGS.iter() {
+            if arg == flag {
+                selection.push(arg.clone());
+                if *takes_value {
+                    selection.extend(args.next().cloned());
+                }
+            } else if *takes_value
+                && (arg.starts_with(&format!("{}=", flag))
+                    || (flag.len() == 2 && arg.starts_with(flag) && arg.len() > 2))
+            {
+                // --features=foo, or -pfoo
+                selection.push(arg.clone());
+            }
+        }
+    }
+
+    selection
+}
+
+// Stops a build started with `spawn` by killing cargo's whole process
+// group, the rustcs and build scripts it started included
+#[derive(Clone, Default)]
+struct Cancel {
+    // Cargo's, once it is running
+    pid: Arc<Mutex<Option<u32>>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Cancel {
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        if let Some(pid) = *self.pid.lock().unwrap() {
+            kill_group(pid);
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+// Cargo leads a process group of its own, see `build`
+#[cfg(unix)]
+fn kill_group(pid: u32) {
+    let _ = Command::new("kill")
+        .args(["-TERM", "--", &format!("-{}", pid)])
+        .status();
+}
+
+#[cfg(not(unix))]
+fn kill_group(pid: u32) {
+    let _ = Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &pid.to_string()])
+        .status();
+}
+
+/// Runs `cargo <cargo_command> <cargo_args>` in the current directory until
+/// it exits, ending with a [`BuildPhase::Finished`] or [`BuildPhase::Failed`]
+/// phase. Crates cargo finds up to date are reported [`BuildEvent::Fresh`] as
+/// soon as it does, unless `cargo_args` choose a message format of their own.
+pub fn run(cargo_command: Vec<&'static str>, cargo_args: Vec<String>, sender: Sender<BuildEvent>) {
+    build(
+        crate::project::cargo(),
+        cargo_command,
+        cargo_args,
+        sender,
+        None,
+    )
+}
+
+/// A build started with [`spawn`], to be cancelled or built over.
+pub struct Running {
+    cancel: Cancel,
+    thread: JoinHandle<()>,
+}
+
+impl Running {
+    pub fn cancel(&self) {
+        self.cancel.cancel()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+}
+
+/// Like [`run`] on a thread of its own, but for stopping part way through.
+/// Cargo doesn't get the terminal's Ctrl+C then, so whoever started it has
+/// to cancel it on the way out.
+///
+/// A build started over a `previous` one waits for that one to be done
+/// first, so the two never write the event log at once. Cancel the previous
+/// one unless it should build all the way.
+pub fn spawn(
+    cargo_command: Vec<&'static str>,
+    cargo_args: Vec<String>,
+    sender: Sender<BuildEvent>,
+    previous: Option<Running>,
+) -> Running {
+    spawn_with(
+        crate::project::cargo,
+        cargo_command,
+        cargo_args,
+        sender,
+        previous,
+    )
+}
+
+fn spawn_with(
+    cargo: fn() -> Command,
+    cargo_command: Vec<&'static str>,
+    cargo_args: Vec<String>,
+    sender: Sender<BuildEvent>,
+    previous: Option<Running>,
+) -> Running {
+    let cancel = Cancel::default();
+    let thread = {
+        let cancel = cancel.clone();
+        thread::spawn(move || {
+            // Its panic was already told about
+            if let Some(previous) = previous {
+                let _ = previous.thread.join();
+            }
+            if !cancel.is_cancelled() {
+                build(cargo(), cargo_command, cargo_args, sender, Some(&cancel));
+            }
+        })
+    };
+
+    Running { cancel, thread }
+}
+
+fn build(
+    mut cargo: Command,
+    cargo_command: Vec<&'static str>,
+    cargo_args: Vec<String>,
+    sender: Sender<BuildEvent>,
+    cancel: Option<&Cancel>,
+) {
+    // Cargo's status lines stay on stderr, its messages come on stdout
+    let messages = !cargo_args
+        .iter()
+        .any(|arg| arg.starts_with("--message-format"));
+    let build_args: Vec<_> = cargo_command
+        .iter()
+        .map(|x| x.to_string())
+        .chain(cargo_args)
+        .chain(messages.then(|| "--message-format=json-render-diagnostics".to_owned()))
+        .collect();
+
+    cargo.args(build_args).stderr(Stdio::piped());
+    if messages {
+        cargo.stdout(Stdio::piped());
+    }
+    #[cfg(unix)]
+    if cancel.is_some() {
+        use std::os::unix::process::CommandExt;
+        cargo.process_group(0);
+    }
+    let mut cargo_proc = cargo.spawn().expect("Failed to run cargo");
+
+    if let Some(cancel) = cancel {
+        *cancel.pid.lock().unwrap() = Some(cargo_proc.id());
+        // In case it was cancelled before there was anything to stop
+        if cancel.is_cancelled() {
+            kill_group(cargo_proc.id());
+        }
+    }
+
+    let mut log = Writer::create(cargo_proc.id());
+
+    let (line_sender, lines) = channel();
+    if let Some(stdout) = cargo_proc.stdout.take() {
+        let line_sender = line_sender.clone();
+        // Whatever isn't one of cargo's messages is the program's, e.g. the
+        // output of the tests
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if line.starts_with('{') {
+                    line_sender.send(line).ok();
+                } else {
+                    println!("{}", line);
+                }
+            }
+        });
+    }
+    if let Some(stderr) = cargo_proc.stderr.take() {
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                line_sender.send(line).ok();
+            }
+        });
+    } else {
+        drop(line_sender);
+    }
+
+    watch_lines(
+        lines.iter(),
+        |event| emit(&sender, &mut log, event),
+        messages,
+    );
+
+    let status = cargo_proc.wait().expect("Failed to wait on cargo");
+
+    if cancel.is_some_and(Cancel::is_cancelled) {
+        emit(
+            &sender,
+            &mut log,
+            BuildEvent::Log("build cancelled".to_owned()),
+        );
+    }
+    emit(
+        &sender,
+        &mut log,
+        BuildEvent::Phase(if status.success() {
+            BuildPhase::Finished
+        } else {
+            BuildPhase::Failed
+        }),
+    );
+}
+
+// The subset of cargo's --message-format=json we make use of
+#[derive(Deserialize)]
+struct Target {
+    name: String,
+    #[serde(default)]
+    kind: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Diagnostic {
+    level: String,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+enum Reason {
+    CompilerArtifact {
+        package_id: String,
+        target: Target,
+        #[serde(default)]
+        fresh: bool,
+    },
+    CompilerMessage {
+        target: Target,
+        message: Diagnostic,
+    },
+    BuildFinished {
+        success: bool,
+    },
+    #[serde(other)]
+    Other,
+}
+
+fn json_events(reason: Reason) -> Vec<BuildEvent> {
+    match reason {
+        // A build script is built before the rest of its package, which can
+        // still be dirty when the script is fresh
+        Reason::CompilerArtifact { target, .. }
+            if target.kind.iter().any(|kind| kind == "custom-build") =>
+        {
+            Vec::new()
+        }
+        Reason::CompilerArtifact {
+            package_id,
+            target,
+            fresh,
+        } => {
+            let name = package_name(&package_id)
+                .unwrap_or(&target.name)
+                .replace("_", "-");
+            if fresh {
+                vec![BuildEvent::Fresh(name)]
+            } else {
+                vec![BuildEvent::Completed(name)]
+            }
+        }
+        Reason::CompilerMessage { target, message } => match message.level.as_str() {
+            "warning" => vec![BuildEvent::Warning],
+            "error" => vec![BuildEvent::Failed(target.name.replace("_", "-"))],
+            _ => Vec::new(),
+        },
+        Reason::BuildFinished { success } => vec![BuildEvent::Phase(if success {
+            BuildPhase::Finished
+        } else {
+            BuildPhase::Failed
+        })],
+        Reason::Other => Vec::new(),
+    }
+}
+
+// Turns cargo's output into events, either the usual status lines or JSON
+// messages, for `emit`. With `json` cargo's artifact messages tell when a
+// crate is done, otherwise that is taken from the status lines until the
+// first message comes. Returns whether cargo said the build succeeded, if it
+// did.
+fn watch_lines<I: Iterator<Item = String>>(
+    lines: I,
+    mut emit: impl FnMut(BuildEvent),
+    mut json: bool,
+) -> Option<bool> {
+    let mut phase = BuildPhase::Waiting;
+    // The crate of the last line, when that was its status line
+    let mut last_started: Option<String> = None;
+    let mut success = None;
+    // A package's other targets can be fresh when it builds, e.g. its binary
+    // when only the library changed
+    let mut started = HashSet::new();
+    // Its first target done is when a package is
+    let mut completed = HashSet::new();
+
+    for line in lines {
+        if line.starts_with('{') {
+            if let Ok(reason) = serde_json::from_str::<Reason>(&line) {
+                json = true;
+
+                for event in json_events(reason) {
+                    let repeated = match event {
+                        BuildEvent::Fresh(ref name) => started.contains(name),
+                        BuildEvent::Completed(ref name) => !completed.insert(name.clone()),
+                        _ => false,
+                    };
+
+                    if let BuildEvent::Phase(finished) = event {
+                        success = Some(finished == BuildPhase::Finished);
+                    } else if !repeated {
+                        if phase != BuildPhase::Building {
+                            phase = BuildPhase::Building;
+                            emit(BuildEvent::Phase(phase));
+                        }
+                        emit(event);
+                    }
+                }
+                continue;
+            }
+        }
+
+        // Without messages, all cargo says is what it starts compiling, and
+        // the next line coming is as good a sign as there is that it is done
+        if let Some(name) = last_started.take() {
+            if !json {
+                emit(BuildEvent::Completed(name));
+            }
+        }
+
+        if let Some(line_phase) = phase_of_line(&line) {
+            if line_phase != phase {
+                phase = line_phase;
+                emit(BuildEvent::Phase(phase));
+            }
+        }
+
+        if let Some(name) = started_crate(&line) {
+            started.insert(name.clone());
+            last_started = Some(name.clone());
+            emit(BuildEvent::Started(name));
+        }
+
+        if let Some(failed) = failed_crate(&line) {
+            emit(BuildEvent::Failed(failed));
+        }
+
+        if is_warning(&line) {
+            emit(BuildEvent::Warning);
+        }
+
+        if let Some((name, count)) = warning_tally(&line) {
+            emit(BuildEvent::CrateWarnings(name, count));
+        }
+
+        emit(BuildEvent::Log(line));
+    }
+
+    success
+}
+
+// Lines of a file cargo is still writing to, waiting at the end for more
+// until the build is over
+struct Tail {
+    reader: BufReader<File>,
+    cargo_pid: Option<u32>,
+    finished: bool,
+}
+
+impl Iterator for Tail {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let mut line = String::new();
+
+        if self.finished {
+            return None;
+        }
+
+        loop {
+            match self.reader.read_line(&mut line) {
+                Ok(0) | Err(_) => {
+                    // A fifo only reaches the end once cargo closes it
+                    if let Some(pid) = self.cargo_pid {
+                        if !event_log::is_running(pid) {
+                            return None;
+                        }
+                    }
+                    thread::sleep(Duration::from_millis(250));
+                }
+                Ok(_) if line.ends_with('\n') => {
+                    line.pop();
+                    self.finished = line.contains("\"reason\":\"build-finished\"");
+                    return Some(line);
+                }
+                Ok(_) => {}
+            }
+        }
+    }
+}
+
+/// Follows a build someone else started, from what cargo printed for it. "-"
+/// reads our stdin, anything else is a file or fifo cargo's output goes to.
+pub fn attach(source: PathBuf, cargo_pid: Option<u32>, sender: Sender<BuildEvent>) {
+    let mut log = Writer::create(cargo_pid.unwrap_or(0));
+
+    let success = if source == Path::new("-") {
+        let stdin = io::stdin();
+        let lines = stdin.lock().lines().map_while(Result::ok);
+
+        watch_lines(lines, |event| emit(&sender, &mut log, event), false)
+    } else {
+        let file = File::open(&source)
+            .unwrap_or_else(|e| panic!("Failed to open {}: {}", source.display(), e));
+        let lines = Tail {
+            reader: BufReader::new(file),
+            cargo_pid,
+            finished: false,
+        };
+
+        watch_lines(lines, |event| emit(&sender, &mut log, event), false)
+    };
+
+    // Without a JSON summary all we know is that the output ended
+    emit(
+        &sender,
+        &mut log,
+        BuildEvent::Phase(if success.unwrap_or(true) {
+            BuildPhase::Finished
+        } else {
+            BuildPhase::Failed
+        }),
+    );
+}
+
+// A cargo started by an earlier treebuild is not our child, so its stderr is
+// gone and all that's left is watching which rustcs it runs
+#[cfg(feature = "gui")]
+pub(crate) fn follow(cargo_pid: u32, mut log: Writer, sender: Sender<BuildEvent>) {
+    let mut running = HashSet::new();
+
+    while event_log::is_running(cargo_pid) {
+        let now = active::get_children(cargo_pid as usize);
+
+        for started in now.difference(&running) {
+            emit(&sender, &mut log, BuildEvent::Started(started.clone()));
+        }
+        for completed in running.difference(&now) {
+            emit(&sender, &mut log, BuildEvent::Completed(completed.clone()));
+        }
+
+        running = now;
+        thread::sleep(Duration::from_millis(250));
+    }
+
+    for completed in running {
+        emit(&sender, &mut log, BuildEvent::Completed(completed));
+    }
+
+    // Its exit status went to whoever started it
+    emit(&sender, &mut log, BuildEvent::Phase(BuildPhase::Finished));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+
+    const FINISHED: &str = r#"{"reason":"build-finished","success":true}"#;
+
+    // What `cargo build --message-format=json-render-diagnostics 2>&1` prints
+    // for a package with a library and a binary depending on one with a build
+    // script and a warning, as cargo 1.95 printed it
+    const JSON_BUILD: &str = r##"     Locking 1 package to latest compatible version
+   Compiling helper_util v0.1.0 (/tmp/fxp/helper)
+{"reason":"compiler-artifact","package_id":"path+file:///tmp/fxp/helper#helper_util@0.1.0","manifest_path":"/tmp/fxp/helper/Cargo.toml","target":{"kind":["custom-build"],"crate_types":["bin"],"name":"build-script-build","src_path":"/tmp/fxp/helper/build.rs","edition":"2018","doc":false,"doctest":false,"test":false},"profile":{"opt_level":"0","debuginfo":0,"debug_assertions":true,"overflow_checks":true,"test":false},"features":[],"filenames":["/tmp/fxp/app/target/debug/build/helper_util-d702df80a53e5021/build-script-build"],"executable":null,"fresh":false}
+{"reason":"build-script-executed","package_id":"path+file:///tmp/fxp/helper#helper_util@0.1.0","linked_libs":[],"linked_paths":[],"cfgs":[],"env":[],"out_dir":"/tmp/fxp/app/target/debug/build/helper_util-820e411a8db26081/out"}
+warning: unused variable: `unused`
+ --> /tmp/fxp/helper/src/lib.rs:1:36
+  |
+1 | pub fn double(x: u32) -> u32 { let unused = 1; x * 2 }
+  |                                    ^^^^^^ help: if this is intentional, prefix it with an underscore: `_unused`
+  |
+  = note: `#[warn(unused_variables)]` (part of `#[warn(unused)]`) on by default
+
+{"reason":"compiler-artifact","package_id":"path+file:///tmp/fxp/helper#helper_util@0.1.0","manifest_path":"/tmp/fxp/helper/Cargo.toml","target":{"kind":["lib"],"crate_types":["lib"],"name":"helper_util","src_path":"/tmp/fxp/helper/src/lib.rs","edition":"2018","doc":true,"doctest":true,"test":true},"profile":{"opt_level":"0","debuginfo":2,"debug_assertions":true,"overflow_checks":true,"test":false},"features":[],"filenames":["/tmp/fxp/app/target/debug/deps/libhelper_util-031fd75710084c17.rlib","/tmp/fxp/app/target/debug/deps/libhelper_util-031fd75710084c17.rmeta"],"executable":null,"fresh":false}
+warning: `helper_util` (lib) generated 1 warning (run `cargo fix --lib -p helper_util` to apply 1 suggestion)
+   Compiling app v0.1.0 (/tmp/fxp/app)
+{"reason":"compiler-artifact","package_id":"path+file:///tmp/fxp/app#0.1.0","manifest_path":"/tmp/fxp/app/Cargo.toml","target":{"kind":["lib"],"crate_types":["lib"],"name":"app","src_path":"/tmp/fxp/app/src/lib.rs","edition":"2018","doc":true,"doctest":true,"test":true},"profile":{"opt_level":"0","debuginfo":2,"debug_assertions":true,"overflow_checks":true,"test":false},"features":[],"filenames":["/tmp/fxp/app/target/debug/libapp.rlib","/tmp/fxp/app/target/debug/deps/libapp-af8a76e38f90037c.rmeta"],"executable":null,"fresh":false}
+{"reason":"compiler-artifact","package_id":"path+file:///tmp/fxp/app#0.1.0","manifest_path":"/tmp/fxp/app/Cargo.toml","target":{"kind":["bin"],"crate_types":["bin"],"name":"app","src_path":"/tmp/fxp/app/src/main.rs","edition":"2018","doc":true,"doctest":false,"test":true},"profile":{"opt_level":"0","debuginfo":2,"debug_assertions":true,"overflow_checks":true,"test":false},"features":[],"filenames":["/tmp/fxp/app/target/debug/app"],"executable":"/tmp/fxp/app/target/debug/app","fresh":false}
+{"reason":"build-finished","success":true}
+    Finished `dev` profile [unoptimized + debuginfo] target(s) in 0.14s"##;
+
+    // The same with only the package's library changed
+    const JSON_REBUILD: &str = r##"{"reason":"compiler-artifact","package_id":"path+file:///tmp/fxp/helper#helper_util@0.1.0","manifest_path":"/tmp/fxp/helper/Cargo.toml","target":{"kind":["custom-build"],"crate_types":["bin"],"name":"build-script-build","src_path":"/tmp/fxp/helper/build.rs","edition":"2018","doc":false,"doctest":false,"test":false},"profile":{"opt_level":"0","debuginfo":0,"debug_assertions":true,"overflow_checks":true,"test":false},"features":[],"filenames":["/tmp/fxp/app/target/debug/build/helper_util-d702df80a53e5021/build-script-build"],"executable":null,"fresh":true}
+{"reason":"build-script-executed","package_id":"path+file:///tmp/fxp/helper#helper_util@0.1.0","linked_libs":[],"linked_paths":[],"cfgs":[],"env":[],"out_dir":"/tmp/fxp/app/target/debug/build/helper_util-820e411a8db26081/out"}
+warning: unused variable: `unused`
+ --> /tmp/fxp/helper/src/lib.rs:1:36
+  |
+1 | pub fn double(x: u32) -> u32 { let unused = 1; x * 2 }
+  |                                    ^^^^^^ help: if this is intentional, prefix it with an underscore: `_unused`
+  |
+  = note: `#[warn(unused_variables)]` (part of `#[warn(unused)]`) on by default
+
+{"reason":"compiler-artifact","package_id":"path+file:///tmp/fxp/helper#helper_util@0.1.0","manifest_path":"/tmp/fxp/helper/Cargo.toml","target":{"kind":["lib"],"crate_types":["lib"],"name":"helper_util","src_path":"/tmp/fxp/helper/src/lib.rs","edition":"2018","doc":true,"doctest":true,"test":true},"profile":{"opt_level":"0","debuginfo":2,"debug_assertions":true,"overflow_checks":true,"test":false},"features":[],"filenames":["/tmp/fxp/app/target/debug/deps/libhelper_util-031fd75710084c17.rlib","/tmp/fxp/app/target/debug/deps/libhelper_util-031fd75710084c17.rmeta"],"executable":null,"fresh":true}
+warning: `helper_util` (lib) generated 1 warning (run `cargo fix --lib -p helper_util` to apply 1 suggestion)
+   Compiling app v0.1.0 (/tmp/fxp/app)
+{"reason":"compiler-artifact","package_id":"path+file:///tmp/fxp/app#0.1.0","manifest_path":"/tmp/fxp/app/Cargo.toml","target":{"kind":["lib"],"crate_types":["lib"],"name":"app","src_path":"/tmp/fxp/app/src/lib.rs","edition":"2018","doc":true,"doctest":true,"test":true},"profile":{"opt_level":"0","debuginfo":2,"debug_assertions":true,"overflow_checks":true,"test":false},"features":[],"filenames":["/tmp/fxp/app/target/debug/libapp.rlib","/tmp/fxp/app/target/debug/deps/libapp-af8a76e38f90037c.rmeta"],"executable":null,"fresh":false}
+{"reason":"compiler-artifact","package_id":"path+file:///tmp/fxp/app#0.1.0","manifest_path":"/tmp/fxp/app/Cargo.toml","target":{"kind":["bin"],"crate_types":["bin"],"name":"app","src_path":"/tmp/fxp/app/src/main.rs","edition":"2018","doc":true,"doctest":false,"test":true},"profile":{"opt_level":"0","debuginfo":2,"debug_assertions":true,"overflow_checks":true,"test":false},"features":[],"filenames":["/tmp/fxp/app/target/debug/app"],"executable":"/tmp/fxp/app/target/debug/app","fresh":false}
+{"reason":"build-finished","success":true}
+    Finished `dev` profile [unoptimized + debuginfo] target(s) in 0.06s"##;
+
+    // `cargo build 2>&1` of the first
+    const HUMAN_BUILD: &str = r##"   Compiling helper_util v0.1.0 (/tmp/fxp/helper)
+warning: unused variable: `unused`
+ --> /tmp/fxp/helper/src/lib.rs:1:36
+  |
+1 | pub fn double(x: u32) -> u32 { let unused = 1; x * 2 }
+  |                                    ^^^^^^ help: if this is intentional, prefix it with an underscore: `_unused`
+  |
+  = note: `#[warn(unused_variables)]` (part of `#[warn(unused)]`) on by default
+
+warning: `helper_util` (lib) generated 1 warning (run `cargo fix --lib -p helper_util` to apply 1 suggestion)
+   Compiling app v0.1.0 (/tmp/fxp/app)
+    Finished `dev` profile [unoptimized + debuginfo] target(s) in 0.13s"##;
+
+    fn tail(path: &Path, cargo_pid: u32) -> Tail {
+        Tail {
+            reader: BufReader::new(File::open(path).unwrap()),
+            cargo_pid: Some(cargo_pid),
+            finished: false,
+        }
+    }
+
+    #[test]
+    fn a_tail_waits_at_the_end_for_more() {
+        let path = std::env::temp_dir().join(format!("treebuild-tail-{}", std::process::id()));
+        fs::write(&path, "   Compiling a v0.1.0\n").unwrap();
+        let mut lines = tail(&path, std::process::id());
+        assert_eq!(lines.next().as_deref(), Some("   Compiling a v0.1.0"));
+
+        let appending = {
+            let path = path.clone();
+            thread::spawn(move || {
+                let mut file = OpenOptions::new().append(true).open(path).unwrap();
+                thread::sleep(Duration::from_millis(300));
+                // Half a line isn't one yet
+                write!(file, "   Compiling b").unwrap();
+                thread::sleep(Duration::from_millis(300));
+                writeln!(file, " v0.1.0\n{}", FINISHED).unwrap();
+            })
+        };
+
+        assert_eq!(lines.next().as_deref(), Some("   Compiling b v0.1.0"));
+        assert_eq!(lines.next().as_deref(), Some(FINISHED));
+        assert_eq!(lines.next(), None);
+
+        appending.join().unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn a_tail_ends_with_its_cargo() {
+        let path = std::env::temp_dir().join(format!("treebuild-gone-{}", std::process::id()));
+        fs::write(&path, "   Compiling a v0.1.0\n").unwrap();
+        let mut cargo = Command::new("sleep").arg("10").spawn().unwrap();
+        cargo.kill().unwrap();
+        cargo.wait().unwrap();
+
+        let mut lines = tail(&path, cargo.id());
+        assert_eq!(lines.next().as_deref(), Some("   Compiling a v0.1.0"));
+        assert_eq!(lines.next(), None);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    // The project's one event log, which every build writes
+    static EVENT_LOG: Mutex<()> = Mutex::new(());
+
+    // Stand-ins for cargo, the build's own words landing in $1 and on. The
+    // slow one takes a while to stop, and has a last word when it does.
+    fn slow_cargo() -> Command {
+        let mut cargo = Command::new("sh");
+        cargo.args([
+            "-c",
+            "trap 'sleep 0.3; echo error: stopped >&2; exit 1' TERM
+            echo '   Compiling slow v0.1.0' >&2
+            sleep 10",
+            "sh",
+        ]);
+        cargo
+    }
+
+    fn quick_cargo() -> Command {
+        let mut cargo = Command::new("sh");
+        cargo.args(["-c", "echo '   Compiling quick v0.1.0' >&2", "sh"]);
+        cargo
+    }
+
+    // The lines of the event log, each checked to be whole
+    fn log_lines() -> Vec<serde_json::Value> {
+        let raw = fs::read_to_string(event_log::path()).unwrap();
+        assert!(!raw.contains('\0'), "{:?}", raw);
+        raw.lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_restarted_build_has_the_event_log_to_itself() {
+        let _log = EVENT_LOG.lock().unwrap();
+
+        let (sender, slow_events) = channel();
+        let slow = spawn_with(slow_cargo, vec!["build"], Vec::new(), sender, None);
+        assert!(slow_events
+            .iter()
+            .any(|event| event == BuildEvent::Started("slow".to_owned())));
+        slow.cancel();
+
+        let (sender, events) = channel();
+        let quick = spawn_with(quick_cargo, vec!["build"], Vec::new(), sender, Some(slow));
+        quick.thread.join().unwrap();
+        assert_eq!(
+            events.iter().last(),
+            Some(BuildEvent::Phase(BuildPhase::Finished))
+        );
+        // Until the slow one is over too
+        assert!(slow_events
+            .iter()
+            .any(|event| event == BuildEvent::Log("build cancelled".to_owned())));
+
+        let lines = log_lines();
+        assert!(lines[0].get("Session").is_some());
+        let events = lines[1..]
+            .iter()
+            .map(|line| line["Event"]["event"].to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                r#"{"Phase":"Building"}"#,
+                r#"{"Started":"quick"}"#,
+                r#"{"Log":"   Compiling quick v0.1.0"}"#,
+                r#"{"Phase":"Finished"}"#,
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn builds_restarted_over_and_over_go_one_after_another() {
+        let _log = EVENT_LOG.lock().unwrap();
+
+        let (sender, _) = channel();
+        let first = spawn_with(slow_cargo, vec!["build"], Vec::new(), sender, None);
+        first.cancel();
+        // Cancelled before the first one is over, so it never starts
+        let (sender, second_events) = channel();
+        let second = spawn_with(slow_cargo, vec!["build"], Vec::new(), sender, Some(first));
+        second.cancel();
+        let (sender, events) = channel();
+        let third = spawn_with(quick_cargo, vec!["build"], Vec::new(), sender, Some(second));
+
+        third.thread.join().unwrap();
+        assert_eq!(second_events.iter().count(), 0);
+        assert_eq!(
+            events.iter().last(),
+            Some(BuildEvent::Phase(BuildPhase::Finished))
+        );
+        let lines = log_lines();
+        assert!(lines[0].get("Session").is_some());
+        assert!(lines[1..]
+            .iter()
+            .all(|line| !line.to_string().contains("slow")));
+    }
+
+    fn watch(transcript: &str, json: bool) -> (Vec<BuildEvent>, Option<bool>) {
+        let mut events = Vec::new();
+        let success = watch_lines(
+            transcript.lines().map(str::to_owned),
+            |event| events.push(event),
+            json,
+        );
+
+        (events, success)
+    }
+
+    fn completed(events: &[BuildEvent]) -> Vec<&str> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                BuildEvent::Completed(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn crates_are_done_when_their_first_artifact_is() {
+        // Run by us cargo is known to send messages, attached to it that is
+        // found out from the first one
+        for &json in [true, false].iter() {
+            let (events, success) = watch(JSON_BUILD, json);
+
+            assert_eq!(completed(&events), ["helper-util", "app"]);
+            assert_eq!(success, Some(true));
+            assert_eq!(
+                events
+                    .iter()
+                    .filter(|event| matches!(event, BuildEvent::Warning))
+                    .count(),
+                1
+            );
+            assert!(events.contains(&BuildEvent::CrateWarnings("helper-util".to_owned(), 1)));
+            assert!(!events
+                .iter()
+                .any(|event| matches!(event, BuildEvent::Fresh(_))));
+        }
+    }
+
+    #[test]
+    fn fresh_packages_are_told_apart_from_rebuilt_ones() {
+        let (events, _) = watch(JSON_REBUILD, true);
+
+        assert_eq!(completed(&events), ["app"]);
+        assert!(events.contains(&BuildEvent::Fresh("helper-util".to_owned())));
+        assert!(events.contains(&BuildEvent::Started("app".to_owned())));
+    }
+
+    #[test]
+    fn without_messages_only_status_lines_complete_crates() {
+        let (events, success) = watch(HUMAN_BUILD, false);
+
+        assert_eq!(completed(&events), ["helper-util", "app"]);
+        assert_eq!(success, None);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::config::Config;
+use crate::events::{BuildEvent, BuildPhase};
+use crate::timings::minutes;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::time::Instant;
+
+const TITLE: &str = "treebuild";
+// The freedesktop sounds most desktops ship with
+const SOUNDS: &str = "/usr/share/sounds/freedesktop/stereo";
+
+// Runs it without waiting for it, false when it couldn't be started
+fn spawn(program: &str, args: &[&str]) -> bool {
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .is_ok()
+}
+
+fn quoted(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Through the desktop's own notifications, with a sound when asked for.
+// Where there is no way to notify, the terminal bell at least is something.
+fn show(body: &str, failed: bool, sound: bool) -> bool {
+    if cfg!(target_os = "macos") {
+        let sound = match (sound, failed) {
+            (false, _) => String::new(),
+            (true, false) => " sound name \"Glass\"".to_owned(),
+            (true, true) => " sound name \"Basso\"".to_owned(),
+        };
+        let script = format!(
+            "display notification \"{}\" with title \"{}\"{}",
+            quoted(body),
+            TITLE,
+            sound
+        );
+        spawn("osascript", &["-e", &script])
+    } else if cfg!(windows) {
+        let icon = if failed { "Error" } else { "Info" };
+        let script = format!(
+            "Add-Type -AssemblyName System.Windows.Forms; \
+             $n = New-Object System.Windows.Forms.NotifyIcon; \
+             $n.Icon = [System.Drawing.SystemIcons]::Application; \
+             $n.Visible = $true; \
+             {}\
+             $n.ShowBalloonTip(10000, '{}', '{}', '{}'); \
+             Start-Sleep -Seconds 10; $n.Dispose()",
+            if sound {
+                "[System.Media.SystemSounds]::Asterisk.Play(); "
+            } else {
+                ""
+            },
+            TITLE,
+            body.replace('\'', "''"),
+            icon
+        );
+        spawn("powershell", &["-NoProfile", "-Command", &script])
+    } else {
+        let urgency = if failed { "critical" } else { "normal" };
+        let shown = spawn(
+            "notify-send",
+            &["--app-name", TITLE, "--urgency", urgency, TITLE, body],
+        );
+
+        if sound {
+            let (event, file) = if failed {
+                ("dialog-error", "dialog-error.oga")
+            } else {
+                ("complete", "complete.oga")
+            };
+            let _ = spawn("canberra-gtk-play", &["--id", event])
+                || spawn("paplay", &[&format!("{}/{}", SOUNDS, file)]);
+        }
+        shown
+    }
+}
+
+// Tells the desktop when the build is over, and as soon as the first crate
+// fails, for whoever is looking at something else in the meantime
+pub struct Notifier {
+    sound: bool,
+    start: Instant,
+    // The first failure was told about, so the rest of them needn't be
+    failed: bool,
+    done: bool,
+}
+
+impl Notifier {
+    // When either --notify, given as `notify` and `sound`, or the config asks
+    // for it
+    pub fn configured(notify: bool, sound: bool, config: &Config) -> Option<Self> {
+        if !notify && !config.notify {
+            return None;
+        }
+
+        Some(Notifier {
+            sound: sound || config.notify_sound,
+            start: Instant::now(),
+            failed: false,
+            done: false,
+        })
+    }
+
+    fn notify(&self, body: &str, failed: bool) {
+        if !show(body, failed, self.sound) {
+            let _ = io::stderr().write_all(b"\x07");
+        }
+    }
+
+    pub fn observe(&mut self, event: &BuildEvent) {
+        if self.done {
+            return;
+        }
+
+        let took = minutes(self.start.elapsed().as_secs_f32());
+        match event {
+            BuildEvent::Failed(name) if !self.failed => {
+                self.failed = true;
+                self.notify(&format!("{} failed to build", name), true);
+            }
+            BuildEvent::Phase(BuildPhase::Finished) => {
+                self.done = true;
+                self.notify(&format!("Build finished in {}", took), false);
+            }
+            BuildEvent::Phase(BuildPhase::Failed) => {
+                self.done = true;
+                // Told about already, with the crate that failed
+                if !self.failed {
+                    self.notify(&format!("Build failed after {}", took), true);
+                }
+            }
+            _ => {}
+        }
+    }
+}
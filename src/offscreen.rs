@@ -0,0 +1,79 @@
+use nannou::image::RgbaImage;
+use nannou::prelude::*;
+use nannou::window::Window;
+
+// Draws into a texture of a fixed size instead of the window, for anything we
+// want to save as an image independently of what is on screen
+pub struct Offscreen {
+    texture: wgpu::Texture,
+    renderer: nannou::draw::Renderer,
+    capturer: wgpu::TextureCapturer,
+    // Pixels per point, for the same framing as the window with as many
+    // pixels as a HiDPI display has
+    scale_factor: f32,
+}
+
+impl Offscreen {
+    pub fn new(window: &Window, size: [u32; 2]) -> Self {
+        Self::scaled(window, size, 1.0)
+    }
+
+    // `size` in points, drawn at `scale_factor` pixels per point
+    pub fn scaled(window: &Window, size: [u32; 2], scale_factor: f32) -> Self {
+        let size = [
+            (size[0] as f32 * scale_factor).round() as u32,
+            (size[1] as f32 * scale_factor).round() as u32,
+        ];
+        let device = window.swap_chain_device();
+
+        let texture = wgpu::TextureBuilder::new()
+            .size(size)
+            .usage(wgpu::TextureUsage::OUTPUT_ATTACHMENT | wgpu::TextureUsage::SAMPLED)
+            .sample_count(1)
+            .format(wgpu::TextureFormat::Rgba16Float)
+            .build(device);
+
+        let renderer = nannou::draw::RendererBuilder::new()
+            .build_from_texture_descriptor(device, texture.descriptor());
+
+        Offscreen {
+            texture,
+            renderer,
+            capturer: wgpu::TextureCapturer::default(),
+            scale_factor,
+        }
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        self.texture.size()
+    }
+
+    // The callback runs on the capturer's thread once the GPU is done with the frame
+    pub fn capture<F>(&mut self, window: &Window, draw: &Draw, callback: F)
+    where
+        F: 'static + Send + FnOnce(RgbaImage),
+    {
+        let device = window.swap_chain_device();
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        self.renderer.encode_render_pass(
+            device,
+            &mut encoder,
+            draw,
+            self.scale_factor,
+            self.texture.size(),
+            &self.texture.view().build(),
+            None,
+        );
+        let snapshot = self.capturer.capture(device, &mut encoder, &self.texture);
+        window
+            .swap_chain_queue()
+            .lock()
+            .unwrap()
+            .submit(&[encoder.finish()]);
+
+        snapshot.read_threaded(move |result| {
+            callback(result.expect("Failed to map offscreen texture"));
+        });
+    }
+}
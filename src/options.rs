@@ -0,0 +1,319 @@
+use crate::cli;
+use crate::layout;
+use crate::monitor;
+use crate::parse_cargo_tree_output::{prune, ChildOrder, TreeNode};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Arc;
+
+const USAGE: &str = "\
+usage: treebuild [+TOOLCHAIN] [OPTIONS] [PATH] [CARGO_ARGS...]
+       treebuild [+TOOLCHAIN] attach|compare|daemon|diff|doctor|history|query|replay|serve ...
+       cargo [+TOOLCHAIN] treebuild [OPTIONS] [PATH] [CARGO_ARGS...]
+       cargo [+TOOLCHAIN] vbuild|vcheck|vinstall [OPTIONS] [PATH] [CARGO_ARGS...]
+
+PATH is the project to build, the current directory by default. Anything
+else is passed on to cargo, everything after -- as it is. The tree shows
+just the crates the packages, features and target selected for cargo build.
+
+    --mode build|check|test   what to run cargo with
+    --layout NAME             radial, force, layered, sunburst or treemap
+    --exclude CRATE           leave a crate out of the tree, and whatever only
+                              it depends on (repeatable)
+    --max-depth N             leave out crates deeper than N
+    --sort-children-by ORDER  lay each crate's dependencies out by
+                              child-count, name or build-time (of the last
+                              build)
+    --aggregate-depth N       fold crates deeper than N into one per parent
+    --cargo PATH              run this instead of cargo, e.g. a wrapper
+    --features FEATURES       passed to cargo build and cargo tree alike
+    --all-features
+    --no-default-features
+    --target TRIPLE
+    --explore                 start with only the root's dependencies shown
+    --tui                     follow the build in the terminal
+    --unit-graph              show what cargo plans to compile, build scripts
+                              included (nightly cargo)
+    --from-lockfile           read the tree from Cargo.lock instead of asking
+                              cargo, with every platform's dependencies
+    --audit[=FILE]            flag crates with RustSec advisories against them,
+                              from cargo audit or what cargo audit --json wrote
+    --offline                 look nothing up online, also passed on to cargo
+    --low-power               as if on battery
+    --load FILE               show an exported build instead of running one
+    --export-json FILE        export the build when it is done (or --save)
+    --export-report FILE      write a report of the build (or --report)
+    --export-gif FILE         record the build (or --record)
+    --export-manifest         write a manifest next to everything exported
+    --emit-events[=FILE]      write every build event as a line of JSON, to
+                              stdout unless given a file
+    --notify[=sound]          notify the desktop when the build is over or a
+                              crate fails, with a sound if asked for
+    --speed N                 replay a recorded build N times as fast
+    --scale FACTOR            draw lines and text, and record, as if the
+                              display's scale factor were FACTOR
+    --watch                   build again whenever a file of the project
+                              changes, stopping the build under way
+    --help";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Build,
+    Check,
+    Test,
+}
+
+impl Mode {
+    pub fn command(self) -> Vec<&'static str> {
+        match self {
+            Mode::Build => vec!["build"],
+            Mode::Check => vec!["check"],
+            Mode::Test => vec!["test"],
+        }
+    }
+}
+
+pub struct Options {
+    pub path: Option<PathBuf>,
+    pub mode: Option<Mode>,
+    // One of `layout::LAYOUT_NAMES`
+    pub layout: Option<String>,
+    pub exclude: Vec<String>,
+    pub max_depth: Option<usize>,
+    // Over what the config says
+    pub sort_children_by: Option<ChildOrder>,
+    pub load: Option<PathBuf>,
+    pub save: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+    pub report: Option<PathBuf>,
+    pub aggregate_depth: Option<usize>,
+    pub explore: bool,
+    // Write a manifest of the build next to everything exported from it
+    pub export_manifest: bool,
+    // As if on battery, whatever the power source
+    pub low_power: bool,
+    // Follow the build in the terminal rather than a window
+    pub tui: bool,
+    // The tree from cargo's unit graph rather than cargo tree
+    pub unit_graph: bool,
+    // The tree from Cargo.lock, without running cargo for it
+    pub from_lockfile: bool,
+    // Look the locked crates up in the RustSec advisory database
+    pub audit: bool,
+    // Output of an earlier cargo audit --json, to read instead of running it
+    pub audit_report: Option<PathBuf>,
+    // Where to write build events as they happen, - for stdout
+    pub emit_events: Option<PathBuf>,
+    // Desktop notifications, whatever the config says
+    pub notify: bool,
+    pub notify_sound: bool,
+    // How much faster than it happened to replay a build
+    pub speed: Option<f32>,
+    // The display's scale factor, instead of the one it says it has
+    pub scale: Option<f32>,
+    // Rebuild on every change to the project's files
+    pub watch: bool,
+    // Only what was fetched before, for crates.io versions and cargo alike
+    pub offline: bool,
+    pub cargo_args: Vec<String>,
+    // The part of `cargo_args` that changes which crates get built, so cargo
+    // tree gets it too
+    pub tree_args: Vec<String>,
+}
+
+fn layout_name(name: &str) -> Option<&'static str> {
+    let name = match name {
+        "radial" => "satellites",
+        "force" => "force-directed",
+        name => name,
+    };
+
+    layout::LAYOUT_NAMES
+        .iter()
+        .copied()
+        .find(|known| *known == name)
+}
+
+fn number(flag: &str, value: String) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} requires a number, not {}", flag, value))
+}
+
+impl Options {
+    // Prints the usage and exits on arguments that don't make sense
+    pub fn from_args() -> Self {
+        match Options::parse(cli::option_args()) {
+            Ok(options) => options,
+            Err(e) => {
+                eprintln!("error: {}\n\n{}", e, USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+        let mut options = Options {
+            path: None,
+            mode: None,
+            layout: None,
+            exclude: Vec::new(),
+            max_depth: None,
+            sort_children_by: None,
+            load: None,
+            save: None,
+            record: None,
+            report: None,
+            aggregate_depth: None,
+            explore: false,
+            export_manifest: false,
+            low_power: false,
+            tui: false,
+            unit_graph: false,
+            from_lockfile: false,
+            audit: false,
+            audit_report: None,
+            emit_events: None,
+            notify: false,
+            notify_sound: false,
+            speed: None,
+            scale: None,
+            watch: false,
+            offline: false,
+            cargo_args: Vec::new(),
+            tree_args: Vec::new(),
+        };
+
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.find('=') {
+                Some(idx) if arg.starts_with("--") => {
+                    (arg[..idx].to_owned(), Some(arg[idx + 1..].to_owned()))
+                }
+                _ => (arg.clone(), None),
+            };
+
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("{} requires a value", flag))
+            };
+
+            match flag.as_str() {
+                "--help" | "-h" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                }
+                "--mode" => {
+                    options.mode = Some(match value()?.as_str() {
+                        "build" => Mode::Build,
+                        "check" => Mode::Check,
+                        "test" => Mode::Test,
+                        mode => return Err(format!("unknown mode {}", mode)),
+                    })
+                }
+                "--layout" => {
+                    let name = value()?;
+                    options.layout = Some(
+                        layout_name(&name)
+                            .ok_or_else(|| format!("unknown layout {}", name))?
+                            .to_owned(),
+                    )
+                }
+                "--exclude" => options.exclude.push(value()?.replace("_", "-")),
+                "--max-depth" => options.max_depth = Some(number(&flag, value()?)?),
+                "--sort-children-by" => {
+                    let name = value()?;
+                    options.sort_children_by = Some(
+                        ChildOrder::by_name(&name)
+                            .ok_or_else(|| format!("can't sort children by {}", name))?,
+                    )
+                }
+                // Taken here only so their values aren't mistaken for the path
+                "--features" | "--target" | "--package" | "-p" | "-F" => {
+                    let value = value()?;
+                    options.cargo_args.extend([flag.clone(), value]);
+                }
+                // Everything after is for cargo as it is
+                "--" => options.cargo_args.extend(args.by_ref()),
+                "--load" => options.load = Some(PathBuf::from(value()?)),
+                "--save" | "--export-json" => options.save = Some(PathBuf::from(value()?)),
+                "--record" | "--export-gif" => options.record = Some(PathBuf::from(value()?)),
+                "--report" | "--export-report" => options.report = Some(PathBuf::from(value()?)),
+                "--aggregate-depth" => options.aggregate_depth = Some(number(&flag, value()?)?),
+                "--speed" => {
+                    let value = value()?;
+                    options.speed = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|speed: &f32| *speed > 0.0)
+                            .ok_or_else(|| format!("--speed requires a number, not {}", value))?,
+                    )
+                }
+                "--scale" => {
+                    let value = value()?;
+                    options.scale = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|scale: &f32| *scale > 0.0)
+                            .ok_or_else(|| format!("--scale requires a number, not {}", value))?,
+                    )
+                }
+                "--offline" => {
+                    options.offline = true;
+                    options.cargo_args.push(arg);
+                }
+                "--explore" => options.explore = true,
+                "--watch" => options.watch = true,
+                "--export-manifest" => options.export_manifest = true,
+                "--low-power" => options.low_power = true,
+                "--tui" => options.tui = true,
+                "--unit-graph" => options.unit_graph = true,
+                "--from-lockfile" => options.from_lockfile = true,
+                // The report is optional, so it only comes as --audit=FILE
+                "--audit" => {
+                    options.audit = true;
+                    options.audit_report = inline_value.clone().map(PathBuf::from);
+                }
+                // Likewise, since the file is optional
+                "--emit-events" => {
+                    options.emit_events = Some(PathBuf::from(
+                        inline_value.clone().unwrap_or_else(|| "-".to_owned()),
+                    ));
+                }
+                "--notify" => {
+                    options.notify = true;
+                    options.notify_sound = match inline_value.as_deref() {
+                        None => false,
+                        Some("sound") => true,
+                        Some(other) => return Err(format!("unknown --notify={}", other)),
+                    };
+                }
+                _ if options.path.is_none()
+                    && !arg.starts_with('-')
+                    && Path::new(&arg).is_dir() =>
+                {
+                    options.path = Some(PathBuf::from(arg))
+                }
+                _ => options.cargo_args.push(arg),
+            }
+        }
+
+        options.tree_args = monitor::selection_args(&options.cargo_args);
+        Ok(options)
+    }
+
+    // The tree without what --exclude and --max-depth leave out
+    pub fn prune(&self, tree: &Arc<TreeNode>) -> Arc<TreeNode> {
+        if self.exclude.is_empty() && self.max_depth.is_none() {
+            return Arc::clone(tree);
+        }
+
+        prune(tree, &|name, depth| {
+            !self.exclude.iter().any(|excluded| excluded == name)
+                && self.max_depth.is_none_or(|max| depth <= max)
+        })
+    }
+}
@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const INDEX: &str = "https://index.crates.io";
+// Seconds a looked up version is trusted for
+const CACHE_FOR: u64 = 24 * 60 * 60;
+// curls running at once, crates.io serves the index from a CDN
+const PARALLEL: usize = 16;
+
+#[derive(Serialize, Deserialize)]
+struct Cached {
+    latest: String,
+    checked_at: u64,
+}
+
+#[derive(Deserialize)]
+struct IndexEntry {
+    vers: String,
+    #[serde(default)]
+    yanked: bool,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+fn cache_path() -> PathBuf {
+    crate::project::state_dir().join("crates-io.json")
+}
+
+// Where a crate's file is in the index, e.g. se/rd/serde
+fn index_path(name: &str) -> String {
+    let name = name.to_lowercase();
+
+    match name.len() {
+        1 => format!("1/{}", name),
+        2 => format!("2/{}", name),
+        3 => format!("3/{}/{}", &name[..1], name),
+        _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+    }
+}
+
+// Numeric major, minor and patch, with prereleases sorting before their
+// release. Build metadata doesn't count.
+fn version_key(version: &str) -> Option<(u64, u64, u64, bool)> {
+    let version = version.split('+').next()?;
+    let (release, pre) = match version.split_once('-') {
+        Some((release, _)) => (release, true),
+        None => (version, false),
+    };
+    let mut parts = release.split('.').map(|part| part.parse().ok());
+
+    Some((parts.next()??, parts.next()??, parts.next()??, !pre))
+}
+
+// The newest version that wasn't yanked, prereleases only when that is all
+// there is
+fn latest<'a>(versions: impl Iterator<Item = &'a str>) -> Option<String> {
+    versions
+        .filter_map(|version| Some((version_key(version)?, version)))
+        .max_by_key(|&(key, _)| (key.3, key))
+        .map(|(_, version)| version.to_owned())
+}
+
+fn latest_in(lines: &[u8]) -> Option<String> {
+    let entries = lines
+        .split(|&byte| byte == b'\n' || byte == 0)
+        .filter(|line| line.first() == Some(&b'{'))
+        .filter_map(|line| serde_json::from_slice::<IndexEntry>(line).ok())
+        .filter(|entry| !entry.yanked)
+        .collect::<Vec<_>>();
+
+    latest(entries.iter().map(|entry| entry.vers.as_str()))
+}
+
+// Cargo keeps what it fetched of the sparse index too, as NUL separated
+// versions and their JSON after a header
+fn cargo_cached(name: &str) -> Option<String> {
+    let home = env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cargo")))?;
+
+    fs::read_dir(home.join("registry").join("index"))
+        .ok()?
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("index.crates.io-")
+        })
+        .filter_map(|entry| fs::read(entry.path().join(".cache").join(index_path(name))).ok())
+        .find_map(|raw| latest_in(&raw))
+}
+
+fn fetch(names: &[&str]) -> HashMap<String, String> {
+    let mut found = HashMap::new();
+
+    for batch in names.chunks(PARALLEL) {
+        let running = batch
+            .iter()
+            .filter_map(|name| {
+                let child = Command::new("curl")
+                    .args(["-sf", "--max-time", "10"])
+                    .arg(format!("{}/{}", INDEX, index_path(name)))
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::null())
+                    .spawn()
+                    .ok()?;
+                Some((name, child))
+            })
+            .collect::<Vec<_>>();
+
+        for (name, child) in running {
+            let latest = child
+                .wait_with_output()
+                .ok()
+                .filter(|output| output.status.success())
+                .and_then(|output| latest_in(&output.stdout));
+
+            if let Some(latest) = latest {
+                found.insert(name.to_string(), latest);
+            }
+        }
+    }
+
+    found
+}
+
+// The newest version on crates.io of each of `names`, as recently as the
+// cache allows, or as recently as it could be looked up. Offline, from what
+// cargo or we fetched before. Crates that can't be found are left out.
+pub fn latest_versions(names: &[String], offline: bool) -> HashMap<String, String> {
+    let mut cache = fs::read_to_string(cache_path())
+        .ok()
+        .and_then(|raw| serde_json::from_str::<HashMap<String, Cached>>(&raw).ok())
+        .unwrap_or_default();
+    let now = now();
+
+    let stale = names
+        .iter()
+        .map(String::as_str)
+        .filter(|name| {
+            cache
+                .get(*name)
+                .is_none_or(|cached| now.saturating_sub(cached.checked_at) > CACHE_FOR)
+        })
+        .collect::<Vec<_>>();
+
+    // What cargo has may be older than what we have, so it isn't cached
+    let looked_up = if offline {
+        stale
+            .iter()
+            .filter_map(|name| Some((name.to_string(), cargo_cached(name)?)))
+            .collect()
+    } else {
+        fetch(&stale)
+    };
+
+    if !offline && !looked_up.is_empty() {
+        for (name, latest) in looked_up.iter() {
+            cache.insert(
+                name.clone(),
+                Cached {
+                    latest: latest.clone(),
+                    checked_at: now,
+                },
+            );
+        }
+        if fs::create_dir_all(crate::project::state_dir()).is_ok() {
+            let _ = fs::write(
+                cache_path(),
+                serde_json::to_string(&cache).expect("Failed to serialize versions"),
+            );
+        }
+    }
+
+    names
+        .iter()
+        .filter_map(|name| {
+            let latest = looked_up
+                .get(name)
+                .cloned()
+                .or_else(|| cache.get(name).map(|cached| cached.latest.clone()))?;
+            Some((name.clone(), latest))
+        })
+        .collect()
+}
+
+// Whether `latest` is a newer release than `current`
+pub fn is_outdated(current: &str, latest: &str) -> bool {
+    match (version_key(current), version_key(latest)) {
+        (Some(current), Some(latest)) => latest > current,
+        _ => false,
+    }
+}
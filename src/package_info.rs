@@ -0,0 +1,35 @@
+use std::path::PathBuf;
+
+// What Cargo.toml of each package says about itself, which is also what
+// crates.io shows for it. Only the window shows all of it, without it a few
+// of the fields are read and the rest just carried along.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+#[derive(Debug, Clone, Default)]
+pub struct PackageInfo {
+    pub version: String,
+    // The name as published on crates.io, None for crates from anywhere else
+    pub published_name: Option<String>,
+    // A path dependency, such as another member of the workspace
+    pub local: bool,
+    // Where its Cargo.toml is
+    pub directory: PathBuf,
+    pub repository: Option<String>,
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    pub documentation: Option<String>,
+    // Without their email addresses
+    pub authors: Vec<String>,
+    pub license: Option<String>,
+    pub categories: Vec<String>,
+    pub keywords: Vec<String>,
+    pub proc_macro: bool,
+    pub links: bool,
+    // Everything under the package's src directory
+    pub source_bytes: u64,
+    // Lines in the Rust files among them
+    pub source_lines: u64,
+    // The features this build turns on for it
+    pub features: Vec<String>,
+    // Every feature it has, in order
+    pub declared_features: Vec<String>,
+}
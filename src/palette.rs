@@ -0,0 +1,43 @@
+use crate::drawing::Color;
+
+// Evenly spaced hues, few enough that neighbours stay tellable apart
+const HUES: u32 = 24;
+// Light enough to read a label on, in every hue
+const SATURATION: f32 = 0.65;
+const LIGHTNESS: [f32; 3] = [0.55, 0.65, 0.75];
+
+fn hash(text: &str) -> u32 {
+    let digest = md5::compute(text.as_bytes());
+
+    u32::from_le_bytes([digest[0], digest[1], digest[2], digest[3]])
+}
+
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> Color {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let offset = lightness - chroma / 2.0;
+    let channel = |value: f32| ((value + offset) * 255.0).round() as u8;
+
+    (channel(r), channel(g), channel(b))
+}
+
+// Crates from the same family (the part of the name before the first dash,
+// e.g. tokio for tokio-util) share a hue and differ only in lightness. The
+// name is all that goes in, so a crate gets the same color on every run and
+// every machine.
+pub fn color_for(name: &str) -> Color {
+    let family = name.split('-').next().unwrap_or(name);
+    let hue = (hash(family) % HUES) as f32 * 360.0 / HUES as f32;
+    let lightness = LIGHTNESS[(hash(name) % LIGHTNESS.len() as u32) as usize];
+
+    hsl_to_rgb(hue, SATURATION, lightness)
+}
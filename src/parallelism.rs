@@ -0,0 +1,231 @@
+use crate::parse_cargo_tree_output::TreeNode;
+use crate::timeline::Timeline;
+use crate::timings::Timings;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+// Crates we have no timing for (fresh or not built yet) are assumed to take
+// this long when simulating the ideal schedule
+const UNKNOWN_SECS: f32 = 1.0;
+
+// The tree as a graph over distinct crate names, dependencies listed per crate
+pub struct CrateGraph {
+    names: Vec<String>,
+    deps: Vec<Vec<usize>>,
+}
+
+impl CrateGraph {
+    pub fn new(tree: &TreeNode) -> Self {
+        fn collect(
+            node: &TreeNode,
+            index: &mut HashMap<String, usize>,
+            edges: &mut Vec<HashSet<usize>>,
+        ) -> usize {
+            let id = match index.get(&node.name) {
+                Some(&id) => id,
+                None => {
+                    index.insert(node.name.clone(), edges.len());
+                    edges.push(HashSet::new());
+                    edges.len() - 1
+                }
+            };
+
+            for child in node.children.iter() {
+                let child_id = collect(child, index, edges);
+                edges[id].insert(child_id);
+            }
+
+            id
+        }
+
+        let mut index = HashMap::new();
+        let mut edges = Vec::new();
+        collect(tree, &mut index, &mut edges);
+
+        let mut names = vec![String::new(); edges.len()];
+        for (name, id) in index {
+            names[id] = name;
+        }
+
+        CrateGraph {
+            names,
+            deps: edges
+                .into_iter()
+                .map(|deps| {
+                    let mut deps = deps.into_iter().collect::<Vec<_>>();
+                    deps.sort_unstable();
+                    deps
+                })
+                .collect(),
+        }
+    }
+
+    // Everything each crate depends on, directly or not
+    fn closure(&self) -> Vec<HashSet<usize>> {
+        fn visit(graph: &CrateGraph, id: usize, closure: &mut Vec<Option<HashSet<usize>>>) {
+            if closure[id].is_some() {
+                return;
+            }
+
+            let mut reachable = HashSet::new();
+            for &dep in graph.deps[id].iter() {
+                visit(graph, dep, closure);
+                reachable.insert(dep);
+                reachable.extend(closure[dep].as_ref().unwrap().iter().copied());
+            }
+
+            closure[id] = Some(reachable);
+        }
+
+        let mut closure = vec![None; self.names.len()];
+        for id in 0..self.names.len() {
+            visit(self, id, &mut closure);
+        }
+
+        closure.into_iter().map(Option::unwrap).collect()
+    }
+
+    // The most crates that could ever compile at the same time, i.e. the
+    // widest set where none depends on another. By Dilworth's theorem that is
+    // the number of crates minus a maximum matching over "depends on".
+    pub fn widest_antichain(&self) -> usize {
+        fn augment(
+            id: usize,
+            closure: &[HashSet<usize>],
+            seen: &mut [bool],
+            matched: &mut [Option<usize>],
+        ) -> bool {
+            for &dep in closure[id].iter() {
+                if seen[dep] {
+                    continue;
+                }
+                seen[dep] = true;
+
+                if matched[dep].is_none_or(|other| augment(other, closure, seen, matched)) {
+                    matched[dep] = Some(id);
+                    return true;
+                }
+            }
+
+            false
+        }
+
+        let closure = self.closure();
+        let mut matched = vec![None; self.names.len()];
+        let mut matching = 0;
+
+        for id in 0..self.names.len() {
+            let mut seen = vec![false; self.names.len()];
+            if augment(id, &closure, &mut seen, &mut matched) {
+                matching += 1;
+            }
+        }
+
+        self.names.len() - matching
+    }
+
+    // When each crate would compile with unlimited cores: as soon as all of
+    // its dependencies are done
+    pub fn ideal_schedule(&self, durations: &HashMap<String, Duration>) -> Vec<(f32, f32)> {
+        fn finish(
+            graph: &CrateGraph,
+            id: usize,
+            durations: &HashMap<String, Duration>,
+            spans: &mut Vec<Option<(f32, f32)>>,
+        ) -> f32 {
+            if let Some((_, end)) = spans[id] {
+                return end;
+            }
+
+            let start = graph.deps[id]
+                .iter()
+                .map(|&dep| finish(graph, dep, durations, spans))
+                .fold(0.0, f32::max);
+            let secs = durations
+                .get(&graph.names[id])
+                .map(|duration| duration.as_secs_f32())
+                .unwrap_or(UNKNOWN_SECS);
+
+            spans[id] = Some((start, start + secs));
+            start + secs
+        }
+
+        let mut spans = vec![None; self.names.len()];
+        for id in 0..self.names.len() {
+            finish(self, id, durations, &mut spans);
+        }
+
+        spans.into_iter().map(Option::unwrap).collect()
+    }
+}
+
+// How many spans are running at each of `samples` evenly spaced moments
+pub fn profile(spans: &[(f32, f32)], total_secs: f32, samples: usize) -> Vec<usize> {
+    (0..samples)
+        .map(|idx| {
+            let at = total_secs * (idx as f32 + 0.5) / samples as f32;
+            spans
+                .iter()
+                .filter(|(start, end)| *start <= at && at < *end)
+                .count()
+        })
+        .collect()
+}
+
+// What cargo was told to use, falling back to its own default of one job per
+// core
+pub fn jobs(cargo_args: &[String]) -> usize {
+    let mut args = cargo_args.iter();
+
+    while let Some(arg) = args.next() {
+        let value = if arg == "-j" || arg == "--jobs" {
+            args.next().cloned()
+        } else {
+            arg.strip_prefix("--jobs=")
+                .or_else(|| arg.strip_prefix("-j"))
+                .map(|value| value.to_owned())
+        };
+
+        if let Some(jobs) = value.and_then(|value| value.parse().ok()) {
+            return jobs;
+        }
+    }
+
+    std::thread::available_parallelism()
+        .map(|jobs| jobs.get())
+        .unwrap_or(1)
+}
+
+const SAMPLES: usize = 120;
+
+// The ideal schedule next to what actually happened, on a shared time axis
+pub struct Comparison {
+    pub widest: usize,
+    pub jobs: usize,
+    pub ideal_secs: f32,
+    pub observed_secs: f32,
+    pub ideal: Vec<usize>,
+    pub observed: Vec<usize>,
+}
+
+pub fn compare(
+    graph: &CrateGraph,
+    widest: usize,
+    jobs: usize,
+    timings: &Timings,
+    timeline: &Timeline,
+) -> Comparison {
+    let observed_secs = timings.elapsed().as_secs_f32();
+    let ideal_spans = graph.ideal_schedule(&timings.durations);
+    let ideal_secs = ideal_spans.iter().map(|(_, end)| *end).fold(0.0, f32::max);
+    let axis = ideal_secs.max(observed_secs);
+
+    Comparison {
+        widest,
+        jobs,
+        ideal_secs,
+        observed_secs,
+        ideal: profile(&ideal_spans, axis, SAMPLES),
+        observed: profile(&timeline.spans(observed_secs), axis, SAMPLES),
+    }
+}
@@ -1,12 +1,58 @@
-use itertools::Itertools;
-use std::rc::Rc;
 use crate::drawing::Color;
+use crate::palette;
+use itertools::{Either, Itertools};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TreeNode {
     pub name: String,
-    pub children: Vec<Rc<TreeNode>>,
-    pub color: Color
+    pub children: Vec<Arc<TreeNode>>,
+    pub color: Color,
+}
+
+// What stands for the workspace when a build covers several of its members,
+// each of them one of its children. No crate can be called that, so a crate
+// called workspace isn't mistaken for it.
+pub const WORKSPACE_ROOT: &str = "<workspace>";
+
+impl TreeNode {
+    pub fn is_workspace(&self) -> bool {
+        self.name == WORKSPACE_ROOT
+    }
+
+    // This crate and every one below it, once each
+    pub fn crates(&self) -> HashSet<&str> {
+        fn collect<'a>(node: &'a TreeNode, names: &mut HashSet<&'a str>) {
+            if names.insert(&node.name) {
+                for child in node.children.iter() {
+                    collect(child, names);
+                }
+            }
+        }
+
+        let mut names = HashSet::new();
+        collect(self, &mut names);
+        names
+    }
+
+    // Distinct crates below this one, since the tree repeats shared dependencies
+    pub fn transitive_count(&self) -> usize {
+        fn collect<'a>(node: &'a TreeNode, names: &mut HashSet<&'a str>) {
+            for child in node.children.iter() {
+                names.insert(&child.name);
+                collect(child, names);
+            }
+        }
+
+        let mut names = HashSet::new();
+        collect(self, &mut names);
+        names.len()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -15,39 +61,52 @@ struct FlatEntry {
     name: String,
 }
 
-fn parse(raw: String) -> Vec<FlatEntry> {
-    let mut result = Vec::<FlatEntry>::new();
-    for line in raw.lines() {
-        let idx = line
-            .chars()
-            .take_while(|x| x.is_ascii_digit())
-            .collect::<String>()
-            .parse::<usize>()
-            .unwrap();
-
-        let start = line.find(|c: char| !c.is_ascii_digit()).unwrap();
-        let stop = line.find(|c: char| c.is_whitespace()).unwrap();
-
-        let package = &line[start..stop];
-
-        result.push(FlatEntry {
-            depth: idx,
-            name: package.replace("_", "-").to_string(),
-        });
+// A line as `--prefix depth` prints it, e.g. "2serde_derive v1.0.229 (proc-macro)"
+fn entry(line: &str) -> Result<FlatEntry, String> {
+    let start = line
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(line.len());
+    let depth = line[..start]
+        .parse::<usize>()
+        .map_err(|_| format!("no depth in front of `{}`", line))?;
+
+    let package = line[start..]
+        .split(char::is_whitespace)
+        .next()
+        .unwrap_or("");
+    if package.is_empty() {
+        return Err(format!("no crate after the depth in `{}`", line));
     }
-    result
+
+    Ok(FlatEntry {
+        depth,
+        name: package.replace("_", "-").to_string(),
+    })
 }
 
-fn tree(flat: Vec<FlatEntry>) -> Rc<TreeNode> {
+// Lines that don't read are left out, each with an error saying why
+fn parse(raw: &str) -> (Vec<FlatEntry>, Vec<String>) {
+    // A blank line between the tree of each workspace member
+    raw.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(entry)
+        .partition_map(|entry| match entry {
+            Ok(entry) => Either::Left(entry),
+            Err(e) => Either::Right(e),
+        })
+}
+
+// `flat` starts with the node's own line, its subtree is what follows deeper
+fn tree(flat: &[FlatEntry], color_of: &dyn Fn(&str) -> Option<Color>) -> Arc<TreeNode> {
     let root = &flat[0];
     let candidates = &flat[1..];
 
     let name = root.name.to_string();
 
-    let digest = md5::compute(name.clone().into_bytes());
+    let color = color_of(&name).unwrap_or_else(|| palette::color_for(&name));
 
-    Rc::<_>::new(TreeNode {
-        color: (digest[0], digest[1], digest[2]),
+    Arc::<_>::new(TreeNode {
+        color,
         name,
         children: candidates
             .iter()
@@ -55,7 +114,7 @@ fn tree(flat: Vec<FlatEntry>) -> Rc<TreeNode> {
             .enumerate()
             .filter_map(|(idx, child)| {
                 if child.depth == root.depth + 1 {
-                    Some(tree(candidates[idx..].to_vec()))
+                    Some(tree(&candidates[idx..], color_of))
                 } else {
                     None
                 }
@@ -65,6 +124,174 @@ fn tree(flat: Vec<FlatEntry>) -> Rc<TreeNode> {
     })
 }
 
-pub fn parse_tree(raw: String) -> Rc<TreeNode> {
-    tree(parse(raw))
+pub fn parse_tree(raw: String) -> Arc<TreeNode> {
+    parse_tree_colored(raw, &|_| None)
+}
+
+// Crates `color_of` has no color for get one derived from their name. A
+// build of several workspace members gets one tree under `WORKSPACE_ROOT`.
+pub fn parse_tree_colored(raw: String, color_of: &dyn Fn(&str) -> Option<Color>) -> Arc<TreeNode> {
+    let (flat, malformed) = parse(&raw);
+    for e in malformed {
+        eprintln!("treebuild: skipping a line of cargo tree's output, {}", e);
+    }
+    assert!(!flat.is_empty(), "cargo tree printed no crates");
+
+    if flat.iter().filter(|entry| entry.depth == 0).count() < 2 {
+        return tree(&flat, color_of);
+    }
+
+    let members = flat
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.depth == 0)
+        .map(|(idx, _)| tree(&flat[idx..], color_of))
+        .collect();
+    Arc::new(TreeNode {
+        name: WORKSPACE_ROOT.to_owned(),
+        children: members,
+        color: color_of(WORKSPACE_ROOT).unwrap_or_else(|| palette::color_for(WORKSPACE_ROOT)),
+    })
+}
+
+// The order of each crate's children, which is the order they are laid out in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChildOrder {
+    // Those with the fewest dependencies of their own first, and otherwise in
+    // the order cargo lists them, which is how trees are parsed
+    ChildCount,
+    Name,
+    // The slowest to build first as of the last build, the rest by name
+    BuildTime,
+}
+
+impl ChildOrder {
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "child-count" => Some(ChildOrder::ChildCount),
+            "name" => Some(ChildOrder::Name),
+            "build-time" => Some(ChildOrder::BuildTime),
+            _ => None,
+        }
+    }
+}
+
+// The tree with every crate's children in `order`, `durations` being the
+// seconds each crate took to build
+pub fn sort_children(
+    tree: &Arc<TreeNode>,
+    order: ChildOrder,
+    durations: &HashMap<String, f32>,
+) -> Arc<TreeNode> {
+    fn compare(
+        order: ChildOrder,
+        durations: &HashMap<String, f32>,
+    ) -> impl Fn(&Arc<TreeNode>, &Arc<TreeNode>) -> Ordering + '_ {
+        move |a, b| match order {
+            ChildOrder::ChildCount => a.children.len().cmp(&b.children.len()),
+            ChildOrder::Name => a.name.cmp(&b.name),
+            ChildOrder::BuildTime => {
+                let took = |node: &TreeNode| durations.get(&node.name).copied().unwrap_or(-1.0);
+                took(b)
+                    .total_cmp(&took(a))
+                    .then_with(|| a.name.cmp(&b.name))
+            }
+        }
+    }
+
+    fn below(
+        node: &TreeNode,
+        compare: &dyn Fn(&Arc<TreeNode>, &Arc<TreeNode>) -> Ordering,
+    ) -> Arc<TreeNode> {
+        let mut children = node
+            .children
+            .iter()
+            .map(|child| below(child, compare))
+            .collect::<Vec<_>>();
+        children.sort_by(compare);
+
+        Arc::new(TreeNode {
+            name: node.name.clone(),
+            color: node.color,
+            children,
+        })
+    }
+
+    // Already how they are parsed
+    if order == ChildOrder::ChildCount {
+        return Arc::clone(tree);
+    }
+    below(tree, &compare(order, durations))
+}
+
+// The tree with only the crates `keep` accepts by name and depth, the root
+// being at depth 0 and always kept. Whatever is below a crate that isn't kept
+// goes with it.
+pub fn prune(tree: &Arc<TreeNode>, keep: &dyn Fn(&str, usize) -> bool) -> Arc<TreeNode> {
+    fn below(node: &TreeNode, depth: usize, keep: &dyn Fn(&str, usize) -> bool) -> Arc<TreeNode> {
+        Arc::new(TreeNode {
+            name: node.name.clone(),
+            color: node.color,
+            children: node
+                .children
+                .iter()
+                .filter(|child| keep(&child.name, depth + 1))
+                .map(|child| below(child, depth + 1, keep))
+                .collect(),
+        })
+    }
+
+    below(tree, 0, keep)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn members_go_under_the_workspace_root() {
+        let tree = parse_tree("0a v0.1.0\n1c v1.0.0\n\n0b v0.1.0\n1a v0.1.0\n".to_owned());
+
+        assert!(tree.is_workspace());
+        assert_eq!(
+            tree.children
+                .iter()
+                .map(|m| m.name.as_str())
+                .collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+    }
+
+    #[test]
+    fn a_crate_called_workspace_is_just_a_crate() {
+        let tree = parse_tree("0workspace v0.1.0 (/w)\n1serde v1.0.0\n".to_owned());
+
+        assert!(!tree.is_workspace());
+        assert_eq!(tree.name, "workspace");
+
+        let tree = parse_tree("0workspace v0.1.0\n\n0b v0.1.0\n1workspace v0.1.0\n".to_owned());
+        assert!(tree.is_workspace());
+        assert!(tree.children.iter().all(|member| !member.is_workspace()));
+    }
+
+    #[test]
+    fn malformed_lines_are_errors() {
+        assert!(entry("serde v1.0.0").is_err());
+        assert!(entry("3").is_err());
+        assert!(entry("2 v1.0.0").is_err());
+        assert_eq!(entry("2serde_json v1.0.152").unwrap().name, "serde-json");
+
+        let (flat, malformed) = parse("0a v0.1.0\nwarning: spurious network error\n1b v1.0.0\n");
+        assert_eq!(
+            flat.iter()
+                .map(|entry| (entry.depth, entry.name.as_str()))
+                .collect::<Vec<_>>(),
+            [(0, "a"), (1, "b")]
+        );
+        assert_eq!(malformed.len(), 1);
+        assert_eq!(
+            parse_tree("0a v0.1.0\n???\n1b v1.0.0\n".to_owned()).transitive_count(),
+            1
+        );
+    }
 }
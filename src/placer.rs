@@ -0,0 +1,111 @@
+use crate::dependency_tree::DependencyTree;
+use crate::drawing::Point;
+use crate::layout::{self, Layout, LayoutView, Positions, SizeMetric};
+use crate::parse_cargo_tree_output::TreeNode;
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+
+// A `LayoutView` and the rest of what placing needs, owned so it can go over
+// to the placing thread
+pub struct Request {
+    pub tree: Arc<DependencyTree>,
+    pub root: Arc<TreeNode>,
+    // Into `LAYOUT_NAMES`
+    pub layout: usize,
+    pub aggregate_depth: Option<usize>,
+    pub expanded: Option<HashSet<usize>>,
+    pub time: f32,
+    pub metric: SizeMetric,
+    pub weights: HashMap<String, f32>,
+    pub fixed: HashMap<String, Point>,
+    pub sized_nodes: bool,
+}
+
+pub struct Placed {
+    // The root the request was for, which may not be shown anymore
+    pub root: Arc<TreeNode>,
+    pub positions: Positions,
+}
+
+// Runs the layouts on a thread of their own, so a big tree or a force-directed
+// layout still settling never holds up a frame. One request is worked on at a
+// time, and the window keeps showing the last placement until the next one
+// comes back.
+pub struct Placer {
+    requests: Sender<Request>,
+    placed: Receiver<(Placed, bool)>,
+    busy: bool,
+    settled: bool,
+}
+
+impl Placer {
+    pub fn new(mut layouts: Vec<Box<dyn Layout>>) -> Self {
+        let (requests, incoming) = channel::<Request>();
+        let (outgoing, placed) = channel();
+
+        thread::spawn(move || {
+            for request in incoming {
+                let view = LayoutView {
+                    root: &request.root,
+                    aggregate_depth: request.aggregate_depth,
+                    expanded: request.expanded.as_ref(),
+                    time: request.time,
+                    metric: request.metric,
+                    weights: &request.weights,
+                    fixed: &request.fixed,
+                };
+                let layout = &mut layouts[request.layout];
+
+                let mut positions = layout.place(&request.tree, &view);
+                if request.sized_nodes {
+                    layout::scale_radii(&mut positions, &view);
+                }
+                let placed = Placed {
+                    root: Arc::clone(&request.root),
+                    positions,
+                };
+
+                // Gone with the window
+                if outgoing.send((placed, layout.settled())).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Placer {
+            requests,
+            placed,
+            busy: false,
+            settled: true,
+        }
+    }
+
+    // Whether a request is still being worked on, until which no other is
+    // taken
+    pub fn busy(&self) -> bool {
+        self.busy
+    }
+
+    pub fn place(&mut self, request: Request) {
+        if !self.busy {
+            self.busy = self.requests.send(request).is_ok();
+        }
+    }
+
+    // The last request's placement, once it is done
+    pub fn placed(&mut self) -> Option<Placed> {
+        let (placed, settled) = self.placed.try_recv().ok()?;
+
+        self.busy = false;
+        self.settled = settled;
+        Some(placed)
+    }
+
+    // Whether the layout last placed with would move anything placing again,
+    // see `Layout::settled`
+    pub fn settled(&self) -> bool {
+        self.settled
+    }
+}
@@ -0,0 +1,38 @@
+use std::fs;
+use std::process::Command;
+
+// Linux lists every power source, a laptop on battery has a battery
+// discharging and no mains adapter online
+fn linux_on_battery() -> Option<bool> {
+    let mut on_battery = None;
+
+    for entry in fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let read = |file: &str| {
+            fs::read_to_string(entry.path().join(file))
+                .map(|contents| contents.trim().to_owned())
+                .unwrap_or_default()
+        };
+
+        match read("type").as_str() {
+            "Mains" if read("online") == "1" => return Some(false),
+            "Battery" if read("status") == "Discharging" => on_battery = Some(true),
+            _ => {}
+        }
+    }
+
+    on_battery
+}
+
+fn macos_on_battery() -> Option<bool> {
+    let output = Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+
+    Some(String::from_utf8_lossy(&output.stdout).contains("'Battery Power'"))
+        .filter(|_| output.status.success())
+}
+
+// Desktops, and anything we can't tell, count as plugged in
+pub fn on_battery() -> bool {
+    linux_on_battery()
+        .or_else(macos_on_battery)
+        .unwrap_or(false)
+}
@@ -0,0 +1,412 @@
+//! The project in the current directory, the way cargo sees it.
+
+use crate::cargo_version::{self, CargoVersion};
+use crate::daemon;
+use crate::lockfile;
+use crate::parse_cargo_tree_output::{parse_tree_colored, TreeNode};
+use crate::theme;
+use crate::unit_graph;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+pub use crate::theme::ColorRule;
+
+lazy_static! {
+    pub(crate) static ref CARGO_VERSION: Option<CargoVersion> =
+        cargo_version::detect(Path::new("."));
+    static ref WORKSPACE: Option<WorkspaceMetadata> = workspace();
+    static ref SELECTED: Mutex<Selection> = Mutex::new(Selection::default());
+}
+
+// What --cargo and +toolchain asked for
+#[derive(Default)]
+struct Selection {
+    program: Option<String>,
+    toolchain: Option<String>,
+}
+
+// Of the workspace the current directory is in, wherever in it we are and
+// wherever CARGO_TARGET_DIR or .cargo/config put the target directory
+#[derive(Deserialize)]
+struct WorkspaceMetadata {
+    target_directory: PathBuf,
+    workspace_root: PathBuf,
+}
+
+fn workspace() -> Option<WorkspaceMetadata> {
+    let output = cargo()
+        .args(["metadata", "--no-deps", "--format-version", "1"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+/// Has everything from now on run `program` instead of cargo, and with
+/// `toolchain` the way `cargo +toolchain` would. Some of what cargo says is
+/// kept for the whole run, so this goes before anything asks it.
+pub fn select_cargo(program: Option<String>, toolchain: Option<String>) {
+    *SELECTED.lock().unwrap() = Selection { program, toolchain };
+}
+
+/// The cargo to run: the one [`select_cargo`] selected, otherwise the one in
+/// `CARGO`, which cargo sets to itself when it runs treebuild as `cargo
+/// treebuild`, otherwise the one on the path.
+pub fn cargo() -> Command {
+    let selected = SELECTED.lock().unwrap();
+    // `CARGO` is one toolchain's own cargo, only rustup's knows about others
+    let program = match (&selected.program, &selected.toolchain) {
+        (Some(program), _) => program.clone(),
+        (None, Some(_)) => "cargo".to_owned(),
+        (None, None) => env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned()),
+    };
+
+    let mut command = Command::new(program);
+    if let Some(ref toolchain) = selected.toolchain {
+        command.arg(format!("+{}", toolchain));
+    }
+    command
+}
+
+/// Where treebuild keeps what it remembers about the project between runs,
+/// in the target directory of the workspace the current directory is in.
+pub fn state_dir() -> PathBuf {
+    WORKSPACE
+        .as_ref()
+        .map_or_else(
+            || PathBuf::from("target"),
+            |workspace| workspace.target_directory.clone(),
+        )
+        .join("treebuild")
+}
+
+/// The project's dependency tree as `cargo tree` prints it, every crate
+/// colored by the first of `color_rules` that matches its name. Asks a
+/// running `treebuild daemon` first, which most likely has it already.
+///
+/// Panics when cargo can't be run or fails.
+pub fn cargo_tree(dev_dependencies: bool, color_rules: &[ColorRule]) -> Arc<TreeNode> {
+    cargo_tree_with(dev_dependencies, color_rules, &[])
+}
+
+/// Like [`cargo_tree`], with `args` such as `--features` or `--target` passed
+/// on to `cargo tree`. The daemon only knows the tree without any, so it isn't
+/// asked when there are some.
+pub fn cargo_tree_with(
+    dev_dependencies: bool,
+    color_rules: &[ColorRule],
+    args: &[String],
+) -> Arc<TreeNode> {
+    let parse = |out| parse_tree_colored(out, &|name| theme::rule_color(color_rules, name));
+
+    if args.is_empty() {
+        if let Some(out) = daemon::ask_here(|project| daemon::Request::Tree {
+            project,
+            dev_dependencies,
+        }) {
+            return parse(out);
+        }
+    }
+
+    let output = cargo()
+        .args(cargo_version::tree_args(*CARGO_VERSION, dev_dependencies))
+        .args(args)
+        .output()
+        .expect("Cargo tree failed");
+
+    io::stderr().write_all(&output.stderr).unwrap();
+
+    assert!(output.status.success());
+    let out = String::from_utf8_lossy(&output.stdout).to_string();
+
+    parse(out)
+}
+
+/// The project's build the way cargo plans it, from `cargo <cargo_command>
+/// <cargo_args> --unit-graph`. Like [`cargo_tree`], but with build scripts as
+/// nodes of their own, named like `foo/build-script-build`, and crates built
+/// for both the host and the target in there twice. Needs a nightly cargo.
+///
+/// Panics when cargo can't be run or fails.
+pub fn unit_graph(
+    cargo_command: &[&str],
+    cargo_args: &[String],
+    color_rules: &[ColorRule],
+) -> Arc<TreeNode> {
+    let output = cargo()
+        .args(cargo_command)
+        .args(["--unit-graph", "-Z", "unstable-options"])
+        .args(cargo_args)
+        .output()
+        .expect("Cargo failed to plan the build");
+
+    io::stderr().write_all(&output.stderr).unwrap();
+
+    assert!(
+        output.status.success(),
+        "The unit graph needs a nightly cargo, e.g. treebuild +nightly"
+    );
+
+    unit_graph::parse_unit_graph(&String::from_utf8_lossy(&output.stdout), &|name| {
+        theme::rule_color(color_rules, name)
+    })
+    .expect("Failed to parse the unit graph")
+}
+
+/// The license expression of every package in the project's dependency
+/// graph that gives one, by package name, from `cargo metadata`. Empty when
+/// cargo can't be run.
+pub fn licenses() -> HashMap<String, String> {
+    crate::metadata::load()
+        .into_iter()
+        .filter_map(|(name, info)| Some((name, info.license.clone()?)))
+        .collect()
+}
+
+// Checked out for as long as it is around
+struct Worktree(PathBuf);
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        let _ = Command::new("git")
+            .args(["worktree", "remove", "--force"])
+            .arg(&self.0)
+            .status();
+    }
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+
+    Some(String::from_utf8_lossy(&output.stdout).into_owned()).filter(|_| output.status.success())
+}
+
+/// The short hash of the commit checked out, None outside of a git
+/// repository.
+pub fn git_commit() -> Option<String> {
+    git(&["rev-parse", "--short", "HEAD"]).map(|commit| commit.trim().to_owned())
+}
+
+/// The name of the directory the workspace is in, None when cargo doesn't
+/// know of one.
+pub fn workspace_name() -> Option<String> {
+    let root = &WORKSPACE.as_ref()?.workspace_root;
+    Some(root.file_name()?.to_string_lossy().into_owned())
+}
+
+/// Like [`cargo_tree_with`], but for the project as of the git revision
+/// `rev`, which is checked out next to it for the while. Never asks the
+/// daemon.
+///
+/// Panics when the revision can't be checked out, or cargo fails on it.
+pub fn cargo_tree_at(
+    rev: &str,
+    dev_dependencies: bool,
+    color_rules: &[ColorRule],
+    args: &[String],
+) -> Arc<TreeNode> {
+    let prefix = git(&["rev-parse", "--show-prefix"]).expect("Not in a git repository");
+    let dir = env::temp_dir().join(format!("treebuild-{}", std::process::id()));
+
+    let checked_out = Command::new("git")
+        .args(["worktree", "add", "--detach", "--quiet"])
+        .arg(&dir)
+        .arg(rev)
+        .status()
+        .expect("Failed to run git");
+    assert!(checked_out.success(), "Failed to check out {}", rev);
+    let worktree = Worktree(dir);
+
+    let manifest = worktree.0.join(prefix.trim()).join("Cargo.toml");
+    let args = args
+        .iter()
+        .cloned()
+        .chain(["--manifest-path".to_owned(), manifest.display().to_string()])
+        .collect::<Vec<_>>();
+    cargo_tree_with(dev_dependencies, color_rules, &args)
+}
+
+/// Every version of each crate the workspace's Cargo.lock locks, by the name
+/// [`cargo_tree`] gives it, or as Cargo.lock was at the git revision `rev`.
+/// Empty when there is no Cargo.lock to go by.
+pub fn locked_versions(rev: Option<&str>) -> HashMap<String, Vec<String>> {
+    let root = match WORKSPACE.as_ref() {
+        Some(workspace) => &workspace.workspace_root,
+        None => return HashMap::new(),
+    };
+
+    let raw = match rev {
+        None => fs::read_to_string(root.join("Cargo.lock")).ok(),
+        Some(rev) => git(&["show", &format!("{}:{}", rev, lockfile_pathspec(root))]),
+    };
+
+    raw.and_then(|raw| lockfile::versions(&raw).ok())
+        .unwrap_or_default()
+}
+
+// The workspace's Cargo.lock from where we are, for git. Git takes paths
+// starting with ./ as relative to where we are.
+fn lockfile_pathspec(workspace_root: &Path) -> String {
+    let depth = env::current_dir()
+        .ok()
+        .and_then(|dir| Some(dir.strip_prefix(workspace_root).ok()?.components().count()))
+        .unwrap_or(0);
+
+    format!("./{}Cargo.lock", "../".repeat(depth))
+}
+
+// The package in the current directory's Cargo.toml, and what it names its
+// dev-dependencies in Cargo.lock
+fn manifest_package() -> Result<(String, HashSet<String>), String> {
+    let raw = fs::read_to_string("Cargo.toml").map_err(|e| format!("Cargo.toml: {}", e))?;
+    let manifest = raw
+        .parse::<toml::Value>()
+        .map_err(|e| format!("Cargo.toml: {}", e))?;
+
+    let name = manifest
+        .get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(toml::Value::as_str)
+        .ok_or("Cargo.toml has no [package], run this in one of the workspace's packages")?;
+
+    let platforms = manifest
+        .get("target")
+        .and_then(toml::Value::as_table)
+        .into_iter()
+        .flat_map(|targets| targets.values());
+    let dev_dependencies = std::iter::once(&manifest)
+        .chain(platforms)
+        .filter_map(|table| table.get("dev-dependencies")?.as_table())
+        .flatten()
+        // Renamed ones say which package they are
+        .map(|(key, dependency)| {
+            dependency
+                .get("package")
+                .and_then(toml::Value::as_str)
+                .unwrap_or(key)
+                .to_owned()
+        })
+        .collect();
+
+    Ok((name.to_owned(), dev_dependencies))
+}
+
+/// The project's dependency tree from Cargo.lock and Cargo.toml alone,
+/// without running cargo, for when that is slow or can't be done, or for a
+/// Cargo.lock from some other time. A Cargo.lock doesn't tell which
+/// dependencies are only for other platforms or behind features that are
+/// off, so the tree has all of them.
+///
+/// The package is the one in the current directory, and `lockfile` the
+/// workspace's own Cargo.lock when None, found from here up.
+pub fn lockfile_tree(
+    lockfile: Option<&Path>,
+    dev_dependencies: bool,
+    color_rules: &[ColorRule],
+) -> Result<Arc<TreeNode>, String> {
+    let (root, dev) = manifest_package()?;
+
+    let lockfile = match lockfile {
+        Some(lockfile) => lockfile.to_owned(),
+        None => env::current_dir()
+            .map_err(|e| e.to_string())?
+            .ancestors()
+            .map(|dir| dir.join("Cargo.lock"))
+            .find(|lockfile| lockfile.is_file())
+            .ok_or("No Cargo.lock here or above")?,
+    };
+    let raw =
+        fs::read_to_string(&lockfile).map_err(|e| format!("{}: {}", lockfile.display(), e))?;
+
+    let skip = if dev_dependencies {
+        HashSet::new()
+    } else {
+        dev
+    };
+    lockfile::parse_lockfile(&raw, &root, &skip, &|name| {
+        theme::rule_color(color_rules, name)
+    })
+}
+
+/// The tree as one commit's Cargo.lock had it, see [`lockfile_history`].
+pub struct LockfileRevision {
+    /// Abbreviated, the way git prints it.
+    pub commit: String,
+    /// When it was committed, in seconds since the Unix epoch.
+    pub time: u64,
+    pub subject: String,
+    pub tree: Arc<TreeNode>,
+    /// Crates in the tree, each counted once.
+    pub crates: usize,
+}
+
+/// The tree for every commit that changed the workspace's Cargo.lock, oldest
+/// first, the way [`lockfile_tree`] reads it. Commits whose Cargo.lock doesn't
+/// have the package are left out, as are the ones before it had a Cargo.lock.
+/// What counts as a dev-dependency goes by today's Cargo.toml throughout.
+pub fn lockfile_history(
+    dev_dependencies: bool,
+    color_rules: &[ColorRule],
+) -> Result<Vec<LockfileRevision>, String> {
+    fn count(node: &TreeNode, names: &mut HashSet<String>) {
+        if names.insert(node.name.clone()) {
+            node.children.iter().for_each(|child| count(child, names));
+        }
+    }
+
+    let (root, dev) = manifest_package()?;
+    let workspace = WORKSPACE.as_ref().ok_or("Not in a cargo workspace")?;
+    let pathspec = lockfile_pathspec(&workspace.workspace_root);
+    let skip = if dev_dependencies {
+        HashSet::new()
+    } else {
+        dev
+    };
+
+    let log = git(&[
+        "log",
+        "--reverse",
+        "--format=%h%x09%ct%x09%s",
+        "--",
+        &pathspec,
+    ])
+    .ok_or("Failed to read the git history of Cargo.lock")?;
+
+    let revisions = log
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let commit = fields.next()?.to_owned();
+            let time = fields.next()?.parse().ok()?;
+            let subject = fields.next().unwrap_or_default().to_owned();
+
+            let raw = git(&["show", &format!("{}:{}", commit, pathspec)])?;
+            let tree = lockfile::parse_lockfile(&raw, &root, &skip, &|name| {
+                theme::rule_color(color_rules, name)
+            })
+            .ok()?;
+
+            let mut names = HashSet::new();
+            count(&tree, &mut names);
+            Some(LockfileRevision {
+                commit,
+                time,
+                subject,
+                tree,
+                crates: names.len(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if revisions.is_empty() {
+        return Err(format!("No commit has {} in Cargo.lock", root));
+    }
+    Ok(revisions)
+}
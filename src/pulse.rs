@@ -0,0 +1,97 @@
+use crate::batch::Batch;
+use crate::drawing::{Color, Point};
+use nannou::prelude::*;
+
+// Seconds per beat of a running crate
+const PERIOD: f32 = 1.2;
+// How much a running crate swells at the top of a beat
+const SWELL: f32 = 0.08;
+const GLOW_RINGS: usize = 3;
+const GLOW_SPREAD: f32 = 0.25;
+// Along the edge into a running crate, in pixels and pixels per second
+const DASH: f32 = 8.0;
+const GAP: f32 = 6.0;
+const SPEED: f32 = 30.0;
+
+// Where in the beat the frame clock is, easing from 0 up to 1 and back
+pub fn phase(time: f32) -> f32 {
+    0.5 - 0.5 * (time * TAU / PERIOD).cos()
+}
+
+pub fn radius(radius: f32, phase: f32) -> f32 {
+    radius * (1.0 + SWELL * phase)
+}
+
+pub fn blend(from: Color, to: Color, phase: f32) -> Color {
+    let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * phase) as u8;
+
+    (mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+}
+
+// Fading rings around the crate, brightest at the top of a beat
+pub fn glow(batch: &mut Batch, center: Point, radius: f32, color: Color, phase: f32) {
+    for ring in 0..GLOW_RINGS {
+        let spread = (ring + 1) as f32 / GLOW_RINGS as f32;
+        let alpha = (phase * (1.0 - spread) * 96.0) as u8;
+
+        batch.ring(
+            center,
+            radius * (1.0 + GLOW_SPREAD * spread),
+            radius * GLOW_SPREAD / GLOW_RINGS as f32,
+            srgba(color.0, color.1, color.2, alpha),
+        );
+    }
+}
+
+// Cuts a path up into dashes that crawl along it towards its end as time goes
+// on, so an edge shows which way the work is flowing
+pub fn dashes(path: &[Point], time: f32) -> Vec<Vec<Point>> {
+    let period = DASH + GAP;
+    let mut dashes = Vec::new();
+    let mut current = Vec::new();
+    // How far along the current period the start of each segment is
+    let mut along = (period - (time * SPEED) % period) % period;
+
+    for segment in path.windows(2) {
+        let (from, to) = (segment[0], segment[1]);
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        let point_at = |distance: f32| {
+            let t = distance / length.max(f32::EPSILON);
+            (from.0 + dx * t, from.1 + dy * t)
+        };
+        let mut covered = 0.0;
+
+        while covered < length {
+            let step = if along < DASH {
+                DASH - along
+            } else {
+                period - along
+            };
+            let step = step.min(length - covered);
+
+            if along < DASH {
+                if current.is_empty() {
+                    current.push(point_at(covered));
+                }
+                current.push(point_at(covered + step));
+            }
+
+            covered += step;
+            along += step;
+
+            if along >= DASH && !current.is_empty() {
+                dashes.push(std::mem::take(&mut current));
+            }
+            if along >= period {
+                along -= period;
+            }
+        }
+    }
+
+    if current.len() > 1 {
+        dashes.push(current);
+    }
+
+    dashes
+}
@@ -0,0 +1,262 @@
+use crate::drawing::Point;
+
+const MAX_ITEMS: usize = 8;
+const MAX_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Bounds {
+    pub fn around(center: Point, radius: f32) -> Self {
+        Bounds {
+            min: (center.0 - radius, center.1 - radius),
+            max: (center.0 + radius, center.1 + radius),
+        }
+    }
+
+    pub fn union(self, other: Bounds) -> Self {
+        Bounds {
+            min: (self.min.0.min(other.min.0), self.min.1.min(other.min.1)),
+            max: (self.max.0.max(other.max.0), self.max.1.max(other.max.1)),
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn contains_point(&self, point: Point) -> bool {
+        point.0 >= self.min.0
+            && point.0 <= self.max.0
+            && point.1 >= self.min.1
+            && point.1 <= self.max.1
+    }
+
+    pub fn overlaps(&self, other: &Bounds) -> bool {
+        other.min.0 <= self.max.0
+            && other.max.0 >= self.min.0
+            && other.min.1 <= self.max.1
+            && other.max.1 >= self.min.1
+    }
+
+    fn contains(&self, other: &Bounds) -> bool {
+        other.min.0 >= self.min.0
+            && other.max.0 <= self.max.0
+            && other.min.1 >= self.min.1
+            && other.max.1 <= self.max.1
+    }
+
+    fn quadrants(&self) -> [Bounds; 4] {
+        let mid = (
+            (self.min.0 + self.max.0) / 2.0,
+            (self.min.1 + self.max.1) / 2.0,
+        );
+
+        [
+            Bounds {
+                min: self.min,
+                max: mid,
+            },
+            Bounds {
+                min: (mid.0, self.min.1),
+                max: (self.max.0, mid.1),
+            },
+            Bounds {
+                min: (self.min.0, mid.1),
+                max: (mid.0, self.max.1),
+            },
+            Bounds {
+                min: mid,
+                max: self.max,
+            },
+        ]
+    }
+}
+
+// Items are kept in the smallest node that fully contains them, so one that
+// straddles a split just stays with the parent
+pub struct QuadTree {
+    bounds: Bounds,
+    depth: usize,
+    items: Vec<(usize, Bounds)>,
+    children: Option<Box<[QuadTree; 4]>>,
+}
+
+impl QuadTree {
+    pub fn new(bounds: Bounds) -> Self {
+        QuadTree::with_depth(bounds, 0)
+    }
+
+    fn with_depth(bounds: Bounds, depth: usize) -> Self {
+        QuadTree {
+            bounds,
+            depth,
+            items: Vec::new(),
+            children: None,
+        }
+    }
+
+    pub fn insert(&mut self, item: usize, bounds: Bounds) {
+        if let Some(ref mut children) = self.children {
+            if let Some(child) = children.iter_mut().find(|c| c.bounds.contains(&bounds)) {
+                child.insert(item, bounds);
+                return;
+            }
+        }
+
+        self.items.push((item, bounds));
+
+        if self.children.is_none() && self.items.len() > MAX_ITEMS && self.depth < MAX_DEPTH {
+            self.split();
+        }
+    }
+
+    fn split(&mut self) {
+        let [a, b, c, d] = self.bounds.quadrants();
+        let depth = self.depth + 1;
+
+        self.children = Some(Box::new([
+            QuadTree::with_depth(a, depth),
+            QuadTree::with_depth(b, depth),
+            QuadTree::with_depth(c, depth),
+            QuadTree::with_depth(d, depth),
+        ]));
+
+        for (item, bounds) in std::mem::take(&mut self.items) {
+            self.insert(item, bounds);
+        }
+    }
+
+    // Every item whose bounds contain the point
+    #[cfg(feature = "gui")]
+    pub fn query_point(&self, point: Point, found: &mut Vec<usize>) {
+        if !self.bounds.contains_point(point) {
+            return;
+        }
+
+        found.extend(
+            self.items
+                .iter()
+                .filter(|(_, bounds)| bounds.contains_point(point))
+                .map(|(item, _)| *item),
+        );
+
+        if let Some(ref children) = self.children {
+            for child in children.iter() {
+                child.query_point(point, found);
+            }
+        }
+    }
+
+    // Every item whose bounds overlap the given ones
+    pub fn query(&self, bounds: &Bounds, found: &mut Vec<usize>) {
+        if !self.bounds.overlaps(bounds) {
+            return;
+        }
+
+        found.extend(
+            self.items
+                .iter()
+                .filter(|(_, item_bounds)| item_bounds.overlaps(bounds))
+                .map(|(item, _)| *item),
+        );
+
+        if let Some(ref children) = self.children {
+            for child in children.iter() {
+                child.query(bounds, found);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(min: Point, size: f32) -> Bounds {
+        Bounds {
+            min,
+            max: (min.0 + size, min.1 + size),
+        }
+    }
+
+    // Enough small items in the top right quadrant for the root to split,
+    // around the boundaries between the quadrants
+    fn split_tree() -> QuadTree {
+        let mut tree = QuadTree::new(square((0.0, 0.0), 100.0));
+        for item in 0..=MAX_ITEMS {
+            tree.insert(item, square((70.0 + item as f32, 70.0), 1.0));
+        }
+        assert!(tree.children.is_some());
+
+        // Straddling the split, right on it, and out in the root's corner
+        tree.insert(100, square((45.0, 45.0), 10.0));
+        tree.insert(101, square((50.0, 50.0), 0.0));
+        tree.insert(102, square((90.0, 90.0), 10.0));
+        tree
+    }
+
+    fn found(query: impl FnOnce(&mut Vec<usize>)) -> Vec<usize> {
+        let mut found = Vec::new();
+        query(&mut found);
+        found.sort_unstable();
+        found
+    }
+
+    #[test]
+    fn items_on_a_split_stay_findable_from_every_side() {
+        let tree = split_tree();
+
+        // Too big for any quadrant, so kept by the root, and the one on the
+        // split itself goes to the first quadrant that fits it
+        assert_eq!(
+            tree.items.iter().map(|(item, _)| *item).collect::<Vec<_>>(),
+            [100]
+        );
+        assert!(tree.children.as_ref().unwrap()[0]
+            .items
+            .iter()
+            .any(|(item, _)| *item == 101));
+
+        for corner in [(40.0, 40.0), (50.0, 40.0), (40.0, 50.0), (50.0, 50.0)].iter() {
+            assert_eq!(
+                found(|found| tree.query(&square(*corner, 10.0), found)),
+                [100, 101],
+                "{:?}",
+                corner
+            );
+        }
+    }
+
+    #[test]
+    fn bounds_that_only_touch_overlap() {
+        let tree = split_tree();
+
+        assert_eq!(
+            found(|found| tree.query(&square((100.0, 100.0), 5.0), found)),
+            [102]
+        );
+        assert_eq!(
+            found(|found| tree.query(&square((55.0, 55.0), 3.0), found)),
+            [100]
+        );
+        assert!(found(|found| tree.query(&square((55.1, 50.1), 3.0), found)).is_empty());
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn points_on_an_edge_are_inside() {
+        let tree = split_tree();
+
+        assert_eq!(
+            found(|found| tree.query_point((50.0, 50.0), found)),
+            [100, 101]
+        );
+        assert_eq!(
+            found(|found| tree.query_point((100.0, 100.0), found)),
+            [102]
+        );
+        assert_eq!(found(|found| tree.query_point((71.0, 71.0), found)), [0, 1]);
+        assert!(found(|found| tree.query_point((100.1, 100.0), found)).is_empty());
+    }
+}
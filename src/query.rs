@@ -0,0 +1,451 @@
+use crate::config;
+use crate::dependency_tree::DependencyTree;
+use crate::event_log;
+use crate::export;
+use crate::metadata;
+use crate::monitor;
+use crate::parse_cargo_tree_output::TreeNode;
+use crate::project;
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process;
+use std::sync::Arc;
+
+const USAGE: &str = "\
+usage: treebuild query [--from-lockfile] [--load EXPORT.json] QUERY [-- CARGO_ARGS...]
+
+    paths-to CRATE                  every way down from the root to the crate
+    duplicates                      crates Cargo.lock has more than one version of
+    count [--transitive] CRATE      the crate's own dependencies, or every crate
+                                    below it with --transitive
+    depth [CRATE]                   how deep the tree goes, or the crate first is
+    heaviest [--by METRIC] [-n N]   the N biggest crates, 10 by default, by
+                                    build-time (as of the last build),
+                                    dependencies, source-size or lines";
+const HEAVIEST: usize = 10;
+
+// Seconds by crate
+type Durations = HashMap<String, f32>;
+
+#[derive(Clone, Copy)]
+enum Metric {
+    BuildTime,
+    Dependencies,
+    SourceSize,
+    Lines,
+}
+
+enum Query {
+    PathsTo(String),
+    Duplicates,
+    Count { name: String, transitive: bool },
+    Depth(Option<String>),
+    Heaviest { metric: Metric, amount: usize },
+}
+
+struct Request {
+    query: Query,
+    from_lockfile: bool,
+    load: Option<PathBuf>,
+    // The part of them that picks which crates are in the tree
+    cargo_args: Vec<String>,
+}
+
+fn parse(args: Vec<String>) -> Result<Request, String> {
+    let mut from_lockfile = false;
+    let mut load = None;
+    let mut words = Vec::new();
+    let mut cargo_args = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from-lockfile" => from_lockfile = true,
+            "--load" => load = Some(PathBuf::from(args.next().ok_or("--load requires a file")?)),
+            "--" => cargo_args.extend(args.by_ref()),
+            _ => words.push(arg),
+        }
+    }
+
+    let mut words = words.into_iter();
+    let name = words.next().ok_or("which query?")?;
+    let mut crate_name = None;
+    let mut transitive = false;
+    let mut metric = Metric::BuildTime;
+    let mut amount = HEAVIEST;
+
+    while let Some(word) = words.next() {
+        match word.as_str() {
+            "--transitive" => transitive = true,
+            "--by" => {
+                metric = match words.next().as_deref() {
+                    Some("build-time") => Metric::BuildTime,
+                    Some("dependencies") => Metric::Dependencies,
+                    Some("source-size") => Metric::SourceSize,
+                    Some("lines") => Metric::Lines,
+                    Some(other) => return Err(format!("can't weigh crates by {}", other)),
+                    None => return Err("--by requires a metric".to_owned()),
+                }
+            }
+            "-n" => {
+                let value = words.next().ok_or("-n requires a number")?;
+                amount = value
+                    .parse()
+                    .map_err(|_| format!("-n requires a number, not {}", value))?;
+            }
+            _ if crate_name.is_none() && !word.starts_with('-') => {
+                crate_name = Some(word.replace("_", "-"))
+            }
+            _ => return Err(format!("unexpected {}", word)),
+        }
+    }
+
+    let crate_name = |query: &str| crate_name.clone().ok_or(format!("{} needs a crate", query));
+    let query = match name.as_str() {
+        "paths-to" => Query::PathsTo(crate_name("paths-to")?),
+        "duplicates" => Query::Duplicates,
+        "count" => Query::Count {
+            name: crate_name("count")?,
+            transitive,
+        },
+        "depth" => Query::Depth(crate_name("depth").ok()),
+        "heaviest" => Query::Heaviest { metric, amount },
+        _ => return Err(format!("unknown query {}", name)),
+    };
+
+    Ok(Request {
+        query,
+        from_lockfile,
+        load,
+        cargo_args: monitor::selection_args(&cargo_args),
+    })
+}
+
+// The tree, and how long its crates took to build if an export says
+fn load_tree(request: &Request) -> Result<(Arc<TreeNode>, Option<Durations>), String> {
+    if let Some(ref path) = request.load {
+        let export = export::load(path)?;
+        return Ok((export.tree, Some(export.durations.into_iter().collect())));
+    }
+
+    let config = config::load().unwrap_or_default();
+    let tree = if request.from_lockfile {
+        project::lockfile_tree(None, config.dev_dependencies, &config.color_rules)?
+    } else {
+        project::cargo_tree_with(
+            config.dev_dependencies,
+            &config.color_rules,
+            &request.cargo_args,
+        )
+    };
+    Ok((tree, None))
+}
+
+fn paths_to(
+    node: &Arc<TreeNode>,
+    name: &str,
+    path: &mut Vec<String>,
+    into: &mut BTreeSet<(usize, String)>,
+) {
+    path.push(node.name.clone());
+
+    if node.name == name {
+        into.insert((path.len(), path.join(" -> ")));
+    }
+    for child in node.children.iter() {
+        paths_to(child, name, path, into);
+    }
+
+    path.pop();
+}
+
+fn depth(node: &TreeNode) -> usize {
+    node.children
+        .iter()
+        .map(|child| depth(child) + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+// The amount of every crate in the tree, the most for crates that show up
+// more than once
+fn weights(
+    node: &Arc<TreeNode>,
+    weigh: &dyn Fn(&TreeNode) -> Option<f32>,
+    into: &mut HashMap<String, f32>,
+) {
+    if let Some(weight) = weigh(node) {
+        let known = into.entry(node.name.clone()).or_insert(weight);
+        *known = known.max(weight);
+    }
+    for child in node.children.iter() {
+        weights(child, weigh, into);
+    }
+}
+
+fn heaviest(
+    tree: &Arc<TreeNode>,
+    durations: Option<Durations>,
+    metric: Metric,
+    amount: usize,
+) -> Result<Vec<String>, String> {
+    let mut found = match metric {
+        Metric::BuildTime => {
+            let durations = durations.unwrap_or_else(event_log::last_durations);
+            if durations.is_empty() {
+                return Err(
+                    "no build times, build with treebuild first or --load an export".to_owned(),
+                );
+            }
+
+            let mut found = HashMap::new();
+            weights(tree, &|node| durations.get(&node.name).copied(), &mut found);
+            found
+        }
+        Metric::Dependencies => {
+            let mut found = HashMap::new();
+            for child in tree.children.iter() {
+                weights(
+                    child,
+                    &|node| Some(node.transitive_count() as f32),
+                    &mut found,
+                );
+            }
+            found
+        }
+        Metric::SourceSize | Metric::Lines => {
+            let metadata = metadata::load();
+            if metadata.is_empty() {
+                return Err("cargo metadata has nothing on the crates".to_owned());
+            }
+
+            let mut found = HashMap::new();
+            weights(
+                tree,
+                &|node| {
+                    let info = metadata.get(&node.name)?;
+                    Some(match metric {
+                        Metric::SourceSize => info.source_bytes,
+                        _ => info.source_lines,
+                    } as f32)
+                },
+                &mut found,
+            );
+            found
+        }
+    }
+    .into_iter()
+    .collect::<Vec<_>>();
+
+    found.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(found
+        .into_iter()
+        .take(amount)
+        .map(|(name, weight)| match metric {
+            Metric::BuildTime => format!("{}\t{:.2}", name, weight),
+            _ => format!("{}\t{}", name, weight as u64),
+        })
+        .collect())
+}
+
+fn answer(request: &Request) -> Result<Vec<String>, String> {
+    // Duplicates only need Cargo.lock
+    let tree = || -> Result<_, String> {
+        let (tree, durations) = load_tree(request)?;
+        Ok((DependencyTree::new(tree), durations))
+    };
+    let not_found = |name: &str| format!("{} isn't in the tree", name);
+
+    match request.query {
+        Query::PathsTo(ref name) => {
+            let (tree, _) = tree()?;
+            let mut paths = BTreeSet::new();
+            paths_to(tree.root(), name, &mut Vec::new(), &mut paths);
+            if paths.is_empty() {
+                return Err(not_found(name));
+            }
+            Ok(paths.into_iter().map(|(_, path)| path).collect())
+        }
+        Query::Duplicates => {
+            let mut duplicates = project::locked_versions(None)
+                .into_iter()
+                .filter(|(_, versions)| versions.len() > 1)
+                .map(|(name, versions)| format!("{}\t{}", name, versions.join(" ")))
+                .collect::<Vec<_>>();
+            duplicates.sort();
+            Ok(duplicates)
+        }
+        Query::Count {
+            ref name,
+            transitive,
+        } => {
+            let (tree, _) = tree()?;
+            let node = tree.by_name(name).ok_or_else(|| not_found(name))?;
+            let count = if transitive {
+                node.transitive_count()
+            } else {
+                node.children.len()
+            };
+            Ok(vec![count.to_string()])
+        }
+        Query::Depth(None) => Ok(vec![depth(tree()?.0.root()).to_string()]),
+        Query::Depth(Some(ref name)) => match tree()?.0.shortest_path(name).len() {
+            0 => Err(not_found(name)),
+            length => Ok(vec![(length - 1).to_string()]),
+        },
+        Query::Heaviest { metric, amount } => {
+            let (tree, durations) = tree()?;
+            heaviest(tree.root(), durations, metric, amount)
+        }
+    }
+}
+
+// Prints the answer a line at a time, for scripts and CI. Exits with 2 on
+// a query that doesn't make sense and 1 on one without an answer.
+pub fn run(args: Vec<String>) {
+    let request = match parse(args) {
+        Ok(request) => request,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    match answer(&request) {
+        Ok(lines) => {
+            // Piped into head, the rest isn't wanted
+            let mut stdout = io::stdout().lock();
+            for line in lines {
+                if writeln!(stdout, "{}", line).is_err() {
+                    break;
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, children: Vec<Arc<TreeNode>>) -> Arc<TreeNode> {
+        Arc::new(TreeNode {
+            name: name.to_owned(),
+            children,
+            color: (0, 0, 0),
+        })
+    }
+
+    fn args(line: &str) -> Vec<String> {
+        line.split(' ').map(str::to_owned).collect()
+    }
+
+    // app -> cli -> log, app -> log, app -> serde -> serde_derive -> log
+    fn tree() -> Arc<TreeNode> {
+        let log = || node("log", Vec::new());
+
+        node(
+            "app",
+            vec![
+                node("cli", vec![log()]),
+                log(),
+                node("serde", vec![node("serde-derive", vec![log()])]),
+            ],
+        )
+    }
+
+    #[test]
+    fn queries_are_read_with_their_options() {
+        let request = parse(args("count --transitive serde_derive --from-lockfile")).unwrap();
+        assert!(request.from_lockfile);
+        assert!(matches!(
+            request.query,
+            Query::Count { ref name, transitive: true } if name == "serde-derive"
+        ));
+
+        let request = parse(args("heaviest --by lines -n 3 -- -p app --release")).unwrap();
+        assert!(matches!(
+            request.query,
+            Query::Heaviest {
+                metric: Metric::Lines,
+                amount: 3
+            }
+        ));
+        // Only what picks the crates goes on to cargo tree
+        assert_eq!(request.cargo_args, ["-p", "app"]);
+
+        assert!(matches!(
+            parse(args("depth")).unwrap().query,
+            Query::Depth(None)
+        ));
+        assert!(matches!(
+            parse(args("heaviest")).unwrap().query,
+            Query::Heaviest {
+                metric: Metric::BuildTime,
+                amount: HEAVIEST
+            }
+        ));
+    }
+
+    #[test]
+    fn queries_that_dont_make_sense_are_errors() {
+        for line in [
+            "",
+            "paths-to",
+            "count --transitive",
+            "heaviest --by weight",
+            "heaviest -n many",
+            "heaviest -n",
+            "depth log serde",
+            "lookup log",
+            "--load",
+        ]
+        .iter()
+        {
+            let args = if line.is_empty() {
+                Vec::new()
+            } else {
+                args(line)
+            };
+            assert!(parse(args).is_err(), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn every_path_to_a_crate_is_found_shortest_first() {
+        let mut paths = BTreeSet::new();
+        paths_to(&tree(), "log", &mut Vec::new(), &mut paths);
+
+        assert_eq!(
+            paths.into_iter().map(|(_, path)| path).collect::<Vec<_>>(),
+            [
+                "app -> log",
+                "app -> cli -> log",
+                "app -> serde -> serde-derive -> log"
+            ]
+        );
+        assert_eq!(depth(&tree()), 3);
+    }
+
+    #[test]
+    fn the_heaviest_crates_come_first_and_ties_by_name() {
+        assert_eq!(
+            heaviest(&tree(), None, Metric::Dependencies, 10).unwrap(),
+            ["serde\t2", "cli\t1", "serde-derive\t1", "log\t0"]
+        );
+
+        let durations = [("log", 1.5), ("serde-derive", 4.0), ("app", 0.25)]
+            .iter()
+            .map(|&(name, secs)| (name.to_owned(), secs))
+            .collect();
+        assert_eq!(
+            heaviest(&tree(), Some(durations), Metric::BuildTime, 2).unwrap(),
+            ["serde-derive\t4.00", "log\t1.50"]
+        );
+    }
+}
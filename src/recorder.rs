@@ -0,0 +1,165 @@
+use crate::offscreen::Offscreen;
+use gif::SetParameter;
+use nannou::image::RgbaImage;
+use nannou::prelude::*;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+const FRAMES_PER_SECOND: u32 = 10;
+// Keep filming for a moment after the build ends so the result is visible
+const TAIL_SECONDS: f32 = 2.0;
+
+pub struct Recorder {
+    offscreen: Offscreen,
+    // Pending captures only hold on to this slot rather than a sender of their
+    // own, so stopping drops the last sender and lets the encoder wind down
+    // even if some capture never completes
+    sink: Arc<Mutex<Option<Sender<RgbaImage>>>>,
+    encoder: Option<JoinHandle<()>>,
+    last_frame: Option<f32>,
+    stop_at: Option<f32>,
+    path: PathBuf,
+}
+
+fn encode_gif(path: &Path, [width, height]: [u32; 2], frames: Receiver<RgbaImage>) {
+    let file = BufWriter::new(File::create(path).expect("Failed to create recording"));
+    let mut encoder = gif::Encoder::new(file, width as u16, height as u16, &[])
+        .expect("Failed to start GIF recording");
+    encoder
+        .set(gif::Repeat::Infinite)
+        .expect("Failed to start GIF recording");
+
+    for mut image in frames {
+        let mut frame = gif::Frame::from_rgba_speed(width as u16, height as u16, &mut image, 10);
+        frame.delay = (100 / FRAMES_PER_SECOND) as u16;
+
+        encoder
+            .write_frame(&frame)
+            .expect("Failed to write GIF frame");
+    }
+}
+
+// Anything other than GIF is left to ffmpeg, which picks the container and
+// codec from the extension (e.g. build.webm)
+fn encode_ffmpeg(path: &Path, [width, height]: [u32; 2], frames: Receiver<RgbaImage>) {
+    let mut ffmpeg = Command::new("ffmpeg")
+        .arg("-y")
+        .args(["-loglevel", "error"])
+        .args(["-f", "rawvideo", "-pixel_format", "rgba"])
+        .arg("-video_size")
+        .arg(format!("{}x{}", width, height))
+        .arg("-framerate")
+        .arg(FRAMES_PER_SECOND.to_string())
+        .args(["-i", "-"])
+        .arg(path)
+        .stdin(Stdio::piped())
+        .spawn()
+        .expect("Failed to run ffmpeg, which is needed for non-GIF recordings");
+
+    if let Some(ref mut stdin) = ffmpeg.stdin {
+        for image in frames {
+            stdin
+                .write_all(&image.into_raw())
+                .expect("Failed to pipe frame to ffmpeg");
+        }
+    }
+
+    // Closing stdin tells ffmpeg there are no more frames
+    drop(ffmpeg.stdin.take());
+    ffmpeg.wait().expect("Failed to wait on ffmpeg");
+}
+
+impl Recorder {
+    // At the window's scale factor unless given another
+    pub fn start(app: &App, path: &Path, scale_factor: Option<f32>) -> Self {
+        let window = app.main_window();
+        // Sized in points like the window for the same framing, with as many
+        // pixels to a point as it has
+        let (width, height) = window.inner_size_points();
+        let offscreen = Offscreen::scaled(
+            &window,
+            [width as u32, height as u32],
+            scale_factor.unwrap_or_else(|| window.scale_factor()),
+        );
+        let size = offscreen.size();
+
+        let (sender, receiver) = channel();
+        let encoder_path = path.to_path_buf();
+        let is_gif = path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("gif"))
+            .unwrap_or(false);
+
+        let encoder = thread::spawn(move || {
+            if is_gif {
+                encode_gif(&encoder_path, size, receiver)
+            } else {
+                encode_ffmpeg(&encoder_path, size, receiver)
+            }
+        });
+
+        Recorder {
+            offscreen,
+            sink: Arc::new(Mutex::new(Some(sender))),
+            encoder: Some(encoder),
+            last_frame: None,
+            stop_at: None,
+            path: path.to_path_buf(),
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.sink.lock().unwrap().is_some()
+    }
+
+    pub fn stop_soon(&mut self, time: f32) {
+        self.stop_at.get_or_insert(time + TAIL_SECONDS);
+    }
+
+    pub fn frame_due(&mut self, time: f32) -> bool {
+        if self.stop_at.map(|stop_at| time >= stop_at).unwrap_or(false) {
+            self.stop();
+        }
+
+        self.is_recording()
+            && self
+                .last_frame
+                .map(|last| time - last >= 1.0 / FRAMES_PER_SECOND as f32)
+                .unwrap_or(true)
+    }
+
+    pub fn capture(&mut self, app: &App, draw: &Draw) {
+        if !self.is_recording() {
+            return;
+        }
+
+        self.last_frame = Some(app.time);
+
+        let sink = Arc::clone(&self.sink);
+        self.offscreen
+            .capture(&app.main_window(), draw, move |image| {
+                if let Some(ref sender) = *sink.lock().unwrap() {
+                    sender.send(image).ok();
+                }
+            });
+    }
+
+    pub fn stop(&mut self) {
+        self.sink.lock().unwrap().take();
+    }
+
+    // Waits for the encoder to flush whatever frames it still has queued
+    pub fn finish(mut self) {
+        self.stop();
+
+        if let Some(encoder) = self.encoder.take() {
+            encoder.join().expect("Recording encoder panicked");
+            println!("Saved recording to {}", self.path.display());
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use crate::event_log;
+use crate::events::{BuildEvent, BuildPhase};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// A line of what --emit-events writes, with just what a replay needs
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum Emitted {
+    Phase { at: f32, phase: BuildPhase },
+    Started { at: f32, name: String },
+    Finished { at: f32, name: String },
+    Failed { at: f32, name: String },
+    Fresh { at: f32, name: String },
+    Warning { at: f32 },
+    CrateWarnings { at: f32, name: String, count: usize },
+    Log { at: f32, line: String },
+    Progress,
+}
+
+fn emitted(raw: &str) -> Option<(f32, BuildEvent)> {
+    Some(match serde_json::from_str::<Emitted>(raw).ok()? {
+        Emitted::Phase { at, phase } => (at, BuildEvent::Phase(phase)),
+        Emitted::Started { at, name } => (at, BuildEvent::Started(name)),
+        Emitted::Finished { at, name } => (at, BuildEvent::Completed(name)),
+        Emitted::Failed { at, name } => (at, BuildEvent::Failed(name)),
+        Emitted::Fresh { at, name } => (at, BuildEvent::Fresh(name)),
+        Emitted::Warning { at } => (at, BuildEvent::Warning),
+        Emitted::CrateWarnings { at, name, count } => (at, BuildEvent::CrateWarnings(name, count)),
+        Emitted::Log { at, line } => (at, BuildEvent::Log(line)),
+        // Worked out again from the rest
+        Emitted::Progress => return None,
+    })
+}
+
+// The events of a recorded build with the seconds into it of each, from
+// treebuild's own event log or from what --emit-events wrote
+pub fn read(path: &Path) -> Result<Vec<(f32, BuildEvent)>, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+
+    let events = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| event_log::event(&line).or_else(|| emitted(&line)))
+        .collect::<Vec<_>>();
+    if events.is_empty() {
+        return Err("no build events in it".to_owned());
+    }
+    Ok(events)
+}
+
+// Sends the events when they happened, `speed` times as fast, and makes sure
+// the build ends even if the recording stopped before it did
+pub fn play(events: Vec<(f32, BuildEvent)>, speed: f32, sender: Sender<BuildEvent>) {
+    let start = Instant::now();
+    let mut ended = false;
+
+    for (at, event) in events {
+        let due = Duration::from_secs_f32((at / speed).max(0.0));
+        if let Some(wait) = due.checked_sub(start.elapsed()) {
+            thread::sleep(wait);
+        }
+
+        ended = matches!(
+            event,
+            BuildEvent::Phase(BuildPhase::Finished) | BuildEvent::Phase(BuildPhase::Failed)
+        );
+        if sender.send(event).is_err() {
+            return;
+        }
+    }
+
+    if !ended {
+        let _ = sender.send(BuildEvent::Phase(BuildPhase::Finished));
+    }
+}
@@ -0,0 +1,92 @@
+use crate::category::CategoryStats;
+use crate::config;
+use crate::events::BuildPhase;
+use crate::timings::Timings;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// A single self-contained page, so it can be attached to a ticket or CI run
+pub fn write(
+    path: &Path,
+    phase: BuildPhase,
+    timings: &Timings,
+    stats: &[CategoryStats],
+    aliases: &HashMap<String, String>,
+) {
+    let mut html = String::new();
+    let slowest_category = stats
+        .iter()
+        .map(|stat| stat.compile.as_secs_f32())
+        .fold(0.0, f32::max);
+
+    html.push_str(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>treebuild report</title>\n<style>\n\
+         body { font-family: sans-serif; background: #141418; color: #eee; margin: 2em; }\n\
+         table { border-collapse: collapse; }\n\
+         td, th { padding: 4px 12px; text-align: left; }\n\
+         .bar { height: 10px; }\n\
+         </style>\n</head>\n<body>\n",
+    );
+
+    writeln!(html, "<h1>Build {:?}</h1>", phase).unwrap();
+    writeln!(
+        html,
+        "<p>{:.1}s, {} crates built, {} warnings</p>",
+        timings.elapsed().as_secs_f32(),
+        timings.durations.len(),
+        timings.warnings
+    )
+    .unwrap();
+
+    html.push_str("<h2>Time by category</h2>\n<table>\n");
+    html.push_str("<tr><th>category</th><th>crates</th><th>compile time</th><th></th></tr>\n");
+    for stat in stats {
+        let secs = stat.compile.as_secs_f32();
+        let (r, g, b) = stat.category.color();
+        let width = if slowest_category > 0.0 {
+            300.0 * secs / slowest_category
+        } else {
+            0.0
+        };
+
+        writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{:.1}s</td>\
+             <td><div class=\"bar\" style=\"width: {:.0}px; background: #{:02x}{:02x}{:02x}\"></div></td></tr>",
+            escape(stat.category.label()),
+            stat.crates,
+            secs,
+            width,
+            r,
+            g,
+            b
+        )
+        .unwrap();
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>Slowest crates</h2>\n<table>\n");
+    for (name, duration) in timings.slowest(20) {
+        writeln!(
+            html,
+            "<tr><td>{}</td><td>{:.1}s</td></tr>",
+            escape(config::display_name(aliases, &name)),
+            duration.as_secs_f32()
+        )
+        .unwrap();
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+
+    fs::write(path, html).expect("Failed to write report");
+    println!("Saved report to {}", path.display());
+}
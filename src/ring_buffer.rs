@@ -0,0 +1,51 @@
+use std::collections::{vec_deque, VecDeque};
+
+// Keeps only the most recent `capacity` items, so anything fed for the whole
+// lifetime of the app stays bounded no matter how long it runs
+#[derive(Debug, Clone)]
+pub struct RingBuffer<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        RingBuffer {
+            items: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.items.len() == self.capacity {
+            self.items.pop_front();
+        }
+
+        self.items.push_back(item);
+    }
+
+    pub fn iter(&self) -> vec_deque::Iter<'_, T> {
+        self.items.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_latest_without_growing() {
+        let mut buffer = RingBuffer::new(8);
+        let allocated = buffer.items.capacity();
+
+        for item in 0..1000 {
+            buffer.push(item);
+            assert!(buffer.iter().len() <= 8);
+            assert_eq!(buffer.items.capacity(), allocated);
+        }
+        assert_eq!(
+            buffer.iter().copied().collect::<Vec<_>>(),
+            (992..1000).collect::<Vec<_>>()
+        );
+    }
+}
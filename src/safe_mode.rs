@@ -0,0 +1,22 @@
+use std::fs;
+use std::path::PathBuf;
+
+// Exists for as long as a session runs, so finding it on startup means the
+// last one never got to exit
+fn sentinel() -> PathBuf {
+    crate::project::state_dir().join("running")
+}
+
+// Whether the previous session crashed
+pub fn begin() -> bool {
+    let crashed = sentinel().exists();
+
+    fs::create_dir_all(crate::project::state_dir()).expect("Failed to create state directory");
+    fs::write(sentinel(), "").expect("Failed to write session sentinel");
+
+    crashed
+}
+
+pub fn end() {
+    let _ = fs::remove_file(sentinel());
+}
@@ -0,0 +1,227 @@
+use crate::drawing::{DrawArc, DrawCrate, DrawLine, Point};
+use crate::quadtree::{Bounds, QuadTree};
+
+// A laid out frame, in world coordinates, with an index for hit-testing
+pub struct Scene {
+    crates: Vec<DrawCrate>,
+    lines: Vec<DrawLine>,
+    arcs: Vec<DrawArc>,
+    index: Option<QuadTree>,
+}
+
+impl Scene {
+    pub fn new(crates: Vec<DrawCrate>, lines: Vec<DrawLine>, arcs: Vec<DrawArc>) -> Self {
+        let bounds = crates
+            .iter()
+            .map(DrawCrate::bounds)
+            .fold(None, |acc: Option<Bounds>, bounds| {
+                Some(acc.map_or(bounds, |acc| acc.union(bounds)))
+            });
+
+        let index = bounds.map(|bounds| {
+            let mut index = QuadTree::new(bounds);
+
+            for (idx, draw_crate) in crates.iter().enumerate() {
+                index.insert(idx, draw_crate.bounds());
+            }
+
+            index
+        });
+
+        Scene {
+            crates,
+            lines,
+            arcs,
+            index,
+        }
+    }
+
+    pub fn crates(&self) -> &[DrawCrate] {
+        &self.crates
+    }
+
+    // A copy for the camera to move into screen coordinates
+    pub fn to_parts(&self) -> (Vec<DrawCrate>, Vec<DrawLine>, Vec<DrawArc>) {
+        (self.crates.clone(), self.lines.clone(), self.arcs.clone())
+    }
+
+    // Likewise, but only what reaches into `view`, in world coordinates, in
+    // the order it is drawn
+    pub fn visible_parts(&self, view: &Bounds) -> (Vec<DrawCrate>, Vec<DrawLine>, Vec<DrawArc>) {
+        let mut shown = Vec::new();
+        if let Some(ref index) = self.index {
+            index.query(view, &mut shown);
+        }
+        shown.sort_unstable();
+        shown.dedup();
+
+        let crates = shown
+            .into_iter()
+            .map(|idx| &self.crates[idx])
+            .filter(|draw_crate| draw_crate.bounds().overlaps(view))
+            .cloned()
+            .collect();
+        let lines = self
+            .lines
+            .iter()
+            .filter(|line| line.bounds().overlaps(view))
+            .cloned()
+            .collect();
+        let arcs = self
+            .arcs
+            .iter()
+            .filter(|arc| arc.bounds().overlaps(view))
+            .cloned()
+            .collect();
+
+        (crates, lines, arcs)
+    }
+
+    // The topmost (last drawn) crate covering a point in world coordinates
+    pub fn pick(&self, point: Point) -> Option<&DrawCrate> {
+        let mut candidates = Vec::new();
+
+        if let Some(ref index) = self.index {
+            index.query_point(point, &mut candidates);
+        }
+
+        candidates
+            .into_iter()
+            .filter(|&idx| self.crates[idx].contains(point))
+            .max()
+            .map(|idx| &self.crates[idx])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drawing::{CrateState, Shape};
+    use crate::parse_cargo_tree_output::TreeNode;
+    use std::sync::Arc;
+
+    fn draw_crate(name: &str, center: Point, radius: f32, shape: Shape) -> DrawCrate {
+        DrawCrate {
+            center,
+            radius,
+            shape,
+            color: (0, 0, 0),
+            name: name.to_owned(),
+            tree: Arc::new(TreeNode {
+                name: name.to_owned(),
+                children: Vec::new(),
+                color: (0, 0, 0),
+            }),
+            state: CrateState::Pending,
+            info: None,
+            warning_delta: 0,
+            units: Vec::new(),
+            members: Vec::new(),
+            pulse: None,
+            pinned: false,
+        }
+    }
+
+    fn line(p1: Point, p2: Point, controls: Vec<Point>) -> DrawLine {
+        DrawLine {
+            p1,
+            p2,
+            controls,
+            arrow: false,
+            color: (0, 0, 0),
+            flowing: false,
+            pinned: false,
+        }
+    }
+
+    fn picked(scene: &Scene, point: Point) -> Option<&str> {
+        scene.pick(point).map(|draw_crate| draw_crate.name.as_str())
+    }
+
+    #[test]
+    fn the_last_drawn_of_overlapping_crates_is_picked() {
+        let scene = Scene::new(
+            vec![
+                draw_crate("below", (0.0, 0.0), 10.0, Shape::Circle),
+                draw_crate("above", (5.0, 0.0), 10.0, Shape::Circle),
+                draw_crate("apart", (100.0, 0.0), 10.0, Shape::Circle),
+            ],
+            Vec::new(),
+            Vec::new(),
+        );
+
+        assert_eq!(picked(&scene, (2.0, 0.0)), Some("above"));
+        assert_eq!(picked(&scene, (-8.0, 0.0)), Some("below"));
+        assert_eq!(picked(&scene, (100.0, 0.0)), Some("apart"));
+        assert_eq!(picked(&scene, (50.0, 0.0)), None);
+    }
+
+    #[test]
+    fn circles_end_short_of_their_edge_and_rects_on_it() {
+        let square = Shape::Rect {
+            width: 20.0,
+            height: 20.0,
+        };
+        let scene = Scene::new(
+            vec![
+                draw_crate("circle", (0.0, 0.0), 10.0, Shape::Circle),
+                draw_crate("left", (30.0, 0.0), 10.0, square),
+                draw_crate("right", (50.0, 0.0), 10.0, square),
+            ],
+            Vec::new(),
+            Vec::new(),
+        );
+
+        // In the corner of its bounds, but outside the circle itself
+        assert_eq!(picked(&scene, (9.9, 0.0)), Some("circle"));
+        assert_eq!(picked(&scene, (-10.0, 0.0)), None);
+        assert_eq!(picked(&scene, (8.0, 8.0)), None);
+
+        // Rects take their corners, and the edge two share goes to the one
+        // drawn last
+        assert_eq!(picked(&scene, (20.0, 10.0)), Some("left"));
+        assert_eq!(picked(&scene, (40.0, 0.0)), Some("right"));
+        assert_eq!(picked(&scene, (60.0, -10.0)), Some("right"));
+        assert_eq!(picked(&scene, (60.1, 0.0)), None);
+    }
+
+    #[test]
+    fn lines_partly_in_view_are_kept() {
+        let scene = Scene::new(
+            vec![
+                draw_crate("a", (0.0, 0.0), 5.0, Shape::Circle),
+                draw_crate("b", (100.0, 100.0), 5.0, Shape::Circle),
+            ],
+            vec![
+                line((0.0, 0.0), (100.0, 100.0), Vec::new()),
+                // Bent out beyond the view, with both ends in it
+                line((0.0, 0.0), (10.0, 0.0), vec![(5.0, -200.0)]),
+                line((200.0, 200.0), (300.0, 200.0), Vec::new()),
+            ],
+            Vec::new(),
+        );
+        let view = Bounds {
+            min: (-20.0, -20.0),
+            max: (50.0, 20.0),
+        };
+
+        let (crates, lines, _) = scene.visible_parts(&view);
+        assert_eq!(
+            crates.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(),
+            ["a"]
+        );
+        assert_eq!(
+            lines.iter().map(|line| line.p2).collect::<Vec<_>>(),
+            [(100.0, 100.0), (10.0, 0.0)]
+        );
+
+        // Only the corner of its bounds is in view, which still keeps it
+        let corner = Bounds {
+            min: (80.0, 0.0),
+            max: (100.0, 10.0),
+        };
+        let (crates, lines, _) = scene.visible_parts(&corner);
+        assert!(crates.is_empty());
+        assert_eq!(lines.len(), 1);
+    }
+}
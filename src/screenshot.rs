@@ -0,0 +1,70 @@
+use crate::offscreen::Offscreen;
+use nannou::prelude::*;
+use std::fs;
+use std::path::PathBuf;
+
+const BANNER_HEIGHT: f32 = 26.0;
+const MARGIN: f32 = 10.0;
+
+// Never one that is already there, however many are taken at once
+fn path() -> PathBuf {
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+
+    let dir = crate::project::state_dir();
+    let mut path = dir.join(format!("screenshot-{}.png", stamp));
+    for n in 1.. {
+        if !path.exists() {
+            break;
+        }
+        path = dir.join(format!("screenshot-{}-{}.png", stamp, n));
+    }
+    path
+}
+
+// Along the bottom, over whatever is there
+fn draw_banner(draw: &Draw, window: Rect, text: &str) {
+    let y = window.bottom() + BANNER_HEIGHT / 2.0;
+
+    draw.rect()
+        .x_y(0.0, y)
+        .w_h(window.w(), BANNER_HEIGHT)
+        .color(srgba(20u8, 20, 24, 225));
+    draw.text(text)
+        .left_justify()
+        .color(WHITE)
+        .x_y(0.0, y)
+        .w_h(window.w() - 2.0 * MARGIN, BANNER_HEIGHT);
+}
+
+// The window as `draw` has it, framed the same way and at `scale_factor`
+// pixels to a point (the window's own unless given), with `banner` along
+// the bottom if there is one. Saved once the GPU is done with it, to the
+// path returned.
+pub fn save(app: &App, draw: &Draw, banner: Option<&str>, scale_factor: Option<f32>) -> PathBuf {
+    let window = app.main_window();
+    let (width, height) = window.inner_size_points();
+    let mut offscreen = Offscreen::scaled(
+        &window,
+        [width as u32, height as u32],
+        scale_factor.unwrap_or_else(|| window.scale_factor()),
+    );
+
+    if let Some(text) = banner {
+        draw_banner(draw, app.window_rect(), text);
+    }
+
+    let path = path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).expect("Failed to create screenshot directory");
+    }
+
+    let saved = path.clone();
+    offscreen.capture(&window, draw, move |image| {
+        image.save(&saved).expect("Failed to save screenshot");
+        println!("Saved a screenshot to {}", saved.display());
+    });
+    path
+}
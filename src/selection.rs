@@ -0,0 +1,41 @@
+use crate::drawing::{node_id, subtree_ids};
+use crate::parse_cargo_tree_output::TreeNode;
+use std::collections::{BTreeSet, HashSet};
+use std::sync::Arc;
+
+// The selected node's own subtree, plus every path from the root down to any
+// occurrence of the same crate, i.e. everything that ends up depending on it
+pub fn highlighted(root: &Arc<TreeNode>, selected: &Arc<TreeNode>) -> HashSet<usize> {
+    let mut ids = HashSet::new();
+    subtree_ids(selected, &mut ids);
+    dependent_paths(root, &selected.name, &mut Vec::new(), &mut ids);
+    ids
+}
+
+// Every path from the root down to the pinned crates, wherever they occur
+pub fn pinned_paths(root: &Arc<TreeNode>, pinned: &BTreeSet<String>) -> HashSet<usize> {
+    let mut ids = HashSet::new();
+    for name in pinned {
+        dependent_paths(root, name, &mut Vec::new(), &mut ids);
+    }
+    ids
+}
+
+fn dependent_paths(
+    tree: &Arc<TreeNode>,
+    name: &str,
+    path: &mut Vec<usize>,
+    ids: &mut HashSet<usize>,
+) {
+    path.push(node_id(tree));
+
+    if tree.name == name {
+        ids.extend(path.iter().copied());
+    }
+
+    for child in tree.children.iter() {
+        dependent_paths(child, name, path, ids);
+    }
+
+    path.pop();
+}
@@ -0,0 +1,331 @@
+use crate::active;
+use crate::config;
+use crate::events::{BuildEvent, BuildPhase};
+use crate::export::BuildExport;
+use crate::metrics::BuildMetrics;
+use crate::monitor;
+use crate::parse_cargo_tree_output::TreeNode;
+use crate::ring_buffer::RingBuffer;
+use crate::timeline::{Timeline, TimelineEvent};
+use crate::timings::Timings;
+use crate::utilization::Utilization;
+use crate::websocket;
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+
+const PAGE: &str = include_str!("serve.html");
+const TICK: Duration = Duration::from_millis(250);
+
+// The latest state in the export format, numbered so each client can tell
+// whether it has already sent it, and as metrics
+#[derive(Default)]
+struct Snapshot {
+    number: u64,
+    json: String,
+    metrics: String,
+}
+
+type Shared = Arc<(Mutex<Snapshot>, Condvar)>;
+
+// Everything the export is made of, kept the way the window's model keeps it
+struct Build {
+    tree: Arc<TreeNode>,
+    // Distinct crates in the tree, the only names counted done or failed
+    crates: HashSet<String>,
+    aliases: HashMap<String, String>,
+    phase: BuildPhase,
+    completed: HashSet<String>,
+    active: HashSet<String>,
+    failed: HashSet<String>,
+    timings: Timings,
+    log: RingBuffer<String>,
+    timeline: Timeline,
+    utilization: Utilization,
+}
+
+impl Build {
+    fn new(tree: Arc<TreeNode>, aliases: HashMap<String, String>) -> Self {
+        let mut crates = HashSet::new();
+        count_crates(&tree, &mut crates);
+
+        Build {
+            crates,
+            tree,
+            aliases,
+            phase: BuildPhase::Waiting,
+            completed: HashSet::new(),
+            active: HashSet::new(),
+            failed: HashSet::new(),
+            timings: Timings::new(),
+            log: RingBuffer::new(crate::monitor::LOG_LINES),
+            timeline: Timeline::default(),
+            utilization: Utilization::default(),
+        }
+    }
+
+    fn apply(&mut self, event: BuildEvent) {
+        let at = self.timings.elapsed().as_secs_f32();
+
+        match event {
+            BuildEvent::Phase(phase) => {
+                self.phase = phase;
+                if phase == BuildPhase::Finished || phase == BuildPhase::Failed {
+                    self.timings.finish();
+                }
+            }
+            BuildEvent::Started(name) => {
+                self.timeline.push(at, TimelineEvent::Started(name.clone()));
+                self.timings.start(name, Instant::now());
+            }
+            BuildEvent::Completed(name) => {
+                self.timeline
+                    .push(at, TimelineEvent::Completed(name.clone()));
+                self.timings.complete(&name, Instant::now());
+                if self.crates.contains(&name) {
+                    self.completed.insert(name);
+                }
+            }
+            BuildEvent::Failed(name) => {
+                self.timeline.push(at, TimelineEvent::Failed(name.clone()));
+                if self.crates.contains(&name) {
+                    self.failed.insert(name);
+                }
+            }
+            BuildEvent::Warning => self.timings.warnings += 1,
+            BuildEvent::Fresh(..) | BuildEvent::CrateWarnings(..) => {}
+            BuildEvent::Log(line) => self.log.push(line),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::to_string(&BuildExport::new(
+            Arc::clone(&self.tree),
+            self.phase,
+            &self.completed,
+            &self.active,
+            &self.failed,
+            &self.timings,
+            &self.log,
+            &self.timeline,
+            &self.utilization,
+            &self.aliases,
+        ))
+        .expect("Failed to serialize the build")
+    }
+
+    fn to_metrics(&self) -> String {
+        BuildMetrics {
+            phase: self.phase,
+            crates: self.crates.len(),
+            completed: &self.completed,
+            active: &self.active,
+            failed: &self.failed,
+            timings: &self.timings,
+        }
+        .render()
+    }
+}
+
+fn count_crates(node: &TreeNode, names: &mut HashSet<String>) {
+    if names.insert(node.name.clone()) {
+        for child in node.children.iter() {
+            count_crates(child, names);
+        }
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) {
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+}
+
+// Sends every snapshot from the one current when the client connected on,
+// until the client goes away
+fn stream_events(mut stream: TcpStream, key: &str, shared: &Shared) {
+    let handshake = write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        websocket::accept_key(key)
+    );
+    if handshake.is_err() {
+        return;
+    }
+
+    let (snapshot, changed) = &**shared;
+    let mut sent = 0;
+
+    loop {
+        let json = {
+            let mut snapshot = snapshot.lock().unwrap();
+            while snapshot.number == sent {
+                snapshot = changed.wait(snapshot).unwrap();
+            }
+            sent = snapshot.number;
+            snapshot.json.clone()
+        };
+
+        if websocket::write_text(&mut stream, &json).is_err() {
+            return;
+        }
+    }
+}
+
+fn handle(stream: TcpStream, shared: Shared) {
+    let mut reader = BufReader::new(stream.try_clone().expect("Failed to clone the connection"));
+    let mut request = String::new();
+    if reader.read_line(&mut request).is_err() {
+        return;
+    }
+
+    let mut key = None;
+    let mut line = String::new();
+    while reader.read_line(&mut line).is_ok_and(|read| read > 0) && line.trim() != "" {
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("sec-websocket-key") {
+                key = Some(value.trim().to_owned());
+            }
+        }
+        line.clear();
+    }
+
+    let mut stream = stream;
+    match (request.split_whitespace().nth(1), key) {
+        (Some("/events"), Some(key)) => stream_events(stream, &key, &shared),
+        (Some("/"), _) => respond(&mut stream, "200 OK", "text/html; charset=utf-8", PAGE),
+        (Some("/state.json"), _) => {
+            let json = shared.0.lock().unwrap().json.clone();
+            respond(&mut stream, "200 OK", "application/json", &json)
+        }
+        // For Prometheus to scrape, e.g. to chart builds next to the machine
+        (Some("/metrics"), _) => {
+            let metrics = shared.0.lock().unwrap().metrics.clone();
+            respond(
+                &mut stream,
+                "200 OK",
+                "text/plain; version=0.0.4; charset=utf-8",
+                &metrics,
+            )
+        }
+        _ => respond(&mut stream, "404 Not Found", "text/plain", "not found"),
+    }
+}
+
+fn publish(shared: &Shared, json: String, metrics: String) {
+    let (snapshot, changed) = &**shared;
+    let mut snapshot = snapshot.lock().unwrap();
+
+    snapshot.number += 1;
+    snapshot.json = json;
+    snapshot.metrics = metrics;
+    changed.notify_all();
+}
+
+// Runs the build without a window and serves it to browsers instead, for
+// builds on machines nobody is sitting at. Keeps serving the final state once
+// the build is over, until stopped.
+pub fn run(addr: &str, cargo_args: Vec<String>) {
+    let config = config::load().unwrap_or_default();
+    let tree = crate::project::cargo_tree_with(
+        config.dev_dependencies,
+        &config.color_rules,
+        &monitor::selection_args(&cargo_args),
+    );
+
+    let listener = TcpListener::bind(addr).expect("Failed to listen for the dashboard");
+    let shared = Shared::default();
+    {
+        let shared = Arc::clone(&shared);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || handle(stream, shared));
+            }
+        });
+    }
+    println!(
+        "Serving the build on http://{}, metrics on http://{}/metrics",
+        addr, addr
+    );
+
+    let (sender, events) = channel();
+    thread::spawn(move || monitor::run(vec!["build"], cargo_args, sender));
+
+    let mut build = Build::new(tree, config.aliases);
+    let mut finished = false;
+
+    loop {
+        while let Ok(event) = events.try_recv() {
+            build.apply(event);
+        }
+
+        // Nothing changes after the build is over, the last state is sent once
+        if !finished {
+            if build.timings.build_end.is_none() {
+                build.active = active::get_active()
+                    .into_iter()
+                    .map(|unit| unit.name)
+                    .collect();
+                build
+                    .utilization
+                    .record(build.timings.elapsed().as_secs_f32(), &build.active);
+            } else {
+                build.active.clear();
+                finished = true;
+                println!("Build over, still serving it until stopped");
+            }
+
+            publish(&shared, build.to_json(), build.to_metrics());
+        }
+
+        thread::sleep(TICK);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, children: Vec<Arc<TreeNode>>) -> Arc<TreeNode> {
+        Arc::new(TreeNode {
+            name: name.to_owned(),
+            children,
+            color: (0, 0, 0),
+        })
+    }
+
+    fn metric<'a>(metrics: &'a str, name: &str) -> &'a str {
+        metrics
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+            .unwrap()
+    }
+
+    #[test]
+    fn only_crates_of_the_tree_are_counted_done() {
+        let tree = node("app", vec![node("log", Vec::new())]);
+        let mut build = Build::new(tree, HashMap::new());
+        for name in ["log", "log(build)", "app(build)", "app"] {
+            build.apply(BuildEvent::Started(name.to_owned()));
+            build.apply(BuildEvent::Completed(name.to_owned()));
+        }
+        build.apply(BuildEvent::Failed("somewhere-else".to_owned()));
+
+        let metrics = build.to_metrics();
+        assert_eq!(metric(&metrics, "treebuild_crates"), "2");
+        assert_eq!(metric(&metrics, "treebuild_crates_completed"), "2");
+        assert_eq!(metric(&metrics, "treebuild_crates_failed"), "0");
+    }
+}
@@ -0,0 +1,48 @@
+use crate::drawing::Point;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+// How the window was left the last time the project was open, to not have
+// to find the way back through a big tree every time
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    // The camera's offset and scale
+    pub camera: Option<(Point, f32)>,
+    pub layout: Option<String>,
+    pub aggregate_depth: Option<usize>,
+    // The crates expanded while exploring, in the order they were, or None
+    // when not exploring
+    pub explored: Option<Vec<String>>,
+    pub filter: Option<String>,
+    pub filter_hides: bool,
+    // By label
+    pub categories: BTreeSet<String>,
+    pub hidden_members: BTreeSet<String>,
+}
+
+fn path() -> PathBuf {
+    crate::project::state_dir().join("session.json")
+}
+
+// Starting afresh when there is none or it doesn't read
+pub fn load() -> Option<Session> {
+    let raw = fs::read_to_string(path()).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+pub fn save(session: &Session) {
+    let path = path();
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).expect("Failed to create treebuild state directory");
+    }
+
+    fs::write(
+        path,
+        serde_json::to_string_pretty(session).expect("Failed to serialize session"),
+    )
+    .expect("Failed to write session");
+}
@@ -0,0 +1,176 @@
+use crate::drawing::Point;
+use crate::offscreen::Offscreen;
+use crate::timings::Timings;
+use nannou::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const CARD_SIZE: [u32; 2] = [600, 315];
+const BUTTON_SIZE: (f32, f32) = (160.0, 36.0);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub total_secs: f32,
+    pub crates_built: usize,
+    pub warnings: usize,
+    pub slowest: Vec<(String, f32)>,
+}
+
+impl RunSummary {
+    pub fn from_timings(timings: &Timings) -> Self {
+        RunSummary {
+            total_secs: timings.elapsed().as_secs_f32(),
+            crates_built: timings.durations.len(),
+            warnings: timings.warnings,
+            slowest: timings
+                .slowest(5)
+                .into_iter()
+                .map(|(name, duration)| (name, duration.as_secs_f32()))
+                .collect(),
+        }
+    }
+}
+
+fn last_run_path() -> PathBuf {
+    crate::project::state_dir().join("last_run.json")
+}
+
+pub fn load_previous() -> Option<RunSummary> {
+    let raw = fs::read_to_string(last_run_path()).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+pub fn save(summary: &RunSummary) {
+    let path = last_run_path();
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).expect("Failed to create treebuild state directory");
+    }
+
+    fs::write(
+        path,
+        serde_json::to_string_pretty(summary).expect("Failed to serialize summary"),
+    )
+    .expect("Failed to write summary");
+}
+
+fn comparison(summary: &RunSummary, previous: Option<&RunSummary>) -> String {
+    match previous {
+        Some(previous) => {
+            let diff = summary.total_secs - previous.total_secs;
+            format!(
+                "previous run: {:.1}s ({}{:.1}s)",
+                previous.total_secs,
+                if diff < 0.0 { "-" } else { "+" },
+                diff.abs()
+            )
+        }
+        None => "no previous run to compare to".to_owned(),
+    }
+}
+
+// Lays out the card centered on the origin, shared by the on-screen summary
+// and the exported image
+fn draw_card(draw: &Draw, summary: &RunSummary, previous: Option<&RunSummary>) {
+    let (w, h) = (CARD_SIZE[0] as f32, CARD_SIZE[1] as f32);
+
+    draw.rect()
+        .x_y(0.0, 0.0)
+        .w_h(w, h)
+        .color(srgba(20u8, 24, 32, 235));
+
+    draw.text("Build finished")
+        .font_size(28)
+        .color(WHITE)
+        .x_y(0.0, h / 2.0 - 30.0)
+        .w_h(w, 40.0);
+
+    let stats = format!(
+        "{:.1}s total\n{} crates built\n{} warnings\n{}",
+        summary.total_secs,
+        summary.crates_built,
+        summary.warnings,
+        comparison(summary, previous),
+    );
+
+    draw.text(&stats)
+        .font_size(16)
+        .color(WHITE)
+        .left_justify()
+        .align_text_top()
+        .x_y(-w / 4.0 + 10.0, -20.0)
+        .w_h(w / 2.0 - 40.0, h - 100.0);
+
+    let slowest = std::iter::once("Slowest crates:".to_owned())
+        .chain(
+            summary
+                .slowest
+                .iter()
+                .map(|(name, secs)| format!("{}  {:.1}s", name, secs)),
+        )
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    draw.text(&slowest)
+        .font_size(16)
+        .color(srgb(0x98u8, 0xfb, 0x98))
+        .left_justify()
+        .align_text_top()
+        .x_y(w / 4.0, -20.0)
+        .w_h(w / 2.0 - 40.0, h - 100.0);
+}
+
+fn button_center() -> Point {
+    (0.0, -(CARD_SIZE[1] as f32) / 2.0 - BUTTON_SIZE.1)
+}
+
+pub fn draw_summary(draw: &Draw, summary: &RunSummary, previous: Option<&RunSummary>) {
+    draw_card(draw, summary, previous);
+
+    let (x, y) = button_center();
+    draw.rect()
+        .x_y(x, y)
+        .w_h(BUTTON_SIZE.0, BUTTON_SIZE.1)
+        .color(srgb(60u8, 90, 140));
+    draw.text("export card")
+        .color(WHITE)
+        .x_y(x, y)
+        .w_h(BUTTON_SIZE.0, BUTTON_SIZE.1);
+}
+
+pub fn export_button_contains(point: Point) -> bool {
+    let (x, y) = button_center();
+
+    (point.0 - x).abs() < BUTTON_SIZE.0 / 2.0 && (point.1 - y).abs() < BUTTON_SIZE.1 / 2.0
+}
+
+pub fn card_path() -> PathBuf {
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    crate::project::state_dir().join(format!("card-{}.png", stamp))
+}
+
+// Renders the card into its own small texture rather than grabbing the window,
+// so the image comes out the same size regardless of the window
+pub fn export_card(app: &App, summary: &RunSummary, previous: Option<&RunSummary>, path: &Path) {
+    let window = app.main_window();
+    let mut offscreen = Offscreen::new(&window, CARD_SIZE);
+
+    let draw = Draw::new();
+    draw.background().color(BLACK);
+    draw_card(&draw, summary, previous);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).expect("Failed to create card directory");
+    }
+
+    let path = path.to_path_buf();
+    offscreen.capture(&window, &draw, move |image| {
+        image.save(&path).expect("Failed to save card");
+        println!("Saved build card to {}", path.display());
+    });
+}
@@ -0,0 +1,269 @@
+use crate::drawing::Color;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::events::BuildPhase;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "gui")]
+use std::collections::HashMap;
+
+// Colors are [r, g, b] in TOML. A custom theme only needs the ones it changes,
+// the rest come from the dark theme.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    // The background, by build phase
+    pub waiting: Color,
+    pub downloading: Color,
+    pub building: Color,
+    pub failed: Color,
+    pub finished: Color,
+    // Crates, by build state. Pending crates keep their own color, except the
+    // root which has none.
+    pub root: Color,
+    pub active: Color,
+    pub completed: Color,
+    pub crate_failed: Color,
+    pub aggregate: Color,
+    pub edge: Color,
+    pub bundle: Color,
+    pub outline: Color,
+    pub text: Color,
+    pub notice: Color,
+}
+
+// Backgrounds stay dark so the graph remains readable, only tinted enough to
+// tell the phase apart from across the room
+pub const DEFAULT_THEME: Theme = Theme {
+    waiting: (0x00, 0x00, 0x00),
+    downloading: (0x08, 0x0c, 0x1c),
+    building: (0x10, 0x10, 0x08),
+    failed: (0x24, 0x06, 0x06),
+    finished: (0x06, 0x1a, 0x0a),
+    root: (200, 100, 130),
+    active: (0x98, 0xfb, 0x98),
+    completed: (0x98, 0xfb, 0x98),
+    crate_failed: (0xff, 0x45, 0x45),
+    aggregate: (0x80, 0x80, 0x80),
+    edge: (0xff, 0xff, 0xff),
+    bundle: (0x70, 0xa0, 0xff),
+    outline: (0x00, 0x00, 0x00),
+    text: (0xff, 0xff, 0xff),
+    notice: (0xff, 0xc0, 0x40),
+};
+
+impl Default for Theme {
+    fn default() -> Self {
+        DEFAULT_THEME
+    }
+}
+
+impl Theme {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn background(&self, phase: BuildPhase) -> Color {
+        match phase {
+            BuildPhase::Waiting => self.waiting,
+            BuildPhase::Downloading => self.downloading,
+            BuildPhase::Building => self.building,
+            BuildPhase::Failed => self.failed,
+            BuildPhase::Finished => self.finished,
+        }
+    }
+
+    // Turns a color halfway to the notice color, for crates worth a look
+    pub fn tint(&self, color: Color) -> Color {
+        let mix = |from: u8, to: u8| ((from as u16 + to as u16) / 2) as u8;
+
+        (
+            mix(color.0, self.notice.0),
+            mix(color.1, self.notice.1),
+            mix(color.2, self.notice.2),
+        )
+    }
+
+    // Fades a color most of the way into the background, so dimming works on
+    // light themes too
+    pub fn dim(&self, color: Color) -> Color {
+        let mix = |from: u8, to: u8| ((from as u16 + to as u16 * 2) / 3) as u8;
+
+        (
+            mix(color.0, self.waiting.0),
+            mix(color.1, self.waiting.1),
+            mix(color.2, self.waiting.2),
+        )
+    }
+}
+
+// For when the phase tint is more distracting than useful
+#[cfg(feature = "gui")]
+pub const PLAIN_THEME: Theme = Theme {
+    waiting: (0x00, 0x00, 0x00),
+    downloading: (0x00, 0x00, 0x00),
+    building: (0x00, 0x00, 0x00),
+    failed: (0x00, 0x00, 0x00),
+    finished: (0x00, 0x00, 0x00),
+    ..DEFAULT_THEME
+};
+
+#[cfg(feature = "gui")]
+pub const LIGHT_THEME: Theme = Theme {
+    waiting: (0xf4, 0xf4, 0xf4),
+    downloading: (0xe8, 0xee, 0xfa),
+    building: (0xf8, 0xf4, 0xe4),
+    failed: (0xfa, 0xe4, 0xe4),
+    finished: (0xe6, 0xf6, 0xe8),
+    root: (0xb0, 0x40, 0x70),
+    active: (0x2e, 0xa0, 0x43),
+    completed: (0x2e, 0xa0, 0x43),
+    crate_failed: (0xd0, 0x20, 0x20),
+    aggregate: (0x90, 0x90, 0x90),
+    edge: (0x40, 0x40, 0x40),
+    bundle: (0x30, 0x60, 0xd0),
+    outline: (0x60, 0x60, 0x60),
+    text: (0x10, 0x10, 0x10),
+    notice: (0xb0, 0x60, 0x00),
+};
+
+// Ethan Schoonover's palette, base03 and up
+#[cfg(feature = "gui")]
+pub const SOLARIZED_THEME: Theme = Theme {
+    waiting: (0x00, 0x2b, 0x36),
+    downloading: (0x03, 0x2c, 0x40),
+    building: (0x0a, 0x30, 0x30),
+    failed: (0x20, 0x24, 0x30),
+    finished: (0x00, 0x33, 0x30),
+    root: (0xd3, 0x36, 0x82),
+    active: (0x85, 0x99, 0x00),
+    completed: (0x85, 0x99, 0x00),
+    crate_failed: (0xdc, 0x32, 0x2f),
+    aggregate: (0x58, 0x6e, 0x75),
+    edge: (0x93, 0xa1, 0xa1),
+    bundle: (0x26, 0x8b, 0xd2),
+    outline: (0x07, 0x36, 0x42),
+    text: (0xee, 0xe8, 0xd5),
+    notice: (0xb5, 0x89, 0x00),
+};
+
+// Pure colors on black, for projectors and tired eyes
+#[cfg(feature = "gui")]
+pub const HIGH_CONTRAST_THEME: Theme = Theme {
+    waiting: (0x00, 0x00, 0x00),
+    downloading: (0x00, 0x00, 0x00),
+    building: (0x00, 0x00, 0x00),
+    failed: (0x00, 0x00, 0x00),
+    finished: (0x00, 0x00, 0x00),
+    root: (0xff, 0x00, 0xff),
+    active: (0x00, 0xff, 0x00),
+    completed: (0x00, 0xff, 0x00),
+    crate_failed: (0xff, 0x00, 0x00),
+    aggregate: (0xc0, 0xc0, 0xc0),
+    edge: (0xff, 0xff, 0xff),
+    bundle: (0x00, 0xc0, 0xff),
+    outline: (0xff, 0xff, 0xff),
+    text: (0xff, 0xff, 0x00),
+    notice: (0xff, 0xff, 0x00),
+};
+
+// Okabe and Ito's palette, which keeps its colors apart for the common kinds
+// of color blindness. Blue and orange take the place of green and red.
+#[cfg(feature = "gui")]
+pub const COLORBLIND_THEME: Theme = Theme {
+    root: (0xcc, 0x79, 0xa7),
+    active: (0xe6, 0x9f, 0x00),
+    completed: (0x00, 0x72, 0xb2),
+    crate_failed: (0xd5, 0x5e, 0x00),
+    bundle: (0x56, 0xb4, 0xe9),
+    notice: (0xf0, 0xe4, 0x42),
+    ..DEFAULT_THEME
+};
+
+#[cfg(feature = "gui")]
+pub const THEME_NAMES: [&str; 6] = [
+    "dark",
+    "plain",
+    "light",
+    "solarized",
+    "high-contrast",
+    "colorblind",
+];
+
+// Themes defined in the config take precedence over the built in ones
+#[cfg(feature = "gui")]
+pub fn by_name(name: &str, custom: &HashMap<String, Theme>) -> Theme {
+    if let Some(theme) = custom.get(name) {
+        return theme.clone();
+    }
+
+    match name {
+        "plain" => PLAIN_THEME,
+        "light" => LIGHT_THEME,
+        "solarized" => SOLARIZED_THEME,
+        "high-contrast" => HIGH_CONTRAST_THEME,
+        "colorblind" => COLORBLIND_THEME,
+        _ => DEFAULT_THEME,
+    }
+}
+
+// Built in themes first, then the config's own in name order
+#[cfg(feature = "gui")]
+pub fn names(custom: &HashMap<String, Theme>) -> Vec<String> {
+    let mut extra = custom
+        .keys()
+        .filter(|name| !THEME_NAMES.contains(&name.as_str()))
+        .cloned()
+        .collect::<Vec<_>>();
+    extra.sort();
+
+    THEME_NAMES
+        .iter()
+        .map(|name| (*name).to_owned())
+        .chain(extra)
+        .collect()
+}
+
+// Gives every crate matching `pattern` the same color, ahead of the color
+// picked from its name. `*` matches any run of characters.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColorRule {
+    pub pattern: String,
+    // "#rrggbb"
+    pub color: String,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn matches(pattern: &str, name: &str) -> bool {
+    match pattern.find('*') {
+        None => pattern == name,
+        Some(star) => {
+            let (prefix, rest) = (&pattern[..star], &pattern[star + 1..]);
+
+            name.starts_with(prefix)
+                && (prefix.len()..=name.len())
+                    .filter(|&idx| name.is_char_boundary(idx))
+                    .any(|idx| matches(rest, &name[idx..]))
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_hex(color: &str) -> Option<Color> {
+    let hex = color.trim_start_matches('#');
+    let channel = |idx: usize| u8::from_str_radix(hex.get(idx..idx + 2)?, 16).ok();
+
+    if hex.len() == 6 {
+        Some((channel(0)?, channel(2)?, channel(4)?))
+    } else {
+        None
+    }
+}
+
+// The first rule that matches wins
+#[cfg(not(target_arch = "wasm32"))]
+pub fn rule_color(rules: &[ColorRule], name: &str) -> Option<Color> {
+    rules
+        .iter()
+        .find(|rule| matches(&rule.pattern, name))
+        .map(|rule| {
+            parse_hex(&rule.color)
+                .unwrap_or_else(|| panic!("Bad color {:?} for {}", rule.color, rule.pattern))
+        })
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "gui")]
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TimelineEvent {
+    Started(String),
+    Completed(String),
+    Failed(String),
+}
+
+// Crate events in the order they happened, in seconds since the build started
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Timeline {
+    events: Vec<(f32, TimelineEvent)>,
+}
+
+#[cfg(feature = "gui")]
+pub struct TimelineState {
+    pub completed: HashSet<String>,
+    pub active: HashSet<String>,
+    pub failed: HashSet<String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Timeline {
+    pub fn push(&mut self, at: f32, event: TimelineEvent) {
+        self.events.push((at, event));
+    }
+
+    // When each crate was compiling, with the ones still going cut off at `end`
+    #[cfg(feature = "gui")]
+    pub fn spans(&self, end: f32) -> Vec<(f32, f32)> {
+        let mut started = HashMap::new();
+        let mut spans = Vec::new();
+
+        for (at, event) in self.events.iter() {
+            match event {
+                TimelineEvent::Started(name) => {
+                    started.entry(name.as_str()).or_insert(*at);
+                }
+                TimelineEvent::Completed(name) | TimelineEvent::Failed(name) => {
+                    if let Some(start) = started.remove(name.as_str()) {
+                        spans.push((start, *at));
+                    }
+                }
+            }
+        }
+
+        spans.extend(started.values().map(|&start| (start, end)));
+        spans
+    }
+
+    // Replays everything up to the given moment
+    #[cfg(feature = "gui")]
+    pub fn state_at(&self, at: f32) -> TimelineState {
+        let mut state = TimelineState {
+            completed: HashSet::new(),
+            active: HashSet::new(),
+            failed: HashSet::new(),
+        };
+
+        for (_, event) in self.events.iter().take_while(|(time, _)| *time <= at) {
+            match event {
+                TimelineEvent::Started(name) => {
+                    state.active.insert(name.clone());
+                }
+                TimelineEvent::Completed(name) => {
+                    state.active.remove(name);
+                    state.completed.insert(name.clone());
+                }
+                TimelineEvent::Failed(name) => {
+                    state.active.remove(name);
+                    state.failed.insert(name.clone());
+                }
+            }
+        }
+
+        state
+    }
+}
@@ -0,0 +1,185 @@
+#[cfg(feature = "gui")]
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+pub struct Timings {
+    pub build_start: Instant,
+    pub build_end: Option<Instant>,
+    started: HashMap<String, Instant>,
+    pub durations: HashMap<String, Duration>,
+    pub warnings: usize,
+}
+
+impl Timings {
+    pub fn new() -> Self {
+        Timings {
+            build_start: Instant::now(),
+            build_end: None,
+            started: HashMap::new(),
+            durations: HashMap::new(),
+            warnings: 0,
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn restore(
+        elapsed: Duration,
+        durations: HashMap<String, Duration>,
+        warnings: usize,
+    ) -> Self {
+        let now = Instant::now();
+
+        Timings {
+            build_start: now - elapsed,
+            build_end: Some(now),
+            started: HashMap::new(),
+            durations,
+            warnings,
+        }
+    }
+
+    // Picks up a build that has been going for a while already
+    #[cfg(feature = "gui")]
+    pub fn resume(elapsed: Duration) -> Self {
+        Timings {
+            build_start: Instant::now() - elapsed,
+            ..Self::new()
+        }
+    }
+
+    // Replayed events happened before now, so they say when
+    #[cfg(feature = "gui")]
+    pub fn at(&self, secs: f32) -> Instant {
+        self.build_start + Duration::from_secs_f32(secs.max(0.0))
+    }
+
+    pub fn start(&mut self, name: String, at: Instant) {
+        self.started.entry(name).or_insert(at);
+    }
+
+    // Only crates we saw starting get a duration, which also filters out the
+    // non-crate words picked out of cargo's status lines
+    pub fn complete(&mut self, name: &str, at: Instant) {
+        if let Some(start) = self.started.remove(name) {
+            self.durations.insert(name.to_owned(), at - start);
+        }
+    }
+
+    pub fn running_for(&self, name: &str) -> Option<Duration> {
+        self.started.get(name).map(|start| start.elapsed())
+    }
+
+    // Drops starts we never saw finish (e.g. a rustc that was killed) so they
+    // don't pile up over a long session
+    #[cfg(feature = "gui")]
+    pub fn expire_stale(&mut self, max_age: Duration) {
+        self.started.retain(|_, start| start.elapsed() < max_age);
+    }
+
+    pub fn finish(&mut self) {
+        self.build_end.get_or_insert_with(Instant::now);
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.build_end.unwrap_or_else(Instant::now) - self.build_start
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn slowest(&self, amount: usize) -> Vec<(String, Duration)> {
+        self.durations
+            .iter()
+            .map(|(name, duration)| (name.clone(), *duration))
+            .sorted_by(|a, b| b.1.cmp(&a.1))
+            .take(amount)
+            .collect()
+    }
+}
+
+pub fn minutes(secs: f32) -> String {
+    let secs = secs.max(0.0) as u32;
+
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The rest of what a long session keeps is gui only
+#[cfg(all(test, feature = "gui"))]
+mod tests {
+    use super::*;
+    use crate::crates_io::{self, Listings};
+    use crate::dependency_tree::DependencyTree;
+    use crate::monitor::LOG_LINES;
+    use crate::parse_cargo_tree_output::TreeNode;
+    use crate::ring_buffer::RingBuffer;
+    use std::sync::Arc;
+
+    const CYCLES: usize = 500;
+    const CRATES: usize = 40;
+
+    // A session following rebuilds for a long while: every cycle builds the
+    // same crates again with a few rustcs killed before they finish, cargo
+    // keeps printing, and the tree shown moves on to a revision with some
+    // crates swapped for others
+    #[test]
+    fn a_long_session_stays_bounded() {
+        let node = |name: String, children| {
+            Arc::new(TreeNode {
+                name,
+                children,
+                color: (0, 0, 0),
+            })
+        };
+        let max_age = Duration::from_secs(60);
+        let killed_at = Instant::now()
+            .checked_sub(2 * max_age)
+            .expect("Up for longer than two minutes");
+
+        let mut timings = Timings::new();
+        let mut log = RingBuffer::new(LOG_LINES);
+        let mut listings = Listings::new();
+        let (mut started_capacity, mut listings_capacity) = (None, None);
+
+        for cycle in 0..CYCLES {
+            for idx in 0..CRATES {
+                let name = format!("crate-{}", idx);
+                if (idx + cycle) % 10 == 0 {
+                    timings.start(name, killed_at);
+                } else {
+                    timings.start(name.clone(), Instant::now());
+                    timings.complete(&name, Instant::now());
+                }
+                log.push(format!("   Compiling crate-{} v0.1.{}", idx, cycle));
+            }
+            timings.expire_stale(max_age);
+
+            let shown = (cycle..cycle + CRATES)
+                .map(|idx| node(format!("crate-{}", idx), Vec::new()))
+                .collect();
+            let tree = DependencyTree::new(node("root".to_owned(), shown));
+            crates_io::forget_gone(&mut listings, &tree);
+            for idx in cycle..cycle + CRATES {
+                listings.entry(format!("crate-{}", idx)).or_insert(None);
+            }
+
+            assert!(timings.started.is_empty());
+            assert!(timings.durations.len() <= CRATES);
+            assert_eq!(log.iter().len(), LOG_LINES.min((cycle + 1) * CRATES));
+            assert_eq!(listings.len(), CRATES);
+
+            // Settled in after the first few cycles, not growing after that
+            if cycle == CYCLES / 2 {
+                started_capacity = Some(timings.started.capacity());
+                listings_capacity = Some(listings.capacity());
+            } else if cycle > CYCLES / 2 {
+                assert_eq!(Some(timings.started.capacity()), started_capacity);
+                assert_eq!(Some(listings.capacity()), listings_capacity);
+            }
+        }
+    }
+}
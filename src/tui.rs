@@ -0,0 +1,496 @@
+use crate::active;
+use crate::config;
+use crate::drawing::node_id;
+use crate::emit::Emitter;
+use crate::events::{BuildEvent, BuildPhase};
+use crate::monitor;
+use crate::notify::Notifier;
+use crate::parse_cargo_tree_output::TreeNode;
+use crate::timings::minutes;
+use crate::unit_graph;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufReader, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const FRAME: Duration = Duration::from_millis(100);
+// The header above the tree and the footer below it
+const CHROME_ROWS: usize = 4;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RED: &str = "\x1b[31m";
+const INVERT: &str = "\x1b[7m";
+
+enum Input {
+    Up,
+    Down,
+    Toggle,
+    Quit,
+}
+
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()
+        .ok()?;
+
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+        .filter(|_| output.status.success())
+}
+
+// Raw keys and the alternate screen while we own the terminal, put back the
+// way it was however we leave
+struct Terminal {
+    saved: String,
+}
+
+impl Terminal {
+    // None when stdin isn't a terminal, e.g. under CI
+    fn enter() -> Option<Self> {
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "min", "1"]);
+        print!("\x1b[?1049h\x1b[?25l");
+
+        Some(Terminal { saved })
+    }
+
+    fn size() -> (usize, usize) {
+        stty(&["size"])
+            .and_then(|size| {
+                let mut numbers = size.split_whitespace().map(|number| number.parse().ok());
+                Some((numbers.next()??, numbers.next()??))
+            })
+            .unwrap_or((24, 80))
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        io::stdout().flush().ok();
+
+        stty(&[&self.saved]);
+    }
+}
+
+// Arrow keys come in as escape sequences, vi keys work too
+fn read_input() -> Receiver<Input> {
+    let (sender, receiver) = channel();
+
+    thread::spawn(move || {
+        let mut bytes = BufReader::new(io::stdin()).bytes().flatten();
+
+        while let Some(byte) = bytes.next() {
+            let input = match byte {
+                b'q' => Input::Quit,
+                b'k' => Input::Up,
+                b'j' => Input::Down,
+                b' ' | b'\n' | b'\r' => Input::Toggle,
+                0x1b => match (bytes.next(), bytes.next()) {
+                    (Some(b'['), Some(b'A')) => Input::Up,
+                    (Some(b'['), Some(b'B')) => Input::Down,
+                    _ => continue,
+                },
+                _ => continue,
+            };
+
+            if sender.send(input).is_err() {
+                break;
+            }
+        }
+    });
+
+    receiver
+}
+
+struct Row {
+    id: usize,
+    depth: usize,
+    name: String,
+    // What it is shown as
+    label: String,
+    folded: Option<bool>,
+}
+
+// The build as a folding tree. Subtrees with something building or failed in
+// them open by themselves, toggling a crate flips that.
+struct View {
+    tree: Arc<TreeNode>,
+    aliases: HashMap<String, String>,
+    command: String,
+    // Everything in the tree, by name
+    crates: HashSet<String>,
+    completed: HashSet<String>,
+    active: HashSet<String>,
+    failed: HashSet<String>,
+    fresh: HashSet<String>,
+    started: HashMap<String, Instant>,
+    phase: BuildPhase,
+    build_start: Instant,
+    build_end: Option<Instant>,
+    last_log: String,
+    toggled: HashSet<usize>,
+    selected: usize,
+    scroll: usize,
+    emitter: Option<Emitter>,
+    notifier: Option<Notifier>,
+}
+
+impl View {
+    fn apply(&mut self, event: BuildEvent) {
+        if let Some(ref mut emitter) = self.emitter {
+            emitter.emit(&event);
+        }
+        if let Some(ref mut notifier) = self.notifier {
+            notifier.observe(&event);
+        }
+
+        match event {
+            BuildEvent::Phase(phase) => {
+                self.phase = phase;
+                if phase == BuildPhase::Finished || phase == BuildPhase::Failed {
+                    self.build_end.get_or_insert_with(Instant::now);
+                    self.active.clear();
+                }
+            }
+            BuildEvent::Completed(name) => {
+                self.fresh.remove(&name);
+                self.active.remove(&name);
+                self.completed.insert(name);
+            }
+            BuildEvent::Failed(name) => {
+                self.fresh.remove(&name);
+                self.active.remove(&name);
+                self.failed.insert(name);
+            }
+            BuildEvent::Started(name) => {
+                self.fresh.remove(&name);
+            }
+            BuildEvent::Fresh(name)
+                if self.crates.contains(&name)
+                    && !self.active.contains(&name)
+                    && !self.completed.contains(&name) =>
+            {
+                self.fresh.insert(name);
+            }
+            BuildEvent::Log(line) => self.last_log = line,
+            _ => {}
+        }
+    }
+
+    fn set_active(&mut self, active: HashSet<String>) {
+        for name in active.difference(&self.active) {
+            self.started.insert(name.clone(), Instant::now());
+        }
+        self.active = active;
+    }
+
+    fn busy(&self, node: &Arc<TreeNode>, memo: &mut HashMap<usize, bool>) -> bool {
+        if let Some(&busy) = memo.get(&node_id(node)) {
+            return busy;
+        }
+
+        let busy = self.active.contains(&node.name)
+            || self.failed.contains(&node.name)
+            || node.children.iter().any(|child| self.busy(child, memo));
+        memo.insert(node_id(node), busy);
+        busy
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        fn walk(
+            view: &View,
+            node: &Arc<TreeNode>,
+            depth: usize,
+            memo: &mut HashMap<usize, bool>,
+            rows: &mut Vec<Row>,
+        ) {
+            let id = node_id(node);
+            let open = (depth == 0 || view.busy(node, memo)) != view.toggled.contains(&id);
+
+            rows.push(Row {
+                id,
+                depth,
+                name: node.name.clone(),
+                label: config::display_name(&view.aliases, &node.name).to_owned(),
+                folded: Some(!open).filter(|_| !node.children.is_empty()),
+            });
+
+            if open {
+                for child in node.children.iter() {
+                    walk(view, child, depth + 1, memo, rows);
+                }
+            }
+        }
+
+        let mut rows = Vec::new();
+        walk(self, &self.tree, 0, &mut HashMap::new(), &mut rows);
+        rows
+    }
+
+    fn handle(&mut self, input: &Input, rows: &[Row]) {
+        match input {
+            Input::Up => self.selected = self.selected.saturating_sub(1),
+            Input::Down => self.selected = (self.selected + 1).min(rows.len().saturating_sub(1)),
+            Input::Toggle => {
+                if let Some(row) = rows.get(self.selected) {
+                    if !self.toggled.remove(&row.id) {
+                        self.toggled.insert(row.id);
+                    }
+                }
+            }
+            Input::Quit => {}
+        }
+    }
+
+    // Only what gets built is counted as work
+    fn to_build(&self) -> usize {
+        self.crates.len() - self.fresh.len()
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.build_end.unwrap_or_else(Instant::now) - self.build_start
+    }
+
+    fn status(&self) -> String {
+        let phase = match self.phase {
+            BuildPhase::Waiting => "waiting",
+            BuildPhase::Downloading => "downloading",
+            BuildPhase::Building => "building",
+            BuildPhase::Failed => "failed",
+            BuildPhase::Finished => "finished",
+        };
+
+        format!(
+            "{} | {} | {}/{} built, {} building, {} failed | {}",
+            self.command,
+            phase,
+            self.completed.len(),
+            self.to_build(),
+            self.active.len(),
+            self.failed.len(),
+            minutes(self.elapsed().as_secs_f32())
+        )
+    }
+
+    fn glyph(&self, name: &str) -> (&'static str, &'static str) {
+        let package = unit_graph::owner(name);
+
+        if self.active.contains(name) {
+            (YELLOW, "*")
+        } else if self.failed.contains(package) {
+            (RED, "x")
+        } else if self.completed.contains(package) {
+            (GREEN, "+")
+        } else if self.fresh.contains(package) {
+            (DIM, "=")
+        } else {
+            (DIM, ".")
+        }
+    }
+
+    fn draw(&mut self, rows: &[Row], (height, width): (usize, usize)) -> String {
+        let shown = height.saturating_sub(CHROME_ROWS).max(1);
+        self.selected = self.selected.min(rows.len().saturating_sub(1));
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + shown {
+            self.scroll = self.selected + 1 - shown;
+        }
+
+        let fit = |text: &str, width: usize| text.chars().take(width).collect::<String>();
+        let mut screen = String::from("\x1b[H\x1b[2J");
+
+        screen.push_str(&format!(
+            "{}{}{}\r\n",
+            BOLD,
+            fit(&self.status(), width),
+            RESET
+        ));
+
+        let done = (self.completed.len() + self.failed.len()).min(self.to_build());
+        let bar_width = width.saturating_sub(2);
+        let filled = bar_width * done / self.to_build().max(1);
+        screen.push_str(&format!(
+            "[{}{}{}{}]\r\n",
+            GREEN,
+            "#".repeat(filled),
+            RESET,
+            " ".repeat(bar_width - filled)
+        ));
+
+        for (idx, row) in rows.iter().enumerate().skip(self.scroll).take(shown) {
+            let marker = match row.folded {
+                Some(true) => "> ",
+                Some(false) => "v ",
+                None => "  ",
+            };
+            let (color, glyph) = self.glyph(&row.name);
+            let running = self
+                .started
+                .get(&row.name)
+                .filter(|_| self.active.contains(&row.name))
+                .map(|start| format!(" {}s", start.elapsed().as_secs()))
+                .unwrap_or_default();
+            let prefix = format!("{}{}", "  ".repeat(row.depth), marker);
+            let label = fit(
+                &format!("{}{}", row.label, running),
+                width.saturating_sub(prefix.chars().count() + 2),
+            );
+            let highlight = if idx == self.selected { INVERT } else { "" };
+
+            screen.push_str(&format!(
+                "{}{}{}{}{} {}{}\r\n",
+                highlight, prefix, color, glyph, RESET, highlight, label
+            ));
+            screen.push_str(RESET);
+        }
+
+        screen.push_str(&format!(
+            "\x1b[{};1H{}{}{}\r\n{}",
+            height - 1,
+            DIM,
+            fit(&self.last_log, width),
+            RESET,
+            fit("up/down or j/k to move, space to fold, q to quit", width)
+        ));
+
+        screen
+    }
+}
+
+// Build scripts count as part of their package
+fn crate_names(tree: &Arc<TreeNode>, names: &mut HashSet<String>) {
+    names.insert(unit_graph::owner(&tree.name).to_owned());
+    for child in tree.children.iter() {
+        crate_names(child, names);
+    }
+}
+
+// Without a terminal to draw on, a line per crate as it finishes
+fn run_plain(view: &mut View, events: &Receiver<BuildEvent>) {
+    println!("{}", view.command);
+
+    loop {
+        while let Ok(event) = events.try_recv() {
+            let finished = match event {
+                BuildEvent::Completed(ref name) if !view.completed.contains(name) => {
+                    Some(format!("built {}", name))
+                }
+                BuildEvent::Failed(ref name) if !view.failed.contains(name) => {
+                    Some(format!("FAILED {}", name))
+                }
+                _ => None,
+            };
+            view.apply(event);
+
+            if let Some(line) = finished {
+                println!(
+                    "[{}/{}] {}",
+                    view.completed.len() + view.failed.len(),
+                    view.to_build(),
+                    line
+                );
+            }
+        }
+
+        if view.build_end.is_some() {
+            println!("{}", view.status());
+            return;
+        }
+
+        thread::sleep(FRAME);
+    }
+}
+
+fn run_interactive(view: &mut View, events: &Receiver<BuildEvent>, terminal: Terminal) {
+    let inputs = read_input();
+
+    loop {
+        while let Ok(event) = events.try_recv() {
+            view.apply(event);
+        }
+        if view.build_end.is_none() {
+            view.set_active(
+                active::get_active()
+                    .into_iter()
+                    .map(|unit| unit.name)
+                    .collect(),
+            );
+        }
+
+        let mut rows = view.rows();
+        while let Ok(input) = inputs.try_recv() {
+            if let Input::Quit = input {
+                drop(terminal);
+                println!("{}", view.status());
+                return;
+            }
+
+            view.handle(&input, &rows);
+            rows = view.rows();
+        }
+
+        print!("{}", view.draw(&rows, Terminal::size()));
+        io::stdout().flush().ok();
+
+        thread::sleep(FRAME);
+    }
+}
+
+// Runs the build and follows it in the terminal instead of a window, for SSH
+// sessions and CI, showing `tree` as it gets built
+pub fn run(
+    cargo_command: Vec<&'static str>,
+    cargo_args: Vec<String>,
+    tree: Arc<TreeNode>,
+    emitter: Option<Emitter>,
+    notifier: Option<Notifier>,
+) {
+    let config = config::load().unwrap_or_default();
+    let (sender, events) = channel();
+    let command = std::iter::once("cargo")
+        .chain(cargo_command.iter().copied())
+        .chain(cargo_args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    thread::spawn(move || monitor::run(cargo_command, cargo_args, sender));
+
+    let mut crates = HashSet::new();
+    crate_names(&tree, &mut crates);
+
+    let mut view = View {
+        crates,
+        tree,
+        aliases: config.aliases,
+        command,
+        completed: HashSet::new(),
+        active: HashSet::new(),
+        failed: HashSet::new(),
+        fresh: HashSet::new(),
+        started: HashMap::new(),
+        phase: BuildPhase::Waiting,
+        build_start: Instant::now(),
+        build_end: None,
+        last_log: String::new(),
+        toggled: HashSet::new(),
+        selected: 0,
+        scroll: 0,
+        emitter,
+        notifier,
+    };
+
+    match Terminal::enter() {
+        Some(terminal) => run_interactive(&mut view, &events, terminal),
+        None => run_plain(&mut view, &events),
+    }
+}
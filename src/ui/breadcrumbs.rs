@@ -0,0 +1,63 @@
+use crate::config;
+use crate::drawing::Point;
+use crate::parse_cargo_tree_output::TreeNode;
+use nannou::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const MARGIN: f32 = 10.0;
+const HEIGHT: f32 = 24.0;
+const GAP: f32 = 6.0;
+// Rough width of a character at the default font size, good enough to size
+// the boxes around the labels
+const CHAR_WIDTH: f32 = 8.0;
+
+// Boxes laid out left to right along the top of the window
+fn layout(window: Rect, trail: &[Arc<TreeNode>], aliases: &HashMap<String, String>) -> Vec<Rect> {
+    let mut x = window.left() + MARGIN;
+    let y = window.top() - MARGIN - HEIGHT / 2.0;
+
+    trail
+        .iter()
+        .map(|crumb| {
+            let width =
+                config::display_name(aliases, &crumb.name).len() as f32 * CHAR_WIDTH + 2.0 * MARGIN;
+            let rect = Rect::from_x_y_w_h(x + width / 2.0, y, width, HEIGHT);
+            x += width + GAP;
+            rect
+        })
+        .collect()
+}
+
+pub fn draw_breadcrumbs(
+    draw: &Draw,
+    window: Rect,
+    trail: &[Arc<TreeNode>],
+    aliases: &HashMap<String, String>,
+) {
+    for (idx, (crumb, rect)) in trail.iter().zip(layout(window, trail, aliases)).enumerate() {
+        let last = idx + 1 == trail.len();
+
+        draw.rect().xy(rect.xy()).wh(rect.wh()).color(if last {
+            srgba(60u8, 90, 140, 220)
+        } else {
+            srgba(40u8, 40, 48, 220)
+        });
+
+        draw.text(config::display_name(aliases, &crumb.name))
+            .color(WHITE)
+            .xy(rect.xy())
+            .wh(rect.wh());
+    }
+}
+
+pub fn breadcrumb_at(
+    window: Rect,
+    trail: &[Arc<TreeNode>],
+    aliases: &HashMap<String, String>,
+    point: Point,
+) -> Option<usize> {
+    layout(window, trail, aliases)
+        .iter()
+        .position(|rect| rect.contains(pt2(point.0, point.1)))
+}
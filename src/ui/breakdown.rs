@@ -0,0 +1,52 @@
+use crate::category::CategoryStats;
+use nannou::prelude::*;
+
+const MARGIN: f32 = 10.0;
+const ROW_HEIGHT: f32 = 34.0;
+const WIDTH: f32 = 300.0;
+const BAR_HEIGHT: f32 = 8.0;
+
+// A bar per category along the right edge, scaled to the slowest one
+pub fn draw_breakdown(draw: &Draw, window: Rect, stats: &[CategoryStats]) {
+    let height = stats.len() as f32 * ROW_HEIGHT + 2.0 * MARGIN;
+    let left = window.right() - MARGIN - WIDTH;
+    let top = window.top() - MARGIN;
+    let slowest = stats
+        .iter()
+        .map(|stat| stat.compile.as_secs_f32())
+        .fold(0.0, f32::max);
+
+    draw.rect()
+        .x_y(left + WIDTH / 2.0, top - height / 2.0)
+        .w_h(WIDTH, height)
+        .color(srgba(20u8, 20, 24, 220));
+
+    for (idx, stat) in stats.iter().enumerate() {
+        let y = top - MARGIN - idx as f32 * ROW_HEIGHT;
+        let inner = WIDTH - 2.0 * MARGIN;
+        let secs = stat.compile.as_secs_f32();
+        let (r, g, b) = stat.category.color();
+
+        let label = format!(
+            "{}: {} crates, {:.0}s",
+            stat.category.label(),
+            stat.crates,
+            secs
+        );
+        draw.text(&label)
+            .left_justify()
+            .color(WHITE)
+            .x_y(left + MARGIN + inner / 2.0, y - 10.0)
+            .w_h(inner, 20.0);
+
+        let bar = if slowest > 0.0 {
+            inner * secs / slowest
+        } else {
+            0.0
+        };
+        draw.rect()
+            .x_y(left + MARGIN + bar / 2.0, y - 26.0)
+            .w_h(bar, BAR_HEIGHT)
+            .color(rgb(r, g, b));
+    }
+}
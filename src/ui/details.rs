@@ -0,0 +1,196 @@
+use crate::crates_io::Listing;
+use crate::drawing::Point;
+use crate::package_info::PackageInfo;
+use nannou::prelude::*;
+
+const MARGIN: f32 = 10.0;
+const LINE: f32 = 18.0;
+const WIDTH: f32 = 340.0;
+// Below where the filter panel goes
+const TOP: f32 = 100.0;
+// Characters that fit on a line, roughly
+const COLUMNS: usize = 46;
+const LINK_COLOR: (u8, u8, u8) = (120, 170, 240);
+
+pub struct Line {
+    text: String,
+    // Opened in the browser when the line is clicked
+    link: Option<String>,
+    heading: bool,
+}
+
+impl Line {
+    fn text(text: String) -> Self {
+        Line {
+            text,
+            link: None,
+            heading: false,
+        }
+    }
+}
+
+// Breaks text up at spaces into lines of about `COLUMNS` characters
+fn wrap(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > COLUMNS {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+// What the panel says about a crate, from cargo metadata where it knows, and
+// crates.io otherwise
+pub fn lines(name: &str, info: Option<&PackageInfo>, listing: Option<&Listing>) -> Vec<Line> {
+    let mut lines = vec![Line {
+        text: match info {
+            Some(info) => format!("{} {}", name, info.version),
+            None => name.to_owned(),
+        },
+        link: None,
+        heading: true,
+    }];
+
+    let known = |field: fn(&PackageInfo) -> &Option<String>,
+                 listed: fn(&Listing) -> &Option<String>| {
+        info.and_then(|info| field(info).clone())
+            .or_else(|| listing.and_then(|listing| listed(listing).clone()))
+    };
+
+    let description = known(|info| &info.description, |listing| &listing.description);
+    if let Some(description) = description {
+        lines.extend(wrap(&description).into_iter().map(Line::text));
+    }
+
+    let published = info.map_or(listing.is_some(), |info| info.published_name.is_some());
+    let links = [
+        (
+            "repository",
+            known(|info| &info.repository, |listing| &listing.repository),
+        ),
+        (
+            "homepage",
+            known(|info| &info.homepage, |listing| &listing.homepage),
+        ),
+        (
+            "docs",
+            known(|info| &info.documentation, |listing| &listing.documentation),
+        ),
+        (
+            "crates.io",
+            Some(format!("https://crates.io/crates/{}", name)).filter(|_| published),
+        ),
+    ];
+    for (label, url) in links {
+        if let Some(url) = url {
+            lines.push(Line {
+                text: format!("{}: {}", label, url),
+                link: Some(url),
+                heading: false,
+            });
+        }
+    }
+
+    let info = match info {
+        Some(info) => info,
+        None => return lines,
+    };
+
+    if !info.authors.is_empty() {
+        lines.extend(
+            wrap(&format!("by {}", info.authors.join(", ")))
+                .into_iter()
+                .map(Line::text),
+        );
+    }
+
+    if !info.declared_features.is_empty() {
+        lines.push(Line {
+            text: "features, * where on in this build".to_owned(),
+            link: None,
+            heading: true,
+        });
+        let features = info
+            .declared_features
+            .iter()
+            .map(|feature| {
+                if info.features.contains(feature) {
+                    format!("{}*", feature)
+                } else {
+                    feature.clone()
+                }
+            })
+            .collect::<Vec<_>>();
+        lines.extend(wrap(&features.join(" ")).into_iter().map(Line::text));
+    }
+
+    lines
+}
+
+fn frame(window: Rect, lines: &[Line]) -> Rect {
+    let height = lines.len() as f32 * LINE + 2.0 * MARGIN;
+
+    Rect::from_x_y_w_h(
+        window.right() - MARGIN - WIDTH / 2.0,
+        window.top() - TOP - height / 2.0,
+        WIDTH,
+        height,
+    )
+}
+
+fn line_at(window: Rect, lines: &[Line], point: Point) -> Option<usize> {
+    let frame = frame(window, lines);
+    if !frame.contains(pt2(point.0, point.1)) {
+        return None;
+    }
+
+    let offset = frame.top() - MARGIN - point.1;
+    if offset < 0.0 {
+        return None;
+    }
+
+    Some((offset / LINE) as usize).filter(|&idx| idx < lines.len())
+}
+
+pub fn contains(window: Rect, lines: &[Line], point: Point) -> bool {
+    frame(window, lines).contains(pt2(point.0, point.1))
+}
+
+pub fn link_at(window: Rect, lines: &[Line], point: Point) -> Option<&str> {
+    lines[line_at(window, lines, point)?].link.as_deref()
+}
+
+// A panel down the right side of the window
+pub fn draw_details(draw: &Draw, window: Rect, lines: &[Line]) {
+    let frame = frame(window, lines);
+
+    draw.rect()
+        .xy(frame.xy())
+        .wh(frame.wh())
+        .color(srgba(30u8, 30, 36, 235));
+
+    for (idx, line) in lines.iter().enumerate() {
+        let color = match (&line.link, line.heading) {
+            (Some(_), _) => srgb(LINK_COLOR.0, LINK_COLOR.1, LINK_COLOR.2),
+            (None, true) => srgb(255u8, 255, 255),
+            (None, false) => srgb(190u8, 190, 190),
+        };
+
+        draw.text(&line.text)
+            .left_justify()
+            .no_line_wrap()
+            .color(color)
+            .x_y(frame.x(), frame.top() - MARGIN - LINE * (idx as f32 + 0.5))
+            .w_h(WIDTH - 2.0 * MARGIN, LINE);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::filter::Filter;
+use nannou::prelude::*;
+
+const MARGIN: f32 = 10.0;
+const LINE: f32 = 20.0;
+const WIDTH: f32 = 440.0;
+const FIELDS: &str = "name depth state build_time dependencies category license version";
+
+// The expression being typed, applied as it is typed whenever it parses
+pub struct FilterPanel {
+    text: String,
+    error: Option<String>,
+    // The key that opened the panel arrives as a character too
+    opening: bool,
+}
+
+impl FilterPanel {
+    pub fn new(text: &str) -> Self {
+        FilterPanel {
+            text: text.to_owned(),
+            error: None,
+            opening: true,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    // None for an empty expression, which matches everything
+    pub fn parse(&mut self) -> Option<Filter> {
+        if self.text.trim().is_empty() {
+            self.error = None;
+            return None;
+        }
+
+        match Filter::parse(&self.text) {
+            Ok(filter) => {
+                self.error = None;
+                Some(filter)
+            }
+            Err(e) => {
+                self.error = Some(e);
+                None
+            }
+        }
+    }
+
+    pub fn has_error(&self) -> bool {
+        self.error.is_some()
+    }
+
+    pub fn push(&mut self, c: char) {
+        let opener = std::mem::take(&mut self.opening) && c.eq_ignore_ascii_case(&'q');
+        if c.is_control() || opener {
+            return;
+        }
+
+        self.text.push(c);
+    }
+
+    pub fn pop(&mut self) {
+        self.text.pop();
+    }
+}
+
+// A box in the top right corner with the expression, what it matches or why
+// it doesn't parse, and what it can ask about
+pub fn draw_filter(
+    draw: &Draw,
+    window: Rect,
+    panel: &FilterPanel,
+    matches: Option<usize>,
+    hides: bool,
+) {
+    let height = 3.0 * LINE + 2.0 * MARGIN;
+    let frame = Rect::from_x_y_w_h(
+        window.right() - MARGIN - WIDTH / 2.0,
+        window.top() - MARGIN - height / 2.0,
+        WIDTH,
+        height,
+    );
+
+    draw.rect()
+        .xy(frame.xy())
+        .wh(frame.wh())
+        .color(srgba(30u8, 30, 36, 235));
+
+    let line = |idx: usize, text: &str, color: Srgb<u8>| {
+        draw.text(text)
+            .left_justify()
+            .color(color)
+            .x_y(frame.x(), frame.top() - MARGIN - LINE * (idx as f32 + 0.5))
+            .w_h(WIDTH - 2.0 * MARGIN, LINE);
+    };
+
+    line(0, &format!("filter: {}_", panel.text), WHITE);
+    match (&panel.error, matches) {
+        (Some(error), _) => line(1, error, srgb(230, 120, 100)),
+        (None, Some(matches)) => line(
+            1,
+            &format!(
+                "{} crates match, {} the rest (tab switches)",
+                matches,
+                if hides { "hiding" } else { "dimming" }
+            ),
+            GRAY,
+        ),
+        (None, None) => line(1, "e.g. depth <= 3 and name ~ \"tokio*\"", GRAY),
+    }
+    line(2, FIELDS, GRAY);
+}
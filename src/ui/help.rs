@@ -0,0 +1,48 @@
+use nannou::prelude::*;
+
+const MARGIN: f32 = 16.0;
+const LINE: f32 = 18.0;
+const FONT_SIZE: u32 = 13;
+const KEYS_WIDTH: f32 = 90.0;
+const COLUMN_WIDTH: f32 = 340.0;
+
+// Every key and what it does, in as many columns as it takes to fit the
+// window, over the middle of it
+pub fn draw_help(draw: &Draw, window: Rect, bindings: &[(String, &str)]) {
+    let rows = (((window.h() - 2.0 * MARGIN) / LINE) as usize)
+        .saturating_sub(1)
+        .max(1);
+    let columns = bindings.len().div_ceil(rows).max(1);
+    let rows = bindings.len().div_ceil(columns);
+    let (width, height) = (
+        columns as f32 * COLUMN_WIDTH + MARGIN,
+        rows as f32 * LINE + 2.0 * MARGIN,
+    );
+
+    draw.rect()
+        .x_y(0.0, 0.0)
+        .w_h(width, height)
+        .color(srgba(20u8, 20, 24, 235));
+
+    for (idx, (keys, description)) in bindings.iter().enumerate() {
+        let (column, row) = (idx / rows, idx % rows);
+        let left = -width / 2.0 + MARGIN + column as f32 * COLUMN_WIDTH;
+        let y = height / 2.0 - MARGIN - (row as f32 + 0.5) * LINE;
+
+        draw.text(keys)
+            .left_justify()
+            .font_size(FONT_SIZE)
+            .color(srgb(240u8, 200, 90))
+            .x_y(left + KEYS_WIDTH / 2.0, y)
+            .w_h(KEYS_WIDTH, LINE);
+        draw.text(description)
+            .left_justify()
+            .font_size(FONT_SIZE)
+            .color(WHITE)
+            .x_y(
+                left + KEYS_WIDTH + (COLUMN_WIDTH - KEYS_WIDTH - MARGIN) / 2.0,
+                y,
+            )
+            .w_h(COLUMN_WIDTH - KEYS_WIDTH - MARGIN, LINE);
+    }
+}
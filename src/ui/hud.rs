@@ -0,0 +1,198 @@
+use crate::events::{BuildEvent, BuildPhase};
+use crate::parse_cargo_tree_output::{TreeNode, WORKSPACE_ROOT};
+use crate::timings::minutes;
+use crate::unit_graph;
+use nannou::prelude::*;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+const MARGIN: f32 = 10.0;
+const WIDTH: f32 = 320.0;
+const LINE_HEIGHT: f32 = 18.0;
+const FONT_SIZE: u32 = 12;
+
+// The build in numbers, kept up to date from the monitor's events rather than
+// worked out from the drawn tree
+pub struct Hud {
+    command: String,
+    crates: HashSet<String>,
+    completed: HashSet<String>,
+    active: HashSet<String>,
+    failed: HashSet<String>,
+    // Up to date, so not counted as work unless they get built after all
+    fresh: HashSet<String>,
+    phase: BuildPhase,
+    // How long the last run took, the best guess at how long this one will
+    previous_secs: Option<f32>,
+    // The crates of each, when building several workspace members
+    members: Vec<(String, HashSet<String>)>,
+}
+
+// Build scripts count as part of their package
+fn crate_names(tree: &Arc<TreeNode>, names: &mut HashSet<String>) {
+    names.insert(unit_graph::owner(&tree.name).to_owned());
+    for child in tree.children.iter() {
+        crate_names(child, names);
+    }
+}
+
+impl Hud {
+    pub fn new(command: String, root: &Arc<TreeNode>, previous_secs: Option<f32>) -> Self {
+        let mut crates = HashSet::new();
+        crate_names(root, &mut crates);
+
+        let members = if root.is_workspace() {
+            // Which isn't a crate of its own
+            crates.remove(WORKSPACE_ROOT);
+            root.children
+                .iter()
+                .map(|member| {
+                    let mut crates = HashSet::new();
+                    crate_names(member, &mut crates);
+                    (member.name.clone(), crates)
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Hud {
+            command,
+            crates,
+            completed: HashSet::new(),
+            active: HashSet::new(),
+            failed: HashSet::new(),
+            fresh: HashSet::new(),
+            phase: BuildPhase::Waiting,
+            previous_secs,
+            members,
+        }
+    }
+
+    // For the same command run again from the start
+    pub fn restart(&mut self) {
+        self.completed.clear();
+        self.active.clear();
+        self.failed.clear();
+        self.fresh.clear();
+        self.phase = BuildPhase::Waiting;
+    }
+
+    // Cargo's status lines name things that aren't crates of the tree, those
+    // don't count
+    pub fn observe(&mut self, event: &BuildEvent) {
+        match event {
+            BuildEvent::Phase(phase) => self.phase = *phase,
+            BuildEvent::Started(name) if self.crates.contains(name) => {
+                self.fresh.remove(name);
+                self.active.insert(name.clone());
+            }
+            BuildEvent::Completed(name) if self.crates.contains(name) => {
+                self.fresh.remove(name);
+                self.active.remove(name);
+                self.completed.insert(name.clone());
+            }
+            BuildEvent::Failed(name) if self.crates.contains(name) => {
+                self.fresh.remove(name);
+                self.active.remove(name);
+                self.failed.insert(name.clone());
+            }
+            BuildEvent::Fresh(name)
+                if self.crates.contains(name)
+                    && !self.active.contains(name)
+                    && !self.completed.contains(name) =>
+            {
+                self.fresh.insert(name.clone());
+            }
+            _ => {}
+        }
+    }
+
+    fn to_build(&self) -> usize {
+        self.crates.len() - self.fresh.len()
+    }
+
+    // Going by the last run when there was one, otherwise by how fast crates
+    // have been getting done so far
+    fn remaining_secs(&self, elapsed: f32) -> Option<f32> {
+        if self.phase != BuildPhase::Building && self.phase != BuildPhase::Downloading {
+            return None;
+        }
+
+        match self.previous_secs {
+            Some(previous) if previous > elapsed => Some(previous - elapsed),
+            _ if !self.completed.is_empty() => {
+                let left = self
+                    .to_build()
+                    .saturating_sub(self.completed.len() + self.failed.len());
+                Some(elapsed / self.completed.len() as f32 * left as f32)
+            }
+            _ => None,
+        }
+    }
+
+    fn lines(&self, elapsed: f32) -> Vec<String> {
+        let mut counts = format!(
+            "{} crates: {} built, {} building",
+            self.to_build(),
+            self.completed.len(),
+            self.active.len()
+        );
+        if !self.fresh.is_empty() {
+            counts.push_str(&format!(", {} up to date", self.fresh.len()));
+        }
+        if !self.failed.is_empty() {
+            counts.push_str(&format!(", {} failed", self.failed.len()));
+        }
+
+        let timing = match self.remaining_secs(elapsed) {
+            Some(remaining) => format!(
+                "{} elapsed, about {} left",
+                minutes(elapsed),
+                minutes(remaining)
+            ),
+            None => format!("{} elapsed", minutes(elapsed)),
+        };
+
+        let mut lines = vec![self.command.clone(), counts, timing];
+        for (name, crates) in self.members.iter() {
+            let count = |of: &HashSet<String>| crates.intersection(of).count();
+            let mut line = format!(
+                "{}: {}/{} built",
+                name,
+                count(&self.completed),
+                crates.len() - count(&self.fresh)
+            );
+            if count(&self.active) > 0 {
+                line.push_str(&format!(", {} building", count(&self.active)));
+            }
+            lines.push(line);
+        }
+        lines
+    }
+}
+
+// Along the top left, under the breadcrumbs
+pub fn draw_hud(draw: &Draw, window: Rect, hud: &Hud, elapsed: f32) {
+    let lines = hud.lines(elapsed);
+    let height = lines.len() as f32 * LINE_HEIGHT + MARGIN;
+    let left = window.left() + MARGIN;
+    let top = window.top() - MARGIN - 40.0;
+
+    draw.rect()
+        .x_y(left + WIDTH / 2.0, top - height / 2.0)
+        .w_h(WIDTH, height)
+        .color(srgba(20u8, 20, 24, 200));
+
+    for (idx, line) in lines.iter().enumerate() {
+        draw.text(line)
+            .left_justify()
+            .font_size(FONT_SIZE)
+            .color(WHITE)
+            .x_y(
+                left + WIDTH / 2.0,
+                top - MARGIN / 2.0 - (idx as f32 + 0.5) * LINE_HEIGHT,
+            )
+            .w_h(WIDTH - 2.0 * MARGIN, LINE_HEIGHT);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::category::{Category, ALL_CATEGORIES};
+use crate::license::{LicenseSummary, ALL_FAMILIES};
+use nannou::prelude::*;
+use std::collections::HashSet;
+
+const MARGIN: f32 = 10.0;
+const ROW_HEIGHT: f32 = 20.0;
+const SWATCH: f32 = 12.0;
+const WIDTH: f32 = 170.0;
+
+// One row per category along the bottom left, numbered by the key that
+// toggles its filter
+pub fn draw_legend(draw: &Draw, window: Rect, filter: &HashSet<Category>) {
+    let height = ALL_CATEGORIES.len() as f32 * ROW_HEIGHT + MARGIN;
+
+    draw.rect()
+        .x_y(
+            window.left() + MARGIN + WIDTH / 2.0,
+            window.bottom() + MARGIN + height / 2.0,
+        )
+        .w_h(WIDTH, height)
+        .color(srgba(20u8, 20, 24, 200));
+
+    for (idx, category) in ALL_CATEGORIES.iter().enumerate() {
+        let y = window.bottom() + height - (idx as f32 + 0.5) * ROW_HEIGHT;
+        let x = window.left() + 2.0 * MARGIN;
+        let shown = filter.is_empty() || filter.contains(category);
+        let (r, g, b) = category.color();
+
+        draw.rect()
+            .x_y(x + SWATCH / 2.0, y)
+            .w_h(SWATCH, SWATCH)
+            .color(srgba(r, g, b, if shown { 255 } else { 60 }));
+
+        let label = format!("{} {}", idx + 1, category.label());
+        draw.text(&label)
+            .left_justify()
+            .color(if shown { WHITE } else { GRAY })
+            .x_y(x + SWATCH + MARGIN + 60.0, y)
+            .w_h(120.0, ROW_HEIGHT);
+    }
+}
+
+// One row per license family, with how many crates are under it
+pub fn draw_license_legend(draw: &Draw, window: Rect, licenses: &LicenseSummary) {
+    let height = ALL_FAMILIES.len() as f32 * ROW_HEIGHT + MARGIN;
+
+    draw.rect()
+        .x_y(
+            window.left() + MARGIN + WIDTH / 2.0,
+            window.bottom() + MARGIN + height / 2.0,
+        )
+        .w_h(WIDTH, height)
+        .color(srgba(20u8, 20, 24, 200));
+
+    for (idx, family) in ALL_FAMILIES.iter().enumerate() {
+        let y = window.bottom() + height - (idx as f32 + 0.5) * ROW_HEIGHT;
+        let x = window.left() + 2.0 * MARGIN;
+        let (r, g, b) = family.color();
+
+        draw.rect()
+            .x_y(x + SWATCH / 2.0, y)
+            .w_h(SWATCH, SWATCH)
+            .color(srgb(r, g, b));
+
+        let label = format!("{} {}", family.label(), licenses.count(*family));
+        draw.text(&label)
+            .left_justify()
+            .color(WHITE)
+            .x_y(x + SWATCH + MARGIN + 60.0, y)
+            .w_h(120.0, ROW_HEIGHT);
+    }
+}
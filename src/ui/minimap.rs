@@ -0,0 +1,84 @@
+use crate::batch::Batch;
+use crate::camera::Camera;
+use crate::drawing::{DrawCrate, Point};
+use crate::theme::Theme;
+use nannou::prelude::*;
+
+const MARGIN: f32 = 10.0;
+const WIDTH: f32 = 200.0;
+const HEIGHT: f32 = 150.0;
+
+fn frame(window: Rect) -> Rect {
+    Rect::from_x_y_w_h(
+        window.right() - MARGIN - WIDTH / 2.0,
+        window.top() - MARGIN - HEIGHT / 2.0,
+        WIDTH,
+        HEIGHT,
+    )
+}
+
+// Fits the whole scene into the frame, the same way the main view fits it
+// into the window
+fn overview(window: Rect, crates: &[DrawCrate]) -> Camera {
+    let frame = frame(window);
+    let mut camera = Camera::new();
+
+    camera.fit(crates, (frame.w(), frame.h()));
+    camera.pan((frame.x(), frame.y()));
+    camera
+}
+
+pub fn contains(window: Rect, point: Point) -> bool {
+    frame(window).contains(pt2(point.0, point.1))
+}
+
+// The point in the world under the cursor, for jumping the main view there
+pub fn world_at(window: Rect, crates: &[DrawCrate], point: Point) -> Point {
+    overview(window, crates).to_world(point)
+}
+
+// Every crate as a dot, with the part the window shows outlined
+pub fn draw_minimap(
+    draw: &Draw,
+    window: Rect,
+    crates: &[DrawCrate],
+    camera: Camera,
+    theme: &Theme,
+) {
+    let frame = frame(window);
+    let overview = overview(window, crates);
+
+    draw.rect()
+        .xy(frame.xy())
+        .wh(frame.wh())
+        .color(srgba(20u8, 20, 24, 200));
+
+    let mut dots = Batch::default();
+    for draw_crate in crates {
+        let center = overview.to_screen(draw_crate.center);
+        let half = f32::max(draw_crate.radius * overview.scale, 1.0);
+        let color = draw_crate.color;
+
+        dots.square(center, half, srgba(color.0, color.1, color.2, 200));
+    }
+    dots.draw(draw);
+
+    // The window's corners, through the main camera back into the world and
+    // from there into the minimap
+    let corner = |x: f32, y: f32| overview.to_screen(camera.to_world((x, y)));
+    let (min, max) = (
+        corner(window.left(), window.bottom()),
+        corner(window.right(), window.top()),
+    );
+    let (left, bottom) = (min.0.max(frame.left()), min.1.max(frame.bottom()));
+    let (right, top) = (max.0.min(frame.right()), max.1.min(frame.top()));
+
+    if left < right && bottom < top {
+        draw.rect()
+            .x_y((left + right) / 2.0, (bottom + top) / 2.0)
+            .w_h(right - left, top - bottom)
+            .no_fill()
+            .stroke(rgb(theme.text.0, theme.text.1, theme.text.2))
+            .stroke_weight(1.0);
+    }
+}
@@ -0,0 +1,17 @@
+pub mod breadcrumbs;
+pub mod breakdown;
+pub mod details;
+pub mod filter;
+pub mod help;
+pub mod hud;
+pub mod legend;
+pub mod minimap;
+pub mod note;
+pub mod parallelism;
+pub mod revisions;
+pub mod scrubber;
+pub mod search;
+pub mod tooltip;
+pub mod utilization;
+pub mod warnings;
+pub mod wizard;
@@ -0,0 +1,83 @@
+use crate::drawing::Point;
+use nannou::prelude::*;
+
+const MARGIN: f32 = 10.0;
+const HEIGHT: f32 = 30.0;
+const WIDTH: f32 = 440.0;
+const NOTE_WIDTH: f32 = 200.0;
+const NOTE_HEIGHT: f32 = 20.0;
+
+// The note on one crate while it is being written
+pub struct NoteEditor {
+    name: String,
+    text: String,
+    // The key that opened the editor arrives as a character too
+    opening: bool,
+}
+
+impl NoteEditor {
+    pub fn new(name: &str, text: &str) -> Self {
+        NoteEditor {
+            name: name.to_owned(),
+            text: text.to_owned(),
+            opening: true,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn push(&mut self, c: char) {
+        let opener = std::mem::take(&mut self.opening) && c.eq_ignore_ascii_case(&'y');
+        if c.is_control() || opener {
+            return;
+        }
+
+        self.text.push(c);
+    }
+
+    pub fn pop(&mut self) {
+        self.text.pop();
+    }
+}
+
+// A single line box along the bottom of the window, like the search
+pub fn draw_note_editor(draw: &Draw, window: Rect, editor: &NoteEditor) {
+    let y = window.bottom() + MARGIN + HEIGHT / 2.0;
+
+    draw.rect()
+        .x_y(0.0, y)
+        .w_h(WIDTH, HEIGHT)
+        .color(srgba(30u8, 30, 36, 235));
+
+    let text = format!("note on {}: {}_", editor.name, editor.text);
+    draw.text(&text)
+        .left_justify()
+        .color(WHITE)
+        .x_y(0.0, y)
+        .w_h(WIDTH - 2.0 * MARGIN, HEIGHT);
+}
+
+// Each note on a tag to the right of its crate, with crates in screen
+// coordinates
+pub fn draw_notes(draw: &Draw, notes: &[(Point, f32, &str)]) {
+    for &((x, y), radius, text) in notes {
+        let left = x + radius + MARGIN / 2.0;
+        let width = (text.chars().count() as f32 * 7.0 + MARGIN).min(NOTE_WIDTH);
+
+        draw.rect()
+            .x_y(left + width / 2.0, y)
+            .w_h(width, NOTE_HEIGHT)
+            .color(srgba(250u8, 230, 140, 220));
+        draw.text(text)
+            .left_justify()
+            .color(srgb(40u8, 36, 20))
+            .x_y(left + width / 2.0, y)
+            .w_h(width - MARGIN / 2.0, NOTE_HEIGHT);
+    }
+}
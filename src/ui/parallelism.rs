@@ -0,0 +1,97 @@
+use crate::parallelism::Comparison;
+use nannou::prelude::*;
+
+const MARGIN: f32 = 10.0;
+const WIDTH: f32 = 420.0;
+const HEIGHT: f32 = 220.0;
+const PLOT_HEIGHT: f32 = 130.0;
+const IDEAL_COLOR: (u8, u8, u8) = (0x98, 0xfb, 0x98);
+const OBSERVED_COLOR: (u8, u8, u8) = (0x4e, 0x9a, 0xf1);
+
+fn verdict(comparison: &Comparison) -> String {
+    let ideal_peak = comparison.ideal.iter().copied().max().unwrap_or(0);
+
+    if ideal_peak > comparison.jobs {
+        format!(
+            "more cores would help, up to {} at once",
+            ideal_peak.min(comparison.widest)
+        )
+    } else {
+        "limited by dependencies, more cores won't help".to_owned()
+    }
+}
+
+// Two step plots sharing one time axis, with the job limit as a line across
+pub fn draw_parallelism(draw: &Draw, window: Rect, comparison: &Comparison) {
+    let left = window.right() - MARGIN - WIDTH;
+    let bottom = window.bottom() + MARGIN;
+    let plot_left = left + MARGIN;
+    let plot_bottom = bottom + MARGIN;
+    let plot_width = WIDTH - 2.0 * MARGIN;
+
+    draw.rect()
+        .x_y(left + WIDTH / 2.0, bottom + HEIGHT / 2.0)
+        .w_h(WIDTH, HEIGHT)
+        .color(srgba(20u8, 20, 24, 220));
+
+    let peak = comparison
+        .ideal
+        .iter()
+        .chain(comparison.observed.iter())
+        .copied()
+        .chain(std::iter::once(comparison.jobs))
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    let to_y = |count: usize| plot_bottom + PLOT_HEIGHT * count as f32 / peak as f32;
+
+    for (samples, (r, g, b)) in [
+        (&comparison.ideal, IDEAL_COLOR),
+        (&comparison.observed, OBSERVED_COLOR),
+    ] {
+        let step = plot_width / samples.len().max(1) as f32;
+        let points = samples
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, &count)| {
+                let x = plot_left + idx as f32 * step;
+                vec![pt2(x, to_y(count)), pt2(x + step, to_y(count))]
+            })
+            .collect::<Vec<_>>();
+
+        draw.polyline()
+            .weight(2.0)
+            .points(points)
+            .color(rgb(r, g, b));
+    }
+
+    draw.line()
+        .start(pt2(plot_left, to_y(comparison.jobs)))
+        .end(pt2(plot_left + plot_width, to_y(comparison.jobs)))
+        .weight(1.0)
+        .color(srgba(255u8, 255, 255, 100));
+
+    let lines = [
+        format!(
+            "unlimited cores: {:.1}s, observed: {:.1}s with -j{}",
+            comparison.ideal_secs, comparison.observed_secs, comparison.jobs
+        ),
+        format!(
+            "widest independent set: {} crates, {}",
+            comparison.widest,
+            verdict(comparison)
+        ),
+    ];
+
+    for (idx, line) in lines.iter().enumerate() {
+        draw.text(line)
+            .left_justify()
+            .font_size(12)
+            .color(WHITE)
+            .x_y(
+                left + WIDTH / 2.0,
+                bottom + HEIGHT - 20.0 - idx as f32 * 18.0,
+            )
+            .w_h(plot_width, 18.0);
+    }
+}
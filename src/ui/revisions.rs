@@ -0,0 +1,118 @@
+use crate::drawing::Point;
+use crate::project::LockfileRevision;
+use nannou::prelude::*;
+
+const MARGIN: f32 = 10.0;
+const HEIGHT: f32 = 40.0;
+
+fn track(window: Rect) -> Rect {
+    Rect::from_x_y_w_h(
+        0.0,
+        window.bottom() + MARGIN + HEIGHT / 2.0,
+        window.w() - 2.0 * MARGIN,
+        HEIGHT,
+    )
+}
+
+pub fn contains(window: Rect, point: Point) -> bool {
+    track(window).contains(pt2(point.0, point.1))
+}
+
+// The revision under the cursor, out of `count`
+pub fn revision_at(window: Rect, point: Point, count: usize) -> usize {
+    let track = track(window);
+    let fraction = ((point.0 - track.left()) / track.w()).clamp(0.0, 1.0);
+
+    ((fraction * count as f32) as usize).min(count.saturating_sub(1))
+}
+
+// Year, month and day of a Unix time, in UTC
+fn date(secs: u64) -> (i64, u32, u32) {
+    // Counted from 0000-03-01 so the leap day is the last of the year
+    let days = (secs / 86400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+
+    (era * 400 + year_of_era + (month <= 2) as i64, month, day)
+}
+
+// A bar for every revision as tall as its tree is large, reddish where
+// crates came in and greenish where they went
+pub fn draw_revisions(
+    draw: &Draw,
+    window: Rect,
+    revisions: &[LockfileRevision],
+    current: usize,
+    playing: bool,
+) {
+    let track = track(window);
+    let most = revisions.iter().map(|revision| revision.crates).max();
+    let most = most.unwrap_or(0).max(1) as f32;
+    let width = track.w() / revisions.len().max(1) as f32;
+
+    draw.rect()
+        .xy(track.xy())
+        .wh(track.wh())
+        .color(srgba(30u8, 30, 36, 220));
+
+    for (idx, revision) in revisions.iter().enumerate() {
+        let previous = idx.checked_sub(1).map(|idx| revisions[idx].crates);
+        let (r, g, b) = match previous {
+            Some(previous) if revision.crates > previous => (220u8, 110, 90),
+            Some(previous) if revision.crates < previous => (110, 200, 120),
+            _ => (120, 160, 220),
+        };
+        // Dimmer for what is still to come
+        let alpha = if idx <= current { 255u8 } else { 100 };
+        let height = (HEIGHT - 4.0) * revision.crates as f32 / most;
+
+        draw.rect()
+            .x_y(
+                track.left() + width * (idx as f32 + 0.5),
+                track.bottom() + 2.0 + height / 2.0,
+            )
+            .w_h((width - 1.0).max(1.0), height.max(1.0))
+            .color(srgba(r, g, b, alpha));
+    }
+
+    draw.rect()
+        .x_y(track.left() + width * (current as f32 + 0.5), track.y())
+        .w_h(2.0, HEIGHT)
+        .color(WHITE);
+
+    let revision = match revisions.get(current) {
+        Some(revision) => revision,
+        None => return,
+    };
+    let (year, month, day) = date(revision.time);
+    let change = match current.checked_sub(1).map(|idx| revisions[idx].crates) {
+        Some(previous) if previous != revision.crates => {
+            format!(" ({:+})", revision.crates as i64 - previous as i64)
+        }
+        _ => String::new(),
+    };
+    let label = format!(
+        "{} {}-{:02}-{:02} {}, {} crates{}, {}/{}{}",
+        revision.commit,
+        year,
+        month,
+        day,
+        revision.subject,
+        revision.crates,
+        change,
+        current + 1,
+        revisions.len(),
+        if playing { "" } else { ", paused" },
+    );
+    draw.text(&label)
+        .right_justify()
+        .color(WHITE)
+        .x_y(track.x(), track.y() + HEIGHT)
+        .w_h(track.w(), HEIGHT);
+}
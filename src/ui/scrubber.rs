@@ -0,0 +1,65 @@
+use crate::drawing::Point;
+use nannou::prelude::*;
+
+const MARGIN: f32 = 10.0;
+const HEIGHT: f32 = 24.0;
+// Dropping the handle this close to the end goes back to following the build
+const LIVE_SNAP: f32 = 0.99;
+
+fn track(window: Rect) -> Rect {
+    Rect::from_x_y_w_h(
+        0.0,
+        window.bottom() + MARGIN + HEIGHT / 2.0,
+        window.w() - 2.0 * MARGIN,
+        HEIGHT,
+    )
+}
+
+pub fn contains(window: Rect, point: Point) -> bool {
+    track(window).contains(pt2(point.0, point.1))
+}
+
+// The moment under the cursor, or None for the live end of the track
+pub fn position_at(window: Rect, point: Point, total_secs: f32) -> Option<f32> {
+    let track = track(window);
+    let fraction = ((point.0 - track.left()) / track.w()).clamp(0.0, 1.0);
+
+    if fraction >= LIVE_SNAP {
+        None
+    } else {
+        Some(fraction * total_secs)
+    }
+}
+
+pub fn draw_scrubber(draw: &Draw, window: Rect, position: Option<f32>, total_secs: f32) {
+    let track = track(window);
+    let fraction = match position {
+        Some(secs) if total_secs > 0.0 => secs / total_secs,
+        _ => 1.0,
+    };
+
+    draw.rect()
+        .xy(track.xy())
+        .wh(track.wh())
+        .color(srgba(30u8, 30, 36, 220));
+
+    draw.rect()
+        .x_y(track.left() + track.w() * fraction / 2.0, track.y())
+        .w_h(track.w() * fraction, 4.0)
+        .color(srgba(120u8, 160, 220, 255));
+
+    draw.rect()
+        .x_y(track.left() + track.w() * fraction, track.y())
+        .w_h(4.0, HEIGHT)
+        .color(WHITE);
+
+    let label = match position {
+        Some(secs) => format!("{:.1}s / {:.1}s", secs, total_secs),
+        None => format!("live, {:.1}s", total_secs),
+    };
+    draw.text(&label)
+        .right_justify()
+        .color(WHITE)
+        .x_y(track.x(), track.y() + HEIGHT)
+        .w_h(track.w(), HEIGHT);
+}
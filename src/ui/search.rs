@@ -0,0 +1,57 @@
+use nannou::prelude::*;
+
+const MARGIN: f32 = 10.0;
+const HEIGHT: f32 = 30.0;
+const WIDTH: f32 = 360.0;
+
+#[derive(Default)]
+pub struct Search {
+    query: String,
+}
+
+impl Search {
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn push(&mut self, c: char) {
+        // The '/' that opened the search arrives as a character too
+        if c.is_control() || (c == '/' && self.query.is_empty()) {
+            return;
+        }
+
+        self.query.push(c);
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+    }
+}
+
+// A single line box along the bottom of the window, with the match count
+pub fn draw_search(draw: &Draw, window: Rect, search: &Search, matches: usize) {
+    let y = window.bottom() + MARGIN + HEIGHT / 2.0;
+
+    draw.rect()
+        .x_y(0.0, y)
+        .w_h(WIDTH, HEIGHT)
+        .color(srgba(30u8, 30, 36, 235));
+
+    let text = format!("/{}_", search.query());
+    draw.text(&text)
+        .left_justify()
+        .color(WHITE)
+        .x_y(0.0, y)
+        .w_h(WIDTH - 2.0 * MARGIN, HEIGHT);
+
+    let count = if search.query().is_empty() {
+        String::new()
+    } else {
+        format!("{} matches", matches)
+    };
+    draw.text(&count)
+        .right_justify()
+        .color(GRAY)
+        .x_y(0.0, y)
+        .w_h(WIDTH - 2.0 * MARGIN, HEIGHT);
+}
@@ -0,0 +1,135 @@
+use crate::audit::Advisory;
+use crate::drawing::{line_height, measure_lines, CrateState, DrawCrate, Point};
+use crate::timings::Timings;
+use crate::units::Units;
+use nannou::prelude::*;
+
+const FONT_SIZE: u32 = 13;
+const PADDING: f32 = 8.0;
+// Keeps the panel from sitting right under the cursor
+const OFFSET: f32 = 16.0;
+
+// What was looked up about the crate beyond the build
+pub struct Findings<'a> {
+    pub advisories: &'a [Advisory],
+    // A newer release of the crate, when there is one
+    pub latest: Option<&'a str>,
+}
+
+fn tooltip_lines(
+    draw_crate: &DrawCrate,
+    timings: &Timings,
+    units: &Units,
+    findings: &Findings,
+) -> Vec<String> {
+    let tree = &draw_crate.tree;
+    // The label may be an alias, so the real name goes up top
+    let mut lines = vec![match draw_crate.info {
+        Some(ref info) => format!("{} {}", tree.name, info.version),
+        None => tree.name.clone(),
+    }];
+
+    lines.push(format!(
+        "{} direct, {} transitive dependencies",
+        tree.children.len(),
+        tree.transitive_count()
+    ));
+
+    if let Some(license) = draw_crate
+        .info
+        .as_ref()
+        .and_then(|info| info.license.as_ref())
+    {
+        lines.push(format!("license: {}", license));
+    }
+
+    if let Some(latest) = findings.latest {
+        lines.push(format!("{} is out", latest));
+    }
+
+    for advisory in findings.advisories {
+        lines.push(format!(
+            "{} in {} ({}): {}",
+            advisory.id,
+            advisory.version,
+            advisory.severity.label(),
+            advisory.title
+        ));
+        if !advisory.patched.is_empty() {
+            lines.push(format!("  fixed in {}", advisory.patched.join(", ")));
+        }
+    }
+
+    let duration = match draw_crate.state {
+        CrateState::Active => timings.running_for(&tree.name),
+        _ => timings.durations.get(&tree.name).copied(),
+    };
+
+    lines.push(match duration {
+        Some(duration) => format!(
+            "{} ({:.1}s)",
+            draw_crate.state.label(),
+            duration.as_secs_f32()
+        ),
+        None => draw_crate.state.label().to_owned(),
+    });
+
+    let now = timings.elapsed().as_secs_f32();
+    for (kind, timing) in units.of(&tree.name).into_iter().flatten() {
+        lines.push(format!(
+            "  {}: {} ({:.1}s)",
+            kind.label(),
+            if timing.finished.is_some() {
+                "done"
+            } else {
+                "building"
+            },
+            timing.duration(now)
+        ));
+    }
+
+    lines
+}
+
+// Placed below and to the right of the cursor, flipped when that would run
+// off the window
+pub fn draw_tooltip(
+    draw: &Draw,
+    window: Rect,
+    cursor: Point,
+    draw_crate: &DrawCrate,
+    timings: &Timings,
+    units: &Units,
+    findings: &Findings,
+) {
+    let lines = tooltip_lines(draw_crate, timings, units, findings);
+    let (text_w, text_h) = measure_lines(&lines, FONT_SIZE);
+    let (w, h) = (text_w + 2.0 * PADDING, text_h + 2.0 * PADDING);
+
+    let x = if cursor.0 + OFFSET + w > window.right() {
+        cursor.0 - OFFSET - w / 2.0
+    } else {
+        cursor.0 + OFFSET + w / 2.0
+    };
+    let y = if cursor.1 - OFFSET - h < window.bottom() {
+        cursor.1 + OFFSET + h / 2.0
+    } else {
+        cursor.1 - OFFSET - h / 2.0
+    };
+
+    draw.rect()
+        .x_y(x, y)
+        .w_h(w, h)
+        .color(srgba(20u8, 20, 24, 235));
+
+    for (idx, line) in lines.iter().enumerate() {
+        let line_y = y + h / 2.0 - PADDING - (idx as f32 + 0.5) * line_height(FONT_SIZE);
+
+        draw.text(line)
+            .left_justify()
+            .font_size(FONT_SIZE)
+            .color(WHITE)
+            .x_y(x, line_y)
+            .w_h(text_w, line_height(FONT_SIZE));
+    }
+}
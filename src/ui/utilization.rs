@@ -0,0 +1,87 @@
+use crate::utilization::Utilization;
+use nannou::prelude::*;
+
+const MARGIN: f32 = 10.0;
+const WIDTH: f32 = 420.0;
+const HEIGHT: f32 = 200.0;
+const PLOT_HEIGHT: f32 = 100.0;
+// Serial stretches named under the chart
+const LISTED: usize = 3;
+
+// Slots in use as bars over time, red where only one crate was compiling
+pub fn draw_utilization(
+    draw: &Draw,
+    window: Rect,
+    utilization: &Utilization,
+    jobs: usize,
+    total_secs: f32,
+) {
+    let left = window.left() + MARGIN;
+    let top = window.top() - MARGIN - 40.0;
+    let plot_left = left + MARGIN;
+    let plot_width = WIDTH - 2.0 * MARGIN;
+    let plot_bottom = top - MARGIN - 20.0 - PLOT_HEIGHT;
+    let samples = utilization.samples();
+    let serial = utilization.serial_stretches();
+
+    draw.rect()
+        .x_y(left + WIDTH / 2.0, top - HEIGHT / 2.0)
+        .w_h(WIDTH, HEIGHT)
+        .color(srgba(20u8, 20, 24, 220));
+
+    draw.text(&format!("job slots in use (-j{})", jobs))
+        .left_justify()
+        .font_size(12)
+        .color(WHITE)
+        .x_y(left + WIDTH / 2.0, top - MARGIN - 8.0)
+        .w_h(plot_width, 18.0);
+
+    let peak = samples
+        .iter()
+        .map(|sample| sample.jobs)
+        .chain(std::iter::once(jobs))
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    let to_x = |at: f32| plot_left + plot_width * (at / total_secs.max(0.01)).min(1.0);
+
+    for (sample, next) in samples.iter().zip(samples.iter().skip(1)) {
+        let (x1, x2) = (to_x(sample.at), to_x(next.at));
+        let height = PLOT_HEIGHT * sample.jobs as f32 / peak as f32;
+        let in_serial = serial
+            .iter()
+            .any(|stretch| sample.at >= stretch.start && sample.at < stretch.end);
+
+        draw.rect()
+            .x_y((x1 + x2) / 2.0, plot_bottom + height / 2.0)
+            .w_h(x2 - x1, height)
+            .color(if in_serial {
+                rgb(0xffu8, 0x45, 0x45)
+            } else {
+                rgb(0x4eu8, 0x9a, 0xf1)
+            });
+    }
+
+    let jobs_y = plot_bottom + PLOT_HEIGHT * jobs as f32 / peak as f32;
+    draw.line()
+        .start(pt2(plot_left, jobs_y))
+        .end(pt2(plot_left + plot_width, jobs_y))
+        .weight(1.0)
+        .color(srgba(255u8, 255, 255, 100));
+
+    for (idx, stretch) in serial.iter().take(LISTED).enumerate() {
+        let line = format!(
+            "serial {:.1}s at {:.0}s: {}",
+            stretch.end - stretch.start,
+            stretch.start,
+            stretch.name
+        );
+
+        draw.text(&line)
+            .left_justify()
+            .font_size(12)
+            .color(rgb(0xffu8, 0x90, 0x90))
+            .x_y(left + WIDTH / 2.0, plot_bottom - 14.0 - idx as f32 * 16.0)
+            .w_h(plot_width, 16.0);
+    }
+}
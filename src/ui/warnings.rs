@@ -0,0 +1,60 @@
+use nannou::prelude::*;
+use std::collections::HashMap;
+
+const MARGIN: f32 = 10.0;
+const ROW_HEIGHT: f32 = 18.0;
+const WIDTH: f32 = 300.0;
+const LISTED: usize = 10;
+
+// The crates whose warnings grew the most since their last build
+pub fn draw_leaderboard(
+    draw: &Draw,
+    window: Rect,
+    deltas: &HashMap<String, i64>,
+    current: &HashMap<String, usize>,
+) {
+    let mut grown = deltas
+        .iter()
+        .filter(|(_, delta)| **delta > 0)
+        .collect::<Vec<_>>();
+    grown.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    grown.truncate(LISTED);
+
+    let mut lines = vec!["warnings grown since last build".to_owned()];
+    if grown.is_empty() {
+        lines.push("none, nice".to_owned());
+    }
+    lines.extend(grown.into_iter().map(|(name, delta)| {
+        format!(
+            "{}  +{} (now {})",
+            name,
+            delta,
+            current.get(name).copied().unwrap_or(0)
+        )
+    }));
+
+    let height = lines.len() as f32 * ROW_HEIGHT + 2.0 * MARGIN;
+    let left = window.right() - MARGIN - WIDTH;
+    let bottom = window.bottom() + MARGIN;
+
+    draw.rect()
+        .x_y(left + WIDTH / 2.0, bottom + height / 2.0)
+        .w_h(WIDTH, height)
+        .color(srgba(20u8, 20, 24, 220));
+
+    for (idx, line) in lines.iter().enumerate() {
+        draw.text(line)
+            .left_justify()
+            .font_size(12)
+            .color(if idx == 0 {
+                WHITE
+            } else {
+                rgb(0xffu8, 0x90, 0x90)
+            })
+            .x_y(
+                left + WIDTH / 2.0,
+                bottom + height - MARGIN - (idx as f32 + 0.5) * ROW_HEIGHT,
+            )
+            .w_h(WIDTH - 2.0 * MARGIN, ROW_HEIGHT);
+    }
+}
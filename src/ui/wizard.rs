@@ -0,0 +1,146 @@
+use crate::config::Config;
+use crate::layout::LAYOUT_NAMES;
+use crate::theme::THEME_NAMES;
+use nannou::prelude::*;
+
+const WIDTH: f32 = 520.0;
+const HEIGHT: f32 = 260.0;
+const ROW_HEIGHT: f32 = 26.0;
+
+struct Step {
+    question: &'static str,
+    options: &'static [&'static str],
+    // Which option the current config corresponds to, and how to apply one
+    current: fn(&Config) -> usize,
+    apply: fn(&mut Config, usize),
+}
+
+const YES_NO: [&str; 2] = ["yes", "no"];
+
+fn yes_no(value: bool) -> usize {
+    if value {
+        0
+    } else {
+        1
+    }
+}
+
+const STEPS: [Step; 5] = [
+    Step {
+        question: "Which layout should the tree start in?",
+        options: &LAYOUT_NAMES,
+        current: |config| crate::layout::index_of(&config.layout),
+        apply: |config, choice| config.layout = LAYOUT_NAMES[choice].to_owned(),
+    },
+    Step {
+        question: "Which theme?",
+        options: &THEME_NAMES,
+        current: |config| {
+            THEME_NAMES
+                .iter()
+                .position(|name| *name == config.theme)
+                .unwrap_or(0)
+        },
+        apply: |config, choice| config.theme = THEME_NAMES[choice].to_owned(),
+    },
+    Step {
+        question: "Include dev-dependencies in the tree? (next run)",
+        options: &YES_NO,
+        current: |config| yes_no(config.dev_dependencies),
+        apply: |config, choice| config.dev_dependencies = choice == 0,
+    },
+    Step {
+        question: "Look up crate details like categories and licenses? (next run)",
+        options: &YES_NO,
+        current: |config| yes_no(config.enrichment),
+        apply: |config, choice| config.enrichment = choice == 0,
+    },
+    Step {
+        question: "Keep timing and warning history between builds?",
+        options: &YES_NO,
+        current: |config| yes_no(config.history),
+        apply: |config, choice| config.history = choice == 0,
+    },
+];
+
+// Walks through the main settings one question at a time on the first run
+// in a project
+pub struct Wizard {
+    step: usize,
+    choice: usize,
+    config: Config,
+}
+
+impl Wizard {
+    pub fn new(config: Config) -> Self {
+        Wizard {
+            step: 0,
+            choice: (STEPS[0].current)(&config),
+            config,
+        }
+    }
+
+    pub fn move_choice(&mut self, delta: isize) {
+        let len = STEPS[self.step].options.len() as isize;
+        self.choice = (self.choice as isize + delta).rem_euclid(len) as usize;
+    }
+
+    // Applies the highlighted option, giving back the finished config after
+    // the last question
+    pub fn confirm(&mut self) -> Option<Config> {
+        (STEPS[self.step].apply)(&mut self.config, self.choice);
+        self.step += 1;
+
+        match STEPS.get(self.step) {
+            Some(step) => {
+                self.choice = (step.current)(&self.config);
+                None
+            }
+            None => Some(self.config.clone()),
+        }
+    }
+
+    // Everything left keeps its default
+    pub fn skip(self) -> Config {
+        self.config
+    }
+}
+
+pub fn draw_wizard(draw: &Draw, wizard: &Wizard) {
+    let step = &STEPS[wizard.step];
+
+    draw.rect()
+        .x_y(0.0, 0.0)
+        .w_h(WIDTH, HEIGHT)
+        .color(srgba(24u8, 24, 30, 240));
+
+    let title = format!("Welcome to treebuild ({}/{})", wizard.step + 1, STEPS.len());
+    draw.text(&title)
+        .font_size(18)
+        .color(WHITE)
+        .x_y(0.0, HEIGHT / 2.0 - 30.0)
+        .w_h(WIDTH - 40.0, 30.0);
+
+    draw.text(step.question)
+        .color(WHITE)
+        .x_y(0.0, HEIGHT / 2.0 - 70.0)
+        .w_h(WIDTH - 40.0, 30.0);
+
+    for (idx, option) in step.options.iter().enumerate() {
+        let selected = idx == wizard.choice;
+        let y = HEIGHT / 2.0 - 110.0 - idx as f32 * ROW_HEIGHT;
+        let label = format!("{} {}", if selected { ">" } else { " " }, option);
+
+        draw.text(&label)
+            .left_justify()
+            .color(if selected { WHITE } else { GRAY })
+            .x_y(0.0, y)
+            .w_h(WIDTH - 120.0, ROW_HEIGHT);
+    }
+
+    draw.text("up/down to choose, enter to confirm, escape to keep the defaults")
+        .font_size(11)
+        .color(GRAY)
+        .x_y(0.0, -HEIGHT / 2.0 + 20.0)
+        .w_h(WIDTH - 40.0, 20.0);
+}
@@ -0,0 +1,172 @@
+// Only `owner` is of use on wasm32, where nothing gets built
+#[cfg(not(target_arch = "wasm32"))]
+use {
+    crate::drawing::Color, crate::palette, crate::parse_cargo_tree_output::TreeNode,
+    itertools::Itertools, serde::Deserialize, std::collections::BTreeSet, std::sync::Arc,
+};
+
+// Cargo's name for every build script, the tree puts its package's in front
+#[cfg(not(target_arch = "wasm32"))]
+const BUILD_SCRIPT: &str = "build-script-build";
+
+// The subset of `cargo build --unit-graph` we make use of
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Deserialize)]
+struct Target {
+    kind: Vec<String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Deserialize)]
+struct Dependency {
+    index: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Deserialize)]
+struct Unit {
+    pkg_id: String,
+    target: Target,
+    dependencies: Vec<Dependency>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Deserialize)]
+struct UnitGraph {
+    units: Vec<Unit>,
+    roots: Vec<usize>,
+}
+
+// Targets can be named other than their package, e.g. xml-rs's library is
+// xml. Package ids are "name version (source)" up to cargo 1.77, then
+// "source#name@version", or "source#version" when the name is the last part
+// of the source.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn package_name(package_id: &str) -> Option<&str> {
+    match package_id.split_once('#') {
+        Some((source, spec)) => match spec.split_once('@') {
+            Some((name, _)) => Some(name),
+            None => source.trim_end_matches('/').rsplit('/').next(),
+        },
+        None => package_id.split(' ').next(),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn build_script_node(package: &str) -> String {
+    format!("{}/{}", package, BUILD_SCRIPT)
+}
+
+// The package a node is part of, itself unless it is a package's build script
+pub fn owner(name: &str) -> &str {
+    name.split('/').next().unwrap_or(name)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Unit {
+    // Both compiling the script and running it
+    fn is_build_script(&self) -> bool {
+        self.target.kind.iter().any(|kind| kind == "custom-build")
+    }
+
+    fn package(&self) -> String {
+        package_name(&self.pkg_id)
+            .unwrap_or(&self.pkg_id)
+            .replace("_", "-")
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl UnitGraph {
+    // Running a build script depends on compiling it, the two are one node
+    fn dependencies(&self, idx: usize) -> BTreeSet<usize> {
+        let unit = &self.units[idx];
+        let mut dependencies = BTreeSet::new();
+
+        for dependency in unit.dependencies.iter() {
+            let other = &self.units[dependency.index];
+            if unit.is_build_script() && other.is_build_script() && unit.pkg_id == other.pkg_id {
+                dependencies.extend(self.dependencies(dependency.index));
+            } else {
+                dependencies.insert(dependency.index);
+            }
+        }
+
+        dependencies
+    }
+
+    fn node(&self, idx: usize, color_of: &dyn Fn(&str) -> Option<Color>) -> Arc<TreeNode> {
+        let unit = &self.units[idx];
+        let package = unit.package();
+
+        self.node_with(
+            if unit.is_build_script() {
+                build_script_node(&package)
+            } else {
+                package.clone()
+            },
+            &package,
+            self.dependencies(idx),
+            color_of,
+        )
+    }
+
+    // Build scripts take their package's color, to be seen as part of it
+    fn node_with(
+        &self,
+        name: String,
+        package: &str,
+        dependencies: BTreeSet<usize>,
+        color_of: &dyn Fn(&str) -> Option<Color>,
+    ) -> Arc<TreeNode> {
+        Arc::new(TreeNode {
+            name,
+            color: color_of(package).unwrap_or_else(|| palette::color_for(package)),
+            children: dependencies
+                .into_iter()
+                .map(|dependency| self.node(dependency, color_of))
+                .sorted_by_key(|child| child.children.len())
+                .collect(),
+        })
+    }
+}
+
+// The first package cargo would build, with everything it compiles as a unit
+// of its own: build scripts as nodes named after their package, e.g.
+// foo/build-script-build, and crates compiled both for the host and for the
+// target twice. The package's own library, binaries and tests are one node.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn parse_unit_graph(
+    raw: &str,
+    color_of: &dyn Fn(&str) -> Option<Color>,
+) -> Result<Arc<TreeNode>, String> {
+    let graph = serde_json::from_str::<UnitGraph>(raw).map_err(|e| e.to_string())?;
+    let root = *graph.roots.first().ok_or("Nothing to build")?;
+    let root_package = &graph.units[root].pkg_id;
+
+    let mut members = graph
+        .roots
+        .iter()
+        .copied()
+        .filter(|idx| graph.units[*idx].pkg_id == *root_package)
+        .collect::<Vec<_>>();
+    let mut seen = members.iter().copied().collect::<BTreeSet<_>>();
+    let mut dependencies = BTreeSet::new();
+
+    // e.g. its binaries depend on its library
+    while let Some(member) = members.pop() {
+        for dependency in graph.dependencies(member) {
+            let unit = &graph.units[dependency];
+            if unit.pkg_id == *root_package && !unit.is_build_script() {
+                if seen.insert(dependency) {
+                    members.push(dependency);
+                }
+            } else {
+                dependencies.insert(dependency);
+            }
+        }
+    }
+
+    let package = graph.units[root].package();
+    Ok(graph.node_with(package.clone(), &package, dependencies, color_of))
+}
@@ -0,0 +1,100 @@
+#[cfg(feature = "gui")]
+use std::collections::HashSet;
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+use std::collections::{BTreeMap, HashMap};
+
+// The separate rustc invocations a single package can take. Nothing runs
+// rustc on wasm32, where the units are only ever empty.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum UnitKind {
+    BuildScript,
+    Lib,
+    Bin,
+    Test,
+}
+
+#[cfg(feature = "gui")]
+impl UnitKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            UnitKind::BuildScript => "build script",
+            UnitKind::Lib => "lib",
+            UnitKind::Bin => "bin",
+            UnitKind::Test => "test",
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Unit {
+    pub name: String,
+    pub kind: UnitKind,
+}
+
+// Seconds into the build
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnitTiming {
+    pub started: f32,
+    pub finished: Option<f32>,
+}
+
+#[cfg(feature = "gui")]
+impl UnitTiming {
+    pub fn duration(&self, now: f32) -> f32 {
+        self.finished.unwrap_or(now) - self.started
+    }
+}
+
+// Every unit seen running, by package, going by which rustcs are around
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+#[derive(Default)]
+pub struct Units {
+    by_crate: HashMap<String, BTreeMap<UnitKind, UnitTiming>>,
+    #[cfg(feature = "gui")]
+    running: HashSet<Unit>,
+    #[cfg(feature = "gui")]
+    changes: usize,
+}
+
+#[cfg(any(feature = "gui", target_arch = "wasm32"))]
+impl Units {
+    #[cfg(feature = "gui")]
+    pub fn update(&mut self, running: HashSet<Unit>, now: f32) {
+        for unit in running.difference(&self.running) {
+            self.by_crate.entry(unit.name.clone()).or_default().insert(
+                unit.kind,
+                UnitTiming {
+                    started: now,
+                    finished: None,
+                },
+            );
+            self.changes += 1;
+        }
+
+        for unit in self.running.difference(&running) {
+            if let Some(timing) = self
+                .by_crate
+                .get_mut(&unit.name)
+                .and_then(|units| units.get_mut(&unit.kind))
+            {
+                timing.finished = Some(now);
+                self.changes += 1;
+            }
+        }
+
+        self.running = running;
+    }
+
+    pub fn of(&self, name: &str) -> Option<&BTreeMap<UnitKind, UnitTiming>> {
+        self.by_crate.get(name)
+    }
+
+    // Goes up whenever a unit starts or finishes
+    #[cfg(feature = "gui")]
+    pub fn changes(&self) -> usize {
+        self.changes
+    }
+}
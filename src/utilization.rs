@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::HashSet;
+
+// Often enough to see the shape of the build without keeping a sample for
+// every frame of a long one
+#[cfg(not(target_arch = "wasm32"))]
+const SAMPLE_INTERVAL: f32 = 0.25;
+// A single crate compiling alone for at least this long is worth calling out
+#[cfg(feature = "gui")]
+const SERIAL_MIN_SECS: f32 = 3.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sample {
+    pub at: f32,
+    pub jobs: usize,
+    // The crate holding the only slot, when just one is in use
+    pub sole: Option<String>,
+}
+
+// How many job slots the running rustc processes take up over time
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Utilization {
+    samples: Vec<Sample>,
+}
+
+#[cfg(feature = "gui")]
+pub struct SerialStretch {
+    pub start: f32,
+    pub end: f32,
+    pub name: String,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Utilization {
+    pub fn record(&mut self, at: f32, active: &HashSet<String>) {
+        if self
+            .samples
+            .last()
+            .is_some_and(|last| at - last.at < SAMPLE_INTERVAL)
+        {
+            return;
+        }
+
+        self.samples.push(Sample {
+            at,
+            jobs: active.len(),
+            sole: if active.len() == 1 {
+                active.iter().next().cloned()
+            } else {
+                None
+            },
+        });
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    // Stretches where the build was down to one crate, longest first
+    #[cfg(feature = "gui")]
+    pub fn serial_stretches(&self) -> Vec<SerialStretch> {
+        let mut stretches = Vec::<SerialStretch>::new();
+        let mut current: Option<SerialStretch> = None;
+
+        for sample in self.samples.iter() {
+            match (current.as_mut(), sample.sole.as_ref()) {
+                (Some(stretch), Some(name)) if stretch.name == *name => stretch.end = sample.at,
+                (_, sole) => {
+                    stretches.extend(current.take());
+                    current = sole.map(|name| SerialStretch {
+                        start: sample.at,
+                        end: sample.at,
+                        name: name.clone(),
+                    });
+                }
+            }
+        }
+        stretches.extend(current);
+
+        stretches.retain(|stretch| stretch.end - stretch.start >= SERIAL_MIN_SECS);
+        stretches.sort_by(|a, b| (b.end - b.start).total_cmp(&(a.end - a.start)));
+        stretches
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+// Warnings per crate as of the last build that compiled it
+fn history_path() -> PathBuf {
+    crate::project::state_dir().join("warnings.json")
+}
+
+pub fn load_history() -> HashMap<String, usize> {
+    fs::read_to_string(history_path())
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+// Crates this build didn't compile keep what they had before
+pub fn save_history<'a>(
+    previous: &HashMap<String, usize>,
+    current: &HashMap<String, usize>,
+    rebuilt: impl Iterator<Item = &'a String>,
+) {
+    let mut history = previous.clone();
+    for name in rebuilt {
+        history.insert(name.clone(), current.get(name).copied().unwrap_or(0));
+    }
+
+    let path = history_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).expect("Failed to create treebuild state directory");
+    }
+
+    fs::write(
+        path,
+        serde_json::to_string_pretty(&history).expect("Failed to serialize warning history"),
+    )
+    .expect("Failed to write warning history");
+}
+
+// How much each rebuilt crate's warning count moved since its last build.
+// Crates we have never seen before count as starting from zero.
+pub fn deltas<'a>(
+    previous: &HashMap<String, usize>,
+    current: &HashMap<String, usize>,
+    rebuilt: impl Iterator<Item = &'a String>,
+) -> HashMap<String, i64> {
+    if previous.is_empty() {
+        return HashMap::new();
+    }
+
+    rebuilt
+        .map(|name| {
+            let before = previous.get(name).copied().unwrap_or(0) as i64;
+            let now = current.get(name).copied().unwrap_or(0) as i64;
+            (name.clone(), now - before)
+        })
+        .filter(|(_, delta)| *delta != 0)
+        .collect()
+}
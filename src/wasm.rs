@@ -0,0 +1,214 @@
+// The layouts and the drawing of the tree for a browser page. Build it with
+//
+//     cargo rustc --lib --release --target wasm32-unknown-unknown --no-default-features \
+//         --crate-type cdylib
+//
+// and load it with web/treebuild.js, which feeds it each state of the build
+// as `treebuild serve` sends it and draws what comes back onto a canvas.
+// Strings go in through memory from `treebuild_alloc` and come out of
+// `treebuild_output`, both as UTF-8 JSON.
+#![allow(clippy::missing_safety_doc)]
+
+use crate::dependency_tree::DependencyTree;
+use crate::drawing::{draw_tree, DrawState, Point, Shape};
+use crate::export::BuildExport;
+use crate::layout::{self, Layout, LayoutView, SizeMetric};
+use crate::theme::Theme;
+use crate::units::Units;
+use serde::Serialize;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::slice;
+use std::sync::Arc;
+
+#[derive(Serialize)]
+struct FrameCrate {
+    center: Point,
+    radius: f32,
+    shape: Shape,
+    color: (u8, u8, u8),
+    name: String,
+}
+
+#[derive(Serialize)]
+struct FrameLine {
+    path: Vec<Point>,
+    color: (u8, u8, u8),
+    arrow: bool,
+    flowing: bool,
+}
+
+// In the layout's own coordinates, the page fits them to the canvas
+#[derive(Serialize)]
+struct Frame {
+    crates: Vec<FrameCrate>,
+    lines: Vec<FrameLine>,
+}
+
+struct Renderer {
+    // The tree as it came in, to tell whether a new state brings a new tree
+    raw_tree: String,
+    tree: DependencyTree,
+    layout: Box<dyn Layout>,
+    theme: Theme,
+    output: String,
+}
+
+thread_local! {
+    static RENDERER: RefCell<Option<Renderer>> = const { RefCell::new(None) };
+    static LAYOUT: Cell<usize> = const { Cell::new(0) };
+}
+
+impl Renderer {
+    fn new(raw_tree: String, export: &BuildExport) -> Self {
+        Renderer {
+            raw_tree,
+            tree: DependencyTree::new(Arc::clone(&export.tree)),
+            layout: layout::all().remove(LAYOUT.with(Cell::get)),
+            theme: Theme::default(),
+            output: String::new(),
+        }
+    }
+
+    fn render(&mut self, export: &BuildExport) -> String {
+        let weights = HashMap::new();
+        let view = LayoutView {
+            root: self.tree.root(),
+            aggregate_depth: None,
+            expanded: None,
+            time: export.elapsed_secs,
+            metric: SizeMetric::Dependencies,
+            weights: &weights,
+            fixed: &HashMap::new(),
+        };
+        let positions = self.layout.place(&self.tree, &view);
+
+        let set = |names: &BTreeSet<String>| names.iter().cloned().collect::<HashSet<_>>();
+        let active = set(&export.active);
+        let completed = &set(&export.completed) - &active;
+        let aliases = export
+            .aliases
+            .iter()
+            .map(|(name, alias)| (name.clone(), alias.clone()))
+            .collect();
+
+        let (crates, lines) = draw_tree(
+            self.tree.root(),
+            &positions,
+            &DrawState {
+                completed: &completed,
+                active: &active,
+                failed: &set(&export.failed),
+                fresh: &HashSet::new(),
+                highlighted: None,
+                pinned: &HashSet::new(),
+                categories: &HashMap::new(),
+                color_by_category: false,
+                color_by_license: false,
+                outdated: None,
+                diff: None,
+                hide_removed: false,
+                comparison: None,
+                category_filter: &HashSet::new(),
+                metadata: &HashMap::new(),
+                matches: None,
+                warning_deltas: &HashMap::new(),
+                arrows: true,
+                bundles: false,
+                theme: &self.theme,
+                aliases: &aliases,
+                units: &Units::default(),
+                edge_states: true,
+            },
+        );
+
+        let frame = Frame {
+            crates: crates
+                .into_iter()
+                .map(|draw_crate| FrameCrate {
+                    center: draw_crate.center,
+                    radius: draw_crate.radius,
+                    shape: draw_crate.shape,
+                    color: draw_crate.color,
+                    name: draw_crate.name,
+                })
+                .collect(),
+            lines: lines
+                .iter()
+                .map(|line| FrameLine {
+                    path: line.path(),
+                    color: line.color,
+                    arrow: line.arrow,
+                    flowing: line.flowing,
+                })
+                .collect(),
+        };
+        serde_json::to_string(&frame).unwrap_or_default()
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn treebuild_alloc(len: usize) -> *mut u8 {
+    let mut buffer = Vec::<u8>::with_capacity(len);
+    let ptr = buffer.as_mut_ptr();
+    std::mem::forget(buffer);
+    ptr
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn treebuild_dealloc(ptr: *mut u8, len: usize) {
+    if !ptr.is_null() {
+        drop(Vec::from_raw_parts(ptr, 0, len));
+    }
+}
+
+// One of `layout::LAYOUT_NAMES` by index, from the next tree on
+#[no_mangle]
+pub extern "C" fn treebuild_set_layout(idx: usize) {
+    let idx = idx.min(layout::LAYOUT_NAMES.len() - 1);
+
+    LAYOUT.with(|layout| layout.set(idx));
+    RENDERER.with(|renderer| *renderer.borrow_mut() = None);
+}
+
+// Takes a state of the build in the export format and lays it out, returning
+// the length of the frame waiting in `treebuild_output`. Zero when the state
+// didn't parse.
+#[no_mangle]
+pub unsafe extern "C" fn treebuild_render(state: *const u8, len: usize) -> usize {
+    if state.is_null() {
+        return 0;
+    }
+
+    let state = String::from_utf8_lossy(slice::from_raw_parts(state, len));
+    let export = match serde_json::from_str::<BuildExport>(&state) {
+        Ok(export) => export,
+        Err(_) => return 0,
+    };
+    let raw_tree = serde_json::to_string(&export.tree).unwrap_or_default();
+
+    RENDERER.with(|renderer| {
+        let mut renderer = renderer.borrow_mut();
+        if renderer
+            .as_ref()
+            .is_none_or(|renderer| renderer.raw_tree != raw_tree)
+        {
+            *renderer = Some(Renderer::new(raw_tree, &export));
+        }
+
+        let renderer = renderer.as_mut().unwrap();
+        renderer.output = renderer.render(&export);
+        renderer.output.len()
+    })
+}
+
+// Valid until the next call to `treebuild_render`
+#[no_mangle]
+pub extern "C" fn treebuild_output() -> *const u8 {
+    RENDERER.with(|renderer| {
+        renderer
+            .borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |renderer| renderer.output.as_ptr())
+    })
+}
@@ -0,0 +1,125 @@
+use crate::metadata;
+use crate::parse_cargo_tree_output::TreeNode;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+// Often enough to feel immediate, rarely enough not to matter on a big
+// workspace
+const POLL: Duration = Duration::from_millis(700);
+
+// The newest change to anything in a package, and how many files it has so
+// that deleting one counts too
+type Fingerprint = (Option<SystemTime>, usize);
+
+fn fingerprint(directory: &Path) -> Fingerprint {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(_) => return (None, 0),
+    };
+
+    entries
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            // What the build itself writes, and what editors and git keep
+            name != "target" && !name.starts_with('.')
+        })
+        .map(|entry| match entry.file_type() {
+            Ok(kind) if kind.is_dir() => fingerprint(&entry.path()),
+            _ => (entry.metadata().and_then(|meta| meta.modified()).ok(), 1),
+        })
+        .fold((None, 0), |(newest, files), (modified, more)| {
+            (newest.max(modified), files + more)
+        })
+}
+
+// The packages of the project itself, the rest can't be edited
+fn local_packages() -> Vec<(String, PathBuf)> {
+    let mut packages = metadata::load()
+        .into_iter()
+        .filter(|(_, info)| info.local)
+        .map(|(name, info)| (name, info.directory.clone()))
+        .collect::<Vec<_>>();
+
+    // A package in a subdirectory of another is only watched as itself
+    packages.sort_by_key(|(_, directory)| std::cmp::Reverse(directory.components().count()));
+    packages
+}
+
+/// The names of the packages whose files changed, every time any do.
+pub fn start() -> Receiver<Vec<String>> {
+    let (sender, receiver) = channel();
+
+    thread::spawn(move || {
+        let packages = local_packages();
+        let snapshot = || {
+            packages
+                .iter()
+                .map(|(_, directory)| fingerprint(directory))
+                .collect::<Vec<_>>()
+        };
+
+        let mut last = snapshot();
+        loop {
+            thread::sleep(POLL);
+
+            let now = snapshot();
+            let changed = packages
+                .iter()
+                .zip(last.iter().zip(now.iter()))
+                .filter(|(_, (before, after))| before != after)
+                .map(|((name, _), _)| name.clone())
+                .collect::<Vec<_>>();
+            last = now;
+
+            if !changed.is_empty() && sender.send(changed).is_err() {
+                return;
+            }
+        }
+    });
+
+    receiver
+}
+
+/// What has to be built again after `changed` did, a level at a time from
+/// the changed crates up to those depending on them last.
+pub fn affected(tree: &TreeNode, changed: &[String]) -> Vec<HashSet<String>> {
+    fn collect<'a>(node: &'a TreeNode, dependents: &mut HashMap<&'a str, HashSet<&'a str>>) {
+        for child in node.children.iter() {
+            let known = dependents.contains_key(child.name.as_str());
+            dependents
+                .entry(&child.name)
+                .or_default()
+                .insert(&node.name);
+            if !known {
+                collect(child, dependents);
+            }
+        }
+    }
+
+    let mut dependents = HashMap::new();
+    collect(tree, &mut dependents);
+
+    let mut seen = changed.iter().map(String::as_str).collect::<HashSet<_>>();
+    let mut level = seen.clone();
+    let mut levels = Vec::new();
+
+    while !level.is_empty() {
+        let next = level
+            .iter()
+            .flat_map(|name| dependents.get(name).into_iter().flatten())
+            .copied()
+            .filter(|name| seen.insert(name))
+            .collect::<HashSet<_>>();
+
+        levels.push(level.into_iter().map(str::to_owned).collect());
+        level = next;
+    }
+
+    levels
+}
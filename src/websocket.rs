@@ -0,0 +1,200 @@
+use std::io::{self, Write};
+
+// RFC 6455, a client proves it speaks WebSocket by having its key hashed with
+// this appended
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+const TEXT_FRAME: u8 = 0x81;
+
+fn sha1(message: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [
+        0x6745_2301,
+        0xefcd_ab89,
+        0x98ba_dcfe,
+        0x1032_5476,
+        0xc3d2_e1f0,
+    ];
+
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&(message.len() as u64 * 8).to_be_bytes());
+
+    for block in padded.chunks(64) {
+        let mut words = [0u32; 80];
+        for (idx, word) in block.chunks(4).enumerate() {
+            words[idx] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for idx in 16..80 {
+            words[idx] = (words[idx - 3] ^ words[idx - 8] ^ words[idx - 14] ^ words[idx - 16])
+                .rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (idx, word) in words.iter().enumerate() {
+            let (f, k) = match idx {
+                0..=19 => ((b & c) | (!b & d), 0x5a82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ed9_eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+                _ => (b ^ c ^ d, 0xca62_c1d6),
+            };
+            let next = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = next;
+        }
+
+        for (value, add) in state.iter_mut().zip([a, b, c, d, e]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (chunk, value) in digest.chunks_mut(4).zip(state.iter()) {
+        chunk.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+
+    for chunk in bytes.chunks(3) {
+        let triple = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                encoded.push(BASE64[(triple >> (18 - 6 * idx) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+// What goes in Sec-WebSocket-Accept for the client's Sec-WebSocket-Key
+pub fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{}{}", key.trim(), ACCEPT_GUID).as_bytes()))
+}
+
+// Frames from the server aren't masked, and a whole message always fits in
+// one frame
+pub fn write_text(stream: &mut impl Write, text: &str) -> io::Result<()> {
+    let len = text.len();
+    let mut header = vec![TEXT_FRAME];
+
+    if len < 126 {
+        header.push(len as u8);
+    } else if len <= u16::MAX as usize {
+        header.push(126);
+        header.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        header.push(127);
+        header.extend_from_slice(&(len as u64).to_be_bytes());
+    }
+
+    stream.write_all(&header)?;
+    stream.write_all(text.as_bytes())?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A frame from the server the way a client reads it, into how long its
+    // header is and the text
+    fn read_text(frame: &[u8]) -> (usize, &str) {
+        assert_eq!(frame[0], TEXT_FRAME);
+        assert_eq!(frame[1] & 0x80, 0, "Frames from the server aren't masked");
+
+        let (len, header) = match frame[1] {
+            126 => (u16::from_be_bytes([frame[2], frame[3]]) as usize, 4),
+            127 => {
+                let mut len = [0u8; 8];
+                len.copy_from_slice(&frame[2..10]);
+                (u64::from_be_bytes(len) as usize, 10)
+            }
+            len => (len as usize, 2),
+        };
+
+        assert_eq!(frame.len(), header + len);
+        (header, std::str::from_utf8(&frame[header..]).unwrap())
+    }
+
+    #[test]
+    fn the_accept_key_is_the_rfcs() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+        // Header values can come with whitespace around them
+        assert_eq!(
+            accept_key(" dGhlIHNhbXBsZSBub25jZQ==\r"),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn sha1_and_base64_match_known_values() {
+        let hex = |digest: [u8; 20]| {
+            digest
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<String>()
+        };
+
+        assert_eq!(hex(sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(
+            hex(sha1(b"abc")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        // Long enough for the padding to take a second block
+        assert_eq!(
+            hex(sha1(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+        );
+
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn frames_read_back_on_either_side_of_the_length_limits() {
+        for &(len, header) in [
+            (0, 2),
+            (125, 2),
+            (126, 4),
+            (u16::MAX as usize, 4),
+            (u16::MAX as usize + 1, 10),
+        ]
+        .iter()
+        {
+            let text = "x".repeat(len);
+            let mut frame = Vec::new();
+            write_text(&mut frame, &text).unwrap();
+
+            assert_eq!(read_text(&frame), (header, text.as_str()), "{} bytes", len);
+        }
+    }
+}
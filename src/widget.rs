@@ -0,0 +1,170 @@
+use crate::camera::Camera;
+use crate::config;
+use crate::dependency_tree::DependencyTree;
+use crate::drawing::{draw_tree, DrawState};
+use crate::events::BuildEvent;
+use crate::layout::{self, Layout, LayoutView, Positions, SizeMetric};
+use crate::monitor;
+use crate::parse_cargo_tree_output::TreeNode;
+use crate::theme::{self, Theme};
+use crate::units::Units;
+use nannou::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+// The dependency tree and the state of a build, for other nannou apps to
+// show in part of their own window. None of the standalone app's panels or
+// input handling come along, the host decides what to do with those.
+pub struct TreebuildWidget {
+    tree: DependencyTree,
+    layout: Box<dyn Layout>,
+    positions: Positions,
+    completed: HashSet<String>,
+    active: HashSet<String>,
+    failed: HashSet<String>,
+    fresh: HashSet<String>,
+    receiver: Option<Receiver<BuildEvent>>,
+    theme: Theme,
+    time: f32,
+}
+
+impl TreebuildWidget {
+    pub fn new(tree: Arc<TreeNode>) -> Self {
+        TreebuildWidget {
+            tree: DependencyTree::new(tree),
+            layout: layout::all().remove(0),
+            positions: Positions::default(),
+            completed: HashSet::new(),
+            active: HashSet::new(),
+            failed: HashSet::new(),
+            fresh: HashSet::new(),
+            receiver: None,
+            theme: theme::DEFAULT_THEME,
+            time: 0.0,
+        }
+    }
+
+    // The tree of the package in the current directory
+    pub fn for_project(dev_dependencies: bool) -> Self {
+        TreebuildWidget::new(crate::project::cargo_tree(
+            dev_dependencies,
+            &config::load().unwrap_or_default().color_rules,
+        ))
+    }
+
+    // Takes the same names as the config file, see `layout::LAYOUT_NAMES`
+    pub fn set_layout(&mut self, name: &str) {
+        self.layout = layout::all().remove(layout::index_of(name));
+    }
+
+    // Takes the same names as the config file, see `theme::THEME_NAMES`
+    pub fn set_theme(&mut self, name: &str) {
+        self.theme = theme::by_name(name, &HashMap::new());
+    }
+
+    // Runs `cargo build` in the background and follows along
+    pub fn build(&mut self, cargo_args: Vec<String>) {
+        let (sender, receiver) = channel();
+
+        thread::spawn(move || monitor::run(vec!["build"], cargo_args, sender));
+        self.receiver = Some(receiver);
+    }
+
+    // Meant to be called from the host's own update
+    pub fn update(&mut self, time: f32) {
+        self.time = time;
+
+        if let Some(ref receiver) = self.receiver {
+            while let Ok(event) = receiver.try_recv() {
+                match event {
+                    BuildEvent::Started(name) => {
+                        self.active.insert(name);
+                    }
+                    BuildEvent::Completed(name) => {
+                        self.active.remove(&name);
+                        self.completed.insert(name);
+                    }
+                    BuildEvent::Failed(name) => {
+                        self.active.remove(&name);
+                        self.failed.insert(name);
+                    }
+                    BuildEvent::Fresh(name) => {
+                        self.fresh.insert(name);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let weights = HashMap::new();
+        let view = LayoutView {
+            root: self.tree.root(),
+            aggregate_depth: None,
+            expanded: None,
+            time,
+            metric: SizeMetric::Dependencies,
+            weights: &weights,
+            fixed: &HashMap::new(),
+        };
+
+        self.positions = self.layout.place(&self.tree, &view);
+    }
+
+    // Fits the whole tree into `rect`, in the host's window coordinates
+    pub fn draw(&self, draw: &Draw, rect: Rect) {
+        let (categories, metadata, warning_deltas, aliases) = (
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+        );
+
+        let (mut crates, mut lines) = draw_tree(
+            self.tree.root(),
+            &self.positions,
+            &DrawState {
+                completed: &self.completed,
+                active: &self.active,
+                failed: &self.failed,
+                fresh: &self.fresh,
+                highlighted: None,
+                pinned: &HashSet::new(),
+                categories: &categories,
+                color_by_category: false,
+                color_by_license: false,
+                outdated: None,
+                diff: None,
+                hide_removed: false,
+                comparison: None,
+                category_filter: &HashSet::new(),
+                metadata: &metadata,
+                matches: None,
+                warning_deltas: &warning_deltas,
+                arrows: false,
+                bundles: false,
+                theme: &self.theme,
+                aliases: &aliases,
+                units: &Units::default(),
+                edge_states: true,
+            },
+        );
+
+        let mut camera = Camera::new();
+        camera.fit(&crates, (rect.w(), rect.h()));
+        camera.pan((rect.x(), rect.y()));
+        camera.apply(&mut crates, &mut lines);
+
+        crate::app::draw_shapes(
+            draw,
+            crates,
+            lines,
+            &self.theme,
+            Some(self.time),
+            false,
+            &HashMap::new(),
+            1.0,
+        );
+    }
+}
@@ -0,0 +1,164 @@
+"""Python access to treebuild's dependency trees, layouts and build exports.
+
+The tree and layout functions go through the C ABI, so build the shared
+library first:
+
+    cargo rustc --lib --release --features ffi --crate-type cdylib
+
+and point TREEBUILD_LIB at it if it isn't in target/release. Build exports
+(`--save`) are plain JSON and need nothing but this file.
+"""
+
+import ctypes
+import json
+import os
+from pathlib import Path
+
+
+class _Node(ctypes.Structure):
+    _fields_ = [("name", ctypes.c_char_p), ("depth", ctypes.c_size_t)]
+
+
+class _Edge(ctypes.Structure):
+    _fields_ = [("from_", ctypes.c_size_t), ("to", ctypes.c_size_t)]
+
+
+class _Position(ctypes.Structure):
+    _fields_ = [("x", ctypes.c_float), ("y", ctypes.c_float), ("radius", ctypes.c_float)]
+
+
+def _library():
+    default = Path(__file__).resolve().parent.parent / "target" / "release" / "libtreebuild.so"
+    lib = ctypes.CDLL(os.environ.get("TREEBUILD_LIB", str(default)))
+
+    lib.treebuild_parse_project.argtypes = [ctypes.c_bool]
+    lib.treebuild_parse_project.restype = ctypes.c_void_p
+    lib.treebuild_parse_tree.argtypes = [ctypes.c_char_p]
+    lib.treebuild_parse_tree.restype = ctypes.c_void_p
+    lib.treebuild_graph_free.argtypes = [ctypes.c_void_p]
+    lib.treebuild_node_count.argtypes = [ctypes.c_void_p]
+    lib.treebuild_node_count.restype = ctypes.c_size_t
+    lib.treebuild_node.argtypes = [ctypes.c_void_p, ctypes.c_size_t, ctypes.POINTER(_Node)]
+    lib.treebuild_node.restype = ctypes.c_bool
+    lib.treebuild_edge_count.argtypes = [ctypes.c_void_p]
+    lib.treebuild_edge_count.restype = ctypes.c_size_t
+    lib.treebuild_edge.argtypes = [ctypes.c_void_p, ctypes.c_size_t, ctypes.POINTER(_Edge)]
+    lib.treebuild_edge.restype = ctypes.c_bool
+    lib.treebuild_layout.argtypes = [ctypes.c_void_p, ctypes.c_char_p]
+    lib.treebuild_layout.restype = ctypes.c_void_p
+    lib.treebuild_layout_free.argtypes = [ctypes.c_void_p]
+    lib.treebuild_position.argtypes = [
+        ctypes.c_void_p,
+        ctypes.c_size_t,
+        ctypes.POINTER(_Position),
+    ]
+    lib.treebuild_position.restype = ctypes.c_bool
+
+    return lib
+
+
+class DependencyTree:
+    """The tree `cargo tree` prints, one node per line of it."""
+
+    def __init__(self, handle):
+        if not handle:
+            raise RuntimeError("treebuild could not parse the dependency tree")
+        self._lib = _library()
+        self._handle = handle
+
+    @classmethod
+    def for_project(cls, dev_dependencies=False):
+        """Runs `cargo tree` in the current directory."""
+        return cls(_library().treebuild_parse_project(dev_dependencies))
+
+    @classmethod
+    def from_text(cls, text):
+        """Output of `cargo tree --prefix depth --no-dedupe`."""
+        return cls(_library().treebuild_parse_tree(text.encode()))
+
+    def __del__(self):
+        if getattr(self, "_handle", None):
+            self._lib.treebuild_graph_free(self._handle)
+
+    def nodes(self):
+        """(name, depth) pairs, parents before their children."""
+        node = _Node()
+        return [
+            (node.name.decode(), node.depth)
+            for idx in range(self._lib.treebuild_node_count(self._handle))
+            if self._lib.treebuild_node(self._handle, idx, ctypes.byref(node))
+        ]
+
+    def edges(self):
+        """(dependent, dependency) index pairs into `nodes()`."""
+        edge = _Edge()
+        return [
+            (edge.from_, edge.to)
+            for idx in range(self._lib.treebuild_edge_count(self._handle))
+            if self._lib.treebuild_edge(self._handle, idx, ctypes.byref(edge))
+        ]
+
+    def layout(self, name="satellites"):
+        """{node index: (x, y, radius)} for the nodes the layout placed."""
+        handle = self._lib.treebuild_layout(self._handle, name.encode())
+        if not handle:
+            raise RuntimeError("treebuild could not lay out the tree")
+
+        position = _Position()
+        try:
+            return {
+                idx: (position.x, position.y, position.radius)
+                for idx in range(self._lib.treebuild_node_count(self._handle))
+                if self._lib.treebuild_position(handle, idx, ctypes.byref(position))
+            }
+        finally:
+            self._lib.treebuild_layout_free(handle)
+
+    def stats(self):
+        """Distinct crates, how often the tree repeats them, and its depth."""
+        nodes = self.nodes()
+        counts = {}
+        for name, _ in nodes:
+            counts[name] = counts.get(name, 0) + 1
+
+        return {
+            "crates": len(counts),
+            "nodes": len(nodes),
+            "depth": max((depth for _, depth in nodes), default=0),
+            "most_repeated": sorted(counts.items(), key=lambda item: -item[1])[:10],
+        }
+
+
+class Build:
+    """A build saved with `--save`."""
+
+    def __init__(self, path):
+        with open(path) as f:
+            self.raw = json.load(f)
+
+    @property
+    def phase(self):
+        return self.raw["phase"]
+
+    @property
+    def elapsed(self):
+        return self.raw["elapsed_secs"]
+
+    @property
+    def durations(self):
+        """Seconds each crate took to compile."""
+        return dict(self.raw["durations"])
+
+    def slowest(self, amount=10):
+        return sorted(self.durations.items(), key=lambda item: -item[1])[:amount]
+
+
+def diff(before, after):
+    """How much longer (positive) or shorter each crate took between builds,
+    biggest changes first. Crates only one of them built are left out."""
+    changes = {
+        name: after.durations[name] - took
+        for name, took in before.durations.items()
+        if name in after.durations
+    }
+    return sorted(changes.items(), key=lambda item: -abs(item[1]))
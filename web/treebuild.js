@@ -0,0 +1,109 @@
+// Draws a build onto a canvas with the same layouts as the desktop app,
+// through the wasm build of treebuild (see src/wasm.rs). Every state that
+// comes in over `events`, the WebSocket `treebuild serve` offers at /events,
+// is laid out in wasm and drawn here.
+//
+//     import { attach } from "./treebuild.js";
+//     attach(document.querySelector("canvas"), "treebuild.wasm", "ws://" + location.host + "/events");
+
+const encoder = new TextEncoder();
+const decoder = new TextDecoder();
+
+function rgb([r, g, b]) {
+  return "rgb(" + r + "," + g + "," + b + ")";
+}
+
+// Fits everything into the canvas with a margin, the way the window's camera
+// fits the tree on start
+function fit(frame, canvas) {
+  let [left, bottom, right, top] = [Infinity, Infinity, -Infinity, -Infinity];
+  for (const node of frame.crates) {
+    left = Math.min(left, node.center[0] - node.radius);
+    right = Math.max(right, node.center[0] + node.radius);
+    bottom = Math.min(bottom, node.center[1] - node.radius);
+    top = Math.max(top, node.center[1] + node.radius);
+  }
+
+  const scale = 0.9 * Math.min(canvas.width / (right - left), canvas.height / (top - bottom));
+  const [x, y] = [(left + right) / 2, (bottom + top) / 2];
+  // The layouts have y going up, the canvas has it going down
+  return ([px, py]) => [
+    canvas.width / 2 + (px - x) * scale,
+    canvas.height / 2 - (py - y) * scale,
+    scale,
+  ];
+}
+
+function draw(frame, canvas) {
+  const ctx = canvas.getContext("2d");
+  ctx.fillStyle = "#141418";
+  ctx.fillRect(0, 0, canvas.width, canvas.height);
+  if (frame.crates.length === 0) return;
+
+  const screen = fit(frame, canvas);
+
+  for (const line of frame.lines) {
+    ctx.strokeStyle = rgb(line.color);
+    ctx.setLineDash(line.flowing ? [8, 6] : []);
+    ctx.beginPath();
+    line.path.forEach((point, idx) => {
+      const [x, y] = screen(point);
+      idx === 0 ? ctx.moveTo(x, y) : ctx.lineTo(x, y);
+    });
+    ctx.stroke();
+  }
+  ctx.setLineDash([]);
+
+  for (const node of frame.crates) {
+    const [x, y, scale] = screen(node.center);
+    ctx.fillStyle = rgb(node.color);
+
+    if (node.shape === "Circle") {
+      ctx.beginPath();
+      ctx.arc(x, y, Math.max(node.radius * scale, 1), 0, 2 * Math.PI);
+      ctx.fill();
+    } else if (node.shape.Rect) {
+      const { width, height } = node.shape.Rect;
+      ctx.fillRect(x - width * scale / 2, y - height * scale / 2, width * scale, height * scale);
+    } else if (node.shape.Wedge) {
+      const { origin, inner, outer, start, end } = node.shape.Wedge;
+      const [ox, oy] = screen(origin);
+      // Counterclockwise in the layout is clockwise on the canvas
+      ctx.beginPath();
+      ctx.arc(ox, oy, outer * scale, -start, -end, true);
+      ctx.arc(ox, oy, inner * scale, -end, -start, false);
+      ctx.fill();
+    }
+
+    if (node.radius * scale > 12) {
+      ctx.fillStyle = "#fff";
+      ctx.textAlign = "center";
+      ctx.fillText(node.name, x, y + 4);
+    }
+  }
+}
+
+export async function attach(canvas, wasmUrl, eventsUrl, layout = 0) {
+  const { instance } = await WebAssembly.instantiateStreaming(fetch(wasmUrl));
+  const wasm = instance.exports;
+  wasm.treebuild_set_layout(layout);
+
+  const render = state => {
+    const bytes = encoder.encode(state);
+    const ptr = wasm.treebuild_alloc(bytes.length);
+    new Uint8Array(wasm.memory.buffer, ptr, bytes.length).set(bytes);
+    const len = wasm.treebuild_render(ptr, bytes.length);
+    wasm.treebuild_dealloc(ptr, bytes.length);
+    if (len === 0) return;
+
+    const out = new Uint8Array(wasm.memory.buffer, wasm.treebuild_output(), len);
+    draw(JSON.parse(decoder.decode(out)), canvas);
+  };
+
+  const connect = () => {
+    const socket = new WebSocket(eventsUrl);
+    socket.onmessage = message => render(message.data);
+    socket.onclose = () => setTimeout(connect, 2000);
+  };
+  connect();
+}